@@ -0,0 +1,195 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::Document;
+use mongodb::options::{
+    AggregateOptions, CountOptions, FindOneOptions, InsertOneOptions, UpdateModifications,
+    UpdateOptions,
+};
+use mongodb::{Collection, Cursor};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+use crate::{utils, MongoCollection};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AuditEvent {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub entity: AuditEntity,
+    pub entity_id: String,
+    pub operation: AuditOperation,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+    pub changes: Vec<AuditFieldChange>,
+    pub executed_at: DateTime<Utc>,
+}
+
+impl AuditEvent {
+    pub fn new(
+        entity: AuditEntity,
+        entity_id: String,
+        operation: AuditOperation,
+        actor: Option<String>,
+        changes: Vec<AuditFieldChange>,
+    ) -> Self {
+        Self {
+            id: None,
+            entity,
+            entity_id,
+            operation,
+            actor,
+            changes,
+            executed_at: Utc::now(),
+        }
+    }
+}
+
+/// Value of a single field before and after the mutation,
+/// `None` when the entity did not exist on that side.
+/// A missing side is left out, so it isn't mistaken for a null value
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AuditFieldChange {
+    pub field: String,
+
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_present"
+    )]
+    pub before: Option<Value>,
+
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_present"
+    )]
+    pub after: Option<Value>,
+}
+
+/// Keeps a present null as `Some(Value::Null)`, absent fields fall back to `None`
+pub fn deserialize_present<'de, D>(deserializer: D) -> Result<Option<Value>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Value::deserialize(deserializer).map(Some)
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, Copy, Eq, async_graphql::Enum, utoipa::ToSchema,
+)]
+pub enum AuditEntity {
+    Repository,
+    User,
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, Copy, Eq, async_graphql::Enum, utoipa::ToSchema,
+)]
+pub enum AuditOperation {
+    Create,
+    Update,
+    Delete,
+}
+
+pub struct AuditEventCollection {
+    pub collection: Collection<AuditEvent>,
+}
+
+impl MongoCollection<AuditEvent> for AuditEventCollection {
+    fn get_collection(&self) -> Option<&Collection<AuditEvent>> {
+        Some(&self.collection)
+    }
+}
+
+#[derive(Default)]
+pub struct TestAuditEventCollection {
+    entities: Arc<Mutex<Vec<AuditEvent>>>,
+}
+
+impl TestAuditEventCollection {
+    /// Supports only equality matches on top level fields
+    fn matches(event: &AuditEvent, filter: &Document) -> bool {
+        let doc = mongodb::bson::to_document(event).unwrap();
+        filter.iter().all(|(key, val)| doc.get(key) == Some(val))
+    }
+}
+
+#[async_trait]
+impl MongoCollection<AuditEvent> for TestAuditEventCollection {
+    async fn find_one(
+        &self,
+        filter: Option<Document>,
+        _options: Option<FindOneOptions>,
+    ) -> mongodb::error::Result<Option<AuditEvent>> {
+        let filter = filter.unwrap();
+        let id = filter.get("_id");
+        let entities = self.entities.lock().unwrap();
+        if let Some(id) = id {
+            return Ok(entities
+                .iter()
+                .find(|&e| e.id == id.as_object_id())
+                .cloned());
+        }
+        panic!("Not yet implemented logic")
+    }
+
+    async fn insert_one(
+        &self,
+        mut doc: AuditEvent,
+        _options: Option<InsertOneOptions>,
+    ) -> mongodb::error::Result<ObjectId> {
+        let id = ObjectId::new();
+        doc.id = Some(id);
+        self.entities.lock().unwrap().push(doc);
+        Ok(id)
+    }
+
+    async fn update_one(
+        &self,
+        _query: Document,
+        _update: UpdateModifications,
+        _options: Option<UpdateOptions>,
+    ) -> mongodb::error::Result<()> {
+        panic!("This test collection can't be updated")
+    }
+
+    async fn aggregate(
+        &self,
+        _pipeline: Vec<Document>,
+        _options: Option<AggregateOptions>,
+    ) -> mongodb::error::Result<Cursor<Document>> {
+        panic!("This test collection can't be aggregated")
+    }
+
+    async fn count_documents(
+        &self,
+        filter: Option<Document>,
+        _options: Option<CountOptions>,
+    ) -> mongodb::error::Result<u64> {
+        let filter = filter.unwrap_or_default();
+        let entities = self.entities.lock().unwrap();
+        Ok(entities
+            .iter()
+            .filter(|e| Self::matches(e, &filter))
+            .count() as u64)
+    }
+
+    async fn aggregate_and_collect(
+        &self,
+        pipeline: Vec<Document>,
+        _options: Option<AggregateOptions>,
+    ) -> mongodb::error::Result<Vec<AuditEvent>> {
+        let mut entities = self.entities.lock().unwrap().clone();
+        let r#match = pipeline
+            .first()
+            .and_then(|val| val.get("$match").and_then(|r#match| r#match.as_document()));
+        if let Some(doc) = r#match {
+            entities.retain(|e| Self::matches(e, doc));
+        };
+        Ok(utils::paginate_inmemory_collection(entities, pipeline))
+    }
+}
@@ -4,18 +4,22 @@ use futures_util::TryStreamExt;
 use mongodb::bson::oid::ObjectId;
 use mongodb::bson::Document;
 use mongodb::options::{
-    AggregateOptions, CountOptions, FindOneOptions, InsertOneOptions, UpdateModifications,
-    UpdateOptions,
+    AggregateOptions, CountOptions, DeleteOptions, FindOneOptions, InsertOneOptions,
+    UpdateModifications, UpdateOptions,
 };
 use mongodb::{bson, Collection, Cursor};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
 
+pub mod audit_event;
+pub mod outbox_event;
 #[cfg(test)]
 mod tests;
 pub mod user;
 pub mod user_repo_info;
 pub mod utils;
+pub mod webhook_delivery;
+pub mod webhook_subscription;
 #[async_trait]
 pub trait MongoCollection<T: Serialize + DeserializeOwned + Unpin + Send + Sync>:
     Send + Sync
@@ -63,6 +67,18 @@ pub trait MongoCollection<T: Serialize + DeserializeOwned + Unpin + Send + Sync>
             .map(|_d| ())
     }
 
+    async fn delete_many(
+        &self,
+        query: Document,
+        options: Option<DeleteOptions>,
+    ) -> mongodb::error::Result<u64> {
+        self.get_collection()
+            .unwrap()
+            .delete_many(query, options)
+            .await
+            .map(|res| res.deleted_count)
+    }
+
     async fn aggregate(
         &self,
         pipeline: Vec<Document>,
@@ -85,6 +101,18 @@ pub trait MongoCollection<T: Serialize + DeserializeOwned + Unpin + Send + Sync>
             .await
     }
 
+    /// Collects results of a pipeline which don't correspond to the entity
+    async fn aggregate_documents(
+        &self,
+        pipeline: Vec<Document>,
+        options: Option<AggregateOptions>,
+    ) -> mongodb::error::Result<Vec<Document>> {
+        self.aggregate(pipeline, options)
+            .await?
+            .try_collect::<Vec<_>>()
+            .await
+    }
+
     async fn aggregate_and_collect(
         &self,
         pipeline: Vec<Document>,
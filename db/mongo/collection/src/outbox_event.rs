@@ -0,0 +1,202 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use bson::spec::BinarySubtype;
+use bson::{Binary, Bson};
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::Document;
+use mongodb::options::{
+    AggregateOptions, CountOptions, DeleteOptions, FindOneOptions, InsertOneOptions,
+    UpdateModifications, UpdateOptions,
+};
+use mongodb::{Collection, Cursor};
+use serde::{Deserialize, Serialize};
+
+use crate::{utils, MongoCollection};
+
+/// Message persisted next to the write it originates from, waiting to be
+/// relayed to the broker
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct OutboxEvent {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub queue: String,
+
+    /// Empty for a staged event whose message depends on the outcome of the write
+    pub payload: Binary,
+    pub status: OutboxStatus,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub sent_at: Option<DateTime<Utc>>,
+
+    /// A staged or sending event is left to its owner until then
+    pub locked_until: Option<bson::DateTime>,
+}
+
+impl OutboxEvent {
+    /// Staged for a write that is about to happen
+    pub fn new(queue: String, payload: Vec<u8>, locked_until: DateTime<Utc>) -> Self {
+        Self {
+            id: None,
+            queue,
+            payload: Binary {
+                subtype: BinarySubtype::Generic,
+                bytes: payload,
+            },
+            status: OutboxStatus::Staged,
+            attempts: 0,
+            last_error: None,
+            created_at: Utc::now(),
+            sent_at: None,
+            locked_until: Some(bson::DateTime::from_millis(locked_until.timestamp_millis())),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Copy, Eq)]
+pub enum OutboxStatus {
+    /// Written before the write it belongs to, not relayed until committed
+    Staged,
+
+    /// Claimed by a sender, relayed again if its lock expires
+    Sending,
+    Pending,
+    Sent,
+    /// Ran out of attempts, is no longer relayed
+    Failed,
+}
+
+pub struct OutboxEventCollection {
+    pub collection: Collection<OutboxEvent>,
+}
+
+impl MongoCollection<OutboxEvent> for OutboxEventCollection {
+    fn get_collection(&self) -> Option<&Collection<OutboxEvent>> {
+        Some(&self.collection)
+    }
+}
+
+#[derive(Default)]
+pub struct TestOutboxEventCollection {
+    entities: Arc<Mutex<Vec<OutboxEvent>>>,
+}
+
+impl TestOutboxEventCollection {
+    fn matches(event: &OutboxEvent, filter: &Document) -> bool {
+        let doc = mongodb::bson::to_document(event).unwrap();
+        utils::aggregation::matches(&doc, filter)
+    }
+}
+
+#[async_trait]
+impl MongoCollection<OutboxEvent> for TestOutboxEventCollection {
+    async fn find_one(
+        &self,
+        filter: Option<Document>,
+        _options: Option<FindOneOptions>,
+    ) -> mongodb::error::Result<Option<OutboxEvent>> {
+        let filter = filter.unwrap();
+        let id = filter.get("_id");
+        let entities = self.entities.lock().unwrap();
+        if let Some(id) = id {
+            return Ok(entities
+                .iter()
+                .find(|&e| e.id == id.as_object_id())
+                .cloned());
+        }
+        panic!("Not yet implemented logic")
+    }
+
+    async fn insert_one(
+        &self,
+        mut doc: OutboxEvent,
+        _options: Option<InsertOneOptions>,
+    ) -> mongodb::error::Result<ObjectId> {
+        let id = ObjectId::new();
+        doc.id = Some(id);
+        self.entities.lock().unwrap().push(doc);
+        Ok(id)
+    }
+
+    /// Supports only `$set` and integer `$inc` updates
+    async fn update_one(
+        &self,
+        query: Document,
+        update: UpdateModifications,
+        _options: Option<UpdateOptions>,
+    ) -> mongodb::error::Result<()> {
+        let id = query.get("_id").unwrap().as_object_id();
+        let update = match update {
+            UpdateModifications::Document(doc) => doc,
+            _ => panic!("Not implemented yet"),
+        };
+        let mut entities = self.entities.lock().unwrap();
+        let Some(event) = entities.iter_mut().find(|e| e.id == id) else {
+            return Ok(());
+        };
+
+        let mut doc = mongodb::bson::to_document(event).unwrap();
+        if let Ok(set) = update.get_document("$set") {
+            doc.extend(set.clone());
+        }
+        if let Ok(inc) = update.get_document("$inc") {
+            for (key, val) in inc {
+                let current = doc.get_i64(key).or(doc.get_i32(key).map(i64::from));
+                let sum =
+                    current.unwrap_or(0) + val.as_i64().or(val.as_i32().map(i64::from)).unwrap();
+                doc.insert(key, Bson::Int64(sum));
+            }
+        }
+        *event = mongodb::bson::from_document(doc).unwrap();
+        Ok(())
+    }
+
+    async fn delete_many(
+        &self,
+        query: Document,
+        _options: Option<DeleteOptions>,
+    ) -> mongodb::error::Result<u64> {
+        let mut entities = self.entities.lock().unwrap();
+        let len = entities.len();
+        entities.retain(|e| !Self::matches(e, &query));
+        Ok((len - entities.len()) as u64)
+    }
+
+    async fn aggregate(
+        &self,
+        _pipeline: Vec<Document>,
+        _options: Option<AggregateOptions>,
+    ) -> mongodb::error::Result<Cursor<Document>> {
+        panic!("This test collection can't be aggregated")
+    }
+
+    async fn count_documents(
+        &self,
+        filter: Option<Document>,
+        _options: Option<CountOptions>,
+    ) -> mongodb::error::Result<u64> {
+        let filter = filter.unwrap_or_default();
+        let entities = self.entities.lock().unwrap();
+        Ok(entities
+            .iter()
+            .filter(|e| Self::matches(e, &filter))
+            .count() as u64)
+    }
+
+    async fn aggregate_and_collect(
+        &self,
+        pipeline: Vec<Document>,
+        _options: Option<AggregateOptions>,
+    ) -> mongodb::error::Result<Vec<OutboxEvent>> {
+        let mut entities = self.entities.lock().unwrap().clone();
+        let r#match = pipeline
+            .first()
+            .and_then(|val| val.get("$match").and_then(|r#match| r#match.as_document()));
+        if let Some(doc) = r#match {
+            entities.retain(|e| Self::matches(e, doc));
+        };
+        Ok(utils::paginate_inmemory_collection(entities, pipeline))
+    }
+}
@@ -200,8 +200,18 @@ impl MongoCollection<User> for TestUserCollection {
         pipeline: Vec<Document>,
         _options: Option<AggregateOptions>,
     ) -> mongodb::error::Result<Vec<User>> {
-        let users = self.users.lock().unwrap().clone();
-        let users = users.into_iter().filter(|u| !u.deleted).collect();
-        Ok(utils::paginate_inmemory_collection(users, pipeline))
+        let docs = self
+            .users
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|u| mongodb::bson::to_document(u).unwrap())
+            .collect();
+        Ok(
+            utils::aggregation::aggregate_inmemory_collection(docs, &pipeline)
+                .into_iter()
+                .map(|d| mongodb::bson::from_document(d).unwrap())
+                .collect(),
+        )
     }
 }
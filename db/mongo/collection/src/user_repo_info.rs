@@ -1,13 +1,15 @@
 use std::sync::{Arc, Mutex};
 
 use async_trait::async_trait;
-use bson::serde_helpers::uuid_1_as_binary;
+use bson::serde_helpers::{
+    chrono_datetime_as_bson_datetime, chrono_datetime_as_bson_datetime_optional, uuid_1_as_binary,
+};
 use chrono::{DateTime, Utc};
 use mongodb::bson::oid::ObjectId;
 use mongodb::bson::Document;
 use mongodb::options::{
-    AggregateOptions, CountOptions, FindOneOptions, InsertOneOptions, UpdateModifications,
-    UpdateOptions,
+    AggregateOptions, CountOptions, DeleteOptions, FindOneOptions, InsertOneOptions,
+    UpdateModifications, UpdateOptions,
 };
 use mongodb::{Collection, Cursor};
 use serde::{Deserialize, Serialize};
@@ -24,7 +26,23 @@ pub struct UserRepoInfo {
     #[serde(with = "uuid_1_as_binary")]
     pub repo_id: Uuid,
     pub operation: UserRepoInfoOperation,
+
+    /// Kept as a BSON date, so ranges compare as dates
+    #[serde(with = "chrono_datetime_as_bson_datetime")]
     pub executed_at: DateTime<Utc>,
+
+    /// Previous owner of the repo, set only for transfers
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_user_id: Option<ObjectId>,
+
+    /// Set when the record is restored from an archive, it expires again a full
+    /// retention period after that
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "chrono_datetime_as_bson_datetime_optional"
+    )]
+    pub restored_at: Option<DateTime<Utc>>,
 }
 
 impl UserRepoInfo {
@@ -36,6 +54,8 @@ impl UserRepoInfo {
             repo_id,
             operation,
             executed_at,
+            from_user_id: None,
+            restored_at: None,
         }
     }
 }
@@ -46,6 +66,39 @@ impl UserRepoInfo {
 pub enum UserRepoInfoOperation {
     CreateLink,
     DeleteLink,
+    Transfer,
+}
+
+/// Link activity within a day or a week
+#[derive(Deserialize, Debug)]
+pub struct LinkActivityStat {
+    pub period: String,
+    pub created: u64,
+    pub deleted: u64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UserActivityStat {
+    pub user_id: ObjectId,
+    pub operations: u64,
+}
+
+/// Repo which was unlinked at least once
+#[derive(Deserialize, Debug)]
+pub struct RepoChurnStat {
+    #[serde(with = "uuid_1_as_binary")]
+    pub repo_id: Uuid,
+    pub created: u64,
+    pub deleted: u64,
+}
+
+/// Links gained and lost by the user, transfers count for both sides
+#[derive(Deserialize, Debug)]
+pub struct UserNetLinksStat {
+    pub user_id: ObjectId,
+    pub created: u64,
+    pub deleted: u64,
+    pub net: i64,
 }
 
 pub struct UserRepoInfoCollection {
@@ -87,8 +140,7 @@ impl MongoCollection<UserRepoInfo> for TestUserRepoInfoCollection {
         mut doc: UserRepoInfo,
         _options: Option<InsertOneOptions>,
     ) -> mongodb::error::Result<ObjectId> {
-        let id = ObjectId::new();
-        doc.id = Some(id);
+        let id = *doc.id.get_or_insert_with(ObjectId::new);
         self.entities.lock().unwrap().push(doc);
         Ok(id)
     }
@@ -119,23 +171,48 @@ impl MongoCollection<UserRepoInfo> for TestUserRepoInfoCollection {
         Ok(entities.len() as u64)
     }
 
-    async fn aggregate_and_collect(
+    async fn aggregate_documents(
         &self,
         pipeline: Vec<Document>,
         _options: Option<AggregateOptions>,
+    ) -> mongodb::error::Result<Vec<Document>> {
+        let docs = self
+            .entities
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|u| mongodb::bson::to_document(u).unwrap())
+            .collect();
+        Ok(utils::aggregation::aggregate_inmemory_collection(
+            docs, &pipeline,
+        ))
+    }
+
+    async fn aggregate_and_collect(
+        &self,
+        pipeline: Vec<Document>,
+        options: Option<AggregateOptions>,
     ) -> mongodb::error::Result<Vec<UserRepoInfo>> {
-        let mut entities = self.entities.lock().unwrap().clone();
-        let r#match = pipeline
-            .first()
-            .and_then(|val| val.get("$match").and_then(|r#match| r#match.as_document()));
-        if let Some(doc) = r#match {
-            match doc.get("user_id") {
-                Some(user_id) => {
-                    entities.retain(|u| u.user_id == user_id.as_object_id().unwrap());
-                }
-                None => panic!("user_id match parameters not implemented"),
-            }
-        };
-        Ok(utils::paginate_inmemory_collection(entities, pipeline))
+        Ok(self
+            .aggregate_documents(pipeline, options)
+            .await?
+            .into_iter()
+            .map(|d| mongodb::bson::from_document(d).unwrap())
+            .collect())
+    }
+
+    async fn delete_many(
+        &self,
+        query: Document,
+        _options: Option<DeleteOptions>,
+    ) -> mongodb::error::Result<u64> {
+        let ids = query
+            .get_document("_id")
+            .and_then(|id| id.get_array("$in"))
+            .expect("Only deletion by ids is implemented");
+        let mut entities = self.entities.lock().unwrap();
+        let len = entities.len();
+        entities.retain(|u| !ids.iter().any(|id| u.id == id.as_object_id()));
+        Ok((len - entities.len()) as u64)
     }
 }
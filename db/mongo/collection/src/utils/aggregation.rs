@@ -0,0 +1,298 @@
+use std::cmp::Ordering;
+
+use bson::{Bson, Document};
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// Runs an aggregation pipeline over documents kept in memory. Supports only the
+/// stages and operators the repositories use, panics on anything else
+pub fn aggregate_inmemory_collection(
+    mut docs: Vec<Document>,
+    pipeline: &[Document],
+) -> Vec<Document> {
+    for stage in pipeline {
+        let (name, spec) = stage.iter().next().expect("Empty pipeline stage");
+        docs = match name.as_str() {
+            "$match" => {
+                let filter = spec.as_document().unwrap();
+                docs.into_iter().filter(|d| matches(d, filter)).collect()
+            }
+            "$group" => group(docs, spec.as_document().unwrap()),
+            "$sort" => {
+                sort(&mut docs, spec.as_document().unwrap());
+                docs
+            }
+            "$skip" => docs.into_iter().skip(as_i64(spec) as usize).collect(),
+            "$limit" => docs.into_iter().take(as_i64(spec) as usize).collect(),
+            "$project" => {
+                let spec = spec.as_document().unwrap();
+                docs.iter().map(|d| project(d, spec)).collect()
+            }
+            "$unwind" => unwind(docs, spec.as_str().unwrap()),
+            _ => panic!("Stage {name} is not implemented"),
+        };
+    }
+    docs
+}
+
+/// Supports equality and `$exists`, `$in`, `$ne`, `$gt`, `$gte`, `$lt`, `$lte` conditions
+/// and top level `$or`. Equality and `$in` match arrays having any of the values like Mongo does
+pub fn matches(doc: &Document, filter: &Document) -> bool {
+    filter.iter().all(|(key, cond)| {
+        if key == "$or" {
+            return cond
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|filter| matches(doc, filter.as_document().unwrap()));
+        }
+        let val = get_path(doc, key);
+        let contains = |expected: &Bson| {
+            val.is_some_and(|val| {
+                val == expected || val.as_array().is_some_and(|items| items.contains(expected))
+            })
+        };
+        match cond {
+            Bson::Document(ops) if is_operator(ops) => ops.iter().all(|(op, arg)| {
+                let cmp = || val.map(|val| compare(val, arg));
+                match op.as_str() {
+                    "$exists" => val.is_some() == arg.as_bool().unwrap(),
+                    "$in" => arg.as_array().unwrap().iter().any(contains),
+                    "$ne" => val != Some(arg),
+                    "$gt" => cmp() == Some(Ordering::Greater),
+                    "$gte" => cmp().is_some_and(Ordering::is_ge),
+                    "$lt" => cmp() == Some(Ordering::Less),
+                    "$lte" => cmp().is_some_and(Ordering::is_le),
+                    _ => panic!("Query operator {op} is not implemented"),
+                }
+            }),
+            _ => contains(cond),
+        }
+    })
+}
+
+fn is_operator(doc: &Document) -> bool {
+    !doc.is_empty() && doc.keys().all(|key| key.starts_with('$'))
+}
+
+fn get_path<'a>(doc: &'a Document, path: &str) -> Option<&'a Bson> {
+    let mut parts = path.split('.');
+    let mut val = doc.get(parts.next()?)?;
+    for part in parts {
+        val = val.as_document()?.get(part)?;
+    }
+    Some(val)
+}
+
+fn group(docs: Vec<Document>, spec: &Document) -> Vec<Document> {
+    let mut groups: Vec<Document> = vec![];
+    for doc in docs {
+        let id = eval(&doc, spec.get("_id").unwrap_or(&Bson::Null));
+        let pos = match groups.iter().position(|g| g.get("_id") == Some(&id)) {
+            Some(pos) => pos,
+            None => {
+                let mut group = Document::new();
+                group.insert("_id", id);
+                groups.push(group);
+                groups.len() - 1
+            }
+        };
+
+        for (field, acc) in spec.iter().filter(|(field, _)| *field != "_id") {
+            let (op, expr) = acc
+                .as_document()
+                .and_then(|acc| acc.iter().next())
+                .expect("Accumulator expected");
+            let val = eval(&doc, expr);
+            let group = &mut groups[pos];
+            match op.as_str() {
+                "$sum" => {
+                    let sum = sum(group.get(field).unwrap_or(&Bson::Int64(0)), &val);
+                    group.insert(field, sum);
+                }
+                _ => panic!("Accumulator {op} is not implemented"),
+            }
+        }
+    }
+    groups
+}
+
+fn sort(docs: &mut [Document], spec: &Document) {
+    docs.sort_by(|a, b| {
+        spec.iter()
+            .map(|(key, direction)| {
+                let a = get_path(a, key).unwrap_or(&Bson::Null);
+                let b = get_path(b, key).unwrap_or(&Bson::Null);
+                match as_i64(direction) < 0 {
+                    true => compare(a, b).reverse(),
+                    false => compare(a, b),
+                }
+            })
+            .find(|ord| ord.is_ne())
+            .unwrap_or(Ordering::Equal)
+    });
+}
+
+fn project(doc: &Document, spec: &Document) -> Document {
+    let mut res = Document::new();
+    let exclude_id = spec.get("_id").is_some_and(|id| !is_truthy(id));
+    if let (false, Some(id)) = (exclude_id, doc.get("_id")) {
+        res.insert("_id", id.clone());
+    }
+    for (field, expr) in spec.iter().filter(|(field, _)| *field != "_id") {
+        match expr {
+            Bson::Int32(1) | Bson::Int64(1) | Bson::Boolean(true) => {
+                if let Some(val) = doc.get(field) {
+                    res.insert(field, val.clone());
+                }
+            }
+            _ => {
+                res.insert(field, eval(doc, expr));
+            }
+        }
+    }
+    res
+}
+
+fn unwind(docs: Vec<Document>, path: &str) -> Vec<Document> {
+    let field = path.trim_start_matches('$');
+    docs.into_iter()
+        .flat_map(|doc| {
+            let items = doc.get_array(field).cloned().unwrap_or_default();
+            items.into_iter().map(move |item| {
+                let mut doc = doc.clone();
+                doc.insert(field, item);
+                doc
+            })
+        })
+        .collect()
+}
+
+fn eval(doc: &Document, expr: &Bson) -> Bson {
+    match expr {
+        Bson::String(path) if path.starts_with('$') => {
+            get_path(doc, &path[1..]).cloned().unwrap_or(Bson::Null)
+        }
+        Bson::Array(items) => Bson::Array(items.iter().map(|item| eval(doc, item)).collect()),
+        Bson::Document(spec) if is_operator(spec) => {
+            let (op, args) = spec.iter().next().unwrap();
+            eval_operator(doc, op, args)
+        }
+        Bson::Document(spec) => Bson::Document(
+            spec.iter()
+                .map(|(key, val)| (key.clone(), eval(doc, val)))
+                .collect(),
+        ),
+        _ => expr.clone(),
+    }
+}
+
+fn eval_operator(doc: &Document, op: &str, args: &Bson) -> Bson {
+    let eval_args = || match eval(doc, args) {
+        Bson::Array(args) => args,
+        arg => vec![arg],
+    };
+    match op {
+        "$eq" => {
+            let args = eval_args();
+            Bson::Boolean(args[0] == args[1])
+        }
+        "$cond" => {
+            let args = args.as_array().unwrap();
+            match is_truthy(&eval(doc, &args[0])) {
+                true => eval(doc, &args[1]),
+                false => eval(doc, &args[2]),
+            }
+        }
+        "$substrCP" => {
+            let args = eval_args();
+            let str = args[0].as_str().unwrap_or_default();
+            let (start, len) = (as_i64(&args[1]) as usize, as_i64(&args[2]) as usize);
+            Bson::String(str.chars().skip(start).take(len).collect())
+        }
+        "$dateFromString" => {
+            let spec = args.as_document().unwrap();
+            match eval(doc, spec.get("dateString").unwrap()) {
+                Bson::String(str) => parse_date(&str)
+                    .map(|date| {
+                        Bson::DateTime(bson::DateTime::from_millis(date.timestamp_millis()))
+                    })
+                    .unwrap_or(Bson::Null),
+                _ => Bson::Null,
+            }
+        }
+        "$dateToString" => {
+            let spec = args.as_document().unwrap();
+            let format = spec.get_str("format").unwrap();
+            match eval(doc, spec.get("date").unwrap()) {
+                Bson::DateTime(date) => DateTime::from_timestamp_millis(date.timestamp_millis())
+                    .map(|date| Bson::String(date.format(format).to_string()))
+                    .unwrap_or(Bson::Null),
+                _ => Bson::Null,
+            }
+        }
+        _ => panic!("Expression operator {op} is not implemented"),
+    }
+}
+
+fn parse_date(str: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(str)
+        .map(|date| date.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(str, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|date| date.and_utc())
+        })
+}
+
+fn compare(a: &Bson, b: &Bson) -> Ordering {
+    match (a, b) {
+        (Bson::String(a), Bson::String(b)) => a.cmp(b),
+        (Bson::DateTime(a), Bson::DateTime(b)) => a.cmp(b),
+        (Bson::ObjectId(a), Bson::ObjectId(b)) => a.bytes().cmp(&b.bytes()),
+        _ => match (as_f64(a), as_f64(b)) {
+            (Some(a), Some(b)) => a.total_cmp(&b),
+            _ => Ordering::Equal,
+        },
+    }
+}
+
+/// Non numeric values are ignored like `$sum` does
+fn sum(acc: &Bson, val: &Bson) -> Bson {
+    match (acc, val) {
+        (Bson::Int32(_) | Bson::Int64(_), Bson::Int32(_) | Bson::Int64(_)) => {
+            Bson::Int64(as_i64(acc) + as_i64(val))
+        }
+        _ => match (as_f64(acc), as_f64(val)) {
+            (Some(acc), Some(val)) => Bson::Double(acc + val),
+            _ => acc.clone(),
+        },
+    }
+}
+
+fn is_truthy(val: &Bson) -> bool {
+    match val {
+        Bson::Boolean(val) => *val,
+        Bson::Null | Bson::Undefined => false,
+        _ => as_f64(val).is_none_or(|val| val != 0.0),
+    }
+}
+
+fn as_i64(val: &Bson) -> i64 {
+    match val {
+        Bson::Int32(val) => *val as i64,
+        Bson::Int64(val) => *val,
+        Bson::Double(val) => *val as i64,
+        _ => panic!("Number expected, got {val}"),
+    }
+}
+
+fn as_f64(val: &Bson) -> Option<f64> {
+    match val {
+        Bson::Int32(val) => Some(*val as f64),
+        Bson::Int64(val) => Some(*val as f64),
+        Bson::Double(val) => Some(*val),
+        _ => None,
+    }
+}
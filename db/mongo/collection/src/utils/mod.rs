@@ -1,5 +1,6 @@
 use bson::Document;
 
+pub mod aggregation;
 #[cfg(test)]
 mod tests;
 
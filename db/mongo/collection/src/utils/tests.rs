@@ -1,5 +1,6 @@
 use bson::doc;
 
+use crate::utils::aggregation::{aggregate_inmemory_collection, matches};
 use crate::utils::paginate_inmemory_collection;
 
 #[test]
@@ -62,3 +63,83 @@ fn paginate_inmemory_collection_out_of_bound_success() {
 
     assert_eq!(paginated_numbers, desired_numbers)
 }
+
+#[test]
+fn aggregate_inmemory_collection_match_and_group_success() {
+    let docs = vec![
+        doc! {"user": "a", "op": "create", "at": "2024-05-01T10:00:00Z"},
+        doc! {"user": "a", "op": "delete", "at": "2024-05-01T12:00:00Z"},
+        doc! {"user": "b", "op": "create", "at": "2024-05-02T10:00:00Z"},
+        doc! {"user": "b", "op": "create", "at": "2024-06-02T10:00:00Z"},
+    ];
+    let pipeline = vec![
+        doc! {"$match": {"at": {"$lt": "2024-06-01"}}},
+        doc! {"$group": {
+            "_id": "$user",
+            "created": {"$sum": {"$cond": [{"$eq": ["$op", "create"]}, 1, 0]}},
+            "total": {"$sum": 1},
+        }},
+        doc! {"$sort": {"_id": -1}},
+    ];
+    let desired_docs = vec![
+        doc! {"_id": "b", "created": 1_i64, "total": 1_i64},
+        doc! {"_id": "a", "created": 1_i64, "total": 2_i64},
+    ];
+
+    let aggregated_docs = aggregate_inmemory_collection(docs, &pipeline);
+
+    assert_eq!(aggregated_docs, desired_docs)
+}
+
+#[test]
+fn aggregate_inmemory_collection_dates_success() {
+    let docs = vec![
+        doc! {"at": "2024-05-05T10:00:00.123Z"},
+        doc! {"at": "2024-05-06T10:00:00Z"},
+    ];
+    let pipeline = vec![doc! {"$project": {
+        "_id": 0,
+        "day": {"$dateToString": {
+            "format": "%Y-%m-%d",
+            "date": {"$dateFromString": {"dateString": {"$substrCP": ["$at", 0, 10]}}},
+        }},
+        "week": {"$dateToString": {
+            "format": "%G-W%V",
+            "date": {"$dateFromString": {"dateString": "$at"}},
+        }},
+    }}];
+    let desired_docs = vec![
+        doc! {"day": "2024-05-05", "week": "2024-W18"},
+        doc! {"day": "2024-05-06", "week": "2024-W19"},
+    ];
+
+    let aggregated_docs = aggregate_inmemory_collection(docs, &pipeline);
+
+    assert_eq!(aggregated_docs, desired_docs)
+}
+
+#[test]
+fn aggregate_inmemory_collection_unwind_success() {
+    let docs = vec![doc! {"_id": 1, "items": [{"delta": 1}, {"delta": -1}]}];
+    let pipeline = vec![
+        doc! {"$unwind": "$items"},
+        doc! {"$group": {"_id": null, "net": {"$sum": "$items.delta"}, "count": {"$sum": 1}}},
+    ];
+    let desired_docs = vec![doc! {"_id": null, "net": 0_i64, "count": 2_i64}];
+
+    let aggregated_docs = aggregate_inmemory_collection(docs, &pipeline);
+
+    assert_eq!(aggregated_docs, desired_docs)
+}
+
+#[test]
+fn matches_array_field_success() {
+    let doc = doc! {"events": ["LinkCreated", "LinkDeleted"]};
+
+    assert!(matches(&doc, &doc! {"events": "LinkDeleted"}));
+    assert!(matches(
+        &doc,
+        &doc! {"events": {"$in": ["RepoCreated", "LinkCreated"]}}
+    ));
+    assert!(!matches(&doc, &doc! {"events": "RepoCreated"}));
+}
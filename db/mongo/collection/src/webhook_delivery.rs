@@ -0,0 +1,177 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::Document;
+use mongodb::options::{
+    AggregateOptions, CountOptions, FindOneOptions, InsertOneOptions, UpdateModifications,
+    UpdateOptions,
+};
+use mongodb::{Collection, Cursor};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::aggregation;
+use crate::webhook_subscription::WebhookEventType;
+use crate::MongoCollection;
+
+/// A single attempt to deliver an event to a subscription. It is stored before
+/// it is made, so scheduled attempts outlive restarts
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WebhookDelivery {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub subscription_id: ObjectId,
+
+    /// Shared by all attempts to deliver the same event
+    pub event_id: ObjectId,
+    pub event_type: WebhookEventType,
+    pub payload: String,
+    pub attempt: u32,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<u16>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub success: bool,
+
+    /// The attempt is made once it is due
+    pub scheduled_at: bson::DateTime,
+
+    /// A claimed attempt is left to its sender until then
+    pub locked_until: Option<bson::DateTime>,
+
+    /// Empty until the attempt is made
+    pub attempted_at: Option<DateTime<Utc>>,
+}
+
+impl WebhookDelivery {
+    /// Only 2xx responses count as delivered
+    pub fn is_success(status_code: Option<u16>) -> bool {
+        status_code.is_some_and(|code| (200..300).contains(&code))
+    }
+}
+
+pub struct WebhookDeliveryCollection {
+    pub collection: Collection<WebhookDelivery>,
+}
+
+impl MongoCollection<WebhookDelivery> for WebhookDeliveryCollection {
+    fn get_collection(&self) -> Option<&Collection<WebhookDelivery>> {
+        Some(&self.collection)
+    }
+}
+
+#[derive(Default)]
+pub struct TestWebhookDeliveryCollection {
+    entities: Arc<Mutex<Vec<WebhookDelivery>>>,
+}
+
+impl TestWebhookDeliveryCollection {
+    fn documents(&self) -> Vec<Document> {
+        self.entities
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|d| mongodb::bson::to_document(d).unwrap())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl MongoCollection<WebhookDelivery> for TestWebhookDeliveryCollection {
+    async fn find_one(
+        &self,
+        filter: Option<Document>,
+        _options: Option<FindOneOptions>,
+    ) -> mongodb::error::Result<Option<WebhookDelivery>> {
+        let filter = filter.unwrap();
+        let id = filter.get("_id");
+        let entities = self.entities.lock().unwrap();
+        if let Some(id) = id {
+            return Ok(entities
+                .iter()
+                .find(|&d| d.id == id.as_object_id())
+                .cloned());
+        }
+        panic!("Not yet implemented logic")
+    }
+
+    async fn insert_one(
+        &self,
+        mut doc: WebhookDelivery,
+        _options: Option<InsertOneOptions>,
+    ) -> mongodb::error::Result<ObjectId> {
+        let id = *doc.id.get_or_insert_with(ObjectId::new);
+        self.entities.lock().unwrap().push(doc);
+        Ok(id)
+    }
+
+    async fn update_one(
+        &self,
+        query: Document,
+        update: UpdateModifications,
+        _options: Option<UpdateOptions>,
+    ) -> mongodb::error::Result<()> {
+        let id = query.get("_id").unwrap().as_object_id();
+        let set = match update {
+            UpdateModifications::Document(doc) => doc.get_document("$set").unwrap().clone(),
+            _ => panic!("Not implemented yet"),
+        };
+        let mut entities = self.entities.lock().unwrap();
+        let Some(delivery) = entities.iter_mut().find(|d| d.id == id) else {
+            return Ok(());
+        };
+
+        let mut doc = mongodb::bson::to_document(delivery).unwrap();
+        doc.extend(set);
+        *delivery = mongodb::bson::from_document(doc).unwrap();
+        Ok(())
+    }
+
+    async fn aggregate(
+        &self,
+        _pipeline: Vec<Document>,
+        _options: Option<AggregateOptions>,
+    ) -> mongodb::error::Result<Cursor<Document>> {
+        panic!("This test collection can't be aggregated")
+    }
+
+    async fn count_documents(
+        &self,
+        filter: Option<Document>,
+        _options: Option<CountOptions>,
+    ) -> mongodb::error::Result<u64> {
+        let filter = filter.unwrap_or_default();
+        Ok(self
+            .documents()
+            .iter()
+            .filter(|d| aggregation::matches(d, &filter))
+            .count() as u64)
+    }
+
+    async fn aggregate_documents(
+        &self,
+        pipeline: Vec<Document>,
+        _options: Option<AggregateOptions>,
+    ) -> mongodb::error::Result<Vec<Document>> {
+        Ok(aggregation::aggregate_inmemory_collection(
+            self.documents(),
+            &pipeline,
+        ))
+    }
+
+    async fn aggregate_and_collect(
+        &self,
+        pipeline: Vec<Document>,
+        options: Option<AggregateOptions>,
+    ) -> mongodb::error::Result<Vec<WebhookDelivery>> {
+        Ok(self
+            .aggregate_documents(pipeline, options)
+            .await?
+            .into_iter()
+            .map(|d| mongodb::bson::from_document(d).unwrap())
+            .collect())
+    }
+}
@@ -0,0 +1,175 @@
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::Document;
+use mongodb::options::{
+    AggregateOptions, CountOptions, DeleteOptions, FindOneOptions, InsertOneOptions,
+    UpdateModifications, UpdateOptions,
+};
+use mongodb::{Collection, Cursor};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::aggregation;
+use crate::MongoCollection;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct WebhookSubscription {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub url: String,
+    pub events: Vec<WebhookEventType>,
+
+    /// Key of the payload signature
+    pub secret: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl WebhookSubscription {
+    pub fn new(url: String, events: Vec<WebhookEventType>, secret: String) -> Self {
+        Self {
+            id: None,
+            url,
+            events,
+            secret,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, Copy, Eq, async_graphql::Enum, utoipa::ToSchema,
+)]
+pub enum WebhookEventType {
+    LinkCreated,
+    LinkDeleted,
+    LinkTransferred,
+    RepoCreated,
+    RepoUpdated,
+    RepoDeleted,
+    UserCreated,
+    UserUpdated,
+    UserDeleted,
+}
+
+pub struct WebhookSubscriptionCollection {
+    pub collection: Collection<WebhookSubscription>,
+}
+
+impl MongoCollection<WebhookSubscription> for WebhookSubscriptionCollection {
+    fn get_collection(&self) -> Option<&Collection<WebhookSubscription>> {
+        Some(&self.collection)
+    }
+}
+
+#[derive(Default)]
+pub struct TestWebhookSubscriptionCollection {
+    entities: Arc<Mutex<Vec<WebhookSubscription>>>,
+}
+
+impl TestWebhookSubscriptionCollection {
+    fn documents(&self) -> Vec<Document> {
+        self.entities
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|s| mongodb::bson::to_document(s).unwrap())
+            .collect()
+    }
+}
+
+#[async_trait]
+impl MongoCollection<WebhookSubscription> for TestWebhookSubscriptionCollection {
+    async fn find_one(
+        &self,
+        filter: Option<Document>,
+        _options: Option<FindOneOptions>,
+    ) -> mongodb::error::Result<Option<WebhookSubscription>> {
+        let filter = filter.unwrap();
+        let id = filter.get("_id");
+        let entities = self.entities.lock().unwrap();
+        if let Some(id) = id {
+            return Ok(entities
+                .iter()
+                .find(|&s| s.id == id.as_object_id())
+                .cloned());
+        }
+        panic!("Not yet implemented logic")
+    }
+
+    async fn insert_one(
+        &self,
+        mut doc: WebhookSubscription,
+        _options: Option<InsertOneOptions>,
+    ) -> mongodb::error::Result<ObjectId> {
+        let id = *doc.id.get_or_insert_with(ObjectId::new);
+        self.entities.lock().unwrap().push(doc);
+        Ok(id)
+    }
+
+    async fn update_one(
+        &self,
+        _query: Document,
+        _update: UpdateModifications,
+        _options: Option<UpdateOptions>,
+    ) -> mongodb::error::Result<()> {
+        panic!("This test collection can't be updated")
+    }
+
+    async fn delete_many(
+        &self,
+        query: Document,
+        _options: Option<DeleteOptions>,
+    ) -> mongodb::error::Result<u64> {
+        let mut entities = self.entities.lock().unwrap();
+        let len = entities.len();
+        entities.retain(|s| !aggregation::matches(&mongodb::bson::to_document(s).unwrap(), &query));
+        Ok((len - entities.len()) as u64)
+    }
+
+    async fn aggregate(
+        &self,
+        _pipeline: Vec<Document>,
+        _options: Option<AggregateOptions>,
+    ) -> mongodb::error::Result<Cursor<Document>> {
+        panic!("This test collection can't be aggregated")
+    }
+
+    async fn count_documents(
+        &self,
+        filter: Option<Document>,
+        _options: Option<CountOptions>,
+    ) -> mongodb::error::Result<u64> {
+        let filter = filter.unwrap_or_default();
+        Ok(self
+            .documents()
+            .iter()
+            .filter(|d| aggregation::matches(d, &filter))
+            .count() as u64)
+    }
+
+    async fn aggregate_documents(
+        &self,
+        pipeline: Vec<Document>,
+        _options: Option<AggregateOptions>,
+    ) -> mongodb::error::Result<Vec<Document>> {
+        Ok(aggregation::aggregate_inmemory_collection(
+            self.documents(),
+            &pipeline,
+        ))
+    }
+
+    async fn aggregate_and_collect(
+        &self,
+        pipeline: Vec<Document>,
+        options: Option<AggregateOptions>,
+    ) -> mongodb::error::Result<Vec<WebhookSubscription>> {
+        Ok(self
+            .aggregate_documents(pipeline, options)
+            .await?
+            .into_iter()
+            .map(|d| mongodb::bson::from_document(d).unwrap())
+            .collect())
+    }
+}
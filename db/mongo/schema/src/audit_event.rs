@@ -0,0 +1,71 @@
+use mongodb::bson::doc;
+use mongodb::options::{
+    CreateCollectionOptions, CreateIndexOptions, ValidationAction, ValidationLevel,
+};
+use mongodb::IndexModel;
+
+use collection::audit_event::AuditEvent;
+
+use crate::Scheme;
+
+pub struct AuditEventScheme {}
+
+impl Scheme for AuditEventScheme {
+    type Entity = AuditEvent;
+
+    fn get_collection_name(&self) -> &'static str {
+        "audit_events"
+    }
+
+    fn get_validation_options(&self) -> CreateCollectionOptions {
+        let validator = doc! {
+            "$jsonSchema": doc! {
+                "bsonType": "object",
+                "title": "Audit event object validation",
+                "required": vec!["entity", "entity_id", "operation", "changes", "executed_at"],
+                "properties": doc! {
+                    "entity": doc! {
+                        "bsonType": "string",
+                        "description": "'entity' must be a string and is required"
+                    },
+                    "entity_id": doc! {
+                        "bsonType": "string",
+                        "description": "'entity_id' must be a string representation of the id and is required"
+                    },
+                    "operation": doc! {
+                        "bsonType": "string",
+                        "description": "'operation' must be a string and is required"
+                    },
+                    "actor": doc! {
+                        "bsonType": "string",
+                        "description": "'actor' must be a string"
+                    },
+                    "changes": doc! {
+                        "bsonType": "array",
+                        "description": "'changes' must be an array of changed fields and is required"
+                    },
+                    "executed_at": doc! {
+                        "bsonType": "string",
+                        "description": "'executed_at' must be an ISO representation of date and is required"
+                    },
+                },
+            },
+        };
+        CreateCollectionOptions::builder()
+            .validator(validator)
+            .validation_action(Some(ValidationAction::Error))
+            .validation_level(Some(ValidationLevel::Moderate))
+            .build()
+    }
+
+    fn get_indexes(&self) -> Vec<(IndexModel, impl Into<Option<CreateIndexOptions>>)> {
+        let entity_index = IndexModel::builder()
+            .keys(doc! {"entity": 1, "entity_id": 1})
+            .build();
+        vec![(entity_index, None)]
+    }
+
+    fn new() -> Self {
+        AuditEventScheme {}
+    }
+}
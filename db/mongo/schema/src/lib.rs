@@ -1,16 +1,28 @@
 use mongodb::options::{CreateCollectionOptions, CreateIndexOptions};
 use mongodb::{Collection, Database, IndexModel};
 
+use collection::audit_event::AuditEvent;
+use collection::outbox_event::OutboxEvent;
 use collection::user::User;
 use collection::user_repo_info::UserRepoInfo;
+use collection::webhook_delivery::WebhookDelivery;
+use collection::webhook_subscription::WebhookSubscription;
 
+use crate::audit_event::AuditEventScheme;
 use crate::error::SchemeResult;
+use crate::outbox_event::OutboxEventScheme;
 use crate::user::UserScheme;
 use crate::user_repo_info::UserRepoInfoScheme;
+use crate::webhook_delivery::WebhookDeliveryScheme;
+use crate::webhook_subscription::WebhookSubscriptionScheme;
 
+mod audit_event;
 pub mod error;
+mod outbox_event;
 pub mod user;
 mod user_repo_info;
+mod webhook_delivery;
+mod webhook_subscription;
 
 pub trait GetScheme<Entity = Self> {
     fn get_scheme() -> impl Scheme<Entity = Entity>;
@@ -28,10 +40,41 @@ impl GetScheme for UserRepoInfo {
     }
 }
 
+impl GetScheme for AuditEvent {
+    fn get_scheme() -> impl Scheme<Entity = Self> {
+        AuditEventScheme {}
+    }
+}
+
+impl GetScheme for OutboxEvent {
+    fn get_scheme() -> impl Scheme<Entity = Self> {
+        OutboxEventScheme {}
+    }
+}
+
+impl GetScheme for WebhookSubscription {
+    fn get_scheme() -> impl Scheme<Entity = Self> {
+        WebhookSubscriptionScheme {}
+    }
+}
+
+impl GetScheme for WebhookDelivery {
+    fn get_scheme() -> impl Scheme<Entity = Self> {
+        WebhookDeliveryScheme {}
+    }
+}
+
 pub async fn get_collection<T: GetScheme>(database: &Database) -> SchemeResult<Collection<T>> {
     T::get_scheme().get_collection(database).await
 }
 
+/// See [`UserRepoInfoScheme::migrate_string_dates`]
+pub async fn migrate_user_repo_info_dates(database: &Database) -> SchemeResult<u64> {
+    UserRepoInfoScheme::new()
+        .migrate_string_dates(database)
+        .await
+}
+
 // Template method pattern implementation
 #[allow(async_fn_in_trait)]
 pub trait Scheme {
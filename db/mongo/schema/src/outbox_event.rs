@@ -0,0 +1,67 @@
+use mongodb::bson::doc;
+use mongodb::options::{
+    CreateCollectionOptions, CreateIndexOptions, ValidationAction, ValidationLevel,
+};
+use mongodb::IndexModel;
+
+use collection::outbox_event::OutboxEvent;
+
+use crate::Scheme;
+
+pub struct OutboxEventScheme {}
+
+impl Scheme for OutboxEventScheme {
+    type Entity = OutboxEvent;
+
+    fn get_collection_name(&self) -> &'static str {
+        "outbox_events"
+    }
+
+    fn get_validation_options(&self) -> CreateCollectionOptions {
+        let validator = doc! {
+            "$jsonSchema": doc! {
+                "bsonType": "object",
+                "title": "Outbox event object validation",
+                "required": vec!["queue", "payload", "status", "attempts", "created_at"],
+                "properties": doc! {
+                    "queue": doc! {
+                        "bsonType": "string",
+                        "description": "'queue' must be a string and is required"
+                    },
+                    "payload": doc! {
+                        "bsonType": "binData",
+                        "description": "'payload' must be a binary message and is required"
+                    },
+                    "status": doc! {
+                        "bsonType": "string",
+                        "description": "'status' must be a string and is required"
+                    },
+                    "attempts": doc! {
+                        "bsonType": ["int", "long"],
+                        "description": "'attempts' must be an integer and is required"
+                    },
+                    "created_at": doc! {
+                        "bsonType": "string",
+                        "description": "'created_at' must be an ISO representation of date and is required"
+                    },
+                },
+            },
+        };
+        CreateCollectionOptions::builder()
+            .validator(validator)
+            .validation_action(Some(ValidationAction::Error))
+            .validation_level(Some(ValidationLevel::Moderate))
+            .build()
+    }
+
+    fn get_indexes(&self) -> Vec<(IndexModel, impl Into<Option<CreateIndexOptions>>)> {
+        let pending_index = IndexModel::builder()
+            .keys(doc! {"queue": 1, "status": 1, "created_at": 1})
+            .build();
+        vec![(pending_index, None)]
+    }
+
+    fn new() -> Self {
+        OutboxEventScheme {}
+    }
+}
@@ -1,12 +1,64 @@
-use mongodb::bson::doc;
-use mongodb::options::{CreateCollectionOptions, ValidationAction, ValidationLevel};
+use std::io;
+
+use mongodb::bson::{doc, DateTime, Document};
+use mongodb::options::{
+    CreateCollectionOptions, CreateIndexOptions, ValidationAction, ValidationLevel,
+};
+use mongodb::{Database, IndexModel};
 
 use collection::user_repo_info::UserRepoInfo;
 
+use crate::error::SchemeResult;
 use crate::Scheme;
 
+/// Fields kept as RFC 3339 strings before they became BSON dates
+const DATE_FIELDS: [&str; 2] = ["executed_at", "restored_at"];
+
 pub struct UserRepoInfoScheme {}
 
+impl UserRepoInfoScheme {
+    /// Moves an existing collection to BSON dates. The validator is replaced
+    /// first, then every record with a string date is rewritten. Returns the
+    /// number of rewritten records, so it is safe to rerun
+    pub async fn migrate_string_dates(&self, db: &Database) -> SchemeResult<u64> {
+        let name = self.get_collection_name();
+        let ops = self.get_validation_options();
+        db.run_command(
+            doc! {
+                "collMod": name,
+                "validator": ops.validator.unwrap_or_default(),
+                "validationLevel": "moderate",
+                "validationAction": "error",
+            },
+            None,
+        )
+        .await?;
+
+        let collection: mongodb::Collection<Document> = db.collection(name);
+        let filter =
+            doc! {"$or": DATE_FIELDS.map(|field| doc! {field: {"$type": "string"}}).to_vec()};
+        let mut cursor = collection.find(filter, None).await?;
+        let mut migrated = 0;
+        while cursor.advance().await? {
+            let record = cursor.deserialize_current()?;
+            let mut dates = Document::new();
+            for field in DATE_FIELDS {
+                if let Ok(date) = record.get_str(field) {
+                    let date = DateTime::parse_rfc3339_str(date).map_err(|err| {
+                        io::Error::new(io::ErrorKind::InvalidData, format!("{field}: {err}"))
+                    })?;
+                    dates.insert(field, date);
+                }
+            }
+            collection
+                .update_one(doc! {"_id": record.get("_id")}, doc! {"$set": dates}, None)
+                .await?;
+            migrated += 1;
+        }
+        Ok(migrated)
+    }
+}
+
 impl Scheme for UserRepoInfoScheme {
     type Entity = UserRepoInfo;
 
@@ -35,8 +87,16 @@ impl Scheme for UserRepoInfoScheme {
                         "description": "'operation' must be a string in (3, 200) character limit and is required"
                     },
                     "executed_at": doc! {
-                        "bsonType": "string",
-                        "description": "'executed_at' must be an ISO representation of date and is required"
+                        "bsonType": "date",
+                        "description": "'executed_at' must be a date and is required"
+                    },
+                    "from_user_id": doc! {
+                        "bsonType": "objectId",
+                        "description": "'from_user_id' must be an object ID of the previous owner"
+                    },
+                    "restored_at": doc! {
+                        "bsonType": "date",
+                        "description": "'restored_at' must be a date"
                     },
                 },
             },
@@ -48,6 +108,12 @@ impl Scheme for UserRepoInfoScheme {
             .build()
     }
 
+    /// Retention job picks the oldest records first
+    fn get_indexes(&self) -> Vec<(IndexModel, impl Into<Option<CreateIndexOptions>>)> {
+        let executed_at_index = IndexModel::builder().keys(doc! {"executed_at": 1}).build();
+        vec![(executed_at_index, None)]
+    }
+
     fn new() -> Self {
         UserRepoInfoScheme {}
     }
@@ -0,0 +1,97 @@
+use mongodb::bson::doc;
+use mongodb::options::{
+    CreateCollectionOptions, CreateIndexOptions, ValidationAction, ValidationLevel,
+};
+use mongodb::IndexModel;
+
+use collection::webhook_delivery::WebhookDelivery;
+
+use crate::Scheme;
+
+pub struct WebhookDeliveryScheme {}
+
+impl Scheme for WebhookDeliveryScheme {
+    type Entity = WebhookDelivery;
+
+    fn get_collection_name(&self) -> &'static str {
+        "webhook_deliveries"
+    }
+
+    fn get_validation_options(&self) -> CreateCollectionOptions {
+        let validator = doc! {
+            "$jsonSchema": doc! {
+                "bsonType": "object",
+                "title": "Webhook delivery object validation",
+                "required": vec![
+                    "subscription_id", "event_id", "event_type", "payload", "attempt", "success",
+                    "scheduled_at",
+                ],
+                "properties": doc! {
+                    "subscription_id": doc! {
+                        "bsonType": "objectId",
+                        "description": "'subscription_id' must be an objectId and is required"
+                    },
+                    "event_id": doc! {
+                        "bsonType": "objectId",
+                        "description": "'event_id' must be an objectId and is required"
+                    },
+                    "event_type": doc! {
+                        "bsonType": "string",
+                        "description": "'event_type' must be a string and is required"
+                    },
+                    "payload": doc! {
+                        "bsonType": "string",
+                        "description": "'payload' must be a JSON string and is required"
+                    },
+                    "attempt": doc! {
+                        "bsonType": ["int", "long"],
+                        "description": "'attempt' must be an integer and is required"
+                    },
+                    "status_code": doc! {
+                        "bsonType": "int",
+                        "description": "'status_code' must be an integer"
+                    },
+                    "error": doc! {
+                        "bsonType": "string",
+                        "description": "'error' must be a string"
+                    },
+                    "success": doc! {
+                        "bsonType": "bool",
+                        "description": "'success' must be a boolean and is required"
+                    },
+                    "scheduled_at": doc! {
+                        "bsonType": "date",
+                        "description": "'scheduled_at' must be a date and is required"
+                    },
+                    "locked_until": doc! {
+                        "bsonType": ["date", "null"],
+                        "description": "'locked_until' must be a date"
+                    },
+                    "attempted_at": doc! {
+                        "bsonType": ["string", "null"],
+                        "description": "'attempted_at' must be an ISO representation of date"
+                    },
+                },
+            },
+        };
+        CreateCollectionOptions::builder()
+            .validator(validator)
+            .validation_action(Some(ValidationAction::Error))
+            .validation_level(Some(ValidationLevel::Moderate))
+            .build()
+    }
+
+    fn get_indexes(&self) -> Vec<(IndexModel, impl Into<Option<CreateIndexOptions>>)> {
+        let subscription_index = IndexModel::builder()
+            .keys(doc! {"subscription_id": 1, "attempted_at": -1})
+            .build();
+        let due_index = IndexModel::builder()
+            .keys(doc! {"attempted_at": 1, "scheduled_at": 1})
+            .build();
+        vec![(subscription_index, None), (due_index, None)]
+    }
+
+    fn new() -> Self {
+        WebhookDeliveryScheme {}
+    }
+}
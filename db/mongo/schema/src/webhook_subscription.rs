@@ -0,0 +1,61 @@
+use mongodb::bson::doc;
+use mongodb::options::{
+    CreateCollectionOptions, CreateIndexOptions, ValidationAction, ValidationLevel,
+};
+use mongodb::IndexModel;
+
+use collection::webhook_subscription::WebhookSubscription;
+
+use crate::Scheme;
+
+pub struct WebhookSubscriptionScheme {}
+
+impl Scheme for WebhookSubscriptionScheme {
+    type Entity = WebhookSubscription;
+
+    fn get_collection_name(&self) -> &'static str {
+        "webhook_subscriptions"
+    }
+
+    fn get_validation_options(&self) -> CreateCollectionOptions {
+        let validator = doc! {
+            "$jsonSchema": doc! {
+                "bsonType": "object",
+                "title": "Webhook subscription object validation",
+                "required": vec!["url", "events", "secret", "created_at"],
+                "properties": doc! {
+                    "url": doc! {
+                        "bsonType": "string",
+                        "description": "'url' must be a string and is required"
+                    },
+                    "events": doc! {
+                        "bsonType": "array",
+                        "description": "'events' must be an array of event types and is required"
+                    },
+                    "secret": doc! {
+                        "bsonType": "string",
+                        "description": "'secret' must be a string and is required"
+                    },
+                    "created_at": doc! {
+                        "bsonType": "string",
+                        "description": "'created_at' must be an ISO representation of date and is required"
+                    },
+                },
+            },
+        };
+        CreateCollectionOptions::builder()
+            .validator(validator)
+            .validation_action(Some(ValidationAction::Error))
+            .validation_level(Some(ValidationLevel::Moderate))
+            .build()
+    }
+
+    fn get_indexes(&self) -> Vec<(IndexModel, impl Into<Option<CreateIndexOptions>>)> {
+        let events_index = IndexModel::builder().keys(doc! {"events": 1}).build();
+        vec![(events_index, None)]
+    }
+
+    fn new() -> Self {
+        WebhookSubscriptionScheme {}
+    }
+}
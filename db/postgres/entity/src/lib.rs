@@ -1,5 +1,6 @@
 pub mod prelude;
 pub mod repository;
 pub mod sea_orm_active_enums;
+pub mod user_repository_link;
 
 pub use sea_orm_active_enums::*;
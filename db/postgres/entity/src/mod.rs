@@ -3,3 +3,4 @@
 pub mod prelude;
 pub mod repository;
 pub mod sea_orm_active_enums;
+pub mod user_repository_link;
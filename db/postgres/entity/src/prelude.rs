@@ -1,3 +1,4 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.14
 
 pub use super::repository::Entity as Repository;
+pub use super::user_repository_link::Entity as UserRepositoryLink;
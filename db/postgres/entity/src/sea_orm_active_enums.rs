@@ -24,3 +24,30 @@ pub enum RepositoryType {
     #[sea_orm(string_value = "PUBLIC")]
     PUBLIC,
 }
+
+#[derive(
+    Debug,
+    Clone,
+    Default,
+    PartialEq,
+    Eq,
+    EnumIter,
+    DeriveActiveEnum,
+    Deserialize,
+    Serialize,
+    Copy,
+    async_graphql::Enum,
+    utoipa::ToSchema,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(None)")]
+pub enum LinkRole {
+    #[default]
+    #[sea_orm(string_value = "OWNER")]
+    Owner,
+
+    #[sea_orm(string_value = "MAINTAINER")]
+    Maintainer,
+
+    #[sea_orm(string_value = "VIEWER")]
+    Viewer,
+}
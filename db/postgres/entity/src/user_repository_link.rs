@@ -0,0 +1,35 @@
+use super::sea_orm_active_enums::LinkRole;
+use sea_orm::entity::prelude::*;
+
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
+#[sea_orm(table_name = "user_repository_link")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub repo_id: Uuid,
+    pub user_id: String,
+    pub version: i16,
+    pub linked_at: DateTimeUtc,
+    pub linked_by: Option<String>,
+    pub role: LinkRole,
+    pub pinned: bool,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::repository::Entity",
+        from = "Column::RepoId",
+        to = "super::repository::Column::Id",
+        on_update = "NoAction",
+        on_delete = "Cascade"
+    )]
+    Repository,
+}
+
+impl Related<super::repository::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Repository.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
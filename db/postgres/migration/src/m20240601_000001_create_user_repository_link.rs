@@ -0,0 +1,88 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(UserRepositoryLink::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(UserRepositoryLink::RepoId)
+                            .uuid()
+                            .not_null()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(UserRepositoryLink::UserId)
+                            .string_len(24)
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(UserRepositoryLink::Version)
+                            .small_integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(UserRepositoryLink::LinkedAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(UserRepositoryLink::LinkedBy).string())
+                    .col(ColumnDef::new(UserRepositoryLink::Role).string().not_null())
+                    .col(
+                        ColumnDef::new(UserRepositoryLink::Pinned)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_user_repository_link_repo_id")
+                            .from(UserRepositoryLink::Table, UserRepositoryLink::RepoId)
+                            .to(Repository::Table, Repository::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_user_repository_link_user_id")
+                    .table(UserRepositoryLink::Table)
+                    .col(UserRepositoryLink::UserId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(UserRepositoryLink::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum UserRepositoryLink {
+    Table,
+    RepoId,
+    UserId,
+    Version,
+    LinkedAt,
+    LinkedBy,
+    Role,
+    Pinned,
+}
+
+#[derive(DeriveIden)]
+enum Repository {
+    Table,
+    Id,
+}
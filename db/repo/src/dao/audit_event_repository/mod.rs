@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{doc, to_bson, Document};
+
+use collection::audit_event::AuditEvent;
+use collection::MongoCollection;
+
+use crate::dao::error::{Entity, RepoError, RepoResult};
+use crate::dao::{AuditEventRepositoryTrait, PersistentRepositoryTrait};
+use dto::audit_event_dto::{AuditEventDto, AuditEventFilterDto, CreateAuditEventDto};
+use dto::DtoList;
+
+#[cfg(test)]
+mod tests;
+
+pub struct AuditEventRepository {
+    collection: Arc<dyn MongoCollection<AuditEvent>>,
+}
+
+impl AuditEventRepository {
+    pub fn new(collection: Arc<dyn MongoCollection<AuditEvent>>) -> Self {
+        Self { collection }
+    }
+
+    fn filter_to_doc(
+        AuditEventFilterDto {
+            entity,
+            entity_id,
+            operation,
+            actor,
+        }: AuditEventFilterDto,
+    ) -> RepoResult<Document> {
+        let mut filter = doc! {};
+        if let Some(entity) = entity {
+            filter.insert("entity", to_bson(&entity).map_err(Self::bson_error)?);
+        }
+        if let Some(entity_id) = entity_id {
+            filter.insert("entity_id", entity_id);
+        }
+        if let Some(operation) = operation {
+            filter.insert("operation", to_bson(&operation).map_err(Self::bson_error)?);
+        }
+        if let Some(actor) = actor {
+            filter.insert("actor", actor);
+        }
+        Ok(filter)
+    }
+
+    fn bson_error(err: mongodb::bson::ser::Error) -> RepoError {
+        RepoError::InternalConcrete(err.to_string())
+    }
+}
+
+#[async_trait]
+impl PersistentRepositoryTrait<CreateAuditEventDto, AuditEventDto, ObjectId>
+    for AuditEventRepository
+{
+    async fn create(&self, dto: CreateAuditEventDto) -> RepoResult<AuditEventDto> {
+        let event = AuditEvent::from(dto);
+        let id = self.collection.insert_one(event, None).await?;
+        Ok(self.get(&id).await?)
+    }
+
+    async fn get(&self, id: &ObjectId) -> RepoResult<AuditEventDto> {
+        let event = self
+            .collection
+            .find_one(Some(doc! {"_id": id}), None)
+            .await?
+            .ok_or(RepoError::NotFoundWithObjectId(*id, Entity::AuditEvent))?;
+        Ok(event.into())
+    }
+
+    async fn list(
+        &self,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> RepoResult<DtoList<AuditEventDto>> {
+        self.list_filtered(AuditEventFilterDto::default(), take, offset)
+            .await
+    }
+}
+
+#[async_trait]
+impl AuditEventRepositoryTrait for AuditEventRepository {
+    async fn list_filtered(
+        &self,
+        filter: AuditEventFilterDto,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> RepoResult<DtoList<AuditEventDto>> {
+        let filter = Self::filter_to_doc(filter)?;
+        let pipeline = vec![doc! {"$match": filter.clone()}];
+
+        let dtos = self
+            .collection
+            .paginate_pipeline_and_collect(pipeline, take, offset, None)
+            .await?
+            .into_iter()
+            .map(|e| e.into())
+            .collect();
+
+        let count = self.collection.count_documents(Some(filter), None).await?;
+        Ok(DtoList::new(dtos, count, take, offset))
+    }
+}
@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use collection::audit_event::{AuditEntity, AuditOperation, TestAuditEventCollection};
+use dto::audit_event_dto::{AuditEventFilterDto, CreateAuditEventDto};
+use dto::repo_dto::CreateUpdateRepoDto;
+
+use crate::dao::{AuditEventRepositoryTrait, PersistentRepositoryTrait};
+use crate::utils::repository::repository_test_helper;
+
+use super::AuditEventRepository;
+
+async fn get_filled_repo() -> AuditEventRepository {
+    let repo = AuditEventRepository::new(Arc::new(TestAuditEventCollection::default()));
+    let create_dto = repository_test_helper::get_create_dto();
+    let events = [
+        (AuditEntity::Repository, "1", AuditOperation::Create, None),
+        (
+            AuditEntity::Repository,
+            "1",
+            AuditOperation::Update,
+            Some("admin"),
+        ),
+        (AuditEntity::User, "2", AuditOperation::Create, None),
+    ];
+    for (entity, entity_id, operation, actor) in events {
+        repo.create(CreateAuditEventDto::new::<CreateUpdateRepoDto>(
+            entity,
+            entity_id,
+            operation,
+            actor.map(String::from),
+            None,
+            Some(&create_dto),
+        ))
+        .await
+        .unwrap();
+    }
+    repo
+}
+
+#[tokio::test]
+async fn create_records_changed_fields() {
+    let repo = AuditEventRepository::new(Arc::new(TestAuditEventCollection::default()));
+    let before = repository_test_helper::get_create_dto();
+    let after = repository_test_helper::get_update_dto();
+
+    let event = repo
+        .create(CreateAuditEventDto::new(
+            AuditEntity::Repository,
+            "1",
+            AuditOperation::Update,
+            Some("admin".to_string()),
+            Some(&before),
+            Some(&after),
+        ))
+        .await
+        .unwrap();
+
+    assert!(event.id.is_some());
+    assert_eq!(event.actor, Some("admin".to_string()));
+    assert!(!event.changes.is_empty());
+    assert!(event
+        .changes
+        .iter()
+        .all(|change| change.before != change.after));
+}
+
+#[tokio::test]
+async fn list_filtered_by_entity_success() {
+    let repo = get_filled_repo().await;
+    let filter = AuditEventFilterDto {
+        entity: Some(AuditEntity::Repository),
+        ..Default::default()
+    };
+
+    let events = repo.list_filtered(filter, None, None).await.unwrap();
+
+    assert_eq!(events.count, 2);
+    assert!(events
+        .dtos
+        .iter()
+        .all(|e| e.entity == AuditEntity::Repository));
+}
+
+#[tokio::test]
+async fn list_filtered_by_entity_and_operation_success() {
+    let repo = get_filled_repo().await;
+    let filter = AuditEventFilterDto {
+        entity: Some(AuditEntity::Repository),
+        entity_id: Some("1".to_string()),
+        operation: Some(AuditOperation::Update),
+        ..Default::default()
+    };
+
+    let events = repo.list_filtered(filter, None, None).await.unwrap();
+
+    assert_eq!(events.count, 1);
+    assert_eq!(events.dtos[0].operation, AuditOperation::Update);
+}
+
+#[tokio::test]
+async fn list_filtered_by_actor_success() {
+    let repo = get_filled_repo().await;
+    let filter = AuditEventFilterDto {
+        actor: Some("admin".to_string()),
+        ..Default::default()
+    };
+
+    let events = repo.list_filtered(filter, None, None).await.unwrap();
+
+    assert_eq!(events.count, 1);
+    assert_eq!(events.dtos[0].operation, AuditOperation::Update);
+}
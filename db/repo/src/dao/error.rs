@@ -11,7 +11,13 @@ pub enum Entity {
     Repository,
     User,
     UserRepo,
+    UserExport,
     UserRepoInfo,
+    UserRepoInfoArchive,
+    AuditEvent,
+    OutboxEvent,
+    WebhookSubscription,
+    WebhookDelivery,
 }
 
 #[derive(Error, Debug)]
@@ -49,6 +55,9 @@ pub enum RepoError {
     #[error("{} is not yet connected with {}", .0.as_ref(), .1.as_ref())]
     NotYetConnected(Entity, Entity),
 
+    #[error("{} with id {1} is not connected with any {}", .0.as_ref(), .2.as_ref())]
+    NotConnectedWithAny(Entity, String, Entity),
+
     #[error(transparent)]
     ObjectStore(#[from] object_store::Error),
 }
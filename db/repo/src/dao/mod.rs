@@ -2,18 +2,32 @@ use std::fmt::Display;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
 use futures_util::StreamExt;
 use mongodb::bson::oid::ObjectId;
 use object_store::path::Path;
 use object_store::ObjectStore;
 use uuid::Uuid;
 
+use collection::webhook_subscription::WebhookEventType;
 use error::RepoResult;
 
 use crate::dao::error::Entity;
-use crate::dao::error::RepoError::{AlreadyConnected, InternalConcrete, NotYetConnected};
+use crate::dao::error::RepoError::{AlreadyConnected, NotYetConnected};
+use dto::audit_event_dto::{AuditEventDto, AuditEventFilterDto, CreateAuditEventDto};
+use dto::outbox_event_dto::{CreateOutboxEventDto, OutboxEventDto};
 use dto::user_dto::UpdateUserDto;
-use dto::user_repo_info_dto::{CreateUserRepoInfoDto, UserRepoInfoDto};
+use dto::user_export_dto::{UserExportArchiveDto, UserExportDto};
+use dto::user_repo_dto::LinkMetadataDto;
+use dto::user_repo_info_dto::{
+    CreateUserRepoInfoDto, UserRepoInfoArchiveDto, UserRepoInfoDto, UserRepoInfoStatsDto,
+    UserRepoInfoStatsFilterDto,
+};
+use dto::webhook_dto::{
+    CreateWebhookDeliveryDto, CreateWebhookSubscriptionDto, WebhookDeliveryDto,
+    WebhookSubscriptionDto,
+};
 
 use dto::DtoList;
 use dto::{
@@ -21,11 +35,18 @@ use dto::{
     user_dto::{CreateUserDto, UserDto},
 };
 
+pub mod audit_event_repository;
 pub mod error;
+pub mod outbox_event_repository;
 pub mod repo_repository;
+pub mod user_export_repository;
 pub mod user_repo;
+pub mod user_repo_info_archive_repository;
 pub mod user_repo_info_repository;
 pub mod user_repo_repository;
+pub mod user_repo_sql_repository;
+pub mod webhook_delivery_repository;
+pub mod webhook_subscription_repository;
 
 #[async_trait]
 pub trait RepositoryTrait<C, U, R, I>: Send + Sync
@@ -33,20 +54,36 @@ where
     R: async_graphql::OutputType + utoipa::ToSchema,
 {
     async fn create(&self, dto: C) -> RepoResult<R>;
-    async fn update(&self, id: &I, dto: U) -> RepoResult<R>;
+
+    /// Returns the entity as it was before the update and as it is after
+    async fn update(&self, id: &I, dto: U) -> RepoResult<(R, R)>;
     async fn delete(&self, id: &I) -> RepoResult<R>;
     async fn get(&self, id: &I) -> RepoResult<R>;
     async fn list(&self, take: Option<u64>, offset: Option<u64>) -> RepoResult<DtoList<R>>;
 }
 
+#[async_trait]
 pub trait RepoRepositoryTrait:
     RepositoryTrait<CreateUpdateRepoDto, CreateUpdateRepoDto, RepoDto, Uuid>
 {
+    /// Creates the repo under an id picked upfront, so it can be described before the write
+    async fn create_with_id(&self, id: Uuid, dto: CreateUpdateRepoDto) -> RepoResult<RepoDto>;
+
+    /// Fetches the repos in batches of ids. Results follow the order of `ids`,
+    /// missing and deleted repos are reported per id
+    async fn get_many(&self, ids: &[Uuid]) -> RepoResult<Vec<RepoResult<RepoDto>>>;
 }
 
+#[async_trait]
 pub trait UserRepositoryTrait:
     RepositoryTrait<CreateUserDto, UpdateUserDto, UserDto, ObjectId>
 {
+    /// Creates the user under an id picked upfront, so it can be described before the write
+    async fn create_with_id(&self, id: ObjectId, dto: CreateUserDto) -> RepoResult<UserDto>;
+
+    /// Fetches the users in one query. Results follow the order of `ids`,
+    /// missing and deleted users are reported per id
+    async fn get_many(&self, ids: &[ObjectId]) -> RepoResult<Vec<RepoResult<UserDto>>>;
 }
 
 #[async_trait]
@@ -69,12 +106,182 @@ pub trait UserRepoInfoRepositoryTrait:
         take: Option<u64>,
         offset: Option<u64>,
     ) -> RepoResult<DtoList<UserRepoInfoDto>>;
+
+    /// Oldest records executed before the date, restored records are never listed
+    async fn list_expired(
+        &self,
+        before: DateTime<Utc>,
+        take: u64,
+    ) -> RepoResult<Vec<UserRepoInfoDto>>;
+    async fn delete_many(&self, ids: &[ObjectId]) -> RepoResult<u64>;
+
+    /// Up to `take` records inserted after the one with the id, oldest first.
+    /// The records of a user include the transfers away from them
+    async fn list_after(
+        &self,
+        after: ObjectId,
+        user_id: Option<ObjectId>,
+        take: u64,
+    ) -> RepoResult<Vec<UserRepoInfoDto>>;
+
+    /// Inserts an archived record back keeping its id
+    async fn restore(&self, dto: UserRepoInfoDto) -> RepoResult<UserRepoInfoDto>;
+    async fn stats(&self, filter: UserRepoInfoStatsFilterDto) -> RepoResult<UserRepoInfoStatsDto>;
 }
 
-const DELIMITER: &str = "____";
+#[async_trait]
+pub trait UserRepoInfoArchiveRepositoryTrait: Send + Sync {
+    async fn save_batch(&self, records: &[UserRepoInfoDto]) -> RepoResult<UserRepoInfoArchiveDto>;
+    /// Oldest batches first, only the manifests of the page are read
+    async fn list_batches(
+        &self,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> RepoResult<DtoList<UserRepoInfoArchiveDto>>;
+    async fn get_manifest(&self, archive_id: &Uuid) -> RepoResult<UserRepoInfoArchiveDto>;
+    async fn get_batch(&self, archive_id: &Uuid) -> RepoResult<Vec<UserRepoInfoDto>>;
+    async fn delete_batch(&self, archive_id: &Uuid) -> RepoResult<()>;
+}
+
+#[async_trait]
+pub trait AuditEventRepositoryTrait:
+    PersistentRepositoryTrait<CreateAuditEventDto, AuditEventDto, ObjectId>
+{
+    async fn list_filtered(
+        &self,
+        filter: AuditEventFilterDto,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> RepoResult<DtoList<AuditEventDto>>;
+}
 
 #[async_trait]
-pub trait BlobConnRepositoryTrait<K, V>: Send + Sync
+pub trait OutboxEventRepositoryTrait: Send + Sync {
+    /// Stores the event as staged
+    async fn create(&self, dto: CreateOutboxEventDto) -> RepoResult<OutboxEventDto>;
+
+    /// Pending events of the queue and staged or sending ones whose lock expired
+    /// before `now`, oldest first
+    async fn list_relayable(
+        &self,
+        queue: &str,
+        now: DateTime<Utc>,
+        take: u64,
+    ) -> RepoResult<Vec<OutboxEventDto>>;
+
+    /// Marks the event as being sent until `locked_until`, `payload` replaces the staged one
+    async fn claim(
+        &self,
+        id: &ObjectId,
+        payload: Option<Vec<u8>>,
+        locked_until: DateTime<Utc>,
+    ) -> RepoResult<()>;
+    async fn mark_sent(&self, id: &ObjectId) -> RepoResult<()>;
+
+    /// Counts the failed attempt, `exhausted` events are no longer relayed
+    async fn mark_failed(&self, id: &ObjectId, error: String, exhausted: bool) -> RepoResult<()>;
+
+    /// Drops a staged event whose write failed
+    async fn delete(&self, id: &ObjectId) -> RepoResult<()>;
+}
+
+#[async_trait]
+pub trait WebhookSubscriptionRepositoryTrait:
+    PersistentRepositoryTrait<CreateWebhookSubscriptionDto, WebhookSubscriptionDto, ObjectId>
+{
+    async fn delete(&self, id: &ObjectId) -> RepoResult<WebhookSubscriptionDto>;
+
+    /// Subscriptions listening to the event type
+    async fn list_by_event(
+        &self,
+        event_type: WebhookEventType,
+    ) -> RepoResult<Vec<WebhookSubscriptionDto>>;
+}
+
+#[async_trait]
+pub trait WebhookDeliveryRepositoryTrait:
+    PersistentRepositoryTrait<CreateWebhookDeliveryDto, WebhookDeliveryDto, ObjectId>
+{
+    /// Latest attempts first
+    async fn list_by_subscription(
+        &self,
+        subscription_id: &ObjectId,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> RepoResult<DtoList<WebhookDeliveryDto>>;
+
+    /// Scheduled attempts that are due and not claimed, earliest first
+    async fn list_due(&self, now: DateTime<Utc>, take: u64) -> RepoResult<Vec<WebhookDeliveryDto>>;
+
+    /// Leaves the attempt to its sender, it is due again once the lock expires
+    async fn claim(&self, id: &ObjectId, locked_until: DateTime<Utc>) -> RepoResult<()>;
+
+    /// Records the outcome of the attempt
+    async fn complete(
+        &self,
+        id: &ObjectId,
+        status_code: Option<u16>,
+        error: Option<String>,
+    ) -> RepoResult<WebhookDeliveryDto>;
+
+    /// Highest attempt number stored for the event and subscription
+    async fn last_attempt(
+        &self,
+        subscription_id: &ObjectId,
+        event_id: &ObjectId,
+    ) -> RepoResult<u32>;
+}
+
+#[async_trait]
+pub trait UserExportRepositoryTrait: Send + Sync {
+    async fn save_manifest(&self, manifest: &UserExportDto) -> RepoResult<()>;
+    async fn get_manifest(&self, user_id: &ObjectId, export_id: &Uuid)
+        -> RepoResult<UserExportDto>;
+    /// Writes the data together with its manifest as one zip archive
+    async fn save_archive(&self, archive: &UserExportArchiveDto) -> RepoResult<()>;
+    async fn get_archive(&self, user_id: &ObjectId, export_id: &Uuid) -> RepoResult<Bytes>;
+}
+
+#[async_trait]
+pub trait ConnRepositoryTrait<K, V>: Send + Sync
+where
+    K: Display + Send + Sync,
+    V: Display + Send + Sync,
+{
+    /// Returns the requested page of connected values together with the total count
+    async fn list_pairs(
+        &self,
+        key_id: &K,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> RepoResult<(Vec<V>, u64)>;
+    async fn get_key(&self, val_id: &V) -> RepoResult<K>;
+    async fn list_all_pairs(&self) -> RepoResult<Vec<(K, V)>>;
+    async fn add_pair(&self, key_id: &K, val_id: &V) -> RepoResult<()>;
+    async fn delete_pair(&self, key_id: &K, val_id: &V) -> RepoResult<()>;
+
+    /// Moves the value from one key to another, the applied steps are reverted on failure
+    async fn transfer_pair(&self, from_key_id: &K, to_key_id: &K, val_id: &V) -> RepoResult<()>;
+
+    async fn add_pairs(&self, key_id: &K, val_ids: Vec<&V>) -> Vec<RepoResult<()>> {
+        let mut results = vec![];
+        for val_id in val_ids {
+            results.push(self.add_pair(key_id, val_id).await);
+        }
+        results
+    }
+
+    async fn delete_pairs(&self, key_id: &K, val_ids: Vec<&V>) -> Vec<RepoResult<()>> {
+        let mut results = vec![];
+        for val_id in val_ids {
+            results.push(self.delete_pair(key_id, val_id).await);
+        }
+        results
+    }
+}
+
+#[async_trait]
+pub trait BlobConnRepositoryTrait<K, V>: ConnRepositoryTrait<K, V>
 where
     K: Display + Send + Sync,
     V: Display + Send + Sync,
@@ -83,7 +290,7 @@ where
     where
         Self: Sized,
     {
-        let path = Path::from(format!("{key_id}{DELIMITER}{val_id}"));
+        let path = self.get_key_path(key_id, val_id);
         match self.store().head(&path).await {
             Ok(_) => Err(AlreadyConnected(Self::key_entity(), Self::val_entity())),
             Err(_) => Ok(()),
@@ -105,17 +312,12 @@ where
         Self: Sized,
     {
         let store = self.store();
-        for item in store.list(None).collect::<Vec<_>>().await {
-            let location = item?.location;
-            let filename = location
-                .filename()
-                .ok_or(InternalConcrete(format!("Wrong location: {location}")))?;
-            if filename.contains(&val_id.to_string()) {
-                return Ok(());
-            }
+        let prefix = self.get_val_prefix(val_id);
+        let mut stream = store.list(Some(&prefix));
+        match stream.next().await {
+            Some(item) => item.map(|_| ()).map_err(|err| err.into()),
+            None => Err(NotYetConnected(Self::key_entity(), Self::val_entity())),
         }
-
-        Err(NotYetConnected(Self::key_entity(), Self::val_entity()))
     }
 
     async fn is_val_not_connected(&self, val_id: &V) -> RepoResult<()>
@@ -128,21 +330,41 @@ where
         }
     }
 
-    fn get_file_name(&self, key_id: &K, val_id: &V) -> String {
-        format!("{key_id}{DELIMITER}{val_id}")
+    fn get_key_prefix(&self, key_id: &K) -> Path
+    where
+        Self: Sized,
+    {
+        Path::from(format!("{}/{key_id}", Self::key_prefix()))
     }
-    async fn list_pairs(&self, key_id: &K) -> RepoResult<Vec<V>>;
-    async fn add_pair(&self, key_id: &K, val_id: &V) -> RepoResult<()>;
-    async fn delete_pair(&self, key_id: &K, val_id: &V) -> RepoResult<()>;
 
-    async fn add_pairs(&self, key_id: &K, val_ids: Vec<&V>) -> RepoResult<()> {
-        for val_id in val_ids {
-            self.add_pair(key_id, val_id).await?
-        }
-        Ok(())
+    fn get_val_prefix(&self, val_id: &V) -> Path
+    where
+        Self: Sized,
+    {
+        Path::from(format!("{}/{val_id}", Self::val_prefix()))
+    }
+
+    fn get_key_path(&self, key_id: &K, val_id: &V) -> Path
+    where
+        Self: Sized,
+    {
+        self.get_key_prefix(key_id).child(val_id.to_string())
+    }
+
+    fn get_val_path(&self, key_id: &K, val_id: &V) -> Path
+    where
+        Self: Sized,
+    {
+        self.get_val_prefix(val_id).child(key_id.to_string())
     }
 
     fn store(&self) -> Arc<dyn ObjectStore>;
+    fn key_prefix() -> &'static str
+    where
+        Self: Sized;
+    fn val_prefix() -> &'static str
+    where
+        Self: Sized;
     fn key_entity() -> Entity
     where
         Self: Sized;
@@ -151,4 +373,35 @@ where
         Self: Sized;
 }
 
-pub trait UserRepoRepositoryTrait: BlobConnRepositoryTrait<ObjectId, Uuid> {}
+#[async_trait]
+pub trait UserRepoRepositoryTrait: ConnRepositoryTrait<ObjectId, Uuid> {
+    async fn add_pair_with_metadata(
+        &self,
+        user_id: &ObjectId,
+        repo_id: &Uuid,
+        metadata: &LinkMetadataDto,
+    ) -> RepoResult<()>;
+
+    /// Same as `list_pairs`, but also returns the metadata of every link
+    async fn list_pairs_with_metadata(
+        &self,
+        user_id: &ObjectId,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> RepoResult<(Vec<(Uuid, LinkMetadataDto)>, u64)>;
+
+    async fn get_metadata(&self, user_id: &ObjectId, repo_id: &Uuid)
+        -> RepoResult<LinkMetadataDto>;
+
+    async fn update_metadata(
+        &self,
+        user_id: &ObjectId,
+        repo_id: &Uuid,
+        metadata: &LinkMetadataDto,
+    ) -> RepoResult<()>;
+
+    /// Reverse index entries left without the link they mirror, as `(user_id, repo_id)`
+    async fn list_orphaned_pairs(&self) -> RepoResult<Vec<(ObjectId, Uuid)>>;
+
+    async fn delete_orphaned_pair(&self, user_id: &ObjectId, repo_id: &Uuid) -> RepoResult<()>;
+}
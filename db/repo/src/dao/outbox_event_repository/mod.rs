@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{doc, to_bson, Binary};
+use mongodb::options::UpdateModifications;
+
+use collection::outbox_event::{OutboxEvent, OutboxStatus};
+use collection::MongoCollection;
+
+use crate::dao::error::{Entity, RepoError, RepoResult};
+use crate::dao::OutboxEventRepositoryTrait;
+use dto::outbox_event_dto::{CreateOutboxEventDto, OutboxEventDto};
+
+#[cfg(test)]
+mod tests;
+
+pub struct OutboxEventRepository {
+    collection: Arc<dyn MongoCollection<OutboxEvent>>,
+}
+
+impl OutboxEventRepository {
+    pub fn new(collection: Arc<dyn MongoCollection<OutboxEvent>>) -> Self {
+        Self { collection }
+    }
+
+    fn status_to_bson(status: OutboxStatus) -> RepoResult<mongodb::bson::Bson> {
+        to_bson(&status).map_err(|err| RepoError::InternalConcrete(err.to_string()))
+    }
+
+    fn date_to_bson(date: DateTime<Utc>) -> mongodb::bson::DateTime {
+        mongodb::bson::DateTime::from_millis(date.timestamp_millis())
+    }
+
+    async fn update(&self, id: &ObjectId, update: mongodb::bson::Document) -> RepoResult<()> {
+        self.collection
+            .update_one(
+                doc! {"_id": id},
+                UpdateModifications::Document(update),
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl OutboxEventRepositoryTrait for OutboxEventRepository {
+    async fn create(&self, dto: CreateOutboxEventDto) -> RepoResult<OutboxEventDto> {
+        let event = OutboxEvent::from(dto);
+        let id = self.collection.insert_one(event, None).await?;
+        let event = self
+            .collection
+            .find_one(Some(doc! {"_id": id}), None)
+            .await?
+            .ok_or(RepoError::NotFoundWithObjectId(id, Entity::OutboxEvent))?;
+        Ok(event.into())
+    }
+
+    async fn list_relayable(
+        &self,
+        queue: &str,
+        now: DateTime<Utc>,
+        take: u64,
+    ) -> RepoResult<Vec<OutboxEventDto>> {
+        let locked = vec![
+            Self::status_to_bson(OutboxStatus::Staged)?,
+            Self::status_to_bson(OutboxStatus::Sending)?,
+        ];
+        let pipeline = vec![
+            doc! {"$match": {
+                "queue": queue,
+                "$or": [
+                    {"status": Self::status_to_bson(OutboxStatus::Pending)?},
+                    {"status": {"$in": locked}, "locked_until": {"$lt": Self::date_to_bson(now)}},
+                ],
+            }},
+            doc! {"$sort": {"created_at": 1}},
+        ];
+        Ok(self
+            .collection
+            .paginate_pipeline_and_collect(pipeline, Some(take), None, None)
+            .await?
+            .into_iter()
+            .map(|e| e.into())
+            .collect())
+    }
+
+    async fn claim(
+        &self,
+        id: &ObjectId,
+        payload: Option<Vec<u8>>,
+        locked_until: DateTime<Utc>,
+    ) -> RepoResult<()> {
+        let mut set = doc! {
+            "status": Self::status_to_bson(OutboxStatus::Sending)?,
+            "locked_until": Self::date_to_bson(locked_until),
+        };
+        if let Some(bytes) = payload {
+            let payload = Binary {
+                subtype: mongodb::bson::spec::BinarySubtype::Generic,
+                bytes,
+            };
+            set.insert("payload", payload);
+        }
+        self.update(id, doc! {"$set": set}).await
+    }
+
+    async fn mark_sent(&self, id: &ObjectId) -> RepoResult<()> {
+        let update = doc! {
+            "$set": {
+                "status": Self::status_to_bson(OutboxStatus::Sent)?,
+                "sent_at": Utc::now().to_rfc3339(),
+            },
+            "$inc": {"attempts": 1},
+        };
+        self.update(id, update).await
+    }
+
+    async fn mark_failed(&self, id: &ObjectId, error: String, exhausted: bool) -> RepoResult<()> {
+        let status = match exhausted {
+            true => OutboxStatus::Failed,
+            false => OutboxStatus::Pending,
+        };
+        let update = doc! {
+            "$set": {
+                "status": Self::status_to_bson(status)?,
+                "last_error": error,
+            },
+            "$inc": {"attempts": 1},
+        };
+        self.update(id, update).await
+    }
+
+    async fn delete(&self, id: &ObjectId) -> RepoResult<()> {
+        self.collection.delete_many(doc! {"_id": id}, None).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+
+use collection::outbox_event::{OutboxStatus, TestOutboxEventCollection};
+use dto::outbox_event_dto::CreateOutboxEventDto;
+
+use crate::dao::OutboxEventRepositoryTrait;
+
+use super::OutboxEventRepository;
+
+fn staged(queue: &str, payload: &[u8]) -> CreateOutboxEventDto {
+    CreateOutboxEventDto::new(queue, payload.to_vec(), Utc::now() + Duration::minutes(1))
+}
+
+/// Every event is committed and waits for the relay
+async fn get_filled_repo() -> OutboxEventRepository {
+    let repo = OutboxEventRepository::new(Arc::new(TestOutboxEventCollection::default()));
+    for (queue, payload) in [("first", b"1"), ("first", b"2"), ("second", b"3")] {
+        let event = repo.create(staged(queue, payload)).await.unwrap();
+        repo.mark_failed(&event.id, "down".to_string(), false)
+            .await
+            .unwrap();
+    }
+    repo
+}
+
+#[tokio::test]
+async fn create_is_staged() {
+    let repo = OutboxEventRepository::new(Arc::new(TestOutboxEventCollection::default()));
+
+    let event = repo.create(staged("first", b"payload")).await.unwrap();
+    let relayable = repo.list_relayable("first", Utc::now(), 10).await.unwrap();
+
+    assert_eq!(event.status, OutboxStatus::Staged);
+    assert_eq!(event.payload, b"payload".to_vec());
+    assert_eq!(event.attempts, 0);
+    assert!(relayable.is_empty());
+}
+
+#[tokio::test]
+async fn list_relayable_by_queue() {
+    let repo = get_filled_repo().await;
+
+    let events = repo.list_relayable("first", Utc::now(), 10).await.unwrap();
+    let limited = repo.list_relayable("first", Utc::now(), 1).await.unwrap();
+
+    assert_eq!(events.len(), 2);
+    assert!(events.iter().all(|e| e.queue == "first"));
+    assert_eq!(limited.len(), 1);
+    assert_eq!(limited[0].payload, b"1".to_vec());
+}
+
+#[tokio::test]
+async fn expired_locks_are_relayable() {
+    let repo = OutboxEventRepository::new(Arc::new(TestOutboxEventCollection::default()));
+    let event = repo.create(staged("first", b"staged")).await.unwrap();
+    let later = Utc::now() + Duration::minutes(2);
+
+    assert_eq!(
+        repo.list_relayable("first", later, 10).await.unwrap().len(),
+        1
+    );
+
+    repo.claim(
+        &event.id,
+        Some(b"final".to_vec()),
+        later + Duration::minutes(1),
+    )
+    .await
+    .unwrap();
+    assert!(repo
+        .list_relayable("first", later, 10)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let events = repo
+        .list_relayable("first", later + Duration::minutes(2), 10)
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].status, OutboxStatus::Sending);
+    assert_eq!(events[0].payload, b"final".to_vec());
+}
+
+#[tokio::test]
+async fn mark_sent_removes_from_relayable() {
+    let repo = get_filled_repo().await;
+    let event = repo
+        .list_relayable("first", Utc::now(), 1)
+        .await
+        .unwrap()
+        .remove(0);
+
+    repo.mark_sent(&event.id).await.unwrap();
+    let events = repo.list_relayable("first", Utc::now(), 10).await.unwrap();
+
+    assert_eq!(events.len(), 1);
+    assert_ne!(events[0].id, event.id);
+}
+
+#[tokio::test]
+async fn mark_failed_keeps_relayable_until_exhausted() {
+    let repo = get_filled_repo().await;
+    let event = repo
+        .list_relayable("second", Utc::now(), 1)
+        .await
+        .unwrap()
+        .remove(0);
+
+    repo.mark_failed(&event.id, "down".to_string(), true)
+        .await
+        .unwrap();
+    let events = repo.list_relayable("second", Utc::now(), 1).await.unwrap();
+
+    assert_eq!(event.attempts, 1);
+    assert_eq!(event.last_error, Some("down".to_string()));
+    assert!(events.is_empty());
+}
+
+#[tokio::test]
+async fn delete_drops_staged_event() {
+    let repo = OutboxEventRepository::new(Arc::new(TestOutboxEventCollection::default()));
+    let event = repo.create(staged("first", b"staged")).await.unwrap();
+
+    repo.delete(&event.id).await.unwrap();
+    let later = Utc::now() + Duration::minutes(2);
+
+    assert!(repo
+        .list_relayable("first", later, 10)
+        .await
+        .unwrap()
+        .is_empty());
+}
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 use sea_orm::{
     ActiveModelTrait, ActiveValue::Set, ColumnTrait, DbConn, EntityTrait, PaginatorTrait,
@@ -16,6 +18,9 @@ use super::{CreateUpdateRepoDto, RepoDto, RepoRepositoryTrait};
 #[cfg(test)]
 mod tests;
 
+/// Ids looked up by one query, keeps the `IN` list within the bind parameter limit
+const IDS_PER_QUERY: usize = 1000;
+
 pub struct RepoRepository {
     conn: DbConn,
 }
@@ -47,13 +52,11 @@ impl RepoRepository {
     }
 }
 
-impl RepoRepositoryTrait for RepoRepository {}
-
 #[async_trait]
-impl RepositoryTrait<CreateUpdateRepoDto, CreateUpdateRepoDto, RepoDto, Uuid> for RepoRepository {
-    async fn create(&self, repo_dto: CreateUpdateRepoDto) -> RepoResult<RepoDto> {
+impl RepoRepositoryTrait for RepoRepository {
+    async fn create_with_id(&self, id: Uuid, repo_dto: CreateUpdateRepoDto) -> RepoResult<RepoDto> {
         let repo = repository::ActiveModel {
-            id: Set(Uuid::new_v4()),
+            id: Set(id),
             title: Set(repo_dto.title),
             description: Set(repo_dto.description),
             r#type: Set(repo_dto.repo_type),
@@ -66,8 +69,49 @@ impl RepositoryTrait<CreateUpdateRepoDto, CreateUpdateRepoDto, RepoDto, Uuid> fo
         Ok(repo.into())
     }
 
-    async fn update(&self, id: &Uuid, repo_dto: CreateUpdateRepoDto) -> RepoResult<RepoDto> {
-        let mut repo = self.get_active_repo_model(id).await?;
+    async fn get_many(&self, ids: &[Uuid]) -> RepoResult<Vec<RepoResult<RepoDto>>> {
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut models: HashMap<Uuid, Model> = HashMap::with_capacity(ids.len());
+        for chunk in ids.chunks(IDS_PER_QUERY) {
+            let found = Repository::find()
+                .filter(repository::Column::Id.is_in(chunk.iter().copied()))
+                .all(&self.conn)
+                .await?;
+            models.extend(found.into_iter().map(|model| (model.id, model)));
+        }
+
+        let repos = ids
+            .iter()
+            .map(|id| {
+                let repo = models
+                    .get(id)
+                    .ok_or(RepoError::NotFoundWithUuid(*id, Entity::Repository))?;
+                self.is_repo_deleted(repo)?;
+                Ok(repo.clone().into())
+            })
+            .collect();
+        Ok(repos)
+    }
+}
+
+#[async_trait]
+impl RepositoryTrait<CreateUpdateRepoDto, CreateUpdateRepoDto, RepoDto, Uuid> for RepoRepository {
+    async fn create(&self, repo_dto: CreateUpdateRepoDto) -> RepoResult<RepoDto> {
+        self.create_with_id(Uuid::new_v4(), repo_dto).await
+    }
+
+    async fn update(
+        &self,
+        id: &Uuid,
+        repo_dto: CreateUpdateRepoDto,
+    ) -> RepoResult<(RepoDto, RepoDto)> {
+        let model = self.get_repo_model(id).await?;
+        self.is_repo_deleted(&model)?;
+        let before = RepoDto::from(model.clone());
+        let mut repo: repository::ActiveModel = model.into();
 
         let CreateUpdateRepoDto {
             title,
@@ -80,7 +124,7 @@ impl RepositoryTrait<CreateUpdateRepoDto, CreateUpdateRepoDto, RepoDto, Uuid> fo
         repo.r#type = Set(repo_type);
 
         let repo = repo.update(&self.conn).await?;
-        Ok(repo.into())
+        Ok((before, repo.into()))
     }
 
     async fn delete(&self, id: &Uuid) -> RepoResult<RepoDto> {
@@ -1,11 +1,13 @@
 use sea_orm::DatabaseBackend;
 use serial_test::serial;
+use uuid::Uuid;
 
+use crate::dao::error::RepoError;
 use crate::dao::RepositoryTrait;
 use crate::utils::repository::repository_test_helper;
 
-use super::RepoRepository;
 use super::RepoRepositoryTrait;
+use super::{RepoRepository, IDS_PER_QUERY};
 
 pub async fn get_stub_repo() -> impl RepoRepositoryTrait {
     repository_test_helper::get_model();
@@ -58,3 +60,49 @@ async fn it_should_not_delete_deleted_repo() {
         "Status code of deletion non-existing repository is not equal to the desired"
     );
 }
+
+#[tokio::test]
+#[serial]
+async fn it_should_get_many_in_requested_order() {
+    let alive_repo = repository_test_helper::get_model();
+    let mut deleted_repo = repository_test_helper::get_model();
+    deleted_repo.deleted = true;
+    let missing_id = Uuid::new_v4();
+
+    let conn = sea_orm::MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[deleted_repo.clone(), alive_repo.clone()]])
+        .into_connection();
+    let repository_repo = RepoRepository::new(conn);
+
+    let repos = repository_repo
+        .get_many(&[alive_repo.id, missing_id, deleted_repo.id])
+        .await
+        .unwrap();
+
+    assert_eq!(repos.len(), 3);
+    assert_eq!(repos[0].as_ref().unwrap().id, alive_repo.id);
+    assert!(matches!(repos[1], Err(RepoError::NotFoundWithUuid(..))));
+    assert!(matches!(repos[2], Err(RepoError::DeletedWithUuid(..))));
+}
+
+#[tokio::test]
+#[serial]
+async fn it_should_get_many_in_chunks() {
+    let first_repo = repository_test_helper::get_model();
+    let last_repo = repository_test_helper::get_model();
+    let mut ids: Vec<Uuid> = (0..IDS_PER_QUERY).map(|_| Uuid::new_v4()).collect();
+    ids[0] = first_repo.id;
+    ids.push(last_repo.id);
+
+    let conn = sea_orm::MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results([[first_repo.clone()], [last_repo.clone()]])
+        .into_connection();
+    let repository_repo = RepoRepository::new(conn);
+
+    let repos = repository_repo.get_many(&ids).await.unwrap();
+
+    assert_eq!(repos.len(), IDS_PER_QUERY + 1);
+    assert_eq!(repos[0].as_ref().unwrap().id, first_repo.id);
+    assert_eq!(repos[IDS_PER_QUERY].as_ref().unwrap().id, last_repo.id);
+    assert!(repos[1..IDS_PER_QUERY].iter().all(|repo| repo.is_err()));
+}
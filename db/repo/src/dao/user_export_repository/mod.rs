@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod tests;
+
+use std::io::{Cursor, Write};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use mongodb::bson::oid::ObjectId;
+use object_store::path::Path;
+use object_store::ObjectStore;
+use serde::Serialize;
+use uuid::Uuid;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use dto::user_export_dto::{UserExportArchiveDto, UserExportDto};
+
+use crate::dao::error::RepoError::{InternalConcrete, NotFoundWithUuid};
+use crate::dao::error::{Entity, RepoResult};
+use crate::dao::UserExportRepositoryTrait;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const ARCHIVE_FILE: &str = "export.zip";
+const USER_FILE: &str = "user.json";
+const REPOS_FILE: &str = "repos.json";
+const USER_REPO_INFOS_FILE: &str = "user_repo_infos.json";
+
+/// Keeps every export as `{user_id}/{export_id}/`, with the manifest next to
+/// the zip archive. The store is expected to be an export root of its own,
+/// nothing else is listed or written under it
+#[derive(Clone)]
+pub struct UserExportRepository {
+    store: Arc<dyn ObjectStore>,
+}
+
+impl UserExportRepository {
+    pub fn new(store: Arc<dyn ObjectStore>) -> Self {
+        Self { store }
+    }
+
+    fn get_path(user_id: &ObjectId, export_id: &Uuid, file: &str) -> Path {
+        Path::from(format!("{user_id}/{export_id}/{file}"))
+    }
+
+    fn to_json<T: Serialize>(value: &T) -> RepoResult<Vec<u8>> {
+        serde_json::to_vec_pretty(value).map_err(|err| InternalConcrete(err.to_string()))
+    }
+
+    /// The archive carries its own copy of the manifest, so it reads on its own
+    fn zip_archive(archive: &UserExportArchiveDto) -> RepoResult<Bytes> {
+        let files = [
+            (MANIFEST_FILE, Self::to_json(&archive.manifest)?),
+            (USER_FILE, Self::to_json(&archive.user)?),
+            (REPOS_FILE, Self::to_json(&archive.repos)?),
+            (
+                USER_REPO_INFOS_FILE,
+                Self::to_json(&archive.user_repo_infos)?,
+            ),
+        ];
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut zip = ZipWriter::new(Cursor::new(vec![]));
+        for (name, bytes) in files {
+            zip.start_file(name, options)
+                .map_err(|err| InternalConcrete(err.to_string()))?;
+            zip.write_all(&bytes)
+                .map_err(|err| InternalConcrete(err.to_string()))?;
+        }
+        let cursor = zip
+            .finish()
+            .map_err(|err| InternalConcrete(err.to_string()))?;
+        Ok(Bytes::from(cursor.into_inner()))
+    }
+
+    async fn get_file(
+        &self,
+        user_id: &ObjectId,
+        export_id: &Uuid,
+        file: &str,
+    ) -> RepoResult<Bytes> {
+        let path = Self::get_path(user_id, export_id, file);
+        match self.store.get(&path).await {
+            Ok(res) => Ok(res.bytes().await?),
+            Err(object_store::Error::NotFound { .. }) => {
+                Err(NotFoundWithUuid(*export_id, Entity::UserExport))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl UserExportRepositoryTrait for UserExportRepository {
+    async fn save_manifest(&self, manifest: &UserExportDto) -> RepoResult<()> {
+        let path = Self::get_path(&manifest.user_id, &manifest.id, MANIFEST_FILE);
+        let bytes =
+            serde_json::to_vec(manifest).map_err(|err| InternalConcrete(err.to_string()))?;
+        self.store.put(&path, Bytes::from(bytes)).await?;
+        Ok(())
+    }
+
+    async fn get_manifest(
+        &self,
+        user_id: &ObjectId,
+        export_id: &Uuid,
+    ) -> RepoResult<UserExportDto> {
+        let bytes = self.get_file(user_id, export_id, MANIFEST_FILE).await?;
+        serde_json::from_slice(&bytes).map_err(|err| InternalConcrete(err.to_string()))
+    }
+
+    async fn save_archive(&self, archive: &UserExportArchiveDto) -> RepoResult<()> {
+        let UserExportDto { user_id, id, .. } = &archive.manifest;
+        let path = Self::get_path(user_id, id, ARCHIVE_FILE);
+        self.store.put(&path, Self::zip_archive(archive)?).await?;
+        Ok(())
+    }
+
+    async fn get_archive(&self, user_id: &ObjectId, export_id: &Uuid) -> RepoResult<Bytes> {
+        self.get_file(user_id, export_id, ARCHIVE_FILE).await
+    }
+}
@@ -0,0 +1,61 @@
+use std::io::Cursor;
+
+use uuid::Uuid;
+use zip::ZipArchive;
+
+use dto::user_export_dto::{UserExportDto, UserExportStatus};
+
+use crate::dao::UserExportRepositoryTrait;
+use crate::utils::user_export::user_export_test_helper;
+
+#[tokio::test]
+async fn save_and_get_manifest_success() {
+    let repo = user_export_test_helper::get_mock_repo();
+    let manifest = user_export_test_helper::get_manifest();
+
+    repo.save_manifest(&manifest).await.unwrap();
+    let res = repo.get_manifest(&manifest.user_id, &manifest.id).await;
+
+    assert!(res.is_ok());
+    assert_eq!(res.unwrap(), manifest);
+}
+
+#[tokio::test]
+async fn get_nonexistent_manifest_failure() {
+    let repo = user_export_test_helper::get_mock_repo();
+    let manifest = user_export_test_helper::get_manifest();
+
+    let res = repo.get_manifest(&manifest.user_id, &Uuid::new_v4()).await;
+
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn save_and_get_archive_success() {
+    let repo = user_export_test_helper::get_mock_repo();
+    let manifest = user_export_test_helper::get_manifest();
+    let archive = user_export_test_helper::get_archive(manifest);
+
+    repo.save_archive(&archive).await.unwrap();
+    let res = repo
+        .get_archive(&archive.manifest.user_id, &archive.manifest.id)
+        .await;
+
+    assert!(res.is_ok());
+    let mut zip = ZipArchive::new(Cursor::new(res.unwrap())).unwrap();
+    let mut names: Vec<&str> = zip.file_names().collect();
+    names.sort();
+    assert_eq!(
+        names,
+        [
+            "manifest.json",
+            "repos.json",
+            "user.json",
+            "user_repo_infos.json"
+        ]
+    );
+    let manifest: UserExportDto =
+        serde_json::from_reader(zip.by_name("manifest.json").unwrap()).unwrap();
+    assert_eq!(manifest, archive.manifest);
+    assert_eq!(manifest.status, UserExportStatus::Completed);
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use async_trait::async_trait;
@@ -12,7 +13,7 @@ use dto::{user_dto::UpdateUserDto, DtoList};
 
 use super::error::{
     Entity,
-    RepoError::{DeletedWithObjectId, Internal, Uniqueness},
+    RepoError::{DeletedWithObjectId, Internal, NotFoundWithObjectId, Uniqueness},
     RepoResult,
 };
 use super::{CreateUserDto, UserDto};
@@ -32,7 +33,48 @@ impl UserRepository {
     }
 }
 
-impl UserRepositoryTrait for UserRepository {}
+#[async_trait]
+impl UserRepositoryTrait for UserRepository {
+    async fn create_with_id(&self, id: ObjectId, dto: CreateUserDto) -> RepoResult<UserDto> {
+        self.validate_create_uniqueness(&dto).await?;
+        let user = User {
+            id: Some(id),
+            ..User::from(dto)
+        };
+        let id = self.collection.insert_one(user, None).await?;
+        let user = self.get_user(doc! {"_id": id}).await?;
+        Ok(user.into())
+    }
+
+    async fn get_many(&self, ids: &[ObjectId]) -> RepoResult<Vec<RepoResult<UserDto>>> {
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let pipeline = vec![doc! {"$match": {"_id": {"$in": ids}}}];
+        let users: HashMap<ObjectId, User> = self
+            .collection
+            .aggregate_and_collect(pipeline, None)
+            .await?
+            .into_iter()
+            .filter_map(|user| Some((user.id?, user)))
+            .collect();
+
+        let users = ids
+            .iter()
+            .map(|id| {
+                let user = users
+                    .get(id)
+                    .ok_or(NotFoundWithObjectId(*id, Entity::User))?;
+                if user.deleted {
+                    Err(DeletedWithObjectId(*id, Entity::User))?
+                }
+                Ok(user.clone().into())
+            })
+            .collect();
+        Ok(users)
+    }
+}
 
 impl UserRepository {
     async fn get_user(&self, document: Document) -> RepoResult<User> {
@@ -93,14 +135,10 @@ impl UserRepository {
 #[async_trait]
 impl RepositoryTrait<CreateUserDto, UpdateUserDto, UserDto, ObjectId> for UserRepository {
     async fn create(&self, dto: CreateUserDto) -> RepoResult<UserDto> {
-        self.validate_create_uniqueness(&dto).await?;
-        let user = User::from(dto);
-        let id = self.collection.insert_one(user, None).await?;
-        let user = self.get_user(doc! {"_id": id}).await?;
-        Ok(user.into())
+        self.create_with_id(ObjectId::new(), dto).await
     }
 
-    async fn update(&self, id: &ObjectId, dto: UpdateUserDto) -> RepoResult<UserDto> {
+    async fn update(&self, id: &ObjectId, dto: UpdateUserDto) -> RepoResult<(UserDto, UserDto)> {
         let user = self.get_user(doc! {"_id": id}).await?;
         self.validate_update_uniqueness(&user, &dto).await?;
         let UpdateUserDto {
@@ -120,8 +158,8 @@ impl RepositoryTrait<CreateUserDto, UpdateUserDto, UserDto, ObjectId> for UserRe
         self.collection
             .update_one(filter, update.into(), None)
             .await?;
-        let user = self.get_user(doc! {"_id": id}).await?;
-        Ok(user.into())
+        let updated = self.get_user(doc! {"_id": id}).await?;
+        Ok((user.into(), updated.into()))
     }
 
     async fn delete(&self, id: &ObjectId) -> RepoResult<UserDto> {
@@ -1,10 +1,11 @@
-use crate::dao::error::RepoResult;
+use crate::dao::error::{RepoError, RepoResult};
 use crate::dao::user_repo::UserRepository;
-use crate::dao::RepositoryTrait;
+use crate::dao::{RepositoryTrait, UserRepositoryTrait};
 use crate::utils::user::user_test_helper;
 use collection::user::User;
 use dto::user_dto::UserDto;
 use mongodb::bson::doc;
+use mongodb::bson::oid::ObjectId;
 
 #[tokio::test]
 async fn list_all_not_deleted_users_success() {
@@ -47,6 +48,25 @@ async fn get_deleted_user_failure() {
     assert!(doc.is_err())
 }
 
+#[tokio::test]
+async fn get_many_in_requested_order() {
+    let repo = user_test_helper::get_mock_repo();
+    let alive_dto = run_create_dto1(&repo).await;
+    let deleted_dto = run_create_dto2(&repo).await;
+    let _ = repo.delete(&deleted_dto.id.unwrap()).await;
+    let missing_id = ObjectId::new();
+
+    let users = repo
+        .get_many(&[deleted_dto.id.unwrap(), missing_id, alive_dto.id.unwrap()])
+        .await
+        .unwrap();
+
+    assert_eq!(users.len(), 3);
+    assert!(matches!(users[0], Err(RepoError::DeletedWithObjectId(..))));
+    assert!(matches!(users[1], Err(RepoError::NotFoundWithObjectId(..))));
+    assert_eq!(users[2].as_ref().unwrap(), &alive_dto);
+}
+
 #[tokio::test]
 async fn get_user_success() {
     let repo = user_test_helper::get_mock_repo();
@@ -0,0 +1,162 @@
+#[cfg(test)]
+mod tests;
+
+use std::io::{BufRead, BufReader, Write};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::Utc;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures_util::{future, StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::{ObjectMeta, ObjectStore};
+use uuid::Uuid;
+
+use dto::user_repo_info_dto::{UserRepoInfoArchiveDto, UserRepoInfoDto};
+use dto::DtoList;
+
+use crate::dao::error::RepoError::{InternalConcrete, NotFoundWithUuid};
+use crate::dao::error::{Entity, RepoResult};
+use crate::dao::UserRepoInfoArchiveRepositoryTrait;
+
+const ARCHIVES_PREFIX: &str = "archives/user_repo_info";
+const MANIFEST_FILE: &str = "manifest.json";
+const RECORDS_FILE: &str = "records.ndjson.gz";
+
+/// Manifests of a page read at once
+const MANIFEST_READS: usize = 16;
+
+/// Keeps batches of archived records as gzipped NDJSON next to their manifest
+#[derive(Clone)]
+pub struct UserRepoInfoArchiveRepository {
+    store: Arc<dyn ObjectStore>,
+}
+
+impl UserRepoInfoArchiveRepository {
+    pub fn new(store: Arc<dyn ObjectStore>) -> Self {
+        Self { store }
+    }
+
+    fn get_path(archive_id: &Uuid, file: &str) -> Path {
+        Path::from(format!("{ARCHIVES_PREFIX}/{archive_id}/{file}"))
+    }
+
+    async fn get_file(&self, archive_id: &Uuid, file: &str) -> RepoResult<Bytes> {
+        match self.store.get(&Self::get_path(archive_id, file)).await {
+            Ok(res) => Ok(res.bytes().await?),
+            Err(object_store::Error::NotFound { .. }) => {
+                Err(NotFoundWithUuid(*archive_id, Entity::UserRepoInfoArchive))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn encode(records: &[UserRepoInfoDto]) -> std::io::Result<Vec<u8>> {
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        for record in records {
+            serde_json::to_writer(&mut encoder, record)?;
+            encoder.write_all(b"\n")?;
+        }
+        encoder.finish()
+    }
+
+    fn decode(bytes: &[u8]) -> RepoResult<Vec<UserRepoInfoDto>> {
+        let mut records = vec![];
+        for line in BufReader::new(GzDecoder::new(bytes)).lines() {
+            let line = line.map_err(|err| InternalConcrete(err.to_string()))?;
+            if !line.is_empty() {
+                let record =
+                    serde_json::from_str(&line).map_err(|err| InternalConcrete(err.to_string()))?;
+                records.push(record);
+            }
+        }
+        Ok(records)
+    }
+}
+
+#[async_trait]
+impl UserRepoInfoArchiveRepositoryTrait for UserRepoInfoArchiveRepository {
+    async fn save_batch(&self, records: &[UserRepoInfoDto]) -> RepoResult<UserRepoInfoArchiveDto> {
+        let executed_at = records.iter().map(|r| r.executed_at);
+        let (Some(first_executed_at), Some(last_executed_at)) =
+            (executed_at.clone().min(), executed_at.max())
+        else {
+            return Err(InternalConcrete("Archive batch can't be empty".to_string()));
+        };
+        let manifest = UserRepoInfoArchiveDto {
+            // Time ordered, so batches list in the order they were archived
+            id: Uuid::now_v7(),
+            records: records.len() as u64,
+            first_executed_at,
+            last_executed_at,
+            archived_at: Utc::now(),
+        };
+
+        let bytes = Self::encode(records).map_err(|err| InternalConcrete(err.to_string()))?;
+        let path = Self::get_path(&manifest.id, RECORDS_FILE);
+        self.store.put(&path, Bytes::from(bytes)).await?;
+
+        // The manifest goes last, so only complete batches are listed
+        let bytes =
+            serde_json::to_vec(&manifest).map_err(|err| InternalConcrete(err.to_string()))?;
+        let path = Self::get_path(&manifest.id, MANIFEST_FILE);
+        self.store.put(&path, Bytes::from(bytes)).await?;
+        Ok(manifest)
+    }
+
+    async fn list_batches(
+        &self,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> RepoResult<DtoList<UserRepoInfoArchiveDto>> {
+        let prefix = Path::from(ARCHIVES_PREFIX);
+        let mut manifests: Vec<ObjectMeta> = self
+            .store
+            .list(Some(&prefix))
+            .try_filter(|o| future::ready(o.location.filename() == Some(MANIFEST_FILE)))
+            .try_collect()
+            .await?;
+        // The manifest is written last, time ordered ids settle batches of the same instant
+        manifests
+            .sort_by(|a, b| (a.last_modified, &a.location).cmp(&(b.last_modified, &b.location)));
+        let count = manifests.len() as u64;
+
+        let page = manifests
+            .into_iter()
+            .skip(offset.unwrap_or(0) as usize)
+            .take(take.filter(|&n| n != 0).map_or(usize::MAX, |n| n as usize));
+        let dtos = futures_util::stream::iter(page)
+            .map(|object| async move {
+                let bytes = self.store.get(&object.location).await?.bytes().await?;
+                serde_json::from_slice(&bytes).map_err(|err| InternalConcrete(err.to_string()))
+            })
+            .buffered(MANIFEST_READS)
+            .try_collect()
+            .await?;
+        Ok(DtoList::new(dtos, count, take, offset))
+    }
+
+    async fn get_manifest(&self, archive_id: &Uuid) -> RepoResult<UserRepoInfoArchiveDto> {
+        let bytes = self.get_file(archive_id, MANIFEST_FILE).await?;
+        serde_json::from_slice(&bytes).map_err(|err| InternalConcrete(err.to_string()))
+    }
+
+    async fn get_batch(&self, archive_id: &Uuid) -> RepoResult<Vec<UserRepoInfoDto>> {
+        let bytes = self.get_file(archive_id, RECORDS_FILE).await?;
+        Self::decode(&bytes)
+    }
+
+    async fn delete_batch(&self, archive_id: &Uuid) -> RepoResult<()> {
+        self.get_manifest(archive_id).await?;
+        self.store
+            .delete(&Self::get_path(archive_id, MANIFEST_FILE))
+            .await?;
+        self.store
+            .delete(&Self::get_path(archive_id, RECORDS_FILE))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,62 @@
+use chrono::{Duration, Utc};
+use uuid::Uuid;
+
+use crate::dao::UserRepoInfoArchiveRepositoryTrait;
+use crate::utils::user_repo_info::user_repo_info_test_helper;
+
+#[tokio::test]
+async fn save_and_get_batch_success() {
+    let repo = user_repo_info_test_helper::get_archive_repo();
+    let now = Utc::now();
+    let records = user_repo_info_test_helper::get_records(&[now - Duration::days(2), now]);
+
+    let manifest = repo.save_batch(&records).await.unwrap();
+    let restored = repo.get_batch(&manifest.id).await.unwrap();
+
+    assert_eq!(manifest.records, 2);
+    assert_eq!(manifest.first_executed_at, records[0].executed_at);
+    assert_eq!(manifest.last_executed_at, records[1].executed_at);
+    assert_eq!(restored, records);
+    assert_eq!(restored[0].id, records[0].id);
+}
+
+#[tokio::test]
+async fn save_empty_batch_failure() {
+    let repo = user_repo_info_test_helper::get_archive_repo();
+
+    let res = repo.save_batch(&[]).await;
+
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn list_and_delete_batches_success() {
+    let repo = user_repo_info_test_helper::get_archive_repo();
+    let records = user_repo_info_test_helper::get_records(&[Utc::now()]);
+    let first = repo.save_batch(&records).await.unwrap();
+    let second = repo.save_batch(&records).await.unwrap();
+    let third = repo.save_batch(&records).await.unwrap();
+
+    let listed = repo.list_batches(None, None).await.unwrap();
+    let page = repo.list_batches(Some(1), Some(1)).await.unwrap();
+    repo.delete_batch(&first.id).await.unwrap();
+    let left = repo.list_batches(None, None).await.unwrap();
+
+    assert_eq!(
+        listed.dtos,
+        vec![first.clone(), second.clone(), third.clone()]
+    );
+    assert_eq!(page.count, 3);
+    assert_eq!(page.dtos, vec![second.clone()]);
+    assert_eq!(left.dtos, vec![second, third]);
+    assert!(repo.get_batch(&first.id).await.is_err());
+}
+
+#[tokio::test]
+async fn delete_nonexistent_batch_failure() {
+    let repo = user_repo_info_test_helper::get_archive_repo();
+
+    let res = repo.delete_batch(&Uuid::new_v4()).await;
+
+    assert!(res.is_err());
+}
@@ -1,17 +1,26 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use mongodb::bson::doc;
+use chrono::{DateTime, Utc};
 use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{bson, doc, from_document, to_bson, Bson, Document};
+use serde::de::DeserializeOwned;
 
-use collection::user_repo_info::UserRepoInfo;
+use collection::user_repo_info::{
+    LinkActivityStat, RepoChurnStat, UserActivityStat, UserNetLinksStat, UserRepoInfo,
+    UserRepoInfoOperation,
+};
 use collection::MongoCollection;
 
 use crate::dao::error::{Entity, RepoError, RepoResult};
 use crate::dao::{PersistentRepositoryTrait, UserRepoInfoRepositoryTrait};
-use dto::user_repo_info_dto::{CreateUserRepoInfoDto, UserRepoInfoDto};
+use dto::user_repo_info_dto::{
+    CreateUserRepoInfoDto, UserRepoInfoDto, UserRepoInfoStatsDto, UserRepoInfoStatsFilterDto,
+};
 use dto::DtoList;
 
+const DEFAULT_STATS_TOP: u64 = 10;
+
 #[cfg(test)]
 mod tests;
 
@@ -23,6 +32,49 @@ impl UserRepoInfoRepository {
     pub fn new(collection: Arc<dyn MongoCollection<UserRepoInfo>>) -> Self {
         Self { collection }
     }
+
+    fn date_to_bson(date: DateTime<Utc>) -> mongodb::bson::DateTime {
+        mongodb::bson::DateTime::from_millis(date.timestamp_millis())
+    }
+
+    fn operation_to_bson(operation: UserRepoInfoOperation) -> RepoResult<Bson> {
+        to_bson(&operation).map_err(|err| RepoError::InternalConcrete(err.to_string()))
+    }
+
+    fn range_match(from: Option<DateTime<Utc>>, to: Option<DateTime<Utc>>) -> Document {
+        let mut executed_at = doc! {};
+        if let Some(from) = from {
+            executed_at.insert("$gte", Self::date_to_bson(from));
+        }
+        if let Some(to) = to {
+            executed_at.insert("$lt", Self::date_to_bson(to));
+        }
+        match executed_at.is_empty() {
+            true => doc! {},
+            false => doc! {"executed_at": executed_at},
+        }
+    }
+
+    fn count_operation(operation: &Bson) -> Document {
+        doc! {"$sum": {"$cond": [{"$eq": ["$operation", operation]}, 1, 0]}}
+    }
+
+    async fn collect_stats<S, D>(&self, pipeline: Vec<Document>) -> RepoResult<Vec<D>>
+    where
+        S: DeserializeOwned,
+        D: From<S>,
+    {
+        self.collection
+            .aggregate_documents(pipeline, None)
+            .await?
+            .into_iter()
+            .map(|doc| {
+                from_document::<S>(doc)
+                    .map(D::from)
+                    .map_err(|err| RepoError::InternalConcrete(err.to_string()))
+            })
+            .collect()
+    }
 }
 
 #[async_trait]
@@ -84,4 +136,148 @@ impl UserRepoInfoRepositoryTrait for UserRepoInfoRepository {
 
         Ok(DtoList::new(dtos, count, take, offset))
     }
+
+    async fn list_expired(
+        &self,
+        before: DateTime<Utc>,
+        take: u64,
+    ) -> RepoResult<Vec<UserRepoInfoDto>> {
+        let before = Self::date_to_bson(before);
+        // Restored records are kept for another retention period
+        let pipeline = vec![
+            doc! {"$match": {"$or": [
+                {"restored_at": {"$exists": false}, "executed_at": {"$lt": before}},
+                {"restored_at": {"$lt": before}},
+            ]}},
+            doc! {"$sort": {"executed_at": 1}},
+        ];
+        Ok(self
+            .collection
+            .paginate_pipeline_and_collect(pipeline, Some(take), None, None)
+            .await?
+            .into_iter()
+            .map(|u| u.into())
+            .collect())
+    }
+
+    async fn delete_many(&self, ids: &[ObjectId]) -> RepoResult<u64> {
+        let deleted = self
+            .collection
+            .delete_many(doc! {"_id": {"$in": ids}}, None)
+            .await?;
+        Ok(deleted)
+    }
+
+    async fn list_after(
+        &self,
+        after: ObjectId,
+        user_id: Option<ObjectId>,
+        take: u64,
+    ) -> RepoResult<Vec<UserRepoInfoDto>> {
+        let mut filter = doc! {"_id": {"$gt": after}};
+        if let Some(user_id) = user_id {
+            filter.insert(
+                "$or",
+                bson!([{"user_id": user_id}, {"from_user_id": user_id}]),
+            );
+        }
+        let pipeline = vec![doc! {"$match": filter}, doc! {"$sort": {"_id": 1}}];
+        Ok(self
+            .collection
+            .paginate_pipeline_and_collect(pipeline, Some(take), None, None)
+            .await?
+            .into_iter()
+            .map(|u| u.into())
+            .collect())
+    }
+
+    async fn restore(&self, mut dto: UserRepoInfoDto) -> RepoResult<UserRepoInfoDto> {
+        dto.restored_at = Some(Utc::now());
+        let id = self.collection.insert_one(dto.into(), None).await?;
+        Ok(self.get(&id).await?)
+    }
+
+    async fn stats(&self, filter: UserRepoInfoStatsFilterDto) -> RepoResult<UserRepoInfoStatsDto> {
+        let range = Self::range_match(filter.from, filter.to);
+        let top = filter.top.unwrap_or(DEFAULT_STATS_TOP) as i64;
+        let period = filter.period.unwrap_or_default();
+
+        let create = Self::operation_to_bson(UserRepoInfoOperation::CreateLink)?;
+        let delete = Self::operation_to_bson(UserRepoInfoOperation::DeleteLink)?;
+        let transfer = Self::operation_to_bson(UserRepoInfoOperation::Transfer)?;
+        let mut links_match = range.clone();
+        links_match.insert("operation", doc! {"$in": [&create, &delete]});
+
+        let activity = vec![
+            doc! {"$match": links_match.clone()},
+            doc! {"$group": {
+                "_id": {"$dateToString": {
+                    "format": period.format(),
+                    "date": "$executed_at",
+                }},
+                "created": Self::count_operation(&create),
+                "deleted": Self::count_operation(&delete),
+            }},
+            doc! {"$sort": {"_id": 1}},
+            doc! {"$project": {"_id": 0, "period": "$_id", "created": 1, "deleted": 1}},
+        ];
+
+        let most_active_users = vec![
+            doc! {"$match": range.clone()},
+            doc! {"$group": {"_id": "$user_id", "operations": {"$sum": 1}}},
+            doc! {"$sort": {"operations": -1, "_id": 1}},
+            doc! {"$limit": top},
+            doc! {"$project": {"_id": 0, "user_id": "$_id", "operations": 1}},
+        ];
+
+        let churned_repos = vec![
+            doc! {"$match": links_match},
+            doc! {"$group": {
+                "_id": "$repo_id",
+                "created": Self::count_operation(&create),
+                "deleted": Self::count_operation(&delete),
+            }},
+            doc! {"$match": {"deleted": {"$gt": 0}}},
+            doc! {"$sort": {"deleted": -1, "created": -1}},
+            doc! {"$limit": top},
+            doc! {"$project": {"_id": 0, "repo_id": "$_id", "created": 1, "deleted": 1}},
+        ];
+
+        // A transfer is a link gained by one user and lost by another
+        let net_links = vec![
+            doc! {"$match": range},
+            doc! {"$project": {"entries": {"$cond": [
+                {"$eq": ["$operation", &transfer]},
+                [
+                    {"user_id": "$user_id", "delta": 1},
+                    {"user_id": "$from_user_id", "delta": -1},
+                ],
+                [{
+                    "user_id": "$user_id",
+                    "delta": {"$cond": [{"$eq": ["$operation", &create]}, 1, -1]},
+                }],
+            ]}}},
+            doc! {"$unwind": "$entries"},
+            doc! {"$group": {
+                "_id": "$entries.user_id",
+                "created": {"$sum": {"$cond": [{"$eq": ["$entries.delta", 1]}, 1, 0]}},
+                "deleted": {"$sum": {"$cond": [{"$eq": ["$entries.delta", -1]}, 1, 0]}},
+                "net": {"$sum": "$entries.delta"},
+            }},
+            doc! {"$sort": {"net": -1, "_id": 1}},
+            doc! {"$limit": top},
+            doc! {"$project": {"_id": 0, "user_id": "$_id", "created": 1, "deleted": 1, "net": 1}},
+        ];
+
+        Ok(UserRepoInfoStatsDto {
+            activity: self.collect_stats::<LinkActivityStat, _>(activity).await?,
+            most_active_users: self
+                .collect_stats::<UserActivityStat, _>(most_active_users)
+                .await?,
+            churned_repos: self
+                .collect_stats::<RepoChurnStat, _>(churned_repos)
+                .await?,
+            net_links: self.collect_stats::<UserNetLinksStat, _>(net_links).await?,
+        })
+    }
 }
@@ -1 +1,139 @@
+use chrono::{Duration, Utc};
+use mongodb::bson::oid::ObjectId;
+use uuid::Uuid;
 
+use collection::user_repo_info::{UserRepoInfo, UserRepoInfoOperation};
+use dto::user_repo_info_dto::UserRepoInfoStatsFilterDto;
+
+use crate::dao::{PersistentRepositoryTrait, UserRepoInfoRepositoryTrait};
+use crate::utils::user_repo_info::user_repo_info_test_helper;
+
+#[tokio::test]
+async fn list_expired_oldest_first() {
+    let repo = user_repo_info_test_helper::get_filled_repo(5).await;
+
+    let expired = repo
+        .list_expired(Utc::now() - Duration::hours(60), 10)
+        .await
+        .unwrap();
+    let limited = repo
+        .list_expired(Utc::now() - Duration::hours(60), 1)
+        .await
+        .unwrap();
+
+    assert_eq!(expired.len(), 3);
+    assert!(expired
+        .windows(2)
+        .all(|w| w[0].executed_at < w[1].executed_at));
+    assert_eq!(limited, expired[..1]);
+}
+
+#[tokio::test]
+async fn delete_many_success() {
+    let repo = user_repo_info_test_helper::get_filled_repo(3).await;
+    let expired = repo.list_expired(Utc::now(), 2).await.unwrap();
+    let ids: Vec<_> = expired.iter().map(|r| r.id.unwrap()).collect();
+
+    let deleted = repo.delete_many(&ids).await.unwrap();
+    let left = repo.list(None, None).await.unwrap();
+
+    assert_eq!(deleted, 2);
+    assert_eq!(left.count, 1);
+}
+
+#[tokio::test]
+async fn restored_expires_from_restore() {
+    let repo = user_repo_info_test_helper::get_filled_repo(2).await;
+    let mut expired = repo.list_expired(Utc::now(), 10).await.unwrap();
+    let record = expired.remove(0);
+    let id = record.id.unwrap();
+
+    repo.delete_many(&[id]).await.unwrap();
+    let restored = repo.restore(record).await.unwrap();
+    let expired = repo
+        .list_expired(Utc::now() - Duration::hours(1), 10)
+        .await
+        .unwrap();
+    let expired_later = repo
+        .list_expired(Utc::now() + Duration::hours(1), 10)
+        .await
+        .unwrap();
+
+    assert_eq!(restored.id, Some(id));
+    assert!(restored.restored_at.is_some());
+    assert_eq!(expired.len(), 1);
+    assert_ne!(expired[0].id, Some(id));
+    assert_eq!(expired_later.len(), 2, "Restored record must expire again");
+}
+
+#[tokio::test]
+async fn stats_success() {
+    let (user, other, repo_id) = (ObjectId::new(), ObjectId::new(), Uuid::new_v4());
+    let repo = user_repo_info_test_helper::get_repo_with(vec![
+        UserRepoInfo::new(user, repo_id, UserRepoInfoOperation::CreateLink),
+        UserRepoInfo::new(user, repo_id, UserRepoInfoOperation::DeleteLink),
+        UserRepoInfo::new(other, repo_id, UserRepoInfoOperation::CreateLink),
+        UserRepoInfo {
+            from_user_id: Some(other),
+            ..UserRepoInfo::new(user, repo_id, UserRepoInfoOperation::Transfer)
+        },
+    ])
+    .await;
+
+    let stats = repo.stats(Default::default()).await.unwrap();
+    let empty = repo
+        .stats(UserRepoInfoStatsFilterDto {
+            from: Some(Utc::now() + Duration::days(1)),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(stats.activity.len(), 1);
+    assert_eq!(
+        stats.activity[0].period,
+        Utc::now().format("%Y-%m-%d").to_string()
+    );
+    assert_eq!(
+        (stats.activity[0].created, stats.activity[0].deleted),
+        (2, 1)
+    );
+    assert_eq!(stats.most_active_users[0].user_id, user);
+    assert_eq!(stats.most_active_users[0].operations, 3);
+    assert_eq!(stats.churned_repos.len(), 1);
+    assert_eq!(stats.churned_repos[0].repo_id, repo_id);
+    assert_eq!(stats.net_links[0].user_id, user);
+    assert_eq!(stats.net_links[0].net, 1);
+    assert_eq!(stats.net_links[1].net, 0);
+    assert!(empty.activity.is_empty() && empty.net_links.is_empty());
+}
+
+#[tokio::test]
+async fn list_after_success() {
+    let (user, other) = (ObjectId::new(), ObjectId::new());
+    let transfer = UserRepoInfo {
+        from_user_id: Some(user),
+        ..UserRepoInfo::new(other, Uuid::new_v4(), UserRepoInfoOperation::Transfer)
+    };
+    let repo = user_repo_info_test_helper::get_repo_with(vec![
+        UserRepoInfo::new(user, Uuid::new_v4(), UserRepoInfoOperation::CreateLink),
+        UserRepoInfo::new(other, Uuid::new_v4(), UserRepoInfoOperation::CreateLink),
+        UserRepoInfo::new(user, Uuid::new_v4(), UserRepoInfoOperation::DeleteLink),
+        transfer,
+    ])
+    .await;
+    let all = repo.list(None, None).await.unwrap().dtos;
+    let first = all[0].id.unwrap();
+
+    let after = repo.list_after(first, None, 10).await.unwrap();
+    let limited = repo.list_after(first, None, 2).await.unwrap();
+    let user_after = repo.list_after(first, Some(user), 10).await.unwrap();
+
+    assert_eq!(after, all[1..]);
+    assert_eq!(limited, all[1..3]);
+    assert_eq!(
+        user_after,
+        all[2..],
+        "Transfers away from the user are theirs too"
+    );
+}
@@ -3,86 +3,329 @@ mod tests;
 
 use async_trait::async_trait;
 use std::cmp::Reverse;
+use std::collections::HashSet;
 use std::str::FromStr;
 use std::sync::Arc;
 
-use crate::dao::{BlobConnRepositoryTrait, UserRepoRepositoryTrait, DELIMITER};
+use crate::dao::{BlobConnRepositoryTrait, ConnRepositoryTrait, UserRepoRepositoryTrait};
 use bytes::Bytes;
-use futures_util::StreamExt;
+use futures_util::{StreamExt, TryStreamExt};
 use mongodb::bson::oid::ObjectId;
 use object_store::path::Path;
-use object_store::ObjectStore;
+use object_store::{ObjectMeta, ObjectStore};
 use uuid::Uuid;
 
-use crate::dao::error::RepoError::{Internal, InternalConcrete};
-use crate::dao::error::{Entity, RepoResult};
+use dto::user_repo_dto::LinkMetadataDto;
+
+use crate::dao::error::RepoError::{
+    AlreadyConnected, Internal, InternalConcrete, NotConnectedWithAny, NotYetConnected,
+};
+use crate::dao::error::{Entity, RepoError, RepoResult};
+
+const LEGACY_DELIMITER: &str = "____";
+
+/// Empty markers of pinned links, `pins/{user_id}/{repo_id}`. They come first
+/// in the listing
+const PIN_PREFIX: &str = "pins";
+
+/// Metadata objects of a page read at once
+const METADATA_READS: usize = 16;
 
 #[derive(Clone)]
 pub struct UserRepoRepository {
     store: Arc<dyn ObjectStore>,
 }
 
-impl UserRepoRepositoryTrait for UserRepoRepository {}
-
 impl UserRepoRepository {
     pub fn new(store: Arc<dyn ObjectStore>) -> Self {
         Self { store }
     }
-}
 
-#[async_trait]
-impl BlobConnRepositoryTrait<ObjectId, Uuid> for UserRepoRepository {
-    async fn list_pairs(&self, user_id: &ObjectId) -> RepoResult<Vec<Uuid>> {
-        let stream = self.store.list(None);
+    /// Rewrites pairs stored in the flat `{user_id}____{repo_id}` layout
+    /// into the prefixed one, linked when the legacy object was last modified.
+    /// Returns the number of migrated pairs
+    pub async fn migrate_legacy_pairs(&self) -> RepoResult<u64> {
+        let objects: Vec<ObjectMeta> = self.store.list(None).try_collect().await?;
 
-        let mut uuids = vec![];
-        for repo in stream.collect::<Vec<_>>().await {
-            let repo = repo?;
-            let location = &repo.location;
+        let mut migrated = 0;
+        for object in objects {
+            let location = &object.location;
             let filename = location
                 .filename()
                 .ok_or(InternalConcrete(format!("Wrong location: {location}")))?;
-            let split: Vec<_> = filename.split(DELIMITER).collect();
-            if split.len() != 2 {
-                Err(InternalConcrete(format!("Wrong filename: {filename}")))?
-            }
+            let Some((user_id, repo_id)) = filename.split_once(LEGACY_DELIMITER) else {
+                continue;
+            };
 
-            let curr_user_id = split[0];
-            let repo_id = split[1];
-
-            let curr_user_id = ObjectId::from_str(curr_user_id)
+            let user_id = ObjectId::from_str(user_id)
                 .map_err(|_| InternalConcrete(format!("Wrong user_id: {filename}")))?;
+            let repo_id = Uuid::from_str(repo_id)
+                .map_err(|_| InternalConcrete(format!("Wrong repo_id: {filename}")))?;
+
+            let metadata = LinkMetadataDto {
+                linked_at: object.last_modified,
+                ..LinkMetadataDto::new(None)
+            };
+            self.store
+                .put(
+                    &self.get_key_path(&user_id, &repo_id),
+                    Self::encode_metadata(&metadata)?,
+                )
+                .await?;
+            self.store
+                .put(&self.get_val_path(&user_id, &repo_id), Bytes::new())
+                .await?;
+            self.store.delete(location).await?;
+            migrated += 1;
+        }
+        Ok(migrated)
+    }
+
+    fn parse_repo_id(object: &ObjectMeta) -> RepoResult<Uuid> {
+        let location = &object.location;
+        let filename = location
+            .filename()
+            .ok_or(InternalConcrete(format!("Wrong location: {location}")))?;
+        Uuid::from_str(filename).map_err(|_| Internal("Invalid uuid"))
+    }
+
+    fn get_pin_prefix(user_id: &ObjectId) -> Path {
+        Path::from(format!("{PIN_PREFIX}/{user_id}"))
+    }
+
+    fn get_pin_path(user_id: &ObjectId, repo_id: &Uuid) -> Path {
+        Path::from(format!("{PIN_PREFIX}/{user_id}/{repo_id}"))
+    }
+
+    /// The marker is written after the link and removed before it,
+    /// so it never outlives the link
+    async fn set_pinned(&self, user_id: &ObjectId, repo_id: &Uuid, pinned: bool) -> RepoResult<()> {
+        let path = Self::get_pin_path(user_id, repo_id);
+        match pinned {
+            true => self.store.put(&path, Bytes::new()).await.map(|_| ())?,
+            false => match self.store.delete(&path).await {
+                Ok(_) | Err(object_store::Error::NotFound { .. }) => {}
+                Err(err) => Err(err)?,
+            },
+        }
+        Ok(())
+    }
 
-            if curr_user_id == *user_id {
-                let uuid = Uuid::from_str(repo_id).map_err(|_| Internal("Invalid uuid"))?;
-                let date = repo.last_modified;
-                uuids.push((uuid, date));
+    fn encode_metadata(metadata: &LinkMetadataDto) -> RepoResult<Bytes> {
+        let bytes =
+            serde_json::to_vec(metadata).map_err(|err| InternalConcrete(err.to_string()))?;
+        Ok(Bytes::from(bytes))
+    }
+
+    fn decode_metadata(object: &ObjectMeta, bytes: &[u8]) -> RepoResult<LinkMetadataDto> {
+        if bytes.is_empty() {
+            return Ok(LinkMetadataDto::legacy(object.last_modified));
+        }
+        serde_json::from_slice(bytes).map_err(|err| InternalConcrete(err.to_string()))
+    }
+
+    async fn read_metadata(&self, object: &ObjectMeta) -> RepoResult<LinkMetadataDto> {
+        if object.size == 0 {
+            return Ok(LinkMetadataDto::legacy(object.last_modified));
+        }
+        let bytes = self.store.get(&object.location).await?.bytes().await?;
+        Self::decode_metadata(object, &bytes)
+    }
+
+    /// Reads `{prefix}/{first}/{second}`, a link as `(user_id, repo_id)`
+    /// and a reverse entry as `(repo_id, user_id)`
+    fn parse_location<F, S>(object: &ObjectMeta) -> RepoResult<(F, S)>
+    where
+        F: FromStr,
+        S: FromStr,
+    {
+        let location = object.location.as_ref();
+        let parts: Vec<&str> = location.split('/').collect();
+        let [_, first, second] = parts[..] else {
+            return Err(InternalConcrete(format!("Wrong location: {location}")));
+        };
+        let first =
+            F::from_str(first).map_err(|_| InternalConcrete(format!("Wrong id: {location}")))?;
+        let second =
+            S::from_str(second).map_err(|_| InternalConcrete(format!("Wrong id: {location}")))?;
+        Ok((first, second))
+    }
+
+    /// Pinned links come first, newest first within both groups. The order
+    /// needs the link time of every link, their metadata is read
+    /// `METADATA_READS` at a time and only the requested page is kept
+    async fn list_page(
+        &self,
+        user_id: &ObjectId,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> RepoResult<(Vec<(Uuid, LinkMetadataDto)>, u64)> {
+        let pin_prefix = Self::get_pin_prefix(user_id);
+        let pinned_ids = self
+            .store
+            .list(Some(&pin_prefix))
+            .map(|object| Self::parse_repo_id(&object?))
+            .try_collect::<HashSet<Uuid>>()
+            .await?;
+
+        let prefix = self.get_key_prefix(user_id);
+        let mut links: Vec<(Uuid, LinkMetadataDto)> = self
+            .store
+            .list(Some(&prefix))
+            .map(|object| async move {
+                let object = object?;
+                let repo_id = Self::parse_repo_id(&object)?;
+                Ok::<_, RepoError>((repo_id, self.read_metadata(&object).await?))
+            })
+            .buffered(METADATA_READS)
+            .try_collect()
+            .await?;
+        links.sort_by_key(|(repo_id, metadata)| {
+            Reverse((pinned_ids.contains(repo_id), metadata.linked_at))
+        });
+
+        let count = links.len() as u64;
+        let offset = offset.unwrap_or(0) as usize;
+        let take = take.filter(|&n| n != 0).map_or(usize::MAX, |n| n as usize);
+        let page = links.into_iter().skip(offset).take(take).collect();
+        Ok((page, count))
+    }
+
+    /// Restores deleted objects before removing the written ones,
+    /// so the repo stays connected to someone during the rollback
+    async fn rollback_transfer(&self, written: &[&(Path, Bytes)], deleted: &[&(Path, Bytes)]) {
+        for (path, bytes) in deleted {
+            if let Err(err) = self.store.put(path, bytes.clone()).await {
+                tracing::error!(error = %err, %path, "Failed to restore");
+            }
+        }
+        for (path, _) in written {
+            if let Err(err) = self.store.delete(path).await {
+                tracing::error!(error = %err, %path, "Failed to remove");
             }
         }
+    }
+}
+
+#[async_trait]
+impl ConnRepositoryTrait<ObjectId, Uuid> for UserRepoRepository {
+    async fn list_pairs(
+        &self,
+        user_id: &ObjectId,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> RepoResult<(Vec<Uuid>, u64)> {
+        let (page, count) = self.list_page(user_id, take, offset).await?;
+        Ok((
+            page.into_iter().map(|(repo_id, _)| repo_id).collect(),
+            count,
+        ))
+    }
+
+    async fn get_key(&self, repo_id: &Uuid) -> RepoResult<ObjectId> {
+        let prefix = self.get_val_prefix(repo_id);
+        let object = self
+            .store
+            .list(Some(&prefix))
+            .next()
+            .await
+            .ok_or(NotConnectedWithAny(
+                Entity::Repository,
+                repo_id.to_string(),
+                Entity::User,
+            ))??;
+
+        let location = &object.location;
+        let filename = location
+            .filename()
+            .ok_or(InternalConcrete(format!("Wrong location: {location}")))?;
+        ObjectId::from_str(filename)
+            .map_err(|_| InternalConcrete(format!("Wrong user_id: {filename}")))
+    }
 
-        uuids.sort_by_key(|t| Reverse(t.1));
-        let uuid = uuids.into_iter().map(|t| t.0).collect();
-        Ok(uuid)
+    async fn list_all_pairs(&self) -> RepoResult<Vec<(ObjectId, Uuid)>> {
+        let prefix = Path::from(Self::key_prefix());
+        let objects: Vec<ObjectMeta> = self.store.list(Some(&prefix)).try_collect().await?;
+        objects.iter().map(Self::parse_location).collect()
     }
 
     async fn add_pair(&self, user_id: &ObjectId, repo_id: &Uuid) -> RepoResult<()> {
-        self.is_val_not_connected(repo_id).await?;
-        let path = Path::from(self.get_file_name(user_id, repo_id));
-        self.store.put(&path, Bytes::new()).await?;
-        Ok(())
+        self.add_pair_with_metadata(user_id, repo_id, &LinkMetadataDto::new(None))
+            .await
     }
 
     async fn delete_pair(&self, user_id: &ObjectId, repo_id: &Uuid) -> RepoResult<()> {
         self.are_connected(user_id, repo_id).await?;
-        let path = Path::from(format!("{user_id}{DELIMITER}{repo_id}"));
-        self.store.delete(&path).await?;
+        self.set_pinned(user_id, repo_id, false).await?;
+        self.store
+            .delete(&self.get_key_path(user_id, repo_id))
+            .await?;
+        self.store
+            .delete(&self.get_val_path(user_id, repo_id))
+            .await?;
         Ok(())
     }
 
+    async fn transfer_pair(
+        &self,
+        from_user_id: &ObjectId,
+        to_user_id: &ObjectId,
+        repo_id: &Uuid,
+    ) -> RepoResult<()> {
+        self.are_connected(from_user_id, repo_id).await?;
+        if from_user_id == to_user_id {
+            Err(AlreadyConnected(Entity::User, Entity::Repository))?
+        }
+        // The link keeps its metadata under the new owner
+        let metadata = self.get_metadata(from_user_id, repo_id).await?;
+        let encoded = Self::encode_metadata(&metadata)?;
+        let mut new_paths = vec![
+            (self.get_key_path(to_user_id, repo_id), encoded.clone()),
+            (self.get_val_path(to_user_id, repo_id), Bytes::new()),
+        ];
+        let mut old_paths = vec![
+            (self.get_val_path(from_user_id, repo_id), Bytes::new()),
+            (self.get_key_path(from_user_id, repo_id), encoded),
+        ];
+        if metadata.pinned {
+            new_paths.push((Self::get_pin_path(to_user_id, repo_id), Bytes::new()));
+            old_paths.insert(0, (Self::get_pin_path(from_user_id, repo_id), Bytes::new()));
+        }
+
+        let mut written = vec![];
+        for entry in &new_paths {
+            if let Err(err) = self.store.put(&entry.0, entry.1.clone()).await {
+                self.rollback_transfer(&written, &[]).await;
+                Err(err)?
+            }
+            written.push(entry);
+        }
+
+        let mut deleted = vec![];
+        for entry in &old_paths {
+            if let Err(err) = self.store.delete(&entry.0).await {
+                self.rollback_transfer(&written, &deleted).await;
+                Err(err)?
+            }
+            deleted.push(entry);
+        }
+        Ok(())
+    }
+}
+
+impl BlobConnRepositoryTrait<ObjectId, Uuid> for UserRepoRepository {
     fn store(&self) -> Arc<dyn ObjectStore> {
         Arc::clone(&self.store)
     }
 
+    fn key_prefix() -> &'static str {
+        "users"
+    }
+
+    fn val_prefix() -> &'static str {
+        "repos"
+    }
+
     fn key_entity() -> Entity {
         Entity::User
     }
@@ -91,3 +334,95 @@ impl BlobConnRepositoryTrait<ObjectId, Uuid> for UserRepoRepository {
         Entity::Repository
     }
 }
+
+#[async_trait]
+impl UserRepoRepositoryTrait for UserRepoRepository {
+    async fn add_pair_with_metadata(
+        &self,
+        user_id: &ObjectId,
+        repo_id: &Uuid,
+        metadata: &LinkMetadataDto,
+    ) -> RepoResult<()> {
+        self.is_val_not_connected(repo_id).await?;
+        let key_path = self.get_key_path(user_id, repo_id);
+        let val_path = self.get_val_path(user_id, repo_id);
+
+        self.store
+            .put(&key_path, Self::encode_metadata(metadata)?)
+            .await?;
+        if let Err(err) = self.store.put(&val_path, Bytes::new()).await {
+            self.store.delete(&key_path).await?;
+            Err(err)?
+        }
+        if metadata.pinned {
+            self.set_pinned(user_id, repo_id, true).await?;
+        }
+        Ok(())
+    }
+
+    async fn list_pairs_with_metadata(
+        &self,
+        user_id: &ObjectId,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> RepoResult<(Vec<(Uuid, LinkMetadataDto)>, u64)> {
+        self.list_page(user_id, take, offset).await
+    }
+
+    async fn get_metadata(
+        &self,
+        user_id: &ObjectId,
+        repo_id: &Uuid,
+    ) -> RepoResult<LinkMetadataDto> {
+        let path = self.get_key_path(user_id, repo_id);
+        match self.store.get(&path).await {
+            Ok(res) => {
+                let object = res.meta.clone();
+                let bytes = res.bytes().await?;
+                Self::decode_metadata(&object, &bytes)
+            }
+            Err(object_store::Error::NotFound { .. }) => {
+                Err(NotYetConnected(Entity::User, Entity::Repository))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    async fn update_metadata(
+        &self,
+        user_id: &ObjectId,
+        repo_id: &Uuid,
+        metadata: &LinkMetadataDto,
+    ) -> RepoResult<()> {
+        self.are_connected(user_id, repo_id).await?;
+        let path = self.get_key_path(user_id, repo_id);
+        self.store
+            .put(&path, Self::encode_metadata(metadata)?)
+            .await?;
+        self.set_pinned(user_id, repo_id, metadata.pinned).await
+    }
+
+    async fn list_orphaned_pairs(&self) -> RepoResult<Vec<(ObjectId, Uuid)>> {
+        let pairs: HashSet<(ObjectId, Uuid)> = self.list_all_pairs().await?.into_iter().collect();
+        let prefix = Path::from(Self::val_prefix());
+        let objects: Vec<ObjectMeta> = self.store.list(Some(&prefix)).try_collect().await?;
+
+        let mut orphaned = vec![];
+        for object in &objects {
+            let (repo_id, user_id) = Self::parse_location(object)?;
+            let pair = (user_id, repo_id);
+            if !pairs.contains(&pair) {
+                orphaned.push(pair);
+            }
+        }
+        Ok(orphaned)
+    }
+
+    async fn delete_orphaned_pair(&self, user_id: &ObjectId, repo_id: &Uuid) -> RepoResult<()> {
+        self.are_not_connected(user_id, repo_id).await?;
+        self.store
+            .delete(&self.get_val_path(user_id, repo_id))
+            .await?;
+        Ok(())
+    }
+}
@@ -1,6 +1,13 @@
+use bytes::Bytes;
+use mongodb::bson::oid::ObjectId;
+use object_store::path::Path;
+use object_store::ObjectStore;
 use uuid::Uuid;
 
-use crate::dao::BlobConnRepositoryTrait;
+use crate::dao::error::RepoError;
+use dto::user_repo_dto::{LinkMetadataDto, LinkRole, UpdateLinkMetadataDto, LINK_METADATA_VERSION};
+
+use crate::dao::{BlobConnRepositoryTrait, ConnRepositoryTrait, UserRepoRepositoryTrait};
 use crate::utils::user_repo::user_repo_test_helper;
 
 #[tokio::test]
@@ -50,10 +57,10 @@ async fn list_repos_success() {
     let repo_ids = expected_ids.iter().rev().collect();
 
     let _ = repo.add_pairs(&user_id, repo_ids).await;
-    let res = repo.list_pairs(&user_id).await;
+    let res = repo.list_pairs(&user_id, None, None).await;
 
     assert!(res.is_ok());
-    assert_eq!(res.unwrap(), expected_ids);
+    assert_eq!(res.unwrap(), (expected_ids, 3));
 }
 
 #[tokio::test]
@@ -67,8 +74,377 @@ async fn list_repos_after_delete_success() {
     let _ = repo.add_pairs(&user_id, repo_ids).await;
     let _ = repo.delete_pair(&user_id, &repo_id1).await;
     let _ = repo.delete_pair(&user_id, &repo_id2).await;
-    let res = repo.list_pairs(&user_id).await;
+    let res = repo.list_pairs(&user_id, None, None).await;
+
+    assert!(res.is_ok());
+    assert_eq!(res.unwrap(), (expected_ids, 1));
+}
+
+#[tokio::test]
+async fn list_repos_with_pagination_success() {
+    let (repo, user_id, repo_id1) = user_repo_test_helper::get_mock_repo_with_starter();
+    let repo_id2 = Uuid::new_v4();
+    let repo_id3 = Uuid::new_v4();
+    let repo_ids = vec![&repo_id1, &repo_id2, &repo_id3];
+    let expected_ids = vec![repo_id2, repo_id1];
+
+    let _ = repo.add_pairs(&user_id, repo_ids).await;
+    let res = repo.list_pairs(&user_id, Some(2), Some(1)).await;
+
+    assert!(res.is_ok());
+    assert_eq!(res.unwrap(), (expected_ids, 3));
+}
+
+#[tokio::test]
+async fn list_repos_of_other_user_success() {
+    let (repo, user_id, repo_id) = user_repo_test_helper::get_mock_repo_with_starter();
+    let other_user_id = ObjectId::new();
+
+    let _ = repo.add_pair(&user_id, &repo_id).await;
+    let res = repo.list_pairs(&other_user_id, None, None).await;
+
+    assert!(res.is_ok());
+    assert_eq!(res.unwrap(), (vec![], 0));
+}
+
+#[tokio::test]
+async fn add_repo_connected_to_other_user_failure() {
+    let (repo, user_id, repo_id) = user_repo_test_helper::get_mock_repo_with_starter();
+    let other_user_id = ObjectId::new();
+
+    let _ = repo.add_pair(&user_id, &repo_id).await;
+    let res = repo.add_pair(&other_user_id, &repo_id).await;
+
+    assert!(res.is_err())
+}
+
+#[tokio::test]
+async fn is_val_connected_after_delete_failure() {
+    let (repo, user_id, repo_id) = user_repo_test_helper::get_mock_repo_with_starter();
+
+    let _ = repo.add_pair(&user_id, &repo_id).await;
+    let _ = repo.delete_pair(&user_id, &repo_id).await;
+    let res = repo.is_val_connected(&repo_id).await;
+
+    assert!(res.is_err())
+}
+
+#[tokio::test]
+async fn migrate_legacy_pairs_success() {
+    let (repo, store) = user_repo_test_helper::get_mock_repo_with_store();
+    let user_id = ObjectId::new();
+    let repo_id = Uuid::new_v4();
+    let legacy_path = Path::from(format!("{user_id}____{repo_id}"));
+
+    store.put(&legacy_path, Bytes::new()).await.unwrap();
+    let legacy = store.head(&legacy_path).await.unwrap();
+    let migrated = repo.migrate_legacy_pairs().await;
+    let res = repo.list_pairs(&user_id, None, None).await;
+
+    assert_eq!(migrated.unwrap(), 1);
+    assert_eq!(res.unwrap(), (vec![repo_id], 1));
+    assert_eq!(
+        repo.get_metadata(&user_id, &repo_id)
+            .await
+            .unwrap()
+            .linked_at,
+        legacy.last_modified
+    );
+    assert!(repo.is_val_connected(&repo_id).await.is_ok());
+    assert!(store.head(&legacy_path).await.is_err());
+}
+
+#[tokio::test]
+async fn get_key_success() {
+    let (repo, user_id, repo_id) = user_repo_test_helper::get_mock_repo_with_starter();
+
+    let _ = repo.add_pair(&user_id, &repo_id).await;
+    let res = repo.get_key(&repo_id).await;
+
+    assert!(res.is_ok());
+    assert_eq!(res.unwrap(), user_id);
+}
+
+#[tokio::test]
+async fn get_key_of_unconnected_repo_failure() {
+    let (repo, _, repo_id) = user_repo_test_helper::get_mock_repo_with_starter();
+
+    let res = repo.get_key(&repo_id).await;
+
+    assert!(matches!(res, Err(RepoError::NotConnectedWithAny(..))))
+}
+
+#[tokio::test]
+async fn add_repos_with_duplicate_partial_success() {
+    let (repo, user_id, repo_id1) = user_repo_test_helper::get_mock_repo_with_starter();
+    let repo_id2 = Uuid::new_v4();
+
+    let res = repo
+        .add_pairs(&user_id, vec![&repo_id1, &repo_id1, &repo_id2])
+        .await;
+
+    assert!(res[0].is_ok());
+    assert!(res[1].is_err());
+    assert!(res[2].is_ok());
+    assert_eq!(repo.list_pairs(&user_id, None, None).await.unwrap().1, 2);
+}
+
+#[tokio::test]
+async fn delete_repos_partial_success() {
+    let (repo, user_id, repo_id1) = user_repo_test_helper::get_mock_repo_with_starter();
+    let repo_id2 = Uuid::new_v4();
+
+    let _ = repo.add_pair(&user_id, &repo_id1).await;
+    let res = repo
+        .delete_pairs(&user_id, vec![&repo_id1, &repo_id2])
+        .await;
+
+    assert!(res[0].is_ok());
+    assert!(res[1].is_err());
+    assert!(repo.is_val_connected(&repo_id1).await.is_err());
+}
+
+#[tokio::test]
+async fn transfer_repo_success() {
+    let (repo, user_id, repo_id) = user_repo_test_helper::get_mock_repo_with_starter();
+    let new_user_id = ObjectId::new();
+
+    let _ = repo.add_pair(&user_id, &repo_id).await;
+    let res = repo.transfer_pair(&user_id, &new_user_id, &repo_id).await;
+
+    assert!(res.is_ok());
+    assert_eq!(repo.get_key(&repo_id).await.unwrap(), new_user_id);
+    assert_eq!(repo.list_pairs(&user_id, None, None).await.unwrap().1, 0);
+    assert_eq!(
+        repo.list_pairs(&new_user_id, None, None).await.unwrap().0,
+        vec![repo_id]
+    );
+}
+
+#[tokio::test]
+async fn transfer_repo_keeps_metadata_success() {
+    let (repo, user_id, repo_id) = user_repo_test_helper::get_mock_repo_with_starter();
+    let new_user_id = ObjectId::new();
+    let mut metadata = LinkMetadataDto::new(Some("admin".to_string()));
+    metadata.role = LinkRole::Maintainer;
+    metadata.pinned = true;
+
+    let _ = repo
+        .add_pair_with_metadata(&user_id, &repo_id, &metadata)
+        .await;
+    let res = repo.transfer_pair(&user_id, &new_user_id, &repo_id).await;
 
     assert!(res.is_ok());
-    assert_eq!(res.unwrap(), expected_ids);
+    assert_eq!(
+        repo.get_metadata(&new_user_id, &repo_id).await.unwrap(),
+        metadata
+    );
+    assert_eq!(
+        repo.list_pairs(&new_user_id, None, None).await.unwrap(),
+        (vec![repo_id], 1)
+    );
+    assert_eq!(
+        repo.list_pairs(&user_id, None, None).await.unwrap(),
+        (vec![], 0)
+    );
+}
+
+#[tokio::test]
+async fn transfer_not_owned_repo_failure() {
+    let (repo, user_id, repo_id) = user_repo_test_helper::get_mock_repo_with_starter();
+    let new_user_id = ObjectId::new();
+
+    let res = repo.transfer_pair(&user_id, &new_user_id, &repo_id).await;
+
+    assert!(matches!(res, Err(RepoError::NotYetConnected(..))));
+}
+
+#[tokio::test]
+async fn transfer_repo_to_the_same_user_failure() {
+    let (repo, user_id, repo_id) = user_repo_test_helper::get_mock_repo_with_starter();
+
+    let _ = repo.add_pair(&user_id, &repo_id).await;
+    let res = repo.transfer_pair(&user_id, &user_id, &repo_id).await;
+
+    assert!(matches!(res, Err(RepoError::AlreadyConnected(..))));
+    assert_eq!(repo.get_key(&repo_id).await.unwrap(), user_id);
+}
+
+#[tokio::test]
+async fn list_all_pairs_success() {
+    let (repo, user_id1, repo_id1) = user_repo_test_helper::get_mock_repo_with_starter();
+    let user_id2 = ObjectId::new();
+    let repo_id2 = Uuid::new_v4();
+
+    let _ = repo.add_pair(&user_id1, &repo_id1).await;
+    let _ = repo.add_pair(&user_id2, &repo_id2).await;
+    let mut res = repo.list_all_pairs().await.unwrap();
+    res.sort_by_key(|pair| pair.0);
+
+    let mut expected = vec![(user_id1, repo_id1), (user_id2, repo_id2)];
+    expected.sort_by_key(|pair| pair.0);
+    assert_eq!(res, expected);
+}
+
+#[tokio::test]
+async fn list_orphaned_pairs_success() {
+    let (repo, store) = user_repo_test_helper::get_mock_repo_with_store();
+    let user_id = ObjectId::new();
+    let linked_repo_id = Uuid::new_v4();
+    let orphaned_repo_id = Uuid::new_v4();
+
+    let _ = repo.add_pair(&user_id, &linked_repo_id).await;
+    let _ = repo.add_pair(&user_id, &orphaned_repo_id).await;
+    store
+        .delete(&repo.get_key_path(&user_id, &orphaned_repo_id))
+        .await
+        .unwrap();
+    let res = repo.list_orphaned_pairs().await;
+
+    assert_eq!(res.unwrap(), vec![(user_id, orphaned_repo_id)]);
+}
+
+#[tokio::test]
+async fn delete_orphaned_pair_success() {
+    let (repo, store) = user_repo_test_helper::get_mock_repo_with_store();
+    let user_id = ObjectId::new();
+    let repo_id = Uuid::new_v4();
+
+    let _ = repo.add_pair(&user_id, &repo_id).await;
+    store
+        .delete(&repo.get_key_path(&user_id, &repo_id))
+        .await
+        .unwrap();
+    let res = repo.delete_orphaned_pair(&user_id, &repo_id).await;
+
+    assert!(res.is_ok());
+    assert!(repo.list_orphaned_pairs().await.unwrap().is_empty());
+    assert!(repo.is_val_not_connected(&repo_id).await.is_ok());
+}
+
+#[tokio::test]
+async fn delete_orphaned_pair_of_linked_repo_failure() {
+    let (repo, user_id, repo_id) = user_repo_test_helper::get_mock_repo_with_starter();
+
+    let _ = repo.add_pair(&user_id, &repo_id).await;
+    let res = repo.delete_orphaned_pair(&user_id, &repo_id).await;
+
+    assert!(matches!(res, Err(RepoError::AlreadyConnected(..))));
+    assert!(repo.is_val_connected(&repo_id).await.is_ok());
+}
+
+#[tokio::test]
+async fn add_repo_with_metadata_success() {
+    let (repo, user_id, repo_id) = user_repo_test_helper::get_mock_repo_with_starter();
+    let metadata = LinkMetadataDto::new(Some("admin".to_string()));
+
+    let _ = repo
+        .add_pair_with_metadata(&user_id, &repo_id, &metadata)
+        .await;
+    let res = repo.get_metadata(&user_id, &repo_id).await;
+
+    assert_eq!(res.unwrap(), metadata);
+}
+
+#[tokio::test]
+async fn get_metadata_of_legacy_link_success() {
+    let (repo, store) = user_repo_test_helper::get_mock_repo_with_store();
+    let user_id = ObjectId::new();
+    let repo_id = Uuid::new_v4();
+
+    store
+        .put(&repo.get_key_path(&user_id, &repo_id), Bytes::new())
+        .await
+        .unwrap();
+    store
+        .put(&repo.get_val_path(&user_id, &repo_id), Bytes::new())
+        .await
+        .unwrap();
+    let res = repo.get_metadata(&user_id, &repo_id).await.unwrap();
+
+    assert_eq!(res.version, 0);
+    assert_eq!(res.role, LinkRole::Owner);
+    assert!(!res.pinned);
+    assert_eq!(
+        repo.list_pairs(&user_id, None, None).await.unwrap().0,
+        vec![repo_id]
+    );
+}
+
+#[tokio::test]
+async fn update_metadata_success() {
+    let (repo, user_id, repo_id) = user_repo_test_helper::get_mock_repo_with_starter();
+    let mut metadata = LinkMetadataDto::legacy(Default::default());
+    metadata.update(UpdateLinkMetadataDto {
+        role: Some(LinkRole::Viewer),
+        pinned: Some(true),
+    });
+
+    let _ = repo.add_pair(&user_id, &repo_id).await;
+    let _ = repo.update_metadata(&user_id, &repo_id, &metadata).await;
+    let res = repo.get_metadata(&user_id, &repo_id).await.unwrap();
+
+    assert_eq!(res.version, LINK_METADATA_VERSION);
+    assert_eq!(res.role, LinkRole::Viewer);
+    assert!(res.pinned);
+}
+
+#[tokio::test]
+async fn update_metadata_of_unconnected_repo_failure() {
+    let (repo, user_id, repo_id) = user_repo_test_helper::get_mock_repo_with_starter();
+    let metadata = LinkMetadataDto::new(None);
+
+    let res = repo.update_metadata(&user_id, &repo_id, &metadata).await;
+
+    assert!(matches!(res, Err(RepoError::NotYetConnected(..))));
+}
+
+#[tokio::test]
+async fn list_repos_pinned_first_success() {
+    let (repo, user_id, repo_id1) = user_repo_test_helper::get_mock_repo_with_starter();
+    let repo_id2 = Uuid::new_v4();
+    let mut pinned = LinkMetadataDto::new(None);
+    pinned.pinned = true;
+
+    let _ = repo
+        .add_pair_with_metadata(&user_id, &repo_id1, &pinned)
+        .await;
+    let _ = repo.add_pair(&user_id, &repo_id2).await;
+    let res = repo.list_pairs(&user_id, None, None).await;
+
+    assert_eq!(res.unwrap(), (vec![repo_id1, repo_id2], 2));
+}
+
+#[tokio::test]
+async fn list_repos_page_after_pinned_success() {
+    let (repo, user_id, repo_id1) = user_repo_test_helper::get_mock_repo_with_starter();
+    let repo_id2 = Uuid::new_v4();
+    let repo_id3 = Uuid::new_v4();
+    let _ = repo.add_pairs(&user_id, vec![&repo_id1, &repo_id2]).await;
+    let _ = repo.add_pair(&user_id, &repo_id3).await;
+
+    let mut metadata = repo.get_metadata(&user_id, &repo_id3).await.unwrap();
+    metadata.pinned = true;
+    let _ = repo.update_metadata(&user_id, &repo_id3, &metadata).await;
+
+    let first = repo.list_pairs(&user_id, Some(2), None).await.unwrap();
+    let second = repo.list_pairs(&user_id, Some(2), Some(2)).await.unwrap();
+
+    assert_eq!(first, (vec![repo_id3, repo_id2], 3));
+    assert_eq!(second, (vec![repo_id1], 3));
+}
+
+#[tokio::test]
+async fn delete_pinned_repo_unpins_success() {
+    let (repo, user_id, repo_id) = user_repo_test_helper::get_mock_repo_with_starter();
+    let mut pinned = LinkMetadataDto::new(None);
+    pinned.pinned = true;
+
+    let _ = repo
+        .add_pair_with_metadata(&user_id, &repo_id, &pinned)
+        .await;
+    let _ = repo.delete_pair(&user_id, &repo_id).await;
+    let res = repo.list_pairs(&user_id, None, None).await;
+
+    assert_eq!(res.unwrap(), (vec![], 0));
 }
@@ -0,0 +1,257 @@
+#[cfg(test)]
+mod tests;
+
+use std::str::FromStr;
+
+use async_trait::async_trait;
+use mongodb::bson::oid::ObjectId;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{
+    ActiveValue::Set, ColumnTrait, DbConn, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, SqlErr,
+};
+use uuid::Uuid;
+
+use dto::user_repo_dto::LinkMetadataDto;
+use entity::user_repository_link::Entity as UserRepositoryLink;
+use entity::user_repository_link::{self, Model};
+
+use crate::dao::error::RepoError::{
+    AlreadyConnected, InternalConcrete, NotConnectedWithAny, NotYetConnected,
+};
+use crate::dao::error::{Entity, RepoResult};
+use crate::dao::{ConnRepositoryTrait, UserRepoRepositoryTrait};
+
+pub struct UserRepoSqlRepository {
+    conn: DbConn,
+}
+
+impl UserRepoSqlRepository {
+    pub fn new(conn: DbConn) -> Self {
+        Self { conn }
+    }
+
+    /// Copies every pair of `source` into the table, pairs already present
+    /// are left untouched. Returns the number of copied pairs
+    pub async fn import_pairs(&self, source: &dyn UserRepoRepositoryTrait) -> RepoResult<u64> {
+        let mut imported = 0;
+        for (user_id, repo_id) in source.list_all_pairs().await? {
+            let metadata = source.get_metadata(&user_id, &repo_id).await?;
+            imported +=
+                UserRepositoryLink::insert(Self::to_active_model(&user_id, &repo_id, &metadata))
+                    .on_conflict(
+                        OnConflict::column(user_repository_link::Column::RepoId)
+                            .do_nothing()
+                            .to_owned(),
+                    )
+                    .exec_without_returning(&self.conn)
+                    .await?;
+        }
+        Ok(imported)
+    }
+
+    fn to_active_model(
+        user_id: &ObjectId,
+        repo_id: &Uuid,
+        metadata: &LinkMetadataDto,
+    ) -> user_repository_link::ActiveModel {
+        user_repository_link::ActiveModel {
+            repo_id: Set(*repo_id),
+            user_id: Set(user_id.to_hex()),
+            version: Set(metadata.version as i16),
+            linked_at: Set(metadata.linked_at),
+            linked_by: Set(metadata.linked_by.clone()),
+            role: Set(metadata.role),
+            pinned: Set(metadata.pinned),
+        }
+    }
+
+    fn to_metadata(model: &Model) -> LinkMetadataDto {
+        LinkMetadataDto {
+            version: model.version as u8,
+            linked_at: model.linked_at,
+            linked_by: model.linked_by.clone(),
+            role: model.role,
+            pinned: model.pinned,
+        }
+    }
+
+    fn parse_user_id(model: &Model) -> RepoResult<ObjectId> {
+        ObjectId::from_str(&model.user_id)
+            .map_err(|_| InternalConcrete(format!("Wrong user_id: {}", model.user_id)))
+    }
+
+    async fn get_link_model(&self, user_id: &ObjectId, repo_id: &Uuid) -> RepoResult<Model> {
+        UserRepositoryLink::find_by_id(*repo_id)
+            .filter(user_repository_link::Column::UserId.eq(user_id.to_hex()))
+            .one(&self.conn)
+            .await?
+            .ok_or(NotYetConnected(Entity::User, Entity::Repository))
+    }
+}
+
+#[async_trait]
+impl ConnRepositoryTrait<ObjectId, Uuid> for UserRepoSqlRepository {
+    async fn list_pairs(
+        &self,
+        user_id: &ObjectId,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> RepoResult<(Vec<Uuid>, u64)> {
+        let (pairs, count) = self.list_pairs_with_metadata(user_id, take, offset).await?;
+        Ok((pairs.into_iter().map(|t| t.0).collect(), count))
+    }
+
+    async fn get_key(&self, repo_id: &Uuid) -> RepoResult<ObjectId> {
+        let model = UserRepositoryLink::find_by_id(*repo_id)
+            .one(&self.conn)
+            .await?
+            .ok_or(NotConnectedWithAny(
+                Entity::Repository,
+                repo_id.to_string(),
+                Entity::User,
+            ))?;
+        Self::parse_user_id(&model)
+    }
+
+    async fn list_all_pairs(&self) -> RepoResult<Vec<(ObjectId, Uuid)>> {
+        UserRepositoryLink::find()
+            .all(&self.conn)
+            .await?
+            .iter()
+            .map(|model| Ok((Self::parse_user_id(model)?, model.repo_id)))
+            .collect()
+    }
+
+    async fn add_pair(&self, user_id: &ObjectId, repo_id: &Uuid) -> RepoResult<()> {
+        self.add_pair_with_metadata(user_id, repo_id, &LinkMetadataDto::new(None))
+            .await
+    }
+
+    async fn delete_pair(&self, user_id: &ObjectId, repo_id: &Uuid) -> RepoResult<()> {
+        let res = UserRepositoryLink::delete_many()
+            .filter(user_repository_link::Column::RepoId.eq(*repo_id))
+            .filter(user_repository_link::Column::UserId.eq(user_id.to_hex()))
+            .exec(&self.conn)
+            .await?;
+        if res.rows_affected == 0 {
+            Err(NotYetConnected(Entity::User, Entity::Repository))?
+        }
+        Ok(())
+    }
+
+    async fn transfer_pair(
+        &self,
+        from_user_id: &ObjectId,
+        to_user_id: &ObjectId,
+        repo_id: &Uuid,
+    ) -> RepoResult<()> {
+        let model = self.get_link_model(from_user_id, repo_id).await?;
+        if from_user_id == to_user_id {
+            Err(AlreadyConnected(Entity::User, Entity::Repository))?
+        }
+
+        // A single update keeps the repo connected to exactly one user,
+        // the link keeps its metadata
+        let res = UserRepositoryLink::update_many()
+            .set(Self::to_active_model(
+                to_user_id,
+                repo_id,
+                &Self::to_metadata(&model),
+            ))
+            .filter(user_repository_link::Column::RepoId.eq(*repo_id))
+            .filter(user_repository_link::Column::UserId.eq(from_user_id.to_hex()))
+            .exec(&self.conn)
+            .await?;
+        if res.rows_affected == 0 {
+            Err(NotYetConnected(Entity::User, Entity::Repository))?
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl UserRepoRepositoryTrait for UserRepoSqlRepository {
+    async fn add_pair_with_metadata(
+        &self,
+        user_id: &ObjectId,
+        repo_id: &Uuid,
+        metadata: &LinkMetadataDto,
+    ) -> RepoResult<()> {
+        let res = UserRepositoryLink::insert(Self::to_active_model(user_id, repo_id, metadata))
+            .exec_without_returning(&self.conn)
+            .await;
+        match res {
+            Ok(_) => Ok(()),
+            Err(err) => match err.sql_err() {
+                Some(SqlErr::UniqueConstraintViolation(_)) => {
+                    Err(AlreadyConnected(Entity::User, Entity::Repository))
+                }
+                _ => Err(err.into()),
+            },
+        }
+    }
+
+    async fn list_pairs_with_metadata(
+        &self,
+        user_id: &ObjectId,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> RepoResult<(Vec<(Uuid, LinkMetadataDto)>, u64)> {
+        let filter = UserRepositoryLink::find()
+            .filter(user_repository_link::Column::UserId.eq(user_id.to_hex()));
+        let count = filter.clone().count(&self.conn).await?;
+
+        let mut query = filter
+            .order_by_desc(user_repository_link::Column::Pinned)
+            .order_by_asc(user_repository_link::Column::RepoId)
+            .offset(offset);
+        if let Some(take) = take.filter(|&n| n != 0) {
+            query = query.limit(take);
+        }
+
+        let pairs = query
+            .all(&self.conn)
+            .await?
+            .iter()
+            .map(|model| (model.repo_id, Self::to_metadata(model)))
+            .collect();
+        Ok((pairs, count))
+    }
+
+    async fn get_metadata(
+        &self,
+        user_id: &ObjectId,
+        repo_id: &Uuid,
+    ) -> RepoResult<LinkMetadataDto> {
+        let model = self.get_link_model(user_id, repo_id).await?;
+        Ok(Self::to_metadata(&model))
+    }
+
+    async fn update_metadata(
+        &self,
+        user_id: &ObjectId,
+        repo_id: &Uuid,
+        metadata: &LinkMetadataDto,
+    ) -> RepoResult<()> {
+        let res = UserRepositoryLink::update_many()
+            .set(Self::to_active_model(user_id, repo_id, metadata))
+            .filter(user_repository_link::Column::RepoId.eq(*repo_id))
+            .filter(user_repository_link::Column::UserId.eq(user_id.to_hex()))
+            .exec(&self.conn)
+            .await?;
+        if res.rows_affected == 0 {
+            Err(NotYetConnected(Entity::User, Entity::Repository))?
+        }
+        Ok(())
+    }
+
+    /// A link is a single row, there is no reverse index to fall out of sync
+    async fn list_orphaned_pairs(&self) -> RepoResult<Vec<(ObjectId, Uuid)>> {
+        Ok(vec![])
+    }
+
+    async fn delete_orphaned_pair(&self, _user_id: &ObjectId, _repo_id: &Uuid) -> RepoResult<()> {
+        Ok(())
+    }
+}
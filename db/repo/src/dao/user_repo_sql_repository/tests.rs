@@ -0,0 +1,92 @@
+use mongodb::bson::oid::ObjectId;
+use sea_orm::{DatabaseBackend, MockDatabase, MockExecResult};
+use uuid::Uuid;
+
+use entity::user_repository_link::Model;
+
+use crate::dao::error::RepoError;
+use crate::dao::{ConnRepositoryTrait, UserRepoRepositoryTrait};
+use crate::utils::user_repo::user_repo_test_helper;
+
+use super::UserRepoSqlRepository;
+
+fn get_stub_repo(models: Vec<Vec<Model>>, rows_affected: Vec<u64>) -> UserRepoSqlRepository {
+    let conn = MockDatabase::new(DatabaseBackend::Postgres)
+        .append_query_results(models)
+        .append_exec_results(
+            rows_affected
+                .into_iter()
+                .map(|rows_affected| MockExecResult {
+                    last_insert_id: 0,
+                    rows_affected,
+                }),
+        )
+        .into_connection();
+    UserRepoSqlRepository::new(conn)
+}
+
+#[tokio::test]
+async fn get_key_success() {
+    let (user_id, repo_id) = (ObjectId::new(), Uuid::new_v4());
+    let model = user_repo_test_helper::get_link_model(&user_id, &repo_id);
+    let repo = get_stub_repo(vec![vec![model]], vec![]);
+
+    let res = repo.get_key(&repo_id).await.unwrap();
+
+    assert_eq!(res, user_id);
+}
+
+#[tokio::test]
+async fn get_key_not_connected_failure() {
+    let repo = get_stub_repo(vec![vec![]], vec![]);
+
+    let res = repo.get_key(&Uuid::new_v4()).await;
+
+    assert!(matches!(res, Err(RepoError::NotConnectedWithAny(..))));
+}
+
+#[tokio::test]
+async fn get_metadata_success() {
+    let (user_id, repo_id) = (ObjectId::new(), Uuid::new_v4());
+    let model = user_repo_test_helper::get_link_model(&user_id, &repo_id);
+    let repo = get_stub_repo(vec![vec![model.clone()]], vec![]);
+
+    let metadata = repo.get_metadata(&user_id, &repo_id).await.unwrap();
+
+    assert_eq!(metadata.linked_by, model.linked_by);
+    assert_eq!(metadata.linked_at, model.linked_at);
+    assert_eq!(metadata.version as i16, model.version);
+}
+
+#[tokio::test]
+async fn delete_not_connected_failure() {
+    let repo = get_stub_repo(vec![], vec![0]);
+
+    let res = repo.delete_pair(&ObjectId::new(), &Uuid::new_v4()).await;
+
+    assert!(matches!(res, Err(RepoError::NotYetConnected(..))));
+}
+
+#[tokio::test]
+async fn transfer_to_same_user_failure() {
+    let (user_id, repo_id) = (ObjectId::new(), Uuid::new_v4());
+    let model = user_repo_test_helper::get_link_model(&user_id, &repo_id);
+    let repo = get_stub_repo(vec![vec![model]], vec![]);
+
+    let res = repo.transfer_pair(&user_id, &user_id, &repo_id).await;
+
+    assert!(matches!(res, Err(RepoError::AlreadyConnected(..))));
+}
+
+#[tokio::test]
+async fn import_pairs_skips_existing() {
+    let source = user_repo_test_helper::get_mock_repo();
+    let user_id = ObjectId::new();
+    source.add_pair(&user_id, &Uuid::new_v4()).await.unwrap();
+    source.add_pair(&user_id, &Uuid::new_v4()).await.unwrap();
+    let repo = get_stub_repo(vec![], vec![1, 0]);
+
+    let imported = repo.import_pairs(&source).await.unwrap();
+
+    assert_eq!(imported, 1);
+}
@@ -0,0 +1,170 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mongodb::bson::doc;
+use mongodb::bson::oid::ObjectId;
+use mongodb::options::UpdateModifications;
+
+use collection::webhook_delivery::WebhookDelivery;
+use collection::MongoCollection;
+
+use crate::dao::error::{Entity, RepoError, RepoResult};
+use crate::dao::{PersistentRepositoryTrait, WebhookDeliveryRepositoryTrait};
+use dto::webhook_dto::{CreateWebhookDeliveryDto, WebhookDeliveryDto};
+use dto::DtoList;
+
+#[cfg(test)]
+mod tests;
+
+pub struct WebhookDeliveryRepository {
+    collection: Arc<dyn MongoCollection<WebhookDelivery>>,
+}
+
+impl WebhookDeliveryRepository {
+    pub fn new(collection: Arc<dyn MongoCollection<WebhookDelivery>>) -> Self {
+        Self { collection }
+    }
+
+    fn date_to_bson(date: DateTime<Utc>) -> mongodb::bson::DateTime {
+        mongodb::bson::DateTime::from_millis(date.timestamp_millis())
+    }
+
+    async fn set(&self, id: &ObjectId, set: mongodb::bson::Document) -> RepoResult<()> {
+        self.collection
+            .update_one(
+                doc! {"_id": id},
+                UpdateModifications::Document(doc! {"$set": set}),
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PersistentRepositoryTrait<CreateWebhookDeliveryDto, WebhookDeliveryDto, ObjectId>
+    for WebhookDeliveryRepository
+{
+    async fn create(&self, dto: CreateWebhookDeliveryDto) -> RepoResult<WebhookDeliveryDto> {
+        let delivery = WebhookDelivery::from(dto);
+        let id = self.collection.insert_one(delivery, None).await?;
+        Ok(self.get(&id).await?)
+    }
+
+    async fn get(&self, id: &ObjectId) -> RepoResult<WebhookDeliveryDto> {
+        let delivery = self
+            .collection
+            .find_one(Some(doc! {"_id": id}), None)
+            .await?
+            .ok_or(RepoError::NotFoundWithObjectId(
+                *id,
+                Entity::WebhookDelivery,
+            ))?;
+        Ok(delivery.into())
+    }
+
+    async fn list(
+        &self,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> RepoResult<DtoList<WebhookDeliveryDto>> {
+        let pipeline = vec![doc! {"$sort": {"_id": -1}}];
+
+        let dtos = self
+            .collection
+            .paginate_pipeline_and_collect(pipeline, take, offset, None)
+            .await?
+            .into_iter()
+            .map(|d| d.into())
+            .collect();
+
+        let count = self.collection.count_documents(None, None).await?;
+        Ok(DtoList::new(dtos, count, take, offset))
+    }
+}
+
+#[async_trait]
+impl WebhookDeliveryRepositoryTrait for WebhookDeliveryRepository {
+    async fn list_by_subscription(
+        &self,
+        subscription_id: &ObjectId,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> RepoResult<DtoList<WebhookDeliveryDto>> {
+        let filter = doc! {"subscription_id": subscription_id};
+        let pipeline = vec![doc! {"$match": filter.clone()}, doc! {"$sort": {"_id": -1}}];
+
+        let dtos = self
+            .collection
+            .paginate_pipeline_and_collect(pipeline, take, offset, None)
+            .await?
+            .into_iter()
+            .map(|d| d.into())
+            .collect();
+
+        let count = self.collection.count_documents(Some(filter), None).await?;
+        Ok(DtoList::new(dtos, count, take, offset))
+    }
+    async fn list_due(&self, now: DateTime<Utc>, take: u64) -> RepoResult<Vec<WebhookDeliveryDto>> {
+        let now = Self::date_to_bson(now);
+        let pipeline = vec![
+            doc! {"$match": {
+                "attempted_at": null,
+                "scheduled_at": {"$lte": now},
+                "$or": [{"locked_until": null}, {"locked_until": {"$lt": now}}],
+            }},
+            doc! {"$sort": {"scheduled_at": 1}},
+        ];
+        Ok(self
+            .collection
+            .paginate_pipeline_and_collect(pipeline, Some(take), None, None)
+            .await?
+            .into_iter()
+            .map(|d| d.into())
+            .collect())
+    }
+
+    async fn claim(&self, id: &ObjectId, locked_until: DateTime<Utc>) -> RepoResult<()> {
+        self.set(id, doc! {"locked_until": Self::date_to_bson(locked_until)})
+            .await
+    }
+
+    async fn complete(
+        &self,
+        id: &ObjectId,
+        status_code: Option<u16>,
+        error: Option<String>,
+    ) -> RepoResult<WebhookDeliveryDto> {
+        let mut set = doc! {
+            "success": error.is_none() && WebhookDelivery::is_success(status_code),
+            "locked_until": null,
+            "attempted_at": Utc::now().to_rfc3339(),
+        };
+        if let Some(status_code) = status_code {
+            set.insert("status_code", i32::from(status_code));
+        }
+        if let Some(error) = error {
+            set.insert("error", error);
+        }
+        self.set(id, set).await?;
+        self.get(id).await
+    }
+
+    async fn last_attempt(
+        &self,
+        subscription_id: &ObjectId,
+        event_id: &ObjectId,
+    ) -> RepoResult<u32> {
+        let pipeline = vec![
+            doc! {"$match": {"subscription_id": subscription_id, "event_id": event_id}},
+            doc! {"$sort": {"attempt": -1}},
+        ];
+        Ok(self
+            .collection
+            .paginate_pipeline_and_collect(pipeline, Some(1), None, None)
+            .await?
+            .first()
+            .map_or(0, |d| d.attempt))
+    }
+}
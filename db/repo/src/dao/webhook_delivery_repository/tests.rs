@@ -0,0 +1,133 @@
+use chrono::{Duration, Utc};
+use mongodb::bson::oid::ObjectId;
+
+use crate::dao::{PersistentRepositoryTrait, WebhookDeliveryRepositoryTrait};
+use crate::utils::webhook::webhook_test_helper;
+
+#[tokio::test]
+async fn complete_computes_success() {
+    let repo = webhook_test_helper::get_delivery_repo();
+    let subscription_id = ObjectId::new();
+    let first = repo
+        .create(webhook_test_helper::get_create_delivery_dto(
+            subscription_id,
+            1,
+        ))
+        .await
+        .unwrap();
+    let second = repo
+        .create(webhook_test_helper::get_create_delivery_dto(
+            subscription_id,
+            1,
+        ))
+        .await
+        .unwrap();
+
+    let delivered = repo.complete(&first.id, Some(204), None).await.unwrap();
+    let rejected = repo.complete(&second.id, Some(500), None).await.unwrap();
+
+    assert!(first.attempted_at.is_none());
+    assert!(delivered.success);
+    assert!(delivered.attempted_at.is_some());
+    assert!(!rejected.success);
+    assert_eq!(rejected.status_code, Some(500));
+}
+
+#[tokio::test]
+async fn list_by_subscription_latest_first() {
+    let repo = webhook_test_helper::get_delivery_repo();
+    let subscription_id = ObjectId::new();
+    for attempt in 1..=3 {
+        repo.create(webhook_test_helper::get_create_delivery_dto(
+            subscription_id,
+            attempt,
+        ))
+        .await
+        .unwrap();
+    }
+    repo.create(webhook_test_helper::get_create_delivery_dto(
+        ObjectId::new(),
+        1,
+    ))
+    .await
+    .unwrap();
+
+    let deliveries = repo
+        .list_by_subscription(&subscription_id, Some(2), None)
+        .await
+        .unwrap();
+
+    assert_eq!(deliveries.count, 3);
+    let attempts: Vec<_> = deliveries.dtos.iter().map(|d| d.attempt).collect();
+    assert_eq!(attempts, vec![3, 2]);
+}
+
+#[tokio::test]
+async fn list_due_skips_attempted_claimed_and_later() {
+    let repo = webhook_test_helper::get_delivery_repo();
+    let subscription_id = ObjectId::new();
+    let mut ids = vec![];
+    for attempt in 1..=4 {
+        let delivery = repo
+            .create(webhook_test_helper::get_create_delivery_dto(
+                subscription_id,
+                attempt,
+            ))
+            .await
+            .unwrap();
+        ids.push(delivery.id);
+    }
+    let mut later = webhook_test_helper::get_create_delivery_dto(subscription_id, 5);
+    later.scheduled_at = Utc::now() + Duration::minutes(1);
+    repo.create(later).await.unwrap();
+
+    repo.complete(&ids[0], Some(500), None).await.unwrap();
+    repo.claim(&ids[1], Utc::now() + Duration::minutes(1))
+        .await
+        .unwrap();
+    repo.claim(&ids[2], Utc::now() - Duration::minutes(1))
+        .await
+        .unwrap();
+    let due = repo.list_due(Utc::now(), 10).await.unwrap();
+    let limited = repo.list_due(Utc::now(), 1).await.unwrap();
+
+    let due: Vec<_> = due.iter().map(|d| d.id).collect();
+    assert_eq!(
+        due,
+        vec![ids[2], ids[3]],
+        "Expired claims must be due again"
+    );
+    assert_eq!(limited.len(), 1);
+}
+
+#[tokio::test]
+async fn last_attempt_of_event() {
+    let repo = webhook_test_helper::get_delivery_repo();
+    let subscription_id = ObjectId::new();
+    let first = webhook_test_helper::get_create_delivery_dto(subscription_id, 1);
+    let event_id = first.event_id;
+    repo.create(first).await.unwrap();
+    for attempt in [3, 2] {
+        let mut dto = webhook_test_helper::get_create_delivery_dto(subscription_id, attempt);
+        dto.event_id = event_id;
+        repo.create(dto).await.unwrap();
+    }
+    repo.create(webhook_test_helper::get_create_delivery_dto(
+        subscription_id,
+        7,
+    ))
+    .await
+    .unwrap();
+
+    let last = repo
+        .last_attempt(&subscription_id, &event_id)
+        .await
+        .unwrap();
+    let none = repo
+        .last_attempt(&subscription_id, &ObjectId::new())
+        .await
+        .unwrap();
+
+    assert_eq!(last, 3);
+    assert_eq!(none, 0);
+}
@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use mongodb::bson::oid::ObjectId;
+use mongodb::bson::{doc, to_bson};
+
+use collection::webhook_subscription::{WebhookEventType, WebhookSubscription};
+use collection::MongoCollection;
+
+use crate::dao::error::{Entity, RepoError, RepoResult};
+use crate::dao::{PersistentRepositoryTrait, WebhookSubscriptionRepositoryTrait};
+use dto::webhook_dto::{CreateWebhookSubscriptionDto, WebhookSubscriptionDto};
+use dto::DtoList;
+
+#[cfg(test)]
+mod tests;
+
+pub struct WebhookSubscriptionRepository {
+    collection: Arc<dyn MongoCollection<WebhookSubscription>>,
+}
+
+impl WebhookSubscriptionRepository {
+    pub fn new(collection: Arc<dyn MongoCollection<WebhookSubscription>>) -> Self {
+        Self { collection }
+    }
+}
+
+#[async_trait]
+impl PersistentRepositoryTrait<CreateWebhookSubscriptionDto, WebhookSubscriptionDto, ObjectId>
+    for WebhookSubscriptionRepository
+{
+    async fn create(
+        &self,
+        dto: CreateWebhookSubscriptionDto,
+    ) -> RepoResult<WebhookSubscriptionDto> {
+        let subscription = WebhookSubscription::from(dto);
+        let id = self.collection.insert_one(subscription, None).await?;
+        Ok(self.get(&id).await?)
+    }
+
+    async fn get(&self, id: &ObjectId) -> RepoResult<WebhookSubscriptionDto> {
+        let subscription = self
+            .collection
+            .find_one(Some(doc! {"_id": id}), None)
+            .await?
+            .ok_or(RepoError::NotFoundWithObjectId(
+                *id,
+                Entity::WebhookSubscription,
+            ))?;
+        Ok(subscription.into())
+    }
+
+    async fn list(
+        &self,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> RepoResult<DtoList<WebhookSubscriptionDto>> {
+        let pipeline = vec![doc! {"$sort": {"_id": 1}}];
+
+        let dtos = self
+            .collection
+            .paginate_pipeline_and_collect(pipeline, take, offset, None)
+            .await?
+            .into_iter()
+            .map(|s| s.into())
+            .collect();
+
+        let count = self.collection.count_documents(None, None).await?;
+        Ok(DtoList::new(dtos, count, take, offset))
+    }
+}
+
+#[async_trait]
+impl WebhookSubscriptionRepositoryTrait for WebhookSubscriptionRepository {
+    async fn delete(&self, id: &ObjectId) -> RepoResult<WebhookSubscriptionDto> {
+        let subscription = self.get(id).await?;
+        self.collection.delete_many(doc! {"_id": id}, None).await?;
+        Ok(subscription)
+    }
+
+    async fn list_by_event(
+        &self,
+        event_type: WebhookEventType,
+    ) -> RepoResult<Vec<WebhookSubscriptionDto>> {
+        let event_type =
+            to_bson(&event_type).map_err(|err| RepoError::InternalConcrete(err.to_string()))?;
+        let pipeline = vec![doc! {"$match": {"events": event_type}}];
+        Ok(self
+            .collection
+            .paginate_pipeline_and_collect(pipeline, None, None, None)
+            .await?
+            .into_iter()
+            .map(|s| s.into())
+            .collect())
+    }
+}
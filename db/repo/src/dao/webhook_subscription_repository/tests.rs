@@ -0,0 +1,51 @@
+use collection::webhook_subscription::WebhookEventType;
+
+use crate::dao::error::RepoError;
+use crate::dao::{PersistentRepositoryTrait, WebhookSubscriptionRepositoryTrait};
+use crate::utils::webhook::webhook_test_helper;
+
+#[tokio::test]
+async fn list_by_event_success() {
+    let repo = webhook_test_helper::get_subscription_repo();
+    let links = repo
+        .create(webhook_test_helper::get_create_dto(vec![
+            WebhookEventType::LinkCreated,
+            WebhookEventType::LinkDeleted,
+        ]))
+        .await
+        .unwrap();
+    repo.create(webhook_test_helper::get_create_dto(vec![
+        WebhookEventType::RepoCreated,
+    ]))
+    .await
+    .unwrap();
+
+    let matching = repo
+        .list_by_event(WebhookEventType::LinkDeleted)
+        .await
+        .unwrap();
+    let none = repo
+        .list_by_event(WebhookEventType::UserDeleted)
+        .await
+        .unwrap();
+
+    assert_eq!(matching, vec![links]);
+    assert!(none.is_empty());
+}
+
+#[tokio::test]
+async fn delete_success() {
+    let repo = webhook_test_helper::get_subscription_repo();
+    let created = repo
+        .create(webhook_test_helper::get_create_dto(vec![
+            WebhookEventType::LinkCreated,
+        ]))
+        .await
+        .unwrap();
+
+    let deleted = repo.delete(&created.id).await.unwrap();
+    let res = repo.get(&created.id).await;
+
+    assert_eq!(deleted, created);
+    assert!(matches!(res, Err(RepoError::NotFoundWithObjectId(..))));
+}
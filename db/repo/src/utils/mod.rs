@@ -1,3 +1,6 @@
 pub mod repository;
 pub mod user;
+pub mod user_export;
 pub mod user_repo;
+pub mod user_repo_info;
+pub mod webhook;
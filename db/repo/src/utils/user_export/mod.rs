@@ -0,0 +1 @@
+pub mod user_export_test_helper;
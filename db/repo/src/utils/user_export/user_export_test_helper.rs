@@ -0,0 +1,27 @@
+use std::sync::Arc;
+
+use mongodb::bson::oid::ObjectId;
+use object_store::memory::InMemory;
+
+use crate::dao::user_export_repository::UserExportRepository;
+use crate::utils::user::user_test_helper;
+use dto::user_export_dto::{UserExportArchiveDto, UserExportDto};
+
+pub fn get_mock_repo() -> UserExportRepository {
+    let store = Arc::new(InMemory::new());
+    UserExportRepository::new(store)
+}
+
+pub fn get_manifest() -> UserExportDto {
+    UserExportDto::new(ObjectId::new())
+}
+
+pub fn get_archive(mut manifest: UserExportDto) -> UserExportArchiveDto {
+    manifest.complete(0, 0);
+    UserExportArchiveDto {
+        manifest,
+        user: user_test_helper::get_created_dto1(),
+        repos: vec![],
+        user_repo_infos: vec![],
+    }
+}
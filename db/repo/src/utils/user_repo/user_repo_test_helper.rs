@@ -9,10 +9,16 @@ use crate::utils::repository::repository_test_helper;
 use crate::utils::user::user_test_helper;
 use dto::repo_dto::CreateUpdateRepoDto;
 use dto::user_dto::CreateUserDto;
+use dto::user_repo_dto::LinkMetadataDto;
+use entity::user_repository_link;
 
 pub fn get_mock_repo() -> UserRepoRepository {
+    get_mock_repo_with_store().0
+}
+
+pub fn get_mock_repo_with_store() -> (UserRepoRepository, Arc<InMemory>) {
     let store = Arc::new(InMemory::new());
-    UserRepoRepository::new(store)
+    (UserRepoRepository::new(Arc::clone(&store) as _), store)
 }
 
 pub fn get_mock_repo_with_starter() -> (UserRepoRepository, ObjectId, Uuid) {
@@ -27,3 +33,16 @@ pub fn get_create_dtos() -> (CreateUserDto, CreateUpdateRepoDto) {
     let user_create_dto = user_test_helper::get_create_dto1();
     (user_create_dto, repo_create_dto)
 }
+
+pub fn get_link_model(user_id: &ObjectId, repo_id: &Uuid) -> user_repository_link::Model {
+    let metadata = LinkMetadataDto::new(Some("tester".to_string()));
+    user_repository_link::Model {
+        repo_id: *repo_id,
+        user_id: user_id.to_hex(),
+        version: metadata.version as i16,
+        linked_at: metadata.linked_at,
+        linked_by: metadata.linked_by,
+        role: metadata.role,
+        pinned: metadata.pinned,
+    }
+}
@@ -0,0 +1 @@
+pub mod user_repo_info_test_helper;
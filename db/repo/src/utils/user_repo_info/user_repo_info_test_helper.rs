@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, Utc};
+use mongodb::bson::oid::ObjectId;
+use object_store::memory::InMemory;
+use uuid::Uuid;
+
+use collection::user_repo_info::{TestUserRepoInfoCollection, UserRepoInfo, UserRepoInfoOperation};
+use collection::MongoCollection;
+use dto::user_repo_info_dto::UserRepoInfoDto;
+
+use crate::dao::user_repo_info_archive_repository::UserRepoInfoArchiveRepository;
+use crate::dao::user_repo_info_repository::UserRepoInfoRepository;
+
+pub fn get_archive_repo() -> UserRepoInfoArchiveRepository {
+    UserRepoInfoArchiveRepository::new(Arc::new(InMemory::new()))
+}
+
+/// Repository with a record executed each day of the last `days`, the oldest goes first
+pub async fn get_filled_repo(days: i64) -> UserRepoInfoRepository {
+    let collection = TestUserRepoInfoCollection::default();
+    for day in (1..=days).rev() {
+        let info = UserRepoInfo {
+            executed_at: Utc::now() - Duration::days(day),
+            ..UserRepoInfo::new(
+                ObjectId::new(),
+                Uuid::new_v4(),
+                UserRepoInfoOperation::CreateLink,
+            )
+        };
+        collection.insert_one(info, None).await.unwrap();
+    }
+    UserRepoInfoRepository::new(Arc::new(collection))
+}
+
+/// Repository with the records executed today
+pub async fn get_repo_with(records: Vec<UserRepoInfo>) -> UserRepoInfoRepository {
+    let collection = TestUserRepoInfoCollection::default();
+    for info in records {
+        collection.insert_one(info, None).await.unwrap();
+    }
+    UserRepoInfoRepository::new(Arc::new(collection))
+}
+
+pub fn get_records(executed_at: &[DateTime<Utc>]) -> Vec<UserRepoInfoDto> {
+    executed_at
+        .iter()
+        .map(|&executed_at| {
+            UserRepoInfo {
+                id: Some(ObjectId::new()),
+                executed_at,
+                ..UserRepoInfo::new(
+                    ObjectId::new(),
+                    Uuid::new_v4(),
+                    UserRepoInfoOperation::DeleteLink,
+                )
+            }
+            .into()
+        })
+        .collect()
+}
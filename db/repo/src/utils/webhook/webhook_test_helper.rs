@@ -0,0 +1,43 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use mongodb::bson::oid::ObjectId;
+
+use collection::webhook_delivery::TestWebhookDeliveryCollection;
+use collection::webhook_subscription::{TestWebhookSubscriptionCollection, WebhookEventType};
+use dto::webhook_dto::{CreateWebhookDeliveryDto, CreateWebhookSubscriptionDto};
+
+use crate::dao::webhook_delivery_repository::WebhookDeliveryRepository;
+use crate::dao::webhook_subscription_repository::WebhookSubscriptionRepository;
+
+pub fn get_subscription_repo() -> WebhookSubscriptionRepository {
+    WebhookSubscriptionRepository::new(Arc::new(TestWebhookSubscriptionCollection::default()))
+}
+
+pub fn get_delivery_repo() -> WebhookDeliveryRepository {
+    WebhookDeliveryRepository::new(Arc::new(TestWebhookDeliveryCollection::default()))
+}
+
+pub fn get_create_dto(events: Vec<WebhookEventType>) -> CreateWebhookSubscriptionDto {
+    CreateWebhookSubscriptionDto {
+        url: "http://localhost:8080/webhooks".to_string(),
+        events,
+        secret: "0123456789abcdef".to_string(),
+    }
+}
+
+/// Attempt of a new event, due right away
+pub fn get_create_delivery_dto(
+    subscription_id: ObjectId,
+    attempt: u32,
+) -> CreateWebhookDeliveryDto {
+    CreateWebhookDeliveryDto {
+        subscription_id,
+        event_id: ObjectId::new(),
+        event_type: WebhookEventType::LinkCreated,
+        payload: "{}".to_string(),
+        attempt,
+        scheduled_at: Utc::now(),
+        locked_until: None,
+    }
+}
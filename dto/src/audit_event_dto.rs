@@ -0,0 +1,195 @@
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use collection::audit_event::{
+    deserialize_present, AuditEntity, AuditEvent, AuditFieldChange, AuditOperation,
+};
+
+use crate::utils::{object_id_schema, serialize_option_object_id};
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, async_graphql::SimpleObject, utoipa::ToSchema,
+)]
+pub struct AuditFieldChangeDto {
+    pub field: String,
+
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_present"
+    )]
+    #[schema(value_type = Option<Object>)]
+    pub before: Option<Value>,
+
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_present"
+    )]
+    #[schema(value_type = Option<Object>)]
+    pub after: Option<Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, async_graphql::SimpleObject, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AuditEventDto {
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_object_id"
+    )]
+    #[schema(schema_with = object_id_schema)]
+    pub id: Option<ObjectId>,
+    pub entity: AuditEntity,
+    pub entity_id: String,
+    pub operation: AuditOperation,
+    pub actor: Option<String>,
+    pub changes: Vec<AuditFieldChangeDto>,
+    pub executed_at: DateTime<Utc>,
+}
+
+impl PartialEq for AuditEventDto {
+    fn eq(&self, other: &Self) -> bool {
+        self.entity == other.entity
+            && self.entity_id == other.entity_id
+            && self.operation == other.operation
+            && self.actor == other.actor
+            && self.changes == other.changes
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CreateAuditEventDto {
+    pub entity: AuditEntity,
+    pub entity_id: String,
+    pub operation: AuditOperation,
+    pub actor: Option<String>,
+    pub changes: Vec<AuditFieldChangeDto>,
+}
+
+impl CreateAuditEventDto {
+    /// Builds the event with the fields that differ between `before` and `after`
+    pub fn new<T: Serialize>(
+        entity: AuditEntity,
+        entity_id: impl ToString,
+        operation: AuditOperation,
+        actor: Option<String>,
+        before: Option<&T>,
+        after: Option<&T>,
+    ) -> Self {
+        Self {
+            entity,
+            entity_id: entity_id.to_string(),
+            operation,
+            actor,
+            changes: diff(before, after),
+        }
+    }
+}
+
+/// Top level fields that differ between two serialized snapshots of an entity
+pub fn diff<T: Serialize>(before: Option<&T>, after: Option<&T>) -> Vec<AuditFieldChangeDto> {
+    let to_map = |val: Option<&T>| match val.map(serde_json::to_value) {
+        Some(Ok(Value::Object(map))) => map,
+        _ => serde_json::Map::new(),
+    };
+    let before = to_map(before);
+    let after = to_map(after);
+
+    let fields: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    fields
+        .into_iter()
+        .filter(|field| before.get(*field) != after.get(*field))
+        .map(|field| AuditFieldChangeDto {
+            field: field.clone(),
+            before: before.get(field).cloned(),
+            after: after.get(field).cloned(),
+        })
+        .collect()
+}
+
+#[derive(Deserialize, Debug, Default, async_graphql::InputObject, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditEventFilterDto {
+    #[param(inline)]
+    pub entity: Option<AuditEntity>,
+    pub entity_id: Option<String>,
+
+    #[param(inline)]
+    pub operation: Option<AuditOperation>,
+    pub actor: Option<String>,
+}
+
+impl From<AuditFieldChangeDto> for AuditFieldChange {
+    fn from(
+        AuditFieldChangeDto {
+            field,
+            before,
+            after,
+        }: AuditFieldChangeDto,
+    ) -> Self {
+        Self {
+            field,
+            before,
+            after,
+        }
+    }
+}
+
+impl From<AuditFieldChange> for AuditFieldChangeDto {
+    fn from(
+        AuditFieldChange {
+            field,
+            before,
+            after,
+        }: AuditFieldChange,
+    ) -> Self {
+        Self {
+            field,
+            before,
+            after,
+        }
+    }
+}
+
+impl From<CreateAuditEventDto> for AuditEvent {
+    fn from(
+        CreateAuditEventDto {
+            entity,
+            entity_id,
+            operation,
+            actor,
+            changes,
+        }: CreateAuditEventDto,
+    ) -> Self {
+        let changes = changes.into_iter().map(|c| c.into()).collect();
+        AuditEvent::new(entity, entity_id, operation, actor, changes)
+    }
+}
+
+impl From<AuditEvent> for AuditEventDto {
+    fn from(
+        AuditEvent {
+            id,
+            entity,
+            entity_id,
+            operation,
+            actor,
+            changes,
+            executed_at,
+        }: AuditEvent,
+    ) -> Self {
+        AuditEventDto {
+            id,
+            entity,
+            entity_id,
+            operation,
+            actor,
+            changes: changes.into_iter().map(|c| c.into()).collect(),
+            executed_at,
+        }
+    }
+}
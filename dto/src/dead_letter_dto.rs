@@ -0,0 +1,26 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, utoipa::ToSchema)]
+pub struct DeadLetterDto {
+    /// Message body, invalid UTF-8 sequences are replaced
+    pub payload: String,
+
+    /// Failed deliveries before the message was dead-lettered
+    pub attempts: u32,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, utoipa::ToSchema)]
+pub struct DeadLetterBatchDto {
+    pub queue: String,
+
+    /// How many dead letters were replayed or purged
+    pub count: u32,
+}
+
+#[derive(Deserialize, Debug, Default, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeadLetterLimitDto {
+    /// How many dead letters to take, all of them for replay when absent
+    pub limit: Option<u32>,
+}
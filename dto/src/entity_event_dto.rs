@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+use crate::repo_dto::RepoDto;
+use crate::user_dto::UserDto;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, async_graphql::Enum)]
+pub enum EntityEventKind {
+    Created,
+    Updated,
+    Deleted,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, async_graphql::SimpleObject)]
+pub struct RepoEventDto {
+    pub kind: EntityEventKind,
+    pub repo: RepoDto,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, async_graphql::SimpleObject)]
+pub struct UserEventDto {
+    pub kind: EntityEventKind,
+    pub user: UserDto,
+}
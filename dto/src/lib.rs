@@ -1,19 +1,31 @@
+use audit_event_dto::AuditEventDto;
 use repo_dto::RepoDto;
 use serde::{Deserialize, Serialize};
 use user_dto::UserDto;
-use user_repo_info_dto::UserRepoInfoDto;
+use user_repo_dto::LinkedRepoDto;
+use user_repo_info_dto::{UserRepoInfoArchiveDto, UserRepoInfoDto};
 
+pub mod audit_event_dto;
+pub mod dead_letter_dto;
+pub mod entity_event_dto;
+pub mod outbox_event_dto;
 pub mod repo_dto;
 pub mod user_dto;
+pub mod user_export_dto;
+pub mod user_repo_dto;
 pub mod user_repo_info_dto;
 pub mod utils;
+pub mod webhook_dto;
 
 #[derive(
     Serialize, Deserialize, PartialEq, Debug, async_graphql::SimpleObject, utoipa::ToSchema,
 )]
+#[graphql(concrete(name = "AuditEventDtoList", params(AuditEventDto)))]
 #[graphql(concrete(name = "RepoDtoList", params(RepoDto)))]
+#[graphql(concrete(name = "LinkedRepoDtoList", params(LinkedRepoDto)))]
 #[graphql(concrete(name = "UserDtoList", params(UserDto)))]
 #[graphql(concrete(name = "UserRepoInfoDtoList", params(UserRepoInfoDto)))]
+#[graphql(concrete(name = "UserRepoInfoArchiveDtoList", params(UserRepoInfoArchiveDto)))]
 pub struct DtoList<T>
 where
     T: utoipa::ToSchema + async_graphql::OutputType,
@@ -91,7 +103,7 @@ where
 #[derive(
     Serialize, Deserialize, PartialEq, Debug, async_graphql::SimpleObject, utoipa::ToSchema,
 )]
-#[graphql(concrete(name = "UserToReposDto", params(UserDto, RepoDto)))]
+#[graphql(concrete(name = "UserToReposDto", params(UserDto, LinkedRepoDto)))]
 pub struct OneToManyDto<O, M>
 where
     O: async_graphql::OutputType + utoipa::ToSchema,
@@ -100,6 +112,11 @@ where
 {
     pub one: O,
     pub many: DtoList<M>,
+
+    /// Ids of linked entities that are missing or deleted. They're counted
+    /// in `many` but left out of its page
+    #[serde(default)]
+    pub missing: Vec<String>,
 }
 
 impl<O, M> OneToManyDto<O, M>
@@ -109,6 +126,15 @@ where
     DtoList<M>: async_graphql::OutputType + utoipa::ToSchema,
 {
     pub fn new(one: O, many: DtoList<M>) -> Self {
-        Self { one, many }
+        Self {
+            one,
+            many,
+            missing: vec![],
+        }
+    }
+
+    pub fn with_missing(mut self, missing: Vec<String>) -> Self {
+        self.missing = missing;
+        self
     }
 }
@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+
+use collection::outbox_event::{OutboxEvent, OutboxStatus};
+
+#[derive(Debug, Clone)]
+pub struct OutboxEventDto {
+    pub id: ObjectId,
+    pub queue: String,
+    pub payload: Vec<u8>,
+    pub status: OutboxStatus,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+}
+
+/// Event staged before its write, locked for the writer until `locked_until`
+#[derive(Debug)]
+pub struct CreateOutboxEventDto {
+    pub queue: String,
+    pub payload: Vec<u8>,
+    pub locked_until: DateTime<Utc>,
+}
+
+impl CreateOutboxEventDto {
+    pub fn new(queue: impl ToString, payload: Vec<u8>, locked_until: DateTime<Utc>) -> Self {
+        Self {
+            queue: queue.to_string(),
+            payload,
+            locked_until,
+        }
+    }
+}
+
+impl From<CreateOutboxEventDto> for OutboxEvent {
+    fn from(
+        CreateOutboxEventDto {
+            queue,
+            payload,
+            locked_until,
+        }: CreateOutboxEventDto,
+    ) -> Self {
+        OutboxEvent::new(queue, payload, locked_until)
+    }
+}
+
+impl From<OutboxEvent> for OutboxEventDto {
+    fn from(
+        OutboxEvent {
+            id,
+            queue,
+            payload,
+            status,
+            attempts,
+            last_error,
+            ..
+        }: OutboxEvent,
+    ) -> Self {
+        OutboxEventDto {
+            id: id.unwrap(),
+            queue,
+            payload: payload.bytes,
+            status,
+            attempts,
+            last_error,
+        }
+    }
+}
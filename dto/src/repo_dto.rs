@@ -45,6 +45,33 @@ impl PartialEq for RepoDto {
     }
 }
 
+impl RepoDto {
+    /// The repo as a create with `id` is expected to write it
+    pub fn expected(id: Uuid, dto: &CreateUpdateRepoDto) -> Self {
+        let now = chrono::Utc::now().naive_utc();
+        Self {
+            id,
+            title: dto.title.clone(),
+            description: dto.description.clone(),
+            repo_type: dto.repo_type,
+            stars: 0,
+            created: now,
+            updated: now,
+        }
+    }
+
+    /// The repo as an update with `dto` is expected to leave it
+    pub fn updated_with(&self, dto: &CreateUpdateRepoDto) -> Self {
+        Self {
+            title: dto.title.clone(),
+            description: dto.description.clone(),
+            repo_type: dto.repo_type,
+            updated: chrono::Utc::now().naive_utc(),
+            ..self.clone()
+        }
+    }
+}
+
 impl From<repository::Model> for RepoDto {
     fn from(repo: repository::Model) -> Self {
         let repository::Model {
@@ -73,6 +73,33 @@ pub struct UserDto {
     pub updated: DateTime<Utc>,
 }
 
+impl UserDto {
+    /// The user as a create with `id` is expected to write it
+    pub fn expected(id: ObjectId, dto: &CreateUserDto) -> Self {
+        let now = Utc::now();
+        Self {
+            id: Some(id),
+            email: dto.email.clone(),
+            username: dto.username.clone(),
+            age: dto.age,
+            is_public: dto.is_public,
+            created: now,
+            updated: now,
+        }
+    }
+
+    /// The user as an update with `dto` is expected to leave it
+    pub fn updated_with(&self, dto: &UpdateUserDto) -> Self {
+        Self {
+            username: dto.username.clone(),
+            age: dto.age,
+            is_public: dto.is_public,
+            updated: Utc::now(),
+            ..self.clone()
+        }
+    }
+}
+
 impl From<User> for UserDto {
     fn from(
         User {
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::repo_dto::RepoDto;
+use crate::user_dto::UserDto;
+use crate::user_repo_info_dto::UserRepoInfoDto;
+use crate::utils::{object_id_schema, serialize_object_id};
+
+pub const USER_EXPORT_FORMAT_VERSION: u8 = 1;
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, async_graphql::Enum, utoipa::ToSchema,
+)]
+pub enum UserExportStatus {
+    Pending,
+    Completed,
+    Failed,
+}
+
+/// Manifest of the personal data export. It is stored next to the archive
+/// and is used as the status of the export job
+#[derive(Serialize, Deserialize, Debug, Clone, utoipa::ToSchema)]
+pub struct UserExportDto {
+    pub id: Uuid,
+
+    #[serde(serialize_with = "serialize_object_id")]
+    #[schema(schema_with = object_id_schema)]
+    pub user_id: ObjectId,
+    pub status: UserExportStatus,
+    pub format_version: u8,
+    pub repos_count: Option<u64>,
+    pub user_repo_infos_count: Option<u64>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl UserExportDto {
+    pub fn new(user_id: ObjectId) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            user_id,
+            status: UserExportStatus::Pending,
+            format_version: USER_EXPORT_FORMAT_VERSION,
+            repos_count: None,
+            user_repo_infos_count: None,
+            error: None,
+            created_at: Utc::now(),
+            finished_at: None,
+        }
+    }
+
+    pub fn complete(&mut self, repos_count: u64, user_repo_infos_count: u64) {
+        self.status = UserExportStatus::Completed;
+        self.repos_count = Some(repos_count);
+        self.user_repo_infos_count = Some(user_repo_infos_count);
+        self.finished_at = Some(Utc::now());
+    }
+
+    pub fn fail(&mut self, error: String) {
+        self.status = UserExportStatus::Failed;
+        self.error = Some(error);
+        self.finished_at = Some(Utc::now());
+    }
+}
+
+impl PartialEq for UserExportDto {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+            && self.user_id == other.user_id
+            && self.status == other.status
+            && self.repos_count == other.repos_count
+            && self.user_repo_infos_count == other.user_repo_infos_count
+    }
+}
+
+/// All the data that is kept about the user. It is downloaded as a zip with
+/// the manifest and every other field in a JSON file of its own
+#[derive(Serialize, Deserialize, Debug, utoipa::ToSchema)]
+pub struct UserExportArchiveDto {
+    pub manifest: UserExportDto,
+    pub user: UserDto,
+    pub repos: Vec<RepoDto>,
+    pub user_repo_infos: Vec<UserRepoInfoDto>,
+}
@@ -0,0 +1,188 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+pub use entity::LinkRole;
+
+use crate::repo_dto::RepoDto;
+use crate::user_dto::UserDto;
+use crate::utils::{object_id_schema, serialize_object_id};
+
+#[derive(Serialize, Deserialize, Debug, Validate, async_graphql::InputObject, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct BulkUserRepoDto {
+    #[validate(length(min = 1, max = 100, message = "Must contain between 1 and 100 ids"))]
+    pub repo_ids: Vec<Uuid>,
+
+    /// Roll back every applied change if at least one id fails
+    #[serde(default)]
+    #[graphql(default)]
+    pub all_or_nothing: bool,
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, async_graphql::Enum, utoipa::ToSchema,
+)]
+pub enum BulkOperationStatus {
+    Succeeded,
+    Failed,
+    RolledBack,
+    /// The change is still applied, reverting it failed
+    RollbackFailed,
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, PartialEq, async_graphql::SimpleObject, utoipa::ToSchema,
+)]
+pub struct BulkUserRepoOutcomeDto {
+    pub repo_id: Uuid,
+    pub status: BulkOperationStatus,
+    pub repo: Option<RepoDto>,
+    pub error: Option<String>,
+}
+
+impl BulkUserRepoOutcomeDto {
+    pub fn succeeded(repo: RepoDto) -> Self {
+        Self {
+            repo_id: repo.id,
+            status: BulkOperationStatus::Succeeded,
+            repo: Some(repo),
+            error: None,
+        }
+    }
+
+    pub fn failed(repo_id: Uuid, error: String) -> Self {
+        Self {
+            repo_id,
+            status: BulkOperationStatus::Failed,
+            repo: None,
+            error: Some(error),
+        }
+    }
+
+    pub fn is_succeeded(&self) -> bool {
+        self.status == BulkOperationStatus::Succeeded
+    }
+
+    /// Whether the change stays in place after the operation
+    pub fn is_applied(&self) -> bool {
+        matches!(
+            self.status,
+            BulkOperationStatus::Succeeded | BulkOperationStatus::RollbackFailed
+        )
+    }
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, PartialEq, async_graphql::SimpleObject, utoipa::ToSchema,
+)]
+pub struct BulkUserRepoResultDto {
+    pub user: UserDto,
+    pub outcomes: Vec<BulkUserRepoOutcomeDto>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Validate, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TransferRepoDto {
+    #[serde(serialize_with = "serialize_object_id")]
+    #[schema(schema_with = object_id_schema)]
+    pub to_user_id: ObjectId,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, utoipa::ToSchema)]
+pub enum DanglingLinkReason {
+    UserMissing,
+    RepoMissing,
+    BothMissing,
+
+    /// Only the reverse `repos/` entry is left, the link it mirrors is gone
+    LinkMissing,
+}
+
+/// Link that points to a deleted or never existing user or repo
+#[derive(Serialize, Deserialize, Debug, PartialEq, utoipa::ToSchema)]
+pub struct DanglingLinkDto {
+    #[serde(serialize_with = "serialize_object_id")]
+    #[schema(schema_with = object_id_schema)]
+    pub user_id: ObjectId,
+    pub repo_id: Uuid,
+    pub reason: DanglingLinkReason,
+    pub repaired: bool,
+
+    /// Why the repair of this link failed, the rest are repaired anyway
+    pub error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, utoipa::ToSchema)]
+pub struct LinkConsistencyReportDto {
+    /// Links and orphaned reverse entries checked
+    pub checked: u64,
+    pub dangling: Vec<DanglingLinkDto>,
+}
+
+pub const LINK_METADATA_VERSION: u8 = 1;
+
+/// Payload of the link object. Links written before the payload was introduced
+/// are empty and are read with the defaults and `version` 0
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, async_graphql::SimpleObject, utoipa::ToSchema,
+)]
+pub struct LinkMetadataDto {
+    pub version: u8,
+    pub linked_at: DateTime<Utc>,
+
+    /// Named by the client that created the link, it isn't authenticated
+    pub linked_by: Option<String>,
+    pub role: LinkRole,
+    pub pinned: bool,
+}
+
+impl LinkMetadataDto {
+    pub fn new(linked_by: Option<String>) -> Self {
+        Self {
+            version: LINK_METADATA_VERSION,
+            linked_at: Utc::now(),
+            linked_by,
+            role: LinkRole::default(),
+            pinned: false,
+        }
+    }
+
+    pub fn legacy(linked_at: DateTime<Utc>) -> Self {
+        Self {
+            version: 0,
+            linked_at,
+            linked_by: None,
+            role: LinkRole::default(),
+            pinned: false,
+        }
+    }
+
+    pub fn update(&mut self, UpdateLinkMetadataDto { role, pinned }: UpdateLinkMetadataDto) {
+        self.version = LINK_METADATA_VERSION;
+        if let Some(role) = role {
+            self.role = role;
+        }
+        if let Some(pinned) = pinned {
+            self.pinned = pinned;
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Validate, async_graphql::InputObject, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateLinkMetadataDto {
+    pub role: Option<LinkRole>,
+    pub pinned: Option<bool>,
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, PartialEq, async_graphql::SimpleObject, utoipa::ToSchema,
+)]
+pub struct LinkedRepoDto {
+    #[serde(flatten)]
+    pub repo: RepoDto,
+    pub metadata: LinkMetadataDto,
+}
@@ -2,12 +2,19 @@ use chrono::{DateTime, Utc};
 use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use validator::Validate;
 
-use collection::user_repo_info::{UserRepoInfo, UserRepoInfoOperation};
+use collection::user_repo_info::{
+    LinkActivityStat, RepoChurnStat, UserActivityStat, UserNetLinksStat, UserRepoInfo,
+    UserRepoInfoOperation,
+};
 
-use crate::utils::{object_id_schema, serialize_object_id, serialize_option_object_id};
+use crate::utils::{
+    object_id_schema, serialize_message_object_id, serialize_object_id,
+    serialize_option_message_object_id, serialize_option_object_id,
+};
 
-#[derive(Serialize, Deserialize, Debug, async_graphql::SimpleObject, utoipa::ToSchema)]
+#[derive(Serialize, Deserialize, Debug, Clone, async_graphql::SimpleObject, utoipa::ToSchema)]
 #[serde(deny_unknown_fields)]
 pub struct UserRepoInfoDto {
     #[serde(
@@ -23,6 +30,17 @@ pub struct UserRepoInfoDto {
     pub repo_id: Uuid,
     pub operation: UserRepoInfoOperation,
     pub executed_at: DateTime<Utc>,
+
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "serialize_option_object_id"
+    )]
+    #[schema(schema_with = object_id_schema)]
+    pub from_user_id: Option<ObjectId>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restored_at: Option<DateTime<Utc>>,
 }
 
 impl PartialEq for UserRepoInfoDto {
@@ -30,15 +48,41 @@ impl PartialEq for UserRepoInfoDto {
         self.user_id == other.user_id
             && self.repo_id == other.repo_id
             && self.operation == other.operation
+            && self.from_user_id == other.from_user_id
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, utoipa::ToSchema)]
+#[derive(Serialize, Deserialize, Clone, Debug, utoipa::ToSchema)]
 pub struct CreateUserRepoInfoDto {
+    #[serde(serialize_with = "serialize_message_object_id")]
     #[schema(schema_with = object_id_schema)]
     pub user_id: ObjectId,
     pub repo_id: Uuid,
     pub operation: UserRepoInfoOperation,
+
+    #[serde(default, serialize_with = "serialize_option_message_object_id")]
+    #[schema(schema_with = object_id_schema)]
+    pub from_user_id: Option<ObjectId>,
+}
+
+impl CreateUserRepoInfoDto {
+    pub fn new(user_id: ObjectId, repo_id: Uuid, operation: UserRepoInfoOperation) -> Self {
+        Self {
+            user_id,
+            repo_id,
+            operation,
+            from_user_id: None,
+        }
+    }
+
+    pub fn transfer(from_user_id: ObjectId, to_user_id: ObjectId, repo_id: Uuid) -> Self {
+        Self {
+            user_id: to_user_id,
+            repo_id,
+            operation: UserRepoInfoOperation::Transfer,
+            from_user_id: Some(from_user_id),
+        }
+    }
 }
 
 impl From<CreateUserRepoInfoDto> for UserRepoInfo {
@@ -47,9 +91,13 @@ impl From<CreateUserRepoInfoDto> for UserRepoInfo {
             user_id,
             repo_id,
             operation,
+            from_user_id,
         }: CreateUserRepoInfoDto,
     ) -> Self {
-        Self::new(user_id, repo_id, operation)
+        Self {
+            from_user_id,
+            ..Self::new(user_id, repo_id, operation)
+        }
     }
 }
 
@@ -61,6 +109,8 @@ impl From<UserRepoInfo> for UserRepoInfoDto {
             repo_id,
             operation,
             executed_at,
+            from_user_id,
+            restored_at,
         }: UserRepoInfo,
     ) -> Self {
         UserRepoInfoDto {
@@ -69,21 +119,204 @@ impl From<UserRepoInfo> for UserRepoInfoDto {
             repo_id,
             operation,
             executed_at,
+            from_user_id,
+            restored_at,
         }
     }
 }
 
-impl From<Vec<u8>> for CreateUserRepoInfoDto {
-    fn from(value: Vec<u8>) -> Self {
-        let str = String::from_utf8(value).unwrap();
-        let dto: CreateUserRepoInfoDto = serde_json::from_str(&str).unwrap();
-        dto
+impl From<UserRepoInfoDto> for UserRepoInfo {
+    fn from(
+        UserRepoInfoDto {
+            id,
+            user_id,
+            repo_id,
+            operation,
+            executed_at,
+            from_user_id,
+            restored_at,
+        }: UserRepoInfoDto,
+    ) -> Self {
+        UserRepoInfo {
+            id,
+            user_id,
+            repo_id,
+            operation,
+            executed_at,
+            from_user_id,
+            restored_at,
+        }
     }
 }
 
-impl From<CreateUserRepoInfoDto> for Vec<u8> {
-    fn from(val: CreateUserRepoInfoDto) -> Self {
-        let string = serde_json::to_string(&val).unwrap();
-        string.into_bytes()
+/// Manifest of a batch of expired records moved to the object store
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, async_graphql::SimpleObject, utoipa::ToSchema,
+)]
+pub struct UserRepoInfoArchiveDto {
+    pub id: Uuid,
+    pub records: u64,
+    pub first_executed_at: DateTime<Utc>,
+    pub last_executed_at: DateTime<Utc>,
+    pub archived_at: DateTime<Utc>,
+}
+
+#[derive(
+    Serialize,
+    Deserialize,
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    async_graphql::Enum,
+    utoipa::ToSchema,
+)]
+pub enum StatsPeriod {
+    #[default]
+    Day,
+    Week,
+}
+
+impl StatsPeriod {
+    /// Format of the period name, weeks are ISO ones
+    pub fn format(&self) -> &'static str {
+        match self {
+            StatsPeriod::Day => "%Y-%m-%d",
+            StatsPeriod::Week => "%G-W%V",
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default, Validate, async_graphql::InputObject, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UserRepoInfoStatsFilterDto {
+    /// Inclusive start of the time range
+    pub from: Option<DateTime<Utc>>,
+
+    /// Exclusive end of the time range
+    pub to: Option<DateTime<Utc>>,
+
+    #[param(inline)]
+    pub period: Option<StatsPeriod>,
+
+    /// Length of the top lists, 10 by default
+    #[validate(range(min = 1, max = 100, message = "Must be between 1 and 100"))]
+    #[param(minimum = 1, maximum = 100)]
+    pub top: Option<u64>,
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, PartialEq, async_graphql::SimpleObject, utoipa::ToSchema,
+)]
+pub struct LinkActivityDto {
+    pub period: String,
+    pub created: u64,
+    pub deleted: u64,
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, PartialEq, async_graphql::SimpleObject, utoipa::ToSchema,
+)]
+pub struct UserActivityDto {
+    #[serde(serialize_with = "serialize_object_id")]
+    #[schema(schema_with = object_id_schema)]
+    pub user_id: ObjectId,
+    pub operations: u64,
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, PartialEq, async_graphql::SimpleObject, utoipa::ToSchema,
+)]
+pub struct RepoChurnDto {
+    pub repo_id: Uuid,
+    pub created: u64,
+    pub deleted: u64,
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, PartialEq, async_graphql::SimpleObject, utoipa::ToSchema,
+)]
+pub struct UserNetLinksDto {
+    #[serde(serialize_with = "serialize_object_id")]
+    #[schema(schema_with = object_id_schema)]
+    pub user_id: ObjectId,
+    pub created: u64,
+    pub deleted: u64,
+    pub net: i64,
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, PartialEq, async_graphql::SimpleObject, utoipa::ToSchema,
+)]
+pub struct UserRepoInfoStatsDto {
+    pub activity: Vec<LinkActivityDto>,
+    pub most_active_users: Vec<UserActivityDto>,
+    pub churned_repos: Vec<RepoChurnDto>,
+    pub net_links: Vec<UserNetLinksDto>,
+}
+
+impl From<LinkActivityStat> for LinkActivityDto {
+    fn from(
+        LinkActivityStat {
+            period,
+            created,
+            deleted,
+        }: LinkActivityStat,
+    ) -> Self {
+        Self {
+            period,
+            created,
+            deleted,
+        }
+    }
+}
+
+impl From<UserActivityStat> for UserActivityDto {
+    fn from(
+        UserActivityStat {
+            user_id,
+            operations,
+        }: UserActivityStat,
+    ) -> Self {
+        Self {
+            user_id,
+            operations,
+        }
+    }
+}
+
+impl From<RepoChurnStat> for RepoChurnDto {
+    fn from(
+        RepoChurnStat {
+            repo_id,
+            created,
+            deleted,
+        }: RepoChurnStat,
+    ) -> Self {
+        Self {
+            repo_id,
+            created,
+            deleted,
+        }
+    }
+}
+
+impl From<UserNetLinksStat> for UserNetLinksDto {
+    fn from(
+        UserNetLinksStat {
+            user_id,
+            created,
+            deleted,
+            net,
+        }: UserNetLinksStat,
+    ) -> Self {
+        Self {
+            user_id,
+            created,
+            deleted,
+            net,
+        }
     }
 }
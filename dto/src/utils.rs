@@ -1,5 +1,5 @@
 use mongodb::bson::oid::ObjectId;
-use serde::Serializer;
+use serde::{Serialize, Serializer};
 use utoipa::openapi::{Object, ObjectBuilder};
 
 pub fn serialize_option_object_id<S>(
@@ -22,6 +22,42 @@ where
     serializer.serialize_some(object_id.to_string().as_str())
 }
 
+/// bson always writes `ObjectId` as `{"$oid": ..}` but reads it from raw bytes
+/// in binary formats, so broker messages write the bytes there
+struct MessageObjectId<'a>(&'a ObjectId);
+
+impl Serialize for MessageObjectId<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match serializer.is_human_readable() {
+            true => self.0.serialize(serializer),
+            false => serializer.serialize_bytes(&self.0.bytes()),
+        }
+    }
+}
+
+pub fn serialize_message_object_id<S>(
+    object_id: &ObjectId,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    MessageObjectId(object_id).serialize(serializer)
+}
+
+pub fn serialize_option_message_object_id<S>(
+    object_id: &Option<ObjectId>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    object_id
+        .as_ref()
+        .map(MessageObjectId)
+        .serialize(serializer)
+}
+
 pub fn object_id_schema() -> Object {
     ObjectBuilder::new()
         .schema_type(utoipa::openapi::schema::Type::String)
@@ -30,3 +66,50 @@ pub fn object_id_schema() -> Object {
         .description(Some("Object id from bson"))
         .build()
 }
+
+#[cfg(test)]
+mod tests {
+    use message_broker::codec::{CborCodec, Codec, JsonCodec, MessagePackCodec};
+    use uuid::Uuid;
+
+    use super::*;
+    use crate::user_repo_info_dto::CreateUserRepoInfoDto;
+
+    fn round_trip<C: Codec>(codec: C) {
+        let dto = CreateUserRepoInfoDto::transfer(ObjectId::new(), ObjectId::new(), Uuid::new_v4());
+        let payload = codec.encode(&dto).unwrap();
+        let decoded: CreateUserRepoInfoDto = codec.decode(&payload).unwrap();
+
+        assert_eq!(decoded.user_id, dto.user_id);
+        assert_eq!(decoded.repo_id, dto.repo_id);
+        assert_eq!(decoded.operation, dto.operation);
+        assert_eq!(decoded.from_user_id, dto.from_user_id);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        round_trip(JsonCodec);
+    }
+
+    #[test]
+    fn message_pack_round_trip() {
+        round_trip(MessagePackCodec);
+    }
+
+    #[test]
+    fn cbor_round_trip() {
+        round_trip(CborCodec);
+    }
+
+    #[test]
+    fn missing_from_user_id_round_trips() {
+        let dto = CreateUserRepoInfoDto::new(
+            ObjectId::new(),
+            Uuid::new_v4(),
+            collection::user_repo_info::UserRepoInfoOperation::CreateLink,
+        );
+        let payload = MessagePackCodec.encode(&dto).unwrap();
+        let decoded: CreateUserRepoInfoDto = MessagePackCodec.decode(&payload).unwrap();
+        assert_eq!(decoded.from_user_id, None);
+    }
+}
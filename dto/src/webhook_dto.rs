@@ -0,0 +1,198 @@
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use serde::{Deserialize, Serialize};
+use validator::{Validate, ValidationError};
+
+use collection::webhook_delivery::WebhookDelivery;
+use collection::webhook_subscription::{WebhookEventType, WebhookSubscription};
+
+use crate::utils::{object_id_schema, serialize_object_id};
+
+#[derive(Serialize, Deserialize, Debug, Validate, utoipa::ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct CreateWebhookSubscriptionDto {
+    #[validate(
+        url(message = "Must be a valid URL"),
+        custom(
+            function = "validate_http_url",
+            message = "Must be an http or https URL"
+        )
+    )]
+    #[schema(example = "https://example.com/webhooks")]
+    pub url: String,
+
+    #[validate(length(min = 1, message = "Must contain at least one event type"))]
+    pub events: Vec<WebhookEventType>,
+
+    /// Key of the `X-Webhook-Signature` HMAC-SHA256 signature
+    #[validate(length(min = 16, max = 200, message = "Must be between 16 and 200 characters"))]
+    pub secret: String,
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, async_graphql::SimpleObject, utoipa::ToSchema,
+)]
+pub struct WebhookSubscriptionDto {
+    #[serde(serialize_with = "serialize_object_id")]
+    #[schema(schema_with = object_id_schema)]
+    pub id: ObjectId,
+    pub url: String,
+    pub events: Vec<WebhookEventType>,
+
+    /// Never sent back to the clients
+    #[serde(default, skip_serializing)]
+    #[graphql(skip)]
+    #[schema(ignore)]
+    pub secret: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(
+    Serialize, Deserialize, Debug, Clone, PartialEq, async_graphql::SimpleObject, utoipa::ToSchema,
+)]
+pub struct WebhookDeliveryDto {
+    #[serde(serialize_with = "serialize_object_id")]
+    #[schema(schema_with = object_id_schema)]
+    pub id: ObjectId,
+
+    #[serde(serialize_with = "serialize_object_id")]
+    #[schema(schema_with = object_id_schema)]
+    pub subscription_id: ObjectId,
+
+    #[serde(serialize_with = "serialize_object_id")]
+    #[schema(schema_with = object_id_schema)]
+    pub event_id: ObjectId,
+    pub event_type: WebhookEventType,
+    pub payload: String,
+    pub attempt: u32,
+    pub status_code: Option<u16>,
+    pub error: Option<String>,
+    pub success: bool,
+    pub scheduled_at: DateTime<Utc>,
+
+    /// Empty while the attempt is scheduled
+    pub attempted_at: Option<DateTime<Utc>>,
+}
+
+/// Attempt scheduled for later, its outcome is recorded once it is made
+#[derive(Debug)]
+pub struct CreateWebhookDeliveryDto {
+    pub subscription_id: ObjectId,
+    pub event_id: ObjectId,
+    pub event_type: WebhookEventType,
+    pub payload: String,
+    pub attempt: u32,
+    pub scheduled_at: DateTime<Utc>,
+
+    /// Set when the creator makes the attempt itself
+    pub locked_until: Option<DateTime<Utc>>,
+}
+
+fn validate_http_url(url: &str) -> Result<(), ValidationError> {
+    let scheme = url
+        .split_once("://")
+        .map(|(scheme, _)| scheme.to_lowercase());
+    match scheme.as_deref() {
+        Some("http" | "https") => Ok(()),
+        _ => Err(ValidationError::new("scheme")),
+    }
+}
+
+fn to_bson_date(date: DateTime<Utc>) -> mongodb::bson::DateTime {
+    mongodb::bson::DateTime::from_millis(date.timestamp_millis())
+}
+
+impl From<CreateWebhookSubscriptionDto> for WebhookSubscription {
+    fn from(
+        CreateWebhookSubscriptionDto {
+            url,
+            events,
+            secret,
+        }: CreateWebhookSubscriptionDto,
+    ) -> Self {
+        WebhookSubscription::new(url, events, secret)
+    }
+}
+
+impl From<WebhookSubscription> for WebhookSubscriptionDto {
+    fn from(
+        WebhookSubscription {
+            id,
+            url,
+            events,
+            secret,
+            created_at,
+        }: WebhookSubscription,
+    ) -> Self {
+        WebhookSubscriptionDto {
+            id: id.unwrap(),
+            url,
+            events,
+            secret,
+            created_at,
+        }
+    }
+}
+
+impl From<CreateWebhookDeliveryDto> for WebhookDelivery {
+    fn from(
+        CreateWebhookDeliveryDto {
+            subscription_id,
+            event_id,
+            event_type,
+            payload,
+            attempt,
+            scheduled_at,
+            locked_until,
+        }: CreateWebhookDeliveryDto,
+    ) -> Self {
+        WebhookDelivery {
+            id: None,
+            subscription_id,
+            event_id,
+            event_type,
+            payload,
+            attempt,
+            status_code: None,
+            error: None,
+            success: false,
+            scheduled_at: to_bson_date(scheduled_at),
+            locked_until: locked_until.map(to_bson_date),
+            attempted_at: None,
+        }
+    }
+}
+
+impl From<WebhookDelivery> for WebhookDeliveryDto {
+    fn from(
+        WebhookDelivery {
+            id,
+            subscription_id,
+            event_id,
+            event_type,
+            payload,
+            attempt,
+            status_code,
+            error,
+            success,
+            scheduled_at,
+            locked_until: _,
+            attempted_at,
+        }: WebhookDelivery,
+    ) -> Self {
+        WebhookDeliveryDto {
+            id: id.unwrap(),
+            subscription_id,
+            event_id,
+            event_type,
+            payload,
+            attempt,
+            status_code,
+            error,
+            success,
+            scheduled_at: DateTime::from_timestamp_millis(scheduled_at.timestamp_millis())
+                .unwrap_or_default(),
+            attempted_at,
+        }
+    }
+}
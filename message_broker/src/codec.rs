@@ -0,0 +1,138 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::{MBrokerError, MBrokerResult};
+
+/// Wire format of messages, the content type travels with every message
+pub trait Codec: Default + Send + Sync + 'static {
+    const CONTENT_TYPE: &'static str;
+
+    fn encode<M: Serialize>(&self, message: &M) -> MBrokerResult<Vec<u8>>;
+    fn decode<M: DeserializeOwned>(&self, payload: &[u8]) -> MBrokerResult<M>;
+
+    /// Messages without a content type are taken as written by this codec
+    fn check_content_type(&self, content_type: Option<&str>) -> MBrokerResult<()> {
+        match content_type {
+            Some(content_type) if content_type != Self::CONTENT_TYPE => {
+                Err(MBrokerError::UnexpectedContentType {
+                    expected: Self::CONTENT_TYPE,
+                    actual: content_type.to_string(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct JsonCodec;
+
+impl Codec for JsonCodec {
+    const CONTENT_TYPE: &'static str = "application/json";
+
+    fn encode<M: Serialize>(&self, message: &M) -> MBrokerResult<Vec<u8>> {
+        serde_json::to_vec(message).map_err(|err| MBrokerError::Encode(err.to_string()))
+    }
+
+    fn decode<M: DeserializeOwned>(&self, payload: &[u8]) -> MBrokerResult<M> {
+        serde_json::from_slice(payload).map_err(|err| MBrokerError::Decode(err.to_string()))
+    }
+}
+
+/// Structs are written as maps, so fields can be added without breaking old readers
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MessagePackCodec;
+
+impl Codec for MessagePackCodec {
+    const CONTENT_TYPE: &'static str = "application/msgpack";
+
+    fn encode<M: Serialize>(&self, message: &M) -> MBrokerResult<Vec<u8>> {
+        rmp_serde::to_vec_named(message).map_err(|err| MBrokerError::Encode(err.to_string()))
+    }
+
+    fn decode<M: DeserializeOwned>(&self, payload: &[u8]) -> MBrokerResult<M> {
+        rmp_serde::from_slice(payload).map_err(|err| MBrokerError::Decode(err.to_string()))
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CborCodec;
+
+impl Codec for CborCodec {
+    const CONTENT_TYPE: &'static str = "application/cbor";
+
+    fn encode<M: Serialize>(&self, message: &M) -> MBrokerResult<Vec<u8>> {
+        let mut payload = vec![];
+        ciborium::into_writer(message, &mut payload)
+            .map_err(|err| MBrokerError::Encode(err.to_string()))?;
+        Ok(payload)
+    }
+
+    fn decode<M: DeserializeOwned>(&self, payload: &[u8]) -> MBrokerResult<M> {
+        ciborium::from_reader(payload).map_err(|err| MBrokerError::Decode(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Message {
+        id: u32,
+        name: String,
+        tags: Vec<String>,
+        parent: Option<u32>,
+    }
+
+    fn message() -> Message {
+        Message {
+            id: 7,
+            name: "repo".to_string(),
+            tags: vec!["a".to_string(), "b".to_string()],
+            parent: None,
+        }
+    }
+
+    fn round_trip<C: Codec>() {
+        let codec = C::default();
+        let payload = codec.encode(&message()).unwrap();
+        assert_eq!(codec.decode::<Message>(&payload).unwrap(), message());
+    }
+
+    fn garbage_fails<C: Codec>() {
+        let res = C::default().decode::<Message>(&[0xc1, 0xff, 0x00]);
+        assert!(matches!(res, Err(MBrokerError::Decode(_))));
+    }
+
+    #[test]
+    fn json_round_trip() {
+        round_trip::<JsonCodec>();
+        garbage_fails::<JsonCodec>();
+    }
+
+    #[test]
+    fn message_pack_round_trip() {
+        round_trip::<MessagePackCodec>();
+        garbage_fails::<MessagePackCodec>();
+    }
+
+    #[test]
+    fn cbor_round_trip() {
+        round_trip::<CborCodec>();
+        garbage_fails::<CborCodec>();
+    }
+
+    #[test]
+    fn check_content_type() {
+        let codec = JsonCodec;
+        assert!(codec.check_content_type(None).is_ok());
+        assert!(codec.check_content_type(Some("application/json")).is_ok());
+        assert!(matches!(
+            codec.check_content_type(Some("application/cbor")),
+            Err(MBrokerError::UnexpectedContentType { .. })
+        ));
+    }
+}
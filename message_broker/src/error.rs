@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use thiserror::Error;
 
 pub type MBrokerResult<T> = Result<T, MBrokerError>;
@@ -7,6 +9,51 @@ pub enum MBrokerError {
     #[error(transparent)]
     RabbitMQ(#[from] amqprs::error::Error),
 
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+
     #[error("Can't read message. {0}")]
     CantReadMessage(String),
+
+    #[error("Can't encode message. {0}")]
+    Encode(String),
+
+    #[error("Can't decode message. {0}")]
+    Decode(String),
+
+    #[error("Expected message of {expected} content type, got {actual}")]
+    UnexpectedContentType {
+        expected: &'static str,
+        actual: String,
+    },
+
+    #[error("Connection to the message broker is lost")]
+    Disconnected,
+
+    #[error("Publish buffer is full, {0} messages are waiting for the connection")]
+    BufferFull(usize),
+
+    /// The message isn't confirmed yet, it is sent from the in-memory buffer
+    /// once the connection is back and is lost if the process stops before
+    #[error("Connection to the message broker is lost, the message is buffered")]
+    Buffered,
+
+    #[error("Broker refused the message")]
+    PublishNacked,
+
+    #[error("Broker returned the message as unroutable: {code} {text}")]
+    Unroutable { code: u16, text: String },
+
+    #[error("Queue is closed")]
+    Closed,
+
+    #[error("Broker didn't confirm the message in {0:?}")]
+    ConfirmTimeout(Duration),
+}
+
+impl MBrokerError {
+    /// Publishing the same message again won't help
+    pub fn is_permanent(&self) -> bool {
+        matches!(self, Self::Unroutable { .. } | Self::Encode(_))
+    }
 }
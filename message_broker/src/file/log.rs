@@ -0,0 +1,806 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+use super::FsyncPolicy;
+
+const SEGMENT_EXTENSION: &str = "log";
+const ACKS_FILE: &str = "acks";
+const FLOOR_FILE: &str = "floor";
+const DEAD_LETTERS_FILE: &str = "dead_letters";
+const LOCK_FILE: &str = "lock";
+
+/// Length and checksum of the record body
+const HEADER_LEN: usize = 8;
+
+/// Longest record body, a longer length in a header is taken for corruption
+/// instead of allocated
+const MAX_RECORD_LEN: usize = 64 * 1024 * 1024;
+
+/// Message as it is stored on disk
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Record {
+    pub(crate) offset: u64,
+
+    /// Unix time in ms before which the record isn't delivered
+    pub(crate) not_before: u64,
+
+    /// Failed deliveries so far
+    pub(crate) attempts: u32,
+    pub(crate) content_type: String,
+
+    /// Set for dead letters
+    pub(crate) error: Option<String>,
+    pub(crate) payload: Vec<u8>,
+}
+
+impl Record {
+    pub(crate) fn new(content_type: &str, payload: Vec<u8>) -> Self {
+        Self {
+            offset: 0,
+            not_before: 0,
+            attempts: 0,
+            content_type: content_type.to_string(),
+            error: None,
+            payload,
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::with_capacity(32 + self.content_type.len() + self.payload.len());
+        body.extend(self.offset.to_le_bytes());
+        body.extend(self.not_before.to_le_bytes());
+        body.extend(self.attempts.to_le_bytes());
+        put_bytes(&mut body, self.content_type.as_bytes());
+        match &self.error {
+            Some(error) => {
+                body.push(1);
+                put_bytes(&mut body, error.as_bytes());
+            }
+            None => body.push(0),
+        }
+        put_bytes(&mut body, &self.payload);
+
+        let mut record = Vec::with_capacity(HEADER_LEN + body.len());
+        record.extend((body.len() as u32).to_le_bytes());
+        record.extend(crc32fast::hash(&body).to_le_bytes());
+        record.extend(body);
+        record
+    }
+
+    fn decode(body: &[u8]) -> Option<Self> {
+        let mut body = Bytes(body);
+        let offset = body.u64()?;
+        let not_before = body.u64()?;
+        let attempts = body.u32()?;
+        let content_type = String::from_utf8(body.bytes()?.to_vec()).ok()?;
+        let error = match body.take(1)?[0] {
+            0 => None,
+            _ => Some(String::from_utf8(body.bytes()?.to_vec()).ok()?),
+        };
+        let payload = body.bytes()?.to_vec();
+        Some(Self {
+            offset,
+            not_before,
+            attempts,
+            content_type,
+            error,
+            payload,
+        })
+    }
+
+    /// Bytes the record takes on disk
+    fn len(&self) -> u64 {
+        (HEADER_LEN + 29 + self.content_type.len() + self.payload.len()) as u64
+            + self
+                .error
+                .as_ref()
+                .map_or(0, |error| 4 + error.len() as u64)
+    }
+}
+
+fn put_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    buf.extend((bytes.len() as u32).to_le_bytes());
+    buf.extend(bytes);
+}
+
+struct Bytes<'a>(&'a [u8]);
+
+impl<'a> Bytes<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.0.len() < len {
+            return None;
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Some(head)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?))
+    }
+
+    fn bytes(&mut self) -> Option<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
+
+/// Reads until `buf` is full or the end of the file, returns the bytes read
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(read)
+}
+
+/// `None` at the end of the file, `InvalidData` for a torn or corrupted record
+fn read_record(reader: &mut impl Read) -> io::Result<Option<Record>> {
+    let invalid = |reason: &str| io::Error::new(ErrorKind::InvalidData, reason.to_string());
+
+    let mut header = [0; HEADER_LEN];
+    match read_full(reader, &mut header)? {
+        0 => return Ok(None),
+        HEADER_LEN => {}
+        _ => return Err(invalid("torn record header")),
+    }
+    let len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+    let checksum = u32::from_le_bytes(header[4..].try_into().unwrap());
+    if len > MAX_RECORD_LEN {
+        return Err(invalid("record too large"));
+    }
+
+    let mut body = vec![0; len];
+    if read_full(reader, &mut body)? < len {
+        return Err(invalid("torn record body"));
+    }
+    if crc32fast::hash(&body) != checksum {
+        return Err(invalid("record checksum mismatch"));
+    }
+    Record::decode(&body)
+        .map(Some)
+        .ok_or_else(|| invalid("malformed record"))
+}
+
+/// Fails on a corrupted record instead of hiding the records after it, a torn
+/// tail is already cut off by `Log::open`
+fn read_records(path: &Path) -> io::Result<Vec<Record>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut records = vec![];
+    while let Some(record) = read_record(&mut reader)? {
+        records.push(record);
+    }
+    Ok(records)
+}
+
+fn encode(record: &Record) -> io::Result<Vec<u8>> {
+    let bytes = record.encode();
+    if bytes.len() - HEADER_LEN > MAX_RECORD_LEN {
+        return Err(io::Error::new(ErrorKind::InvalidInput, "record too large"));
+    }
+    Ok(bytes)
+}
+
+/// Holds an exclusive lock on the directory until the file is closed
+fn lock_dir(dir: &Path) -> io::Result<File> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(dir.join(LOCK_FILE))?;
+    match file.try_lock() {
+        Ok(()) => Ok(file),
+        Err(fs::TryLockError::WouldBlock) => Err(io::Error::new(
+            ErrorKind::WouldBlock,
+            format!("{} is used by another process", dir.display()),
+        )),
+        Err(fs::TryLockError::Error(err)) => Err(err),
+    }
+}
+
+fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+fn sync_dir(dir: &Path) -> io::Result<()> {
+    File::open(dir)?.sync_all()
+}
+
+/// Replaces the file in one step, so a crash leaves either the old or the new content
+fn write_atomic(dir: &Path, name: &str, content: &[u8]) -> io::Result<()> {
+    let tmp = dir.join(format!("{name}.tmp"));
+    let mut file = File::create(&tmp)?;
+    file.write_all(content)?;
+    file.sync_all()?;
+    fs::rename(&tmp, dir.join(name))?;
+    sync_dir(dir)
+}
+
+/// What the log has for a receiver
+#[derive(Debug, PartialEq)]
+pub(crate) enum Next {
+    Ready(Record),
+
+    /// Nothing to deliver until something is appended or the due time in ms of
+    /// the earliest delayed record
+    Wait(Option<u64>),
+}
+
+struct SegmentReader {
+    base: u64,
+    reader: BufReader<File>,
+}
+
+/// Append-only log of one queue, split into `<base offset>.log` segments.
+/// Acknowledged offsets are appended to `acks`, `floor` keeps the offset below
+/// which everything is acknowledged. Segments below the floor are deleted
+pub(crate) struct Log {
+    dir: PathBuf,
+    segment_bytes: u64,
+    fsync: FsyncPolicy,
+
+    /// Base offsets of the segments, the last one is appended to
+    segments: BTreeSet<u64>,
+    writer: File,
+    writer_len: u64,
+    next_offset: u64,
+
+    acks: File,
+    floor: u64,
+
+    /// Acknowledged offsets above `floor`
+    acked: BTreeSet<u64>,
+
+    reader: Option<SegmentReader>,
+    read_offset: u64,
+
+    /// Read records waiting for their `not_before`, by due time and offset
+    delayed: BTreeMap<(u64, u64), Record>,
+
+    dead_letters: File,
+
+    /// Keeps other processes out of the directory
+    _lock: File,
+
+    /// Written since the last sync
+    dirty: bool,
+}
+
+impl Log {
+    /// Truncates a record torn by a crash at the end of the log or of the dead
+    /// letters, everything not acknowledged is delivered again.
+    /// Fails with `WouldBlock` if another process has the directory open
+    pub(crate) fn open(dir: &Path, segment_bytes: u64, fsync: FsyncPolicy) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let lock = lock_dir(dir)?;
+        let floor = match fs::read(dir.join(FLOOR_FILE)) {
+            Ok(bytes) => bytes
+                .try_into()
+                .map(u64::from_le_bytes)
+                .map_err(|_| io::Error::new(ErrorKind::InvalidData, "malformed floor file"))?,
+            Err(err) if err.kind() == ErrorKind::NotFound => 0,
+            Err(err) => return Err(err),
+        };
+
+        let mut segments = BTreeSet::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == SEGMENT_EXTENSION) {
+                if let Some(base) = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| stem.parse().ok())
+                {
+                    segments.insert(base);
+                }
+            }
+        }
+        if segments.is_empty() {
+            segments.insert(floor);
+        }
+
+        let active = *segments.last().unwrap();
+        let active_path = dir.join(segment_name(active));
+        let (writer_len, next_offset) = truncate_torn_tail(&active_path, active)?;
+        let writer = open_append(&active_path)?;
+
+        let acks_path = dir.join(ACKS_FILE);
+        let acks_bytes = match fs::read(&acks_path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == ErrorKind::NotFound => vec![],
+            Err(err) => return Err(err),
+        };
+        let acks = open_append(&acks_path)?;
+        // An ack torn by a crash is dropped, its message is delivered again
+        acks.set_len((acks_bytes.len() - acks_bytes.len() % 8) as u64)?;
+        let acked = acks_bytes
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .filter(|offset| *offset >= floor)
+            .collect();
+
+        let dead_letters_path = dir.join(DEAD_LETTERS_FILE);
+        truncate_torn_tail(&dead_letters_path, 0)?;
+
+        let mut log = Self {
+            dir: dir.to_path_buf(),
+            segment_bytes,
+            fsync,
+            segments,
+            writer,
+            writer_len,
+            next_offset,
+            acks,
+            floor,
+            acked,
+            reader: None,
+            read_offset: floor,
+            delayed: BTreeMap::new(),
+            dead_letters: open_append(&dead_letters_path)?,
+            _lock: lock,
+            dirty: false,
+        };
+        log.advance_floor()?;
+        Ok(log)
+    }
+
+    fn segment_path(&self, base: u64) -> PathBuf {
+        self.dir.join(segment_name(base))
+    }
+
+    /// Stores the record under the next offset and returns the offset
+    pub(crate) fn append(&mut self, mut record: Record) -> io::Result<u64> {
+        if self.writer_len >= self.segment_bytes {
+            self.roll()?;
+        }
+        record.offset = self.next_offset;
+        let bytes = encode(&record)?;
+        self.writer.write_all(&bytes)?;
+        self.writer_len += bytes.len() as u64;
+        self.next_offset += 1;
+        self.written()?;
+        Ok(record.offset)
+    }
+
+    fn roll(&mut self) -> io::Result<()> {
+        if !matches!(self.fsync, FsyncPolicy::Never) {
+            self.writer.sync_data()?;
+        }
+        let base = self.next_offset;
+        self.writer = open_append(&self.segment_path(base))?;
+        self.writer_len = 0;
+        self.segments.insert(base);
+        sync_dir(&self.dir)
+    }
+
+    fn written(&mut self) -> io::Result<()> {
+        self.dirty = true;
+        match self.fsync {
+            FsyncPolicy::Always => self.sync(),
+            _ => Ok(()),
+        }
+    }
+
+    pub(crate) fn sync(&mut self) -> io::Result<()> {
+        if self.dirty {
+            self.writer.sync_data()?;
+            self.acks.sync_data()?;
+            self.dead_letters.sync_data()?;
+            self.dirty = false;
+        }
+        Ok(())
+    }
+
+    /// Oldest record that isn't acknowledged or delivered yet and is due at `now`
+    pub(crate) fn next(&mut self, now: u64) -> io::Result<Next> {
+        if let Some(entry) = self.delayed.first_entry() {
+            if entry.key().0 <= now {
+                return Ok(Next::Ready(entry.remove()));
+            }
+        }
+        while let Some(record) = self.read()? {
+            if record.offset < self.floor || self.acked.contains(&record.offset) {
+                continue;
+            }
+            if record.not_before > now {
+                self.delayed
+                    .insert((record.not_before, record.offset), record);
+                continue;
+            }
+            return Ok(Next::Ready(record));
+        }
+        Ok(Next::Wait(self.delayed.keys().next().map(|(due, _)| *due)))
+    }
+
+    /// Hands a record returned by `next` out again right away, it stays unacked
+    /// on disk
+    pub(crate) fn release(&mut self, record: Record) {
+        self.delayed.insert((0, record.offset), record);
+    }
+
+    /// Next record after `read_offset`, a corrupted segment is skipped with its
+    /// offsets acknowledged
+    fn read(&mut self) -> io::Result<Option<Record>> {
+        while self.read_offset < self.next_offset {
+            if self.reader.is_none() {
+                let base = self
+                    .segments
+                    .range(..=self.read_offset)
+                    .next_back()
+                    .or(self.segments.first())
+                    .copied()
+                    .unwrap();
+                let file = File::open(self.segment_path(base))?;
+                self.reader = Some(SegmentReader {
+                    base,
+                    reader: BufReader::new(file),
+                });
+            }
+            let segment = self.reader.as_mut().unwrap();
+            let base = segment.base;
+            match read_record(&mut segment.reader) {
+                Ok(Some(record)) if record.offset < self.read_offset => continue,
+                Ok(Some(record)) => {
+                    self.read_offset = record.offset + 1;
+                    return Ok(Some(record));
+                }
+                Ok(None) => {}
+                Err(err) if err.kind() == ErrorKind::InvalidData => {
+                    eprintln!(
+                        "Skipping the rest of segment {}: {err}",
+                        self.segment_path(base).display()
+                    );
+                }
+                Err(err) => return Err(err),
+            }
+
+            let Some(next) = self.segments.range(base + 1..).next().copied() else {
+                return Ok(None);
+            };
+            self.reader = None;
+            for offset in self.read_offset..next {
+                self.ack(offset)?;
+            }
+            self.read_offset = self.read_offset.max(next);
+        }
+        Ok(None)
+    }
+
+    pub(crate) fn ack(&mut self, offset: u64) -> io::Result<()> {
+        if offset < self.floor || offset >= self.next_offset || !self.acked.insert(offset) {
+            return Ok(());
+        }
+        self.acks.write_all(&offset.to_le_bytes())?;
+        self.written()?;
+        self.advance_floor()
+    }
+
+    fn advance_floor(&mut self) -> io::Result<()> {
+        let floor = self.floor;
+        while self.acked.remove(&self.floor) {
+            self.floor += 1;
+        }
+        match self.floor > floor {
+            true => self.compact(),
+            false => Ok(()),
+        }
+    }
+
+    /// Deletes segments whose records are all acknowledged, the one appended to is kept
+    fn compact(&mut self) -> io::Result<()> {
+        let removable: Vec<u64> = self
+            .segments
+            .iter()
+            .zip(self.segments.iter().skip(1))
+            .take_while(|(_, next)| **next <= self.floor)
+            .map(|(base, _)| *base)
+            .collect();
+        if removable.is_empty() {
+            return Ok(());
+        }
+
+        // The floor is stored before the acks below it are forgotten
+        write_atomic(&self.dir, FLOOR_FILE, &self.floor.to_le_bytes())?;
+        let acks: Vec<u8> = self.acked.iter().flat_map(|o| o.to_le_bytes()).collect();
+        write_atomic(&self.dir, ACKS_FILE, &acks)?;
+        self.acks = open_append(&self.dir.join(ACKS_FILE))?;
+
+        for base in removable {
+            fs::remove_file(self.segment_path(base))?;
+            self.segments.remove(&base);
+        }
+        sync_dir(&self.dir)
+    }
+
+    pub(crate) fn dead_letter(&mut self, record: Record) -> io::Result<()> {
+        self.dead_letters.write_all(&encode(&record)?)?;
+        self.written()
+    }
+
+    pub(crate) fn peek_dead_letters(&self, limit: usize) -> io::Result<Vec<Record>> {
+        let mut records = read_records(&self.dir.join(DEAD_LETTERS_FILE))?;
+        records.truncate(limit);
+        Ok(records)
+    }
+
+    /// Appends dead letters back to the log with a fresh set of attempts
+    pub(crate) fn replay_dead_letters(&mut self, limit: Option<usize>) -> io::Result<u32> {
+        let mut records = read_records(&self.dir.join(DEAD_LETTERS_FILE))?;
+        let replayed = limit.unwrap_or(records.len()).min(records.len());
+        for record in records.drain(..replayed) {
+            self.append(Record {
+                not_before: 0,
+                attempts: 0,
+                error: None,
+                ..record
+            })?;
+        }
+        self.sync()?;
+
+        let rest: Vec<u8> = records.iter().flat_map(Record::encode).collect();
+        write_atomic(&self.dir, DEAD_LETTERS_FILE, &rest)?;
+        self.dead_letters = open_append(&self.dir.join(DEAD_LETTERS_FILE))?;
+        Ok(replayed as u32)
+    }
+
+    pub(crate) fn purge_dead_letters(&mut self) -> io::Result<u32> {
+        let purged = read_records(&self.dir.join(DEAD_LETTERS_FILE))?.len();
+        self.dead_letters.set_len(0)?;
+        self.written()?;
+        Ok(purged as u32)
+    }
+}
+
+fn segment_name(base: u64) -> String {
+    format!("{base:020}.{SEGMENT_EXTENSION}")
+}
+
+/// Cuts a torn tail off a segment or the dead letters, returns the length left
+/// and the offset after the last record
+fn truncate_torn_tail(path: &Path, base: u64) -> io::Result<(u64, u64)> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok((0, base)),
+        Err(err) => return Err(err),
+    };
+    let file_len = file.metadata()?.len();
+    let mut reader = BufReader::new(file);
+    let mut len = 0;
+    let mut next_offset = base;
+    loop {
+        match read_record(&mut reader) {
+            Ok(Some(record)) => {
+                len += record.len();
+                next_offset = record.offset + 1;
+            }
+            Ok(None) => break,
+            Err(err) if err.kind() == ErrorKind::InvalidData => break,
+            Err(err) => return Err(err),
+        }
+    }
+    if len < file_len {
+        eprintln!(
+            "Truncating {} torn bytes of {}",
+            file_len - len,
+            path.display()
+        );
+        OpenOptions::new().write(true).open(path)?.set_len(len)?;
+    }
+    Ok((len, next_offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(payload: &str) -> Record {
+        Record::new("application/json", payload.as_bytes().to_vec())
+    }
+
+    fn open(dir: &Path) -> Log {
+        Log::open(dir, 64, FsyncPolicy::Always).unwrap()
+    }
+
+    fn next_payload(log: &mut Log) -> Option<String> {
+        match log.next(0).unwrap() {
+            Next::Ready(record) => Some(String::from_utf8(record.payload).unwrap()),
+            Next::Wait(_) => None,
+        }
+    }
+
+    fn segments(dir: &Path) -> usize {
+        fs::read_dir(dir)
+            .unwrap()
+            .filter(|entry| {
+                let path = entry.as_ref().unwrap().path();
+                path.extension().is_some_and(|ext| ext == SEGMENT_EXTENSION)
+            })
+            .count()
+    }
+
+    #[test]
+    fn record_round_trip() {
+        let record = Record {
+            offset: 3,
+            not_before: 5,
+            attempts: 2,
+            error: Some("failed".to_string()),
+            ..record("payload")
+        };
+        let bytes = record.encode();
+        assert_eq!(bytes.len() as u64, record.len());
+        assert_eq!(read_record(&mut bytes.as_slice()).unwrap(), Some(record));
+    }
+
+    #[test]
+    fn unacked_records_are_delivered_after_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = open(dir.path());
+        for payload in ["a", "b", "c"] {
+            log.append(record(payload)).unwrap();
+        }
+        assert_eq!(next_payload(&mut log).as_deref(), Some("a"));
+        assert_eq!(next_payload(&mut log).as_deref(), Some("b"));
+        log.ack(1).unwrap();
+        drop(log);
+
+        let mut log = open(dir.path());
+        assert_eq!(next_payload(&mut log).as_deref(), Some("a"));
+        assert_eq!(next_payload(&mut log).as_deref(), Some("c"));
+        assert_eq!(next_payload(&mut log), None);
+        assert_eq!(log.append(record("d")).unwrap(), 3);
+    }
+
+    #[test]
+    fn acked_segments_are_compacted() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = open(dir.path());
+        for offset in 0..10 {
+            log.append(record(&offset.to_string())).unwrap();
+        }
+        assert!(segments(dir.path()) > 2);
+
+        for offset in 0..10 {
+            assert!(next_payload(&mut log).is_some());
+            log.ack(offset).unwrap();
+        }
+        assert_eq!(segments(dir.path()), 1);
+        drop(log);
+
+        let mut log = open(dir.path());
+        assert_eq!(next_payload(&mut log), None);
+        assert_eq!(log.append(record("next")).unwrap(), 10);
+    }
+
+    #[test]
+    fn torn_tail_is_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = Log::open(dir.path(), 1024, FsyncPolicy::Always).unwrap();
+        log.append(record("whole")).unwrap();
+        drop(log);
+
+        let path = dir.path().join(segment_name(0));
+        let mut torn = record("torn").encode();
+        torn.truncate(torn.len() - 2);
+        open_append(&path).unwrap().write_all(&torn).unwrap();
+
+        let mut log = Log::open(dir.path(), 1024, FsyncPolicy::Always).unwrap();
+        assert_eq!(next_payload(&mut log).as_deref(), Some("whole"));
+        assert_eq!(next_payload(&mut log), None);
+        assert_eq!(log.append(record("next")).unwrap(), 1);
+        assert_eq!(next_payload(&mut log).as_deref(), Some("next"));
+    }
+
+    #[test]
+    fn torn_dead_letter_tail_is_truncated() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = open(dir.path());
+        log.dead_letter(record("a")).unwrap();
+        drop(log);
+
+        let path = dir.path().join(DEAD_LETTERS_FILE);
+        let mut torn = record("torn").encode();
+        torn.truncate(torn.len() - 2);
+        open_append(&path).unwrap().write_all(&torn).unwrap();
+
+        let mut log = open(dir.path());
+        log.dead_letter(record("b")).unwrap();
+        let payloads: Vec<_> = log
+            .peek_dead_letters(10)
+            .unwrap()
+            .into_iter()
+            .map(|record| record.payload)
+            .collect();
+        assert_eq!(payloads, [b"a", b"b"]);
+        assert_eq!(log.replay_dead_letters(None).unwrap(), 2);
+    }
+
+    #[test]
+    fn oversized_record_is_rejected() {
+        let mut header = vec![];
+        header.extend_from_slice(&u32::MAX.to_le_bytes());
+        header.extend_from_slice(&0u32.to_le_bytes());
+        let err = read_record(&mut header.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn directory_is_locked() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = open(dir.path());
+        let err = Log::open(dir.path(), 64, FsyncPolicy::Always)
+            .err()
+            .unwrap();
+        assert_eq!(err.kind(), ErrorKind::WouldBlock);
+        drop(log);
+        open(dir.path());
+    }
+
+    #[test]
+    fn released_record_is_delivered_again() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = open(dir.path());
+        log.append(record("a")).unwrap();
+        log.append(record("b")).unwrap();
+        let Next::Ready(first) = log.next(0).unwrap() else {
+            panic!("expected a record");
+        };
+        log.release(first);
+        assert_eq!(next_payload(&mut log).as_deref(), Some("a"));
+        assert_eq!(next_payload(&mut log).as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn delayed_records_wait() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = open(dir.path());
+        log.append(Record {
+            not_before: 100,
+            ..record("later")
+        })
+        .unwrap();
+        log.append(record("now")).unwrap();
+
+        assert!(matches!(log.next(50).unwrap(), Next::Ready(r) if r.payload == b"now"));
+        assert_eq!(log.next(50).unwrap(), Next::Wait(Some(100)));
+        assert!(matches!(log.next(100).unwrap(), Next::Ready(r) if r.payload == b"later"));
+    }
+
+    #[test]
+    fn dead_letters_are_replayed_and_purged() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut log = open(dir.path());
+        for payload in ["a", "b", "c"] {
+            log.dead_letter(Record {
+                attempts: 3,
+                error: Some("failed".to_string()),
+                ..record(payload)
+            })
+            .unwrap();
+        }
+        assert_eq!(log.peek_dead_letters(2).unwrap().len(), 2);
+
+        assert_eq!(log.replay_dead_letters(Some(1)).unwrap(), 1);
+        match log.next(0).unwrap() {
+            Next::Ready(record) => {
+                assert_eq!(record.payload, b"a");
+                assert_eq!(record.attempts, 0);
+                assert_eq!(record.error, None);
+            }
+            next => panic!("expected a record, got {next:?}"),
+        }
+        assert_eq!(log.purge_dead_letters().unwrap(), 2);
+        assert!(log.peek_dead_letters(10).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,536 @@
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex, Weak};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::{Mutex, Notify};
+
+use crate::codec::{Codec, JsonCodec};
+use crate::error::{MBrokerError, MBrokerResult};
+
+use super::{
+    Acker, DeadLetter, DeadLetterQueue, Delivery, Publisher, Receiver, RetryOptions, Subscriber,
+};
+
+mod log;
+
+use log::{Log, Next, Record};
+
+/// When appended messages and acknowledgements are flushed to disk
+#[derive(Debug, Clone, Copy)]
+pub enum FsyncPolicy {
+    /// After every write, nothing that was published or acknowledged is lost
+    Always,
+
+    /// From a background task, a crash loses at most the last interval
+    Interval(Duration),
+
+    /// Left to the operating system
+    Never,
+}
+
+#[derive(Debug, Clone)]
+pub struct FileOptions {
+    pub queue_name: &'static str,
+
+    /// A new segment is started once the current one grows past it
+    pub segment_bytes: u64,
+    pub fsync: FsyncPolicy,
+    pub retry: RetryOptions,
+}
+
+/// Queues kept in `<root>/<queue name>` directories, publishers and receivers of
+/// the same queue name share one queue. A queue directory is locked while it is
+/// open, a second process fails to open it
+#[derive(Clone)]
+pub struct FileBroker {
+    root: PathBuf,
+    queues: Arc<Mutex<HashMap<&'static str, Arc<FileQueue>>>>,
+}
+
+impl FileBroker {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            queues: Arc::default(),
+        }
+    }
+
+    /// The first subscriber of a queue decides its segment size, fsync and retry policy
+    async fn queue(&self, options: &FileOptions) -> MBrokerResult<Arc<FileQueue>> {
+        let mut queues = self.queues.lock().await;
+        if let Some(queue) = queues.get(options.queue_name) {
+            return Ok(Arc::clone(queue));
+        }
+
+        let dir = self.root.join(options.queue_name);
+        let (segment_bytes, fsync) = (options.segment_bytes, options.fsync);
+        let log = blocking(move || Log::open(&dir, segment_bytes, fsync)).await?;
+        let queue = Arc::new(FileQueue {
+            log: StdMutex::new(log),
+            retry: options.retry,
+            appended: Notify::new(),
+            closed: AtomicBool::new(false),
+        });
+        if let FsyncPolicy::Interval(interval) = options.fsync {
+            tokio::spawn(sync_periodically(Arc::downgrade(&queue), interval));
+        }
+        queues.insert(options.queue_name, Arc::clone(&queue));
+        Ok(queue)
+    }
+
+    /// Publishers fail with `Closed` from now on, receivers get the messages
+    /// that are due and then `Closed`. Everything written is flushed to disk
+    pub async fn close(&self) -> MBrokerResult<()> {
+        for queue in self.queues.lock().await.values() {
+            queue.closed.store(true, Ordering::SeqCst);
+            queue.appended.notify_waiters();
+            queue.with_log(Log::sync).await?;
+        }
+        Ok(())
+    }
+}
+
+async fn blocking<T: Send + 'static>(
+    op: impl FnOnce() -> io::Result<T> + Send + 'static,
+) -> MBrokerResult<T> {
+    let res = tokio::task::spawn_blocking(op)
+        .await
+        .map_err(io::Error::other)?;
+    Ok(res?)
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+async fn sync_periodically(queue: Weak<FileQueue>, interval: Duration) {
+    loop {
+        tokio::time::sleep(interval).await;
+        let Some(queue) = queue.upgrade() else {
+            return;
+        };
+        if let Err(err) = queue.with_log(Log::sync).await {
+            eprintln!("Can't flush the message log: {err}");
+        }
+    }
+}
+
+struct FileQueue {
+    log: StdMutex<Log>,
+    retry: RetryOptions,
+
+    /// Wakes receivers waiting for new records
+    appended: Notify,
+    closed: AtomicBool,
+}
+
+impl FileQueue {
+    /// Runs the file operation off the async workers
+    async fn with_log<T: Send + 'static>(
+        self: &Arc<Self>,
+        op: impl FnOnce(&mut Log) -> io::Result<T> + Send + 'static,
+    ) -> MBrokerResult<T> {
+        let queue = Arc::clone(self);
+        blocking(move || op(&mut queue.log.lock().unwrap())).await
+    }
+
+    /// Makes a delivered record due again. Only touches memory, so it is cheap
+    /// enough to run on an async worker or in `Drop`
+    fn release(&self, record: Record) {
+        self.log.lock().unwrap().release(record);
+        self.appended.notify_waiters();
+    }
+
+    async fn publish(self: &Arc<Self>, record: Record) -> MBrokerResult<()> {
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(MBrokerError::Closed);
+        }
+        self.with_log(move |log| log.append(record)).await?;
+        self.appended.notify_waiters();
+        Ok(())
+    }
+
+    /// Appends what takes the place of the record before acknowledging it, so a
+    /// crash in between delivers it twice rather than never
+    async fn settle(self: &Arc<Self>, record: Record, error: String) -> MBrokerResult<()> {
+        let offset = record.offset;
+        let attempts = record.attempts + 1;
+        let retry = self.retry;
+        self.with_log(move |log| {
+            if attempts >= retry.max_attempts {
+                log.dead_letter(Record {
+                    attempts,
+                    error: Some(error),
+                    ..record
+                })?;
+            } else {
+                let delay = retry.delay(attempts).as_millis() as u64;
+                log.append(Record {
+                    attempts,
+                    not_before: unix_millis() + delay,
+                    ..record
+                })?;
+            }
+            log.ack(offset)
+        })
+        .await?;
+        self.appended.notify_waiters();
+        Ok(())
+    }
+}
+
+pub struct FilePublisher<C: Codec = JsonCodec> {
+    queue: Arc<FileQueue>,
+    codec: C,
+}
+
+#[async_trait]
+impl<C: Codec> Subscriber<FileBroker, FileOptions> for FilePublisher<C> {
+    async fn init(broker: FileBroker, options: &FileOptions) -> MBrokerResult<Self> {
+        Ok(Self {
+            queue: broker.queue(options).await?,
+            codec: C::default(),
+        })
+    }
+
+    async fn close(&self) -> MBrokerResult<()> {
+        self.queue.with_log(Log::sync).await
+    }
+}
+
+#[async_trait]
+impl<M, C> Publisher<M> for FilePublisher<C>
+where
+    M: Serialize + Send + Sync,
+    C: Codec,
+{
+    async fn publish(&self, message: M) -> MBrokerResult<()>
+    where
+        M: 'async_trait,
+    {
+        let record = Record::new(C::CONTENT_TYPE, self.codec.encode(&message)?);
+        self.queue.publish(record).await
+    }
+}
+
+pub struct FileReceiver<C: Codec = JsonCodec> {
+    queue: Arc<FileQueue>,
+    codec: C,
+}
+
+#[async_trait]
+impl<C: Codec> Subscriber<FileBroker, FileOptions> for FileReceiver<C> {
+    async fn init(broker: FileBroker, options: &FileOptions) -> MBrokerResult<Self> {
+        Ok(Self {
+            queue: broker.queue(options).await?,
+            codec: C::default(),
+        })
+    }
+
+    async fn close(&self) -> MBrokerResult<()> {
+        self.queue.with_log(Log::sync).await
+    }
+}
+
+#[async_trait]
+impl<M, C> Receiver<M> for FileReceiver<C>
+where
+    M: DeserializeOwned + Send,
+    C: Codec,
+{
+    /// Messages of another content type or that can't be decoded are dead-lettered
+    /// right away instead of returned.
+    /// Messages left unsettled are delivered again after a restart
+    async fn receive(&self) -> MBrokerResult<Delivery<M>> {
+        loop {
+            let appended = self.queue.appended.notified();
+            tokio::pin!(appended);
+            appended.as_mut().enable();
+
+            let due = match self.queue.with_log(|log| log.next(unix_millis())).await? {
+                Next::Ready(record) => {
+                    let message = self
+                        .codec
+                        .check_content_type(Some(&record.content_type))
+                        .and_then(|_| self.codec.decode::<M>(&record.payload));
+                    match message {
+                        Ok(message) => {
+                            let acker = FileAcker {
+                                queue: Arc::clone(&self.queue),
+                                record: Some(record),
+                            };
+                            return Ok(Delivery::new(message, acker));
+                        }
+                        Err(error) => {
+                            let offset = record.offset;
+                            let record = Record {
+                                attempts: record.attempts + 1,
+                                error: Some(error.to_string()),
+                                ..record
+                            };
+                            self.queue
+                                .with_log(move |log| {
+                                    log.dead_letter(record)?;
+                                    log.ack(offset)
+                                })
+                                .await?;
+                            continue;
+                        }
+                    }
+                }
+                Next::Wait(due) => due,
+            };
+
+            if self.queue.closed.load(Ordering::SeqCst) {
+                return Err(MBrokerError::Closed);
+            }
+            match due {
+                Some(due) => {
+                    let delay = Duration::from_millis(due.saturating_sub(unix_millis()));
+                    tokio::select! {
+                        _ = appended => {}
+                        _ = tokio::time::sleep(delay) => {}
+                    }
+                }
+                None => appended.await,
+            }
+        }
+    }
+}
+
+/// Settles one message of a `FileReceiver`. A message dropped unsettled is
+/// delivered again
+struct FileAcker {
+    queue: Arc<FileQueue>,
+
+    /// Taken once the message is settled
+    record: Option<Record>,
+}
+
+impl FileAcker {
+    fn take(&mut self) -> Record {
+        self.record.take().expect("message is settled once")
+    }
+}
+
+#[async_trait]
+impl Acker for FileAcker {
+    async fn ack(mut self: Box<Self>) -> MBrokerResult<()> {
+        let offset = self.take().offset;
+        self.queue.with_log(move |log| log.ack(offset)).await
+    }
+
+    async fn nack(mut self: Box<Self>, error: String) -> MBrokerResult<()> {
+        let record = self.take();
+        self.queue.settle(record, error).await
+    }
+
+    async fn requeue(mut self: Box<Self>) -> MBrokerResult<()> {
+        let record = self.take();
+        self.queue.release(record);
+        Ok(())
+    }
+}
+
+impl Drop for FileAcker {
+    fn drop(&mut self) {
+        if let Some(record) = self.record.take() {
+            self.queue.release(record);
+        }
+    }
+}
+
+/// Admin access to the dead letters of an on-disk queue
+pub struct FileDeadLetterQueue {
+    queue: Arc<FileQueue>,
+}
+
+#[async_trait]
+impl Subscriber<FileBroker, FileOptions> for FileDeadLetterQueue {
+    async fn init(broker: FileBroker, options: &FileOptions) -> MBrokerResult<Self> {
+        Ok(Self {
+            queue: broker.queue(options).await?,
+        })
+    }
+
+    async fn close(&self) -> MBrokerResult<()> {
+        self.queue.with_log(Log::sync).await
+    }
+}
+
+#[async_trait]
+impl DeadLetterQueue for FileDeadLetterQueue {
+    async fn peek(&self, limit: u32) -> MBrokerResult<Vec<DeadLetter>> {
+        let records = self
+            .queue
+            .with_log(move |log| log.peek_dead_letters(limit as usize))
+            .await?;
+        Ok(records
+            .into_iter()
+            .map(|record| DeadLetter {
+                payload: record.payload,
+                attempts: record.attempts,
+                error: record.error,
+            })
+            .collect())
+    }
+
+    async fn replay(&self, limit: Option<u32>) -> MBrokerResult<u32> {
+        let replayed = self
+            .queue
+            .with_log(move |log| log.replay_dead_letters(limit.map(|limit| limit as usize)))
+            .await?;
+        self.queue.appended.notify_waiters();
+        Ok(replayed)
+    }
+
+    async fn purge(&self) -> MBrokerResult<u32> {
+        self.queue.with_log(Log::purge_dead_letters).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn options() -> FileOptions {
+        FileOptions {
+            queue_name: "test",
+            segment_bytes: 1024,
+            fsync: FsyncPolicy::Always,
+            retry: RetryOptions {
+                max_attempts: 2,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(1),
+            },
+        }
+    }
+
+    async fn queue(root: &Path) -> (FilePublisher, FileReceiver, FileDeadLetterQueue) {
+        let broker = FileBroker::new(root);
+        (
+            FilePublisher::init(broker.clone(), &options())
+                .await
+                .unwrap(),
+            FileReceiver::init(broker.clone(), &options())
+                .await
+                .unwrap(),
+            FileDeadLetterQueue::init(broker, &options()).await.unwrap(),
+        )
+    }
+
+    async fn receive(receiver: &FileReceiver) -> Delivery<u32> {
+        tokio::time::timeout(Duration::from_secs(1), receiver.receive())
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn unacked_messages_survive_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let (publisher, receiver, _) = queue(dir.path()).await;
+        publisher.publish(1u32).await.unwrap();
+        publisher.publish(2u32).await.unwrap();
+        receive(&receiver).await.acker.ack().await.unwrap();
+        let unsettled = receive(&receiver).await;
+        drop((publisher, receiver, unsettled));
+
+        let (_, receiver, _) = queue(dir.path()).await;
+        assert_eq!(receive(&receiver).await.message, 2);
+    }
+
+    #[tokio::test]
+    async fn receiver_waits_for_publish() {
+        let dir = tempfile::tempdir().unwrap();
+        let (publisher, receiver, _) = queue(dir.path()).await;
+        let waiting = tokio::spawn(async move { receive(&receiver).await.message });
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        publisher.publish(7u32).await.unwrap();
+        assert_eq!(waiting.await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn dropped_delivery_is_redelivered() {
+        let dir = tempfile::tempdir().unwrap();
+        let (publisher, receiver, _) = queue(dir.path()).await;
+        publisher.publish(1u32).await.unwrap();
+        publisher.publish(2u32).await.unwrap();
+        drop(receive(&receiver).await);
+
+        let redelivered = receive(&receiver).await;
+        assert_eq!(redelivered.message, 1);
+        redelivered.acker.ack().await.unwrap();
+        assert_eq!(receive(&receiver).await.message, 2);
+    }
+
+    #[tokio::test]
+    async fn second_broker_cant_open_queue() {
+        let dir = tempfile::tempdir().unwrap();
+        let _queue = queue(dir.path()).await;
+        let res = FileReceiver::<JsonCodec>::init(FileBroker::new(dir.path()), &options()).await;
+        assert!(
+            matches!(res, Err(MBrokerError::Io(err)) if err.kind() == io::ErrorKind::WouldBlock)
+        );
+    }
+
+    #[tokio::test]
+    async fn nack_retries_then_dead_letters() {
+        let dir = tempfile::tempdir().unwrap();
+        let (publisher, receiver, dlq) = queue(dir.path()).await;
+        publisher.publish(1u32).await.unwrap();
+
+        receive(&receiver)
+            .await
+            .acker
+            .nack("first".to_string())
+            .await
+            .unwrap();
+        let retried = receive(&receiver).await;
+        assert_eq!(retried.message, 1);
+        retried.acker.nack("second".to_string()).await.unwrap();
+
+        let dead_letters = dlq.peek(10).await.unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].attempts, 2);
+        assert_eq!(dead_letters[0].error.as_deref(), Some("second"));
+
+        assert_eq!(dlq.replay(None).await.unwrap(), 1);
+        assert_eq!(receive(&receiver).await.message, 1);
+    }
+
+    #[tokio::test]
+    async fn close_stops_publishing() {
+        let dir = tempfile::tempdir().unwrap();
+        let broker = FileBroker::new(dir.path());
+        let publisher = FilePublisher::<JsonCodec>::init(broker.clone(), &options())
+            .await
+            .unwrap();
+        let receiver = FileReceiver::<JsonCodec>::init(broker.clone(), &options())
+            .await
+            .unwrap();
+        publisher.publish(1u32).await.unwrap();
+        broker.close().await.unwrap();
+
+        assert!(matches!(
+            publisher.publish(2u32).await,
+            Err(MBrokerError::Closed)
+        ));
+        let delivery = receive(&receiver).await;
+        assert_eq!(delivery.message, 1);
+        delivery.acker.ack().await.unwrap();
+        let res: MBrokerResult<Delivery<u32>> = receiver.receive().await;
+        assert!(matches!(res, Err(MBrokerError::Closed)));
+    }
+}
@@ -1,7 +1,13 @@
+use std::sync::Arc;
+use std::time::Duration;
+
 use crate::error::MBrokerResult;
 use async_trait::async_trait;
 
+pub mod codec;
 pub mod error;
+pub mod file;
+pub mod memory;
 pub mod rabbitmq;
 pub mod tests;
 
@@ -13,13 +19,122 @@ where
     async fn publish(&self, message: M) -> R
     where
         M: 'async_trait;
+
+    /// Publishers without routing ignore the key
+    async fn publish_with_key(&self, message: M, _routing_key: &str) -> R
+    where
+        M: 'async_trait,
+    {
+        self.publish(message).await
+    }
+}
+
+/// Publishes every message with the routing key derived from it
+pub struct RoutedPublisher<M> {
+    publisher: Arc<dyn Publisher<M>>,
+    routing_key: fn(&M) -> String,
+}
+
+impl<M> RoutedPublisher<M> {
+    pub fn new(publisher: Arc<dyn Publisher<M>>, routing_key: fn(&M) -> String) -> Self {
+        Self {
+            publisher,
+            routing_key,
+        }
+    }
+}
+
+#[async_trait]
+impl<M> Publisher<M> for RoutedPublisher<M>
+where
+    M: Send + Sync,
+{
+    async fn publish(&self, message: M) -> MBrokerResult<()>
+    where
+        M: 'async_trait,
+    {
+        let routing_key = (self.routing_key)(&message);
+        self.publisher.publish_with_key(message, &routing_key).await
+    }
+
+    async fn publish_with_key(&self, message: M, routing_key: &str) -> MBrokerResult<()>
+    where
+        M: 'async_trait,
+    {
+        self.publisher.publish_with_key(message, routing_key).await
+    }
 }
 
 #[async_trait]
-pub trait Receiver<M, R = MBrokerResult<M>>: Send + Sync {
+pub trait Receiver<M, R = MBrokerResult<Delivery<M>>>: Send + Sync {
     async fn receive(&self) -> R;
 }
 
+/// Received message, it stays unsettled until the consumer settles it through `acker`
+pub struct Delivery<M> {
+    pub message: M,
+    pub acker: Box<dyn Acker>,
+}
+
+impl<M> Delivery<M> {
+    pub fn new(message: M, acker: impl Acker + 'static) -> Self {
+        Self {
+            message,
+            acker: Box::new(acker),
+        }
+    }
+}
+
+#[async_trait]
+pub trait Acker: Send + Sync {
+    /// The message is processed and can be forgotten
+    async fn ack(self: Box<Self>) -> MBrokerResult<()>;
+
+    /// Processing failed, the message goes through the retry policy of the queue
+    async fn nack(self: Box<Self>, error: String) -> MBrokerResult<()>;
+
+    /// Puts the message back for an immediate redelivery, without spending an attempt
+    async fn requeue(self: Box<Self>) -> MBrokerResult<()>;
+}
+
+/// Redelivery of messages whose processing failed
+#[derive(Debug, Clone, Copy)]
+pub struct RetryOptions {
+    /// Deliveries before the message is dead-lettered, including the first one
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryOptions {
+    /// Delay after the failed `attempt`, doubled for each next one up to `max_delay`
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay)
+    }
+}
+
+/// Message that ran out of delivery attempts
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeadLetter {
+    pub payload: Vec<u8>,
+
+    /// Failed deliveries before the message was dead-lettered
+    pub attempts: u32,
+    pub error: Option<String>,
+}
+
+#[async_trait]
+pub trait DeadLetterQueue: Send + Sync {
+    /// Oldest dead letters, they stay in the queue
+    async fn peek(&self, limit: u32) -> MBrokerResult<Vec<DeadLetter>>;
+
+    /// Moves dead letters back to the work queue, all of them when `limit` is `None`
+    async fn replay(&self, limit: Option<u32>) -> MBrokerResult<u32>;
+    async fn purge(&self) -> MBrokerResult<u32>;
+}
+
 #[async_trait]
 pub trait Subscriber<C, O> {
     async fn init(conn: C, options: &O) -> MBrokerResult<Self>
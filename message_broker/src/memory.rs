@@ -0,0 +1,496 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex as StdMutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::{mpsc, watch, Mutex};
+
+use crate::codec::{Codec, JsonCodec};
+use crate::error::{MBrokerError, MBrokerResult};
+
+use super::{
+    Acker, DeadLetter, DeadLetterQueue, Delivery, Publisher, Receiver, RetryOptions, Subscriber,
+};
+
+#[derive(Debug, Clone)]
+pub struct MemoryOptions {
+    pub queue_name: &'static str,
+
+    /// Messages the queue holds before publishers wait for room
+    pub capacity: usize,
+    pub retry: RetryOptions,
+}
+
+/// Queues of a single process. Publishers and receivers of the same queue name
+/// share one queue, the first of them decides its capacity and retry policy
+#[derive(Clone, Default)]
+pub struct MemoryBroker {
+    queues: Arc<StdMutex<HashMap<&'static str, Arc<MemoryQueue>>>>,
+}
+
+impl MemoryBroker {
+    fn queue(&self, options: &MemoryOptions) -> Arc<MemoryQueue> {
+        let mut queues = self.queues.lock().unwrap();
+        let queue = queues
+            .entry(options.queue_name)
+            .or_insert_with(|| Arc::new(MemoryQueue::new(options)));
+        Arc::clone(queue)
+    }
+
+    /// Publishers fail with `Closed` from now on, receivers get the messages
+    /// left in the queues and then `Closed`
+    pub fn close(&self) {
+        for queue in self.queues.lock().unwrap().values() {
+            queue.closed.send_replace(true);
+        }
+    }
+}
+
+/// Published message with what the queue knows about its deliveries
+#[derive(Clone)]
+struct Envelope {
+    payload: Vec<u8>,
+    content_type: &'static str,
+
+    /// Failed deliveries so far
+    attempts: u32,
+}
+
+struct MemoryQueue {
+    sender: mpsc::Sender<Envelope>,
+
+    /// Receivers of the queue compete for messages, each one is delivered once
+    receiver: Mutex<mpsc::Receiver<Envelope>>,
+    dead_letters: StdMutex<VecDeque<(Envelope, Option<String>)>>,
+    closed: watch::Sender<bool>,
+    retry: RetryOptions,
+}
+
+impl MemoryQueue {
+    fn new(options: &MemoryOptions) -> Self {
+        let (sender, receiver) = mpsc::channel(options.capacity.max(1));
+        Self {
+            sender,
+            receiver: Mutex::new(receiver),
+            dead_letters: StdMutex::new(VecDeque::new()),
+            closed: watch::channel(false).0,
+            retry: options.retry,
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        *self.closed.borrow()
+    }
+
+    /// Waits while the queue is full, gives up once it is closed
+    async fn send(&self, envelope: Envelope) -> MBrokerResult<()> {
+        let mut closed = self.closed.subscribe();
+        if *closed.borrow_and_update() {
+            return Err(MBrokerError::Closed);
+        }
+        tokio::select! {
+            res = self.sender.send(envelope) => res.map_err(|_| MBrokerError::Closed),
+            _ = closed.wait_for(|closed| *closed) => Err(MBrokerError::Closed),
+        }
+    }
+
+    /// `None` once the queue is closed and drained
+    async fn recv(&self) -> Option<Envelope> {
+        let mut receiver = self.receiver.lock().await;
+        let mut closed = self.closed.subscribe();
+        tokio::select! {
+            envelope = receiver.recv() => envelope,
+            _ = closed.wait_for(|closed| *closed) => receiver.try_recv().ok(),
+        }
+    }
+
+    fn dead_letter(&self, envelope: Envelope, error: Option<String>) {
+        self.dead_letters
+            .lock()
+            .unwrap()
+            .push_back((envelope, error));
+    }
+
+    /// Sends the message back after `delay`, messages that can't go back are dead-lettered
+    fn redeliver(self: &Arc<Self>, envelope: Envelope, delay: Duration) {
+        let queue = Arc::clone(self);
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            if let Err(err) = queue.send(envelope.clone()).await {
+                queue.dead_letter(envelope, Some(err.to_string()));
+            }
+        });
+    }
+
+    fn retry_or_dead_letter(self: &Arc<Self>, mut envelope: Envelope, error: String) {
+        envelope.attempts += 1;
+        if envelope.attempts >= self.retry.max_attempts || self.is_closed() {
+            return self.dead_letter(envelope, Some(error));
+        }
+        let delay = self.retry.delay(envelope.attempts);
+        self.redeliver(envelope, delay);
+    }
+}
+
+/// Publishing waits while the queue is full
+pub struct MemoryPublisher<C: Codec = JsonCodec> {
+    queue: Arc<MemoryQueue>,
+    codec: C,
+}
+
+#[async_trait]
+impl<C: Codec> Subscriber<MemoryBroker, MemoryOptions> for MemoryPublisher<C> {
+    async fn init(broker: MemoryBroker, options: &MemoryOptions) -> MBrokerResult<Self> {
+        Ok(Self {
+            queue: broker.queue(options),
+            codec: C::default(),
+        })
+    }
+
+    async fn close(&self) -> MBrokerResult<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<M, C> Publisher<M> for MemoryPublisher<C>
+where
+    M: Serialize + Send + Sync,
+    C: Codec,
+{
+    async fn publish(&self, message: M) -> MBrokerResult<()>
+    where
+        M: 'async_trait,
+    {
+        let envelope = Envelope {
+            payload: self.codec.encode(&message)?,
+            content_type: C::CONTENT_TYPE,
+            attempts: 0,
+        };
+        self.queue.send(envelope).await
+    }
+}
+
+pub struct MemoryReceiver<C: Codec = JsonCodec> {
+    queue: Arc<MemoryQueue>,
+    codec: C,
+}
+
+#[async_trait]
+impl<C: Codec> Subscriber<MemoryBroker, MemoryOptions> for MemoryReceiver<C> {
+    async fn init(broker: MemoryBroker, options: &MemoryOptions) -> MBrokerResult<Self> {
+        Ok(Self {
+            queue: broker.queue(options),
+            codec: C::default(),
+        })
+    }
+
+    async fn close(&self) -> MBrokerResult<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<M, C> Receiver<M> for MemoryReceiver<C>
+where
+    M: DeserializeOwned + Send,
+    C: Codec,
+{
+    /// Messages of another content type or that can't be decoded are dead-lettered
+    /// right away instead of returned
+    async fn receive(&self) -> MBrokerResult<Delivery<M>> {
+        loop {
+            let envelope = self.queue.recv().await.ok_or(MBrokerError::Closed)?;
+            let message = self
+                .codec
+                .check_content_type(Some(envelope.content_type))
+                .and_then(|_| self.codec.decode::<M>(&envelope.payload));
+            match message {
+                Ok(message) => {
+                    let acker = MemoryAcker {
+                        queue: Arc::clone(&self.queue),
+                        envelope: Some(envelope),
+                    };
+                    return Ok(Delivery::new(message, acker));
+                }
+                Err(error) => self.queue.dead_letter(envelope, Some(error.to_string())),
+            }
+        }
+    }
+}
+
+/// Settles one message of a `MemoryReceiver`. A message dropped unsettled is
+/// delivered again
+struct MemoryAcker {
+    queue: Arc<MemoryQueue>,
+
+    /// Taken once the message is settled
+    envelope: Option<Envelope>,
+}
+
+impl MemoryAcker {
+    fn take(&mut self) -> Envelope {
+        self.envelope.take().expect("message is settled once")
+    }
+}
+
+#[async_trait]
+impl Acker for MemoryAcker {
+    async fn ack(mut self: Box<Self>) -> MBrokerResult<()> {
+        self.take();
+        Ok(())
+    }
+
+    async fn nack(mut self: Box<Self>, error: String) -> MBrokerResult<()> {
+        let envelope = self.take();
+        self.queue.retry_or_dead_letter(envelope, error);
+        Ok(())
+    }
+
+    async fn requeue(mut self: Box<Self>) -> MBrokerResult<()> {
+        let envelope = self.take();
+        self.queue.redeliver(envelope, Duration::ZERO);
+        Ok(())
+    }
+}
+
+impl Drop for MemoryAcker {
+    fn drop(&mut self) {
+        let Some(envelope) = self.envelope.take() else {
+            return;
+        };
+        // Redelivery needs a runtime to wait for room in the queue
+        if tokio::runtime::Handle::try_current().is_ok() {
+            self.queue.redeliver(envelope, Duration::ZERO);
+        } else {
+            let error = "Dropped unsettled outside of the runtime".to_string();
+            self.queue.dead_letter(envelope, Some(error));
+        }
+    }
+}
+
+/// Admin access to the dead letters of an in-process queue
+pub struct MemoryDeadLetterQueue {
+    queue: Arc<MemoryQueue>,
+}
+
+#[async_trait]
+impl Subscriber<MemoryBroker, MemoryOptions> for MemoryDeadLetterQueue {
+    async fn init(broker: MemoryBroker, options: &MemoryOptions) -> MBrokerResult<Self> {
+        Ok(Self {
+            queue: broker.queue(options),
+        })
+    }
+
+    async fn close(&self) -> MBrokerResult<()> {
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl DeadLetterQueue for MemoryDeadLetterQueue {
+    async fn peek(&self, limit: u32) -> MBrokerResult<Vec<DeadLetter>> {
+        let dead_letters = self.queue.dead_letters.lock().unwrap();
+        Ok(dead_letters
+            .iter()
+            .take(limit as usize)
+            .map(|(envelope, error)| DeadLetter {
+                payload: envelope.payload.clone(),
+                attempts: envelope.attempts,
+                error: error.clone(),
+            })
+            .collect())
+    }
+
+    /// Replayed messages get a fresh set of attempts
+    async fn replay(&self, limit: Option<u32>) -> MBrokerResult<u32> {
+        let mut replayed = 0;
+        while limit.is_none_or(|limit| replayed < limit) {
+            let Some((envelope, error)) = self.queue.dead_letters.lock().unwrap().pop_front()
+            else {
+                break;
+            };
+            let fresh = Envelope {
+                attempts: 0,
+                ..envelope.clone()
+            };
+            if let Err(err) = self.queue.send(fresh).await {
+                self.queue
+                    .dead_letters
+                    .lock()
+                    .unwrap()
+                    .push_front((envelope, error));
+                return Err(err);
+            }
+            replayed += 1;
+        }
+        Ok(replayed)
+    }
+
+    async fn purge(&self) -> MBrokerResult<u32> {
+        let mut dead_letters = self.queue.dead_letters.lock().unwrap();
+        let purged = dead_letters.len() as u32;
+        dead_letters.clear();
+        Ok(purged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn options(capacity: usize) -> MemoryOptions {
+        MemoryOptions {
+            queue_name: "test",
+            capacity,
+            retry: RetryOptions {
+                max_attempts: 2,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(1),
+            },
+        }
+    }
+
+    async fn queue(
+        capacity: usize,
+    ) -> (
+        MemoryBroker,
+        MemoryPublisher,
+        MemoryReceiver,
+        MemoryDeadLetterQueue,
+    ) {
+        let broker = MemoryBroker::default();
+        let options = options(capacity);
+        (
+            broker.clone(),
+            MemoryPublisher::init(broker.clone(), &options)
+                .await
+                .unwrap(),
+            MemoryReceiver::init(broker.clone(), &options)
+                .await
+                .unwrap(),
+            MemoryDeadLetterQueue::init(broker, &options).await.unwrap(),
+        )
+    }
+
+    async fn receive(receiver: &MemoryReceiver) -> Delivery<u32> {
+        tokio::time::timeout(Duration::from_secs(1), receiver.receive())
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        let (_, publisher, receiver, _) = queue(4).await;
+        publisher.publish(1u32).await.unwrap();
+        publisher.publish(2u32).await.unwrap();
+
+        for expected in [1, 2] {
+            let delivery = receive(&receiver).await;
+            assert_eq!(delivery.message, expected);
+            delivery.acker.ack().await.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn full_queue_waits_for_room() {
+        let (_, publisher, receiver, _) = queue(1).await;
+        publisher.publish(1u32).await.unwrap();
+
+        let blocked = tokio::time::timeout(Duration::from_millis(50), publisher.publish(2u32));
+        assert!(blocked.await.is_err());
+
+        receive(&receiver).await.acker.ack().await.unwrap();
+        publisher.publish(3u32).await.unwrap();
+        assert_eq!(receive(&receiver).await.message, 3);
+    }
+
+    #[tokio::test]
+    async fn consumers_share_messages() {
+        let (broker, publisher, first, _) = queue(8).await;
+        let second = MemoryReceiver::<JsonCodec>::init(broker, &options(8))
+            .await
+            .unwrap();
+        for message in 0..4u32 {
+            publisher.publish(message).await.unwrap();
+        }
+
+        let mut received = vec![];
+        for receiver in [&first, &second, &first, &second] {
+            let delivery = receive(receiver).await;
+            received.push(delivery.message);
+            delivery.acker.ack().await.unwrap();
+        }
+        received.sort();
+        assert_eq!(received, vec![0, 1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn dropped_delivery_is_redelivered() {
+        let (_, publisher, receiver, _) = queue(4).await;
+        publisher.publish(1u32).await.unwrap();
+        drop(receive(&receiver).await);
+
+        let redelivered = receive(&receiver).await;
+        assert_eq!(redelivered.message, 1);
+        redelivered.acker.ack().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn close_drains_queue() {
+        let (broker, publisher, receiver, _) = queue(4).await;
+        publisher.publish(1u32).await.unwrap();
+        broker.close();
+
+        assert!(matches!(
+            publisher.publish(2u32).await,
+            Err(MBrokerError::Closed)
+        ));
+        assert_eq!(receive(&receiver).await.message, 1);
+        let res: MBrokerResult<Delivery<u32>> = receiver.receive().await;
+        assert!(matches!(res, Err(MBrokerError::Closed)));
+    }
+
+    #[tokio::test]
+    async fn nack_retries_then_dead_letters() {
+        let (_, publisher, receiver, dlq) = queue(4).await;
+        publisher.publish(1u32).await.unwrap();
+
+        receive(&receiver)
+            .await
+            .acker
+            .nack("first".to_string())
+            .await
+            .unwrap();
+        let retried = receive(&receiver).await;
+        assert_eq!(retried.message, 1);
+        retried.acker.nack("second".to_string()).await.unwrap();
+
+        let dead_letters = dlq.peek(10).await.unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].attempts, 2);
+        assert_eq!(dead_letters[0].error.as_deref(), Some("second"));
+
+        assert_eq!(dlq.replay(None).await.unwrap(), 1);
+        assert_eq!(receive(&receiver).await.message, 1);
+        assert_eq!(dlq.purge().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn undecodable_message_is_dead_lettered() {
+        let (broker, _, receiver, dlq) = queue(4).await;
+        let publisher = MemoryPublisher::<JsonCodec>::init(broker, &options(4))
+            .await
+            .unwrap();
+        publisher.publish("text").await.unwrap();
+        publisher.publish(5u32).await.unwrap();
+
+        assert_eq!(receive(&receiver).await.message, 5);
+        assert_eq!(dlq.purge().await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use amqprs::channel::{BasicPublishArguments, Channel};
+use amqprs::BasicProperties;
+use tokio::sync::oneshot;
+
+use crate::error::{MBrokerError, MBrokerResult};
+
+/// Outcome of a publish awaited in confirm mode
+pub(crate) type Confirmation = oneshot::Receiver<MBrokerResult<()>>;
+
+/// Publishes of one confirm mode channel waiting for the broker, keyed by
+/// delivery tag. Every channel gets its own, so tags start from 1 and a late
+/// close of an old channel can't touch the publishes of a new one
+#[derive(Default)]
+pub(crate) struct PublishConfirms {
+    state: Mutex<ConfirmState>,
+
+    /// Delivery tags follow the publish order, so publishes of the channel
+    /// must not interleave between `register` and `basic_publish`
+    publishing: tokio::sync::Mutex<()>,
+}
+
+#[derive(Default)]
+struct ConfirmState {
+    last_tag: u64,
+    pending: BTreeMap<u64, oneshot::Sender<MBrokerResult<()>>>,
+
+    /// The broker returns an unroutable message right before confirming it
+    returned: Option<MBrokerError>,
+}
+
+impl PublishConfirms {
+    /// Publishes on the channel these confirms belong to, the returned
+    /// confirmation is awaited with `wait`
+    pub(crate) async fn publish(
+        &self,
+        ch: &Channel,
+        props: BasicProperties,
+        payload: Vec<u8>,
+        args: BasicPublishArguments,
+    ) -> MBrokerResult<(u64, Confirmation)> {
+        let _publishing = self.publishing.lock().await;
+        let (tag, confirmation) = self.register();
+        if let Err(err) = ch.basic_publish(props, payload, args).await {
+            self.forget(tag);
+            return Err(err.into());
+        }
+        Ok((tag, confirmation))
+    }
+
+    pub(crate) async fn wait(
+        &self,
+        tag: u64,
+        confirmation: Confirmation,
+        timeout: Duration,
+    ) -> MBrokerResult<()> {
+        match tokio::time::timeout(timeout, confirmation).await {
+            Ok(Ok(res)) => res,
+            // The channel was lost before the confirm, the message may be sent again
+            Ok(Err(_)) => Err(MBrokerError::Disconnected),
+            Err(_) => {
+                self.forget(tag);
+                Err(MBrokerError::ConfirmTimeout(timeout))
+            }
+        }
+    }
+
+    fn register(&self) -> (u64, Confirmation) {
+        let mut state = self.state.lock().unwrap();
+        state.last_tag += 1;
+        let (tx, rx) = oneshot::channel();
+        let tag = state.last_tag;
+        state.pending.insert(tag, tx);
+        (tag, rx)
+    }
+
+    /// Stops waiting for a publish that failed or timed out
+    fn forget(&self, tag: u64) {
+        self.state.lock().unwrap().pending.remove(&tag);
+    }
+
+    /// The channel is lost, publishes still waiting see it as lost
+    pub(crate) fn close(&self) {
+        self.state.lock().unwrap().pending.clear();
+    }
+
+    pub(crate) fn returned(&self, code: u16, text: String) {
+        self.state.lock().unwrap().returned = Some(MBrokerError::Unroutable { code, text });
+    }
+
+    pub(crate) fn ack(&self, tag: u64, multiple: bool) {
+        self.settle(tag, multiple, || Ok(()));
+    }
+
+    pub(crate) fn nack(&self, tag: u64, multiple: bool) {
+        self.settle(tag, multiple, || Err(MBrokerError::PublishNacked));
+    }
+
+    fn settle(&self, tag: u64, multiple: bool, outcome: impl Fn() -> MBrokerResult<()>) {
+        let mut state = self.state.lock().unwrap();
+        let tags: Vec<u64> = match multiple {
+            true => state.pending.range(..=tag).map(|(tag, _)| *tag).collect(),
+            false => vec![tag],
+        };
+        for tag in tags {
+            let Some(tx) = state.pending.remove(&tag) else {
+                continue;
+            };
+            let res = match state.returned.take() {
+                Some(err) => Err(err),
+                None => outcome(),
+            };
+            let _ = tx.send(res);
+        }
+    }
+}
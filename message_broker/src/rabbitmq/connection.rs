@@ -0,0 +1,323 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use amqprs::callbacks::{ChannelCallback, ConnectionCallback};
+use amqprs::channel::{Channel, ConfirmSelectArguments, QueueDeclareArguments};
+use amqprs::connection::{Connection, OpenConnectionArguments};
+use amqprs::error::Error as AmqpError;
+use amqprs::{Ack, BasicProperties, Cancel, Close, CloseChannel, Nack, Return};
+use async_trait::async_trait;
+use tokio::sync::{watch, Mutex, Notify};
+
+use crate::error::{MBrokerError, MBrokerResult};
+
+use super::confirm::PublishConfirms;
+use super::{declare_exchange_topology, declare_retry_topology, RabbitMQOptions};
+
+/// How often the supervisor checks a connection that failed silently
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+pub struct RabbitMQConnectionOptions {
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub password: String,
+
+    /// Delay before the second reconnection attempt, doubled for each next one
+    pub reconnect_base_delay: Duration,
+    pub reconnect_max_delay: Duration,
+}
+
+/// Shared handle of the broker connection. A supervisor task notices when the
+/// connection is lost and reopens it with backoff, channels are reopened lazily
+/// on top of the current connection
+#[derive(Clone)]
+pub struct RabbitMQConnection {
+    inner: Arc<ConnectionInner>,
+}
+
+struct ConnectionInner {
+    options: RabbitMQConnectionOptions,
+
+    /// `None` while reconnecting
+    state: watch::Sender<Option<Connection>>,
+    closed: Arc<Notify>,
+
+    /// Set by `close`, the connection isn't reopened after it
+    shut_down: AtomicBool,
+}
+
+impl RabbitMQConnection {
+    /// Fails when the broker is unreachable at startup, later losses are recovered
+    pub async fn connect(options: RabbitMQConnectionOptions) -> MBrokerResult<Self> {
+        let closed = Arc::new(Notify::new());
+        let conn = open_connection(&options, &closed).await?;
+        let (state, _) = watch::channel(Some(conn.clone()));
+        let inner = Arc::new(ConnectionInner {
+            options,
+            state,
+            closed,
+            shut_down: AtomicBool::new(false),
+        });
+        tokio::spawn(supervise(Arc::clone(&inner), conn));
+        Ok(Self { inner })
+    }
+
+    /// Open connection, `None` while the broker is unreachable
+    pub fn current(&self) -> Option<Connection> {
+        self.inner
+            .state
+            .borrow()
+            .clone()
+            .filter(Connection::is_open)
+    }
+
+    /// Resolves once a connection is open or the connection is closed for good
+    pub async fn connected(&self) {
+        let mut state = self.inner.state.subscribe();
+        let _ = state
+            .wait_for(|conn| self.is_closed() || conn.as_ref().is_some_and(Connection::is_open))
+            .await;
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.inner.shut_down.load(Ordering::SeqCst)
+    }
+
+    /// Closes the connection for good, channels fail with `Closed` from now on
+    pub async fn close(&self) -> MBrokerResult<()> {
+        self.inner.shut_down.store(true, Ordering::SeqCst);
+        let conn = self.inner.state.send_replace(None);
+        self.inner.closed.notify_one();
+        match conn {
+            Some(conn) if conn.is_open() => Ok(conn.close().await?),
+            _ => Ok(()),
+        }
+    }
+
+    /// Changes every time the connection is lost or reopened
+    pub fn subscribe(&self) -> watch::Receiver<Option<Connection>> {
+        self.inner.state.subscribe()
+    }
+}
+
+async fn open_connection(
+    options: &RabbitMQConnectionOptions,
+    closed: &Arc<Notify>,
+) -> MBrokerResult<Connection> {
+    let conn = Connection::open(&OpenConnectionArguments::new(
+        &options.host,
+        options.port,
+        &options.user,
+        &options.password,
+    ))
+    .await?;
+    conn.register_callback(RecoveryConnectionCallback {
+        closed: Arc::clone(closed),
+    })
+    .await?;
+    Ok(conn)
+}
+
+async fn supervise(inner: Arc<ConnectionInner>, mut conn: Connection) {
+    loop {
+        tokio::select! {
+            _ = conn.listen_network_io_failure() => {}
+            _ = inner.closed.notified() => {}
+            _ = async {
+                let mut interval = tokio::time::interval(HEALTH_CHECK_INTERVAL);
+                while conn.is_open() {
+                    interval.tick().await;
+                }
+            } => {}
+        }
+
+        if inner.shut_down.load(Ordering::SeqCst) {
+            return;
+        }
+        inner.state.send_replace(None);
+        eprintln!("Lost connection to RabbitMQ, reconnecting");
+        conn = reconnect(&inner).await;
+        if inner.shut_down.load(Ordering::SeqCst) {
+            // Closed while reconnecting
+            let _ = conn.close().await;
+            return;
+        }
+        inner.state.send_replace(Some(conn.clone()));
+        println!("Reconnected to RabbitMQ");
+    }
+}
+
+async fn reconnect(inner: &ConnectionInner) -> Connection {
+    let options = &inner.options;
+    let mut delay = options.reconnect_base_delay;
+    loop {
+        match open_connection(options, &inner.closed).await {
+            Ok(conn) => return conn,
+            Err(e) => eprintln!("{e}"),
+        }
+        tokio::time::sleep(delay).await;
+        delay = delay.saturating_mul(2).min(options.reconnect_max_delay);
+    }
+}
+
+struct RecoveryConnectionCallback {
+    closed: Arc<Notify>,
+}
+
+#[async_trait]
+impl ConnectionCallback for RecoveryConnectionCallback {
+    async fn close(&mut self, _connection: &Connection, close: Close) -> Result<(), AmqpError> {
+        eprintln!("RabbitMQ closed the connection: {close}");
+        self.closed.notify_one();
+        Ok(())
+    }
+
+    async fn blocked(&mut self, _connection: &Connection, reason: String) {
+        eprintln!("RabbitMQ blocked the connection: {reason}");
+    }
+
+    async fn unblocked(&mut self, _connection: &Connection) {}
+}
+
+/// Closed channels are noticed through `Channel::is_open` and reopened on next use
+struct RecoveryChannelCallback {
+    confirms: Arc<PublishConfirms>,
+}
+
+#[async_trait]
+impl ChannelCallback for RecoveryChannelCallback {
+    async fn close(&mut self, channel: &Channel, close: CloseChannel) -> Result<(), AmqpError> {
+        eprintln!("RabbitMQ closed {channel}: {close}");
+        self.confirms.close();
+        Ok(())
+    }
+
+    async fn cancel(&mut self, channel: &Channel, cancel: Cancel) -> Result<(), AmqpError> {
+        eprintln!(
+            "RabbitMQ cancelled consumer {} on {channel}",
+            cancel.consumer_tag()
+        );
+        Ok(())
+    }
+
+    async fn flow(&mut self, _channel: &Channel, active: bool) -> Result<bool, AmqpError> {
+        Ok(active)
+    }
+
+    async fn publish_ack(&mut self, _channel: &Channel, ack: Ack) {
+        self.confirms.ack(ack.delivery_tag(), ack.mutiple());
+    }
+
+    async fn publish_nack(&mut self, _channel: &Channel, nack: Nack) {
+        self.confirms.nack(nack.delivery_tag(), nack.multiple());
+    }
+
+    async fn publish_return(
+        &mut self,
+        _channel: &Channel,
+        ret: Return,
+        _basic_properties: BasicProperties,
+        _content: Vec<u8>,
+    ) {
+        self.confirms
+            .returned(ret.reply_code(), ret.reply_text().clone());
+    }
+}
+
+/// Channel of one publisher or receiver, reopened with its queues on the
+/// current connection after a loss. Channels are opened in confirm mode, so
+/// messages republished by receivers are confirmed too
+pub(crate) struct ManagedChannel {
+    connection: RabbitMQConnection,
+    options: RabbitMQOptions,
+
+    /// Open channel with the confirms of its publishes
+    channel: Mutex<Option<(Channel, Arc<PublishConfirms>)>>,
+}
+
+impl ManagedChannel {
+    /// Opens the channel right away, so a wrong topology fails at startup
+    pub(crate) async fn open(
+        connection: RabbitMQConnection,
+        options: &RabbitMQOptions,
+    ) -> MBrokerResult<Self> {
+        let managed = Self {
+            connection,
+            options: options.clone(),
+            channel: Mutex::new(None),
+        };
+        managed.get().await?;
+        Ok(managed)
+    }
+
+    /// Fails fast with `Disconnected` while the connection is being recovered
+    /// and with `Closed` once it is closed
+    pub(crate) async fn get(&self) -> MBrokerResult<Channel> {
+        Ok(self.get_confirmed().await?.0)
+    }
+
+    /// Same as `get`, with the confirms publishes on the channel are awaited with
+    pub(crate) async fn get_confirmed(&self) -> MBrokerResult<(Channel, Arc<PublishConfirms>)> {
+        let mut channel = self.channel.lock().await;
+        if let Some((ch, confirms)) = channel.as_ref().filter(|(ch, _)| ch.is_open()) {
+            return Ok((ch.clone(), Arc::clone(confirms)));
+        }
+        if self.connection.is_closed() {
+            return Err(MBrokerError::Closed);
+        }
+        let conn = self
+            .connection
+            .current()
+            .ok_or(MBrokerError::Disconnected)?;
+
+        let ch = conn.open_channel(None).await?;
+        let confirms = Arc::new(PublishConfirms::default());
+        ch.register_callback(RecoveryChannelCallback {
+            confirms: Arc::clone(&confirms),
+        })
+        .await?;
+        ch.confirm_select(ConfirmSelectArguments::default()).await?;
+        let q_args = QueueDeclareArguments::new(self.options.queue_name)
+            .durable(self.options.durable)
+            .finish();
+        ch.queue_declare(q_args).await?;
+        declare_exchange_topology(&ch, &self.options).await?;
+        declare_retry_topology(&ch, &self.options).await?;
+
+        *channel = Some((ch.clone(), Arc::clone(&confirms)));
+        Ok((ch, confirms))
+    }
+
+    /// Same as `get`, but waits for the connection to come back
+    pub(crate) async fn wait(&self) -> MBrokerResult<(Channel, Arc<PublishConfirms>)> {
+        loop {
+            match self.get_confirmed().await {
+                Err(MBrokerError::Disconnected) => self.connection.connected().await,
+                res => return res,
+            }
+        }
+    }
+
+    pub(crate) fn connection(&self) -> &RabbitMQConnection {
+        &self.connection
+    }
+
+    pub(crate) fn queue_name(&self) -> &'static str {
+        self.options.queue_name
+    }
+
+    /// Exchange publishes go to, the default one routes by queue name
+    pub(crate) fn exchange_name(&self) -> &'static str {
+        self.options.exchange.map_or("", |exchange| exchange.name)
+    }
+
+    pub(crate) async fn close(&self) -> MBrokerResult<()> {
+        match self.channel.lock().await.take() {
+            Some((ch, _)) if ch.is_open() => Ok(ch.close().await?),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,600 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use crate::codec::{Codec, JsonCodec};
+use crate::error::{MBrokerError, MBrokerResult};
+use amqprs::channel::{
+    BasicAckArguments, BasicConsumeArguments, BasicGetArguments, BasicNackArguments,
+    BasicPublishArguments, Channel, ConsumerMessage, ExchangeDeclareArguments, ExchangeType,
+    QueueBindArguments, QueueDeclareArguments, QueuePurgeArguments,
+};
+use amqprs::{BasicProperties, FieldTable, FieldValue};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use super::{
+    Acker, DeadLetter, DeadLetterQueue, Delivery, Publisher, Receiver, RetryOptions, Subscriber,
+};
+
+mod confirm;
+mod connection;
+
+use confirm::PublishConfirms;
+use connection::ManagedChannel;
+pub use connection::{RabbitMQConnection, RabbitMQConnectionOptions};
+
+/// Failed deliveries of the message so far
+const RETRY_COUNT_HEADER: &str = "x-retry-count";
+const LAST_ERROR_HEADER: &str = "x-last-error";
+
+#[derive(Debug, Clone)]
+pub struct RabbitMQOptions {
+    pub queue_name: &'static str,
+    pub durable: bool,
+
+    /// Exchange messages are published to and the queue is bound to. Without it
+    /// messages go through the default exchange straight to the queue
+    pub exchange: Option<RabbitMQExchange>,
+
+    /// Keys the queue is bound with to `exchange`, its own name when empty
+    pub binding_keys: Vec<&'static str>,
+
+    /// Failed messages wait in `<queue>.retry.<delay ms>` queues before coming back,
+    /// once attempts run out they are routed through `<queue>.dlx` to `<queue>.dlq`
+    pub retry: RetryOptions,
+
+    /// Messages a publisher keeps while disconnected, 0 fails publishing right away
+    pub publish_buffer: usize,
+
+    /// How long a publisher waits for the broker to confirm a message
+    pub confirm_timeout: Duration,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RabbitMQExchangeKind {
+    Direct,
+    Topic,
+    Fanout,
+}
+
+impl From<RabbitMQExchangeKind> for ExchangeType {
+    fn from(kind: RabbitMQExchangeKind) -> Self {
+        match kind {
+            RabbitMQExchangeKind::Direct => ExchangeType::Direct,
+            RabbitMQExchangeKind::Topic => ExchangeType::Topic,
+            RabbitMQExchangeKind::Fanout => ExchangeType::Fanout,
+        }
+    }
+}
+
+/// Several queues bound to one exchange each get their own copy of a message,
+/// so independent consumers can read the same stream
+#[derive(Debug, Clone, Copy)]
+pub struct RabbitMQExchange {
+    pub name: &'static str,
+    pub kind: RabbitMQExchangeKind,
+    pub durable: bool,
+}
+
+fn dead_letter_exchange(queue_name: &str) -> String {
+    format!("{queue_name}.dlx")
+}
+
+fn dead_letter_queue(queue_name: &str) -> String {
+    format!("{queue_name}.dlq")
+}
+
+fn delay_queue(queue_name: &str, delay: Duration) -> String {
+    format!("{queue_name}.retry.{}", delay.as_millis())
+}
+
+fn field_name(name: &str) -> amqprs::FieldName {
+    name.try_into().unwrap()
+}
+
+fn retry_count(props: &BasicProperties) -> u32 {
+    match props
+        .headers()
+        .and_then(|headers| headers.get(&field_name(RETRY_COUNT_HEADER)))
+    {
+        Some(FieldValue::l(count)) => u32::try_from(*count).unwrap_or_default(),
+        _ => 0,
+    }
+}
+
+/// The exchange of the options and bindings of the queue to it
+async fn declare_exchange_topology(ch: &Channel, options: &RabbitMQOptions) -> MBrokerResult<()> {
+    let Some(exchange) = options.exchange else {
+        return Ok(());
+    };
+    let args = ExchangeDeclareArguments::of_type(exchange.name, exchange.kind.into())
+        .durable(exchange.durable)
+        .finish();
+    ch.exchange_declare(args).await?;
+
+    let binding_keys = match options.binding_keys.is_empty() {
+        true => vec![options.queue_name],
+        false => options.binding_keys.clone(),
+    };
+    for binding_key in binding_keys {
+        ch.queue_bind(QueueBindArguments::new(
+            options.queue_name,
+            exchange.name,
+            binding_key,
+        ))
+        .await?;
+    }
+    Ok(())
+}
+
+/// Dead-letter exchange and queue plus one delay queue per distinct retry delay
+async fn declare_retry_topology(ch: &Channel, options: &RabbitMQOptions) -> MBrokerResult<()> {
+    let RabbitMQOptions {
+        queue_name,
+        durable,
+        ref retry,
+        ..
+    } = *options;
+    let dlx = dead_letter_exchange(queue_name);
+    let dlq = dead_letter_queue(queue_name);
+    ch.exchange_declare(
+        ExchangeDeclareArguments::of_type(&dlx, ExchangeType::Direct)
+            .durable(durable)
+            .finish(),
+    )
+    .await?;
+    ch.queue_declare(QueueDeclareArguments::new(&dlq).durable(durable).finish())
+        .await?;
+    ch.queue_bind(QueueBindArguments::new(&dlq, &dlx, queue_name))
+        .await?;
+
+    let mut delays: Vec<Duration> = (1..retry.max_attempts).map(|a| retry.delay(a)).collect();
+    delays.dedup();
+    for delay in delays {
+        // Expired messages go back to the work queue through the default exchange
+        let mut arguments = FieldTable::new();
+        let ttl = i64::try_from(delay.as_millis()).unwrap_or(i64::MAX);
+        arguments.insert(field_name("x-message-ttl"), FieldValue::l(ttl));
+        arguments.insert(field_name("x-dead-letter-exchange"), "".into());
+        arguments.insert(field_name("x-dead-letter-routing-key"), queue_name.into());
+
+        let args = QueueDeclareArguments::new(&delay_queue(queue_name, delay))
+            .durable(durable)
+            .arguments(arguments)
+            .finish();
+        ch.queue_declare(args).await?;
+    }
+    Ok(())
+}
+
+/// Where a failed message is published for its next delivery, or to be
+/// dead-lettered when attempts run out
+fn retry_or_dead_letter(
+    queue_name: &str,
+    retry: &RetryOptions,
+    mut props: BasicProperties,
+    error: &str,
+) -> (BasicProperties, BasicPublishArguments) {
+    let attempt = retry_count(&props) + 1;
+    let mut headers = props.headers().cloned().unwrap_or_default();
+    headers.insert(
+        field_name(RETRY_COUNT_HEADER),
+        FieldValue::l(attempt.into()),
+    );
+    headers.insert(field_name(LAST_ERROR_HEADER), error.into());
+    props.with_headers(headers);
+
+    let args = match attempt < retry.max_attempts {
+        true => BasicPublishArguments::new("", &delay_queue(queue_name, retry.delay(attempt))),
+        false => BasicPublishArguments::new(&dead_letter_exchange(queue_name), queue_name),
+    };
+    (props, args)
+}
+
+/// Publishes in confirm mode, `publish` resolves once the broker has taken
+/// responsibility for the message. Messages without a routing key are routed
+/// by the queue name. Keeps up to `publish_buffer` messages while
+/// the connection is recovered and sends them, in order, once it is back.
+/// Such a publish fails with `Buffered`, as nothing is confirmed yet
+pub struct RabbitMQPublisher<C: Codec = JsonCodec> {
+    inner: Arc<PublisherInner>,
+    codec: C,
+}
+
+struct Outgoing {
+    routing_key: String,
+    payload: Vec<u8>,
+}
+
+struct PublisherInner {
+    channel: ManagedChannel,
+    buffer: Mutex<VecDeque<Outgoing>>,
+    capacity: usize,
+    confirm_timeout: Duration,
+    content_type: &'static str,
+}
+
+impl PublisherInner {
+    /// Callers hold the buffer lock, so one message at a time waits for its confirm
+    async fn send(&self, outgoing: &Outgoing) -> MBrokerResult<()> {
+        let (ch, confirms) = self.channel.get_confirmed().await?;
+        let mut publish_args =
+            BasicPublishArguments::new(self.channel.exchange_name(), &outgoing.routing_key);
+        publish_args.mandatory(true);
+        let props = BasicProperties::default()
+            .with_delivery_mode(2)
+            .with_content_type(self.content_type)
+            .finish();
+
+        let payload = outgoing.payload.clone();
+        let published = confirms.publish(&ch, props, payload, publish_args).await;
+        let (tag, confirmation) = match published {
+            Ok(published) => published,
+            Err(err) => {
+                return match self.channel.connection().current() {
+                    None => Err(MBrokerError::Disconnected),
+                    Some(_) => Err(err),
+                }
+            }
+        };
+        confirms.wait(tag, confirmation, self.confirm_timeout).await
+    }
+
+    /// Sends buffered messages until the buffer is empty or sending fails
+    async fn flush(&self, buffer: &mut VecDeque<Outgoing>) -> MBrokerResult<()> {
+        while let Some(outgoing) = buffer.front() {
+            self.send(outgoing).await?;
+            buffer.pop_front();
+        }
+        Ok(())
+    }
+
+    async fn publish(&self, routing_key: Option<&str>, payload: Vec<u8>) -> MBrokerResult<()> {
+        let outgoing = Outgoing {
+            routing_key: routing_key.unwrap_or(self.channel.queue_name()).to_string(),
+            payload,
+        };
+        let mut buffer = self.buffer.lock().await;
+        let res = match self.flush(&mut buffer).await {
+            Ok(()) => self.send(&outgoing).await,
+            Err(err) => Err(err),
+        };
+        match res {
+            Err(MBrokerError::Disconnected) if self.capacity > 0 => {
+                if buffer.len() >= self.capacity {
+                    return Err(MBrokerError::BufferFull(self.capacity));
+                }
+                buffer.push_back(outgoing);
+                Err(MBrokerError::Buffered)
+            }
+            res => res,
+        }
+    }
+}
+
+/// Flushes the buffer of the publisher every time the connection is reopened
+async fn flush_on_reconnect(publisher: Weak<PublisherInner>, connection: RabbitMQConnection) {
+    let mut state = connection.subscribe();
+    while state.changed().await.is_ok() {
+        let Some(publisher) = publisher.upgrade() else {
+            return;
+        };
+        if state.borrow_and_update().is_none() {
+            continue;
+        }
+        let mut buffer = publisher.buffer.lock().await;
+        if let Err(err) = publisher.flush(&mut buffer).await {
+            eprintln!("Can't flush buffered messages: {err}");
+        }
+    }
+}
+
+#[async_trait]
+impl<C: Codec> Subscriber<RabbitMQConnection, RabbitMQOptions> for RabbitMQPublisher<C> {
+    async fn init(conn: RabbitMQConnection, options: &RabbitMQOptions) -> MBrokerResult<Self> {
+        let inner = Arc::new(PublisherInner {
+            channel: ManagedChannel::open(conn.clone(), options).await?,
+            buffer: Mutex::new(VecDeque::new()),
+            capacity: options.publish_buffer,
+            confirm_timeout: options.confirm_timeout,
+            content_type: C::CONTENT_TYPE,
+        });
+        if options.publish_buffer > 0 {
+            tokio::spawn(flush_on_reconnect(Arc::downgrade(&inner), conn));
+        }
+        Ok(Self {
+            inner,
+            codec: C::default(),
+        })
+    }
+
+    async fn close(&self) -> MBrokerResult<()> {
+        self.inner.channel.close().await
+    }
+}
+
+#[async_trait]
+impl<M, C> Publisher<M> for RabbitMQPublisher<C>
+where
+    M: Serialize + Send + Sync,
+    C: Codec,
+{
+    async fn publish(&self, message: M) -> MBrokerResult<()>
+    where
+        M: 'async_trait,
+    {
+        self.inner.publish(None, self.codec.encode(&message)?).await
+    }
+
+    async fn publish_with_key(&self, message: M, routing_key: &str) -> MBrokerResult<()>
+    where
+        M: 'async_trait,
+    {
+        let payload = self.codec.encode(&message)?;
+        self.inner.publish(Some(routing_key), payload).await
+    }
+}
+
+/// Consumes again on a new channel when the old one is lost
+pub struct RabbitMQReceiver<C: Codec = JsonCodec> {
+    channel: ManagedChannel,
+    consumer: Mutex<Option<Consumer>>,
+    retry: RetryOptions,
+    confirm_timeout: Duration,
+    codec: C,
+}
+
+struct Consumer {
+    channel: Channel,
+    confirms: Arc<PublishConfirms>,
+    messages: UnboundedReceiver<ConsumerMessage>,
+}
+
+impl<C: Codec> RabbitMQReceiver<C> {
+    async fn consume(&self) -> MBrokerResult<Consumer> {
+        let (ch, confirms) = self.channel.wait().await?;
+        let queue_name = self.channel.queue_name();
+        let consume_tag = format!("{queue_name}_receiver_{}", Uuid::new_v4());
+        let consumer_args = BasicConsumeArguments::new(queue_name, &consume_tag);
+        let (_ctag, messages) = ch.basic_consume_rx(consumer_args).await?;
+        Ok(Consumer {
+            channel: ch,
+            confirms,
+            messages,
+        })
+    }
+}
+
+#[async_trait]
+impl<C: Codec> Subscriber<RabbitMQConnection, RabbitMQOptions> for RabbitMQReceiver<C> {
+    async fn init(conn: RabbitMQConnection, options: &RabbitMQOptions) -> MBrokerResult<Self>
+    where
+        Self: Sized,
+    {
+        let receiver = Self {
+            channel: ManagedChannel::open(conn, options).await?,
+            consumer: Mutex::new(None),
+            retry: options.retry,
+            confirm_timeout: options.confirm_timeout,
+            codec: C::default(),
+        };
+        *receiver.consumer.lock().await = Some(receiver.consume().await?);
+        Ok(receiver)
+    }
+
+    async fn close(&self) -> MBrokerResult<()> {
+        self.consumer.lock().await.take();
+        self.channel.close().await
+    }
+}
+
+/// Settles one message of a `RabbitMQReceiver`
+struct RabbitMQAcker {
+    channel: Channel,
+    confirms: Arc<PublishConfirms>,
+    confirm_timeout: Duration,
+    delivery_tag: u64,
+    queue_name: &'static str,
+    retry: RetryOptions,
+    payload: Vec<u8>,
+    props: BasicProperties,
+}
+
+#[async_trait]
+impl Acker for RabbitMQAcker {
+    async fn ack(self: Box<Self>) -> MBrokerResult<()> {
+        let ack_args = BasicAckArguments::new(self.delivery_tag, false);
+        Ok(self.channel.basic_ack(ack_args).await?)
+    }
+
+    /// Acks only once the broker confirmed the copy for the next delivery, a
+    /// failure leaves the message to be redelivered
+    async fn nack(self: Box<Self>, error: String) -> MBrokerResult<()> {
+        let Self {
+            channel,
+            confirms,
+            confirm_timeout,
+            delivery_tag,
+            queue_name,
+            retry,
+            payload,
+            props,
+        } = *self;
+        let (props, args) = retry_or_dead_letter(queue_name, &retry, props, &error);
+        let (tag, confirmation) = confirms.publish(&channel, props, payload, args).await?;
+        confirms.wait(tag, confirmation, confirm_timeout).await?;
+        Ok(channel
+            .basic_ack(BasicAckArguments::new(delivery_tag, false))
+            .await?)
+    }
+
+    async fn requeue(self: Box<Self>) -> MBrokerResult<()> {
+        let nack_args = BasicNackArguments::new(self.delivery_tag, false, true);
+        Ok(self.channel.basic_nack(nack_args).await?)
+    }
+}
+
+#[async_trait]
+impl<M, C> Receiver<M> for RabbitMQReceiver<C>
+where
+    M: DeserializeOwned + Send,
+    C: Codec,
+{
+    /// Messages of another content type or that can't be decoded are nacked
+    /// right away instead of returned.
+    /// Unsettled messages of a lost channel are redelivered by the broker
+    async fn receive(&self) -> MBrokerResult<Delivery<M>> {
+        let mut consumer = self.consumer.lock().await;
+        loop {
+            let current = match consumer.as_mut() {
+                Some(current) => current,
+                None => consumer.insert(self.consume().await?),
+            };
+            let Some(msg) = current.messages.recv().await else {
+                *consumer = None;
+                continue;
+            };
+            // Without its delivery tag the message can't be settled
+            let Some(deliver) = msg.deliver else {
+                return Err(MBrokerError::CantReadMessage(
+                    "The message has no delivery tag".to_string(),
+                ));
+            };
+            let props = msg.basic_properties.unwrap_or_default();
+            // A message without content is settled like an undecodable one
+            let (payload, message) = match msg.content {
+                Some(payload) => {
+                    let message = self
+                        .codec
+                        .check_content_type(props.content_type().map(String::as_str))
+                        .and_then(|_| self.codec.decode::<M>(&payload));
+                    (payload, message)
+                }
+                None => (
+                    vec![],
+                    Err(MBrokerError::CantReadMessage(
+                        "The message has no content".to_string(),
+                    )),
+                ),
+            };
+            let acker = RabbitMQAcker {
+                channel: current.channel.clone(),
+                confirms: Arc::clone(&current.confirms),
+                confirm_timeout: self.confirm_timeout,
+                delivery_tag: deliver.delivery_tag(),
+                queue_name: self.channel.queue_name(),
+                retry: self.retry,
+                payload,
+                props,
+            };
+
+            match message {
+                Ok(message) => return Ok(Delivery::new(message, acker)),
+                Err(error) => Box::new(acker).nack(error.to_string()).await?,
+            }
+        }
+    }
+}
+
+/// Admin access to the `<queue>.dlq` of a work queue
+pub struct RabbitMQDeadLetterQueue {
+    channel: ManagedChannel,
+    confirm_timeout: Duration,
+
+    /// Operations fetch and settle several messages, they must not interleave
+    operation: Mutex<()>,
+}
+
+#[async_trait]
+impl Subscriber<RabbitMQConnection, RabbitMQOptions> for RabbitMQDeadLetterQueue {
+    async fn init(conn: RabbitMQConnection, options: &RabbitMQOptions) -> MBrokerResult<Self> {
+        Ok(Self {
+            channel: ManagedChannel::open(conn, options).await?,
+            confirm_timeout: options.confirm_timeout,
+            operation: Mutex::new(()),
+        })
+    }
+
+    async fn close(&self) -> MBrokerResult<()> {
+        self.channel.close().await
+    }
+}
+
+#[async_trait]
+impl DeadLetterQueue for RabbitMQDeadLetterQueue {
+    async fn peek(&self, limit: u32) -> MBrokerResult<Vec<DeadLetter>> {
+        let _operation = self.operation.lock().await;
+        let ch = self.channel.get().await?;
+        let dlq = dead_letter_queue(self.channel.queue_name());
+
+        let mut letters = vec![];
+        let mut last_tag = None;
+        while letters.len() < limit as usize {
+            let Some((get_ok, props, payload)) = ch.basic_get(BasicGetArguments::new(&dlq)).await?
+            else {
+                break;
+            };
+            last_tag = Some(get_ok.delivery_tag());
+            let error = props
+                .headers()
+                .and_then(|headers| headers.get(&field_name(LAST_ERROR_HEADER)))
+                .map(ToString::to_string);
+            letters.push(DeadLetter {
+                payload,
+                attempts: retry_count(&props),
+                error,
+            });
+        }
+
+        // Everything fetched goes back to the queue
+        if let Some(delivery_tag) = last_tag {
+            let nack_args = BasicNackArguments::new(delivery_tag, true, true);
+            ch.basic_nack(nack_args).await?;
+        }
+        Ok(letters)
+    }
+
+    async fn replay(&self, limit: Option<u32>) -> MBrokerResult<u32> {
+        let _operation = self.operation.lock().await;
+        let (ch, confirms) = self.channel.get_confirmed().await?;
+        let queue_name = self.channel.queue_name();
+        let dlq = dead_letter_queue(queue_name);
+
+        let mut replayed = 0;
+        while limit.is_none_or(|limit| replayed < limit) {
+            let Some((get_ok, mut props, payload)) =
+                ch.basic_get(BasicGetArguments::new(&dlq)).await?
+            else {
+                break;
+            };
+            // Replayed messages get the whole retry budget again
+            let mut headers = props.headers().cloned().unwrap_or_default();
+            headers.remove(&field_name(RETRY_COUNT_HEADER));
+            headers.remove(&field_name(LAST_ERROR_HEADER));
+            props.with_headers(headers);
+
+            // Removed from the dead letters once the work queue has it
+            let publish_args = BasicPublishArguments::new("", queue_name);
+            let (tag, confirmation) = confirms.publish(&ch, props, payload, publish_args).await?;
+            confirms
+                .wait(tag, confirmation, self.confirm_timeout)
+                .await?;
+            ch.basic_ack(BasicAckArguments::new(get_ok.delivery_tag(), false))
+                .await?;
+            replayed += 1;
+        }
+        Ok(replayed)
+    }
+
+    async fn purge(&self) -> MBrokerResult<u32> {
+        let _operation = self.operation.lock().await;
+        let ch = self.channel.get().await?;
+        let purge_args = QueuePurgeArguments::new(&dead_letter_queue(self.channel.queue_name()));
+        Ok(ch.queue_purge(purge_args).await?.unwrap_or_default())
+    }
+}
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use crate::error::MBrokerResult;
-use crate::{Publisher, Receiver};
+use crate::{Acker, DeadLetter, DeadLetterQueue, Delivery, Publisher, Receiver};
 use async_trait::async_trait;
 use tokio::sync::Mutex;
 
@@ -14,8 +14,26 @@ impl<M> Receiver<M> for ReceiverMock<M>
 where
     M: Send + Sync,
 {
-    async fn receive(&self) -> MBrokerResult<M> {
-        Ok(self.queue.lock().await.remove(0))
+    async fn receive(&self) -> MBrokerResult<Delivery<M>> {
+        Ok(Delivery::new(self.queue.lock().await.remove(0), AckerMock))
+    }
+}
+
+/// Messages of the mock are settled as soon as they are received
+pub struct AckerMock;
+
+#[async_trait]
+impl Acker for AckerMock {
+    async fn ack(self: Box<Self>) -> MBrokerResult<()> {
+        Ok(())
+    }
+
+    async fn nack(self: Box<Self>, _error: String) -> MBrokerResult<()> {
+        Ok(())
+    }
+
+    async fn requeue(self: Box<Self>) -> MBrokerResult<()> {
+        Ok(())
     }
 }
 
@@ -28,7 +46,7 @@ where
     }
 }
 
-pub struct PublisherMock<M, T, R = MBrokerResult<T>> {
+pub struct PublisherMock<M, T, R = MBrokerResult<Delivery<T>>> {
     receiver: Arc<dyn Receiver<T, R>>,
     queue: Arc<Mutex<Vec<M>>>,
 }
@@ -50,3 +68,37 @@ where
         Ok(())
     }
 }
+
+/// Dead letters are only kept in memory, replayed ones are dropped
+#[derive(Default)]
+pub struct DeadLetterQueueMock {
+    letters: Arc<Mutex<Vec<DeadLetter>>>,
+}
+
+impl DeadLetterQueueMock {
+    pub fn new(letters: Arc<Mutex<Vec<DeadLetter>>>) -> Self {
+        Self { letters }
+    }
+}
+
+#[async_trait]
+impl DeadLetterQueue for DeadLetterQueueMock {
+    async fn peek(&self, limit: u32) -> MBrokerResult<Vec<DeadLetter>> {
+        let letters = self.letters.lock().await;
+        Ok(letters.iter().take(limit as usize).cloned().collect())
+    }
+
+    async fn replay(&self, limit: Option<u32>) -> MBrokerResult<u32> {
+        let mut letters = self.letters.lock().await;
+        let count = limit.map_or(letters.len(), |limit| letters.len().min(limit as usize));
+        letters.drain(..count);
+        Ok(count as u32)
+    }
+
+    async fn purge(&self) -> MBrokerResult<u32> {
+        let mut letters = self.letters.lock().await;
+        let count = letters.len() as u32;
+        letters.clear();
+        Ok(count)
+    }
+}
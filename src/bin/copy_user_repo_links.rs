@@ -0,0 +1,19 @@
+//! One-shot copy of user repo links from the object store into postgres.
+//! Links that are already in the table are skipped, so it is safe to rerun.
+//! Links in the legacy layout are not read, `migrate_user_repo_links` converts them first
+
+use ia_11_vorobei_ant::{db, error};
+use repo::dao::user_repo_repository::UserRepoRepository;
+use repo::dao::user_repo_sql_repository::UserRepoSqlRepository;
+
+#[tokio::main]
+async fn main() -> error::InternalResult<()> {
+    let sql_conn = db::init_sql_database().await?;
+    let source = UserRepoRepository::new(db::init_object_store().await?);
+
+    let copied = UserRepoSqlRepository::new(sql_conn)
+        .import_pairs(&source)
+        .await?;
+    println!("Copied {copied} user repo links to postgres");
+    Ok(())
+}
@@ -0,0 +1,11 @@
+//! One-shot move of user repo info dates from RFC 3339 strings to BSON dates,
+//! together with the validator of the collection. Safe to rerun
+use ia_11_vorobei_ant::{db, error};
+
+#[tokio::main]
+async fn main() -> error::InternalResult<()> {
+    let conn = db::init_nosql_database().await?;
+    let migrated = schema::migrate_user_repo_info_dates(&conn).await?;
+    println!("Moved {migrated} user repo info records to BSON dates");
+    Ok(())
+}
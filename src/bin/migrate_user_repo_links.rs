@@ -0,0 +1,14 @@
+//! One-shot rewrite of user repo links from the flat `{user_id}____{repo_id}`
+//! layout into the prefixed one. Migrated objects are removed, so it is safe to rerun
+
+use ia_11_vorobei_ant::{db, error};
+use repo::dao::user_repo_repository::UserRepoRepository;
+
+#[tokio::main]
+async fn main() -> error::InternalResult<()> {
+    let migrated = UserRepoRepository::new(db::init_object_store().await?)
+        .migrate_legacy_pairs()
+        .await?;
+    println!("Migrated {migrated} user repo links to the prefixed layout");
+    Ok(())
+}
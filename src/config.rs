@@ -24,21 +24,35 @@ pub struct Config {
     pub SERVER: ServerConfig,
     pub SQL_DB: SqlDbConfig,
     pub MONGO_DB: MongoDbConfig,
-    pub AWS: AwsConfig,
+    /// Read only for the S3 store backend
+    pub AWS: Option<AwsConfig>,
     pub RABBITMQ: RabbitMQConfig,
+    pub BROKER: BrokerConfig,
     pub RESERVE: ReserveConfig,
+    pub STORE: StoreConfig,
+    pub LINK_CONSISTENCY: LinkConsistencyConfig,
+    pub OUTBOX: OutboxConfig,
+    pub INFO_RETENTION: InfoRetentionConfig,
+    pub WEBHOOK: WebhookConfig,
 }
 
 impl ConfigLoader for Config {
     fn load() -> InternalResult<Config> {
         dotenv().ok();
+        let store = StoreConfig::load()?;
         Ok(Config {
             SQL_DB: SqlDbConfig::load()?,
             MONGO_DB: MongoDbConfig::load()?,
             SERVER: ServerConfig::load()?,
-            AWS: AwsConfig::load()?,
+            AWS: load_aws(store.BACKEND)?,
             RABBITMQ: RabbitMQConfig::load()?,
+            BROKER: BrokerConfig::load()?,
             RESERVE: ReserveConfig::load()?,
+            STORE: store,
+            LINK_CONSISTENCY: LinkConsistencyConfig::load()?,
+            OUTBOX: OutboxConfig::load()?,
+            INFO_RETENTION: InfoRetentionConfig::load()?,
+            WEBHOOK: WebhookConfig::load()?,
         })
     }
 }
@@ -59,6 +73,173 @@ impl ConfigLoader for ReserveConfig {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreBackend {
+    Local,
+    Memory,
+    S3,
+}
+
+impl FromStr for StoreBackend {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "local" => Ok(Self::Local),
+            "memory" => Ok(Self::Memory),
+            "s3" => Ok(Self::S3),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Where user repo links are kept
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkBackend {
+    ObjectStore,
+    Postgres,
+}
+
+impl FromStr for LinkBackend {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "object_store" => Ok(Self::ObjectStore),
+            "postgres" => Ok(Self::Postgres),
+            _ => Err(()),
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+pub struct StoreConfig {
+    pub BACKEND: StoreBackend,
+    pub LINK_BACKEND: LinkBackend,
+
+    /// Prefix that user exports are kept under, apart from the links
+    pub EXPORT_ROOT: String,
+}
+
+impl ConfigLoader for StoreConfig {
+    fn load() -> InternalResult<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            BACKEND: get_optional_env_and_parse("STORE_BACKEND")?.unwrap_or(StoreBackend::Local),
+            LINK_BACKEND: get_optional_env_and_parse("STORE_LINK_BACKEND")?
+                .unwrap_or(LinkBackend::ObjectStore),
+            EXPORT_ROOT: get_optional_env_and_parse("STORE_EXPORT_ROOT")?
+                .unwrap_or("exports".to_string()),
+        })
+    }
+}
+
+#[allow(non_snake_case)]
+pub struct LinkConsistencyConfig {
+    /// Zero disables the scheduled check
+    pub INTERVAL_SECS: u64,
+    pub REPAIR: bool,
+}
+
+impl ConfigLoader for LinkConsistencyConfig {
+    fn load() -> InternalResult<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            INTERVAL_SECS: get_optional_env_and_parse("LINK_CONSISTENCY_INTERVAL_SECS")?
+                .unwrap_or(3600),
+            REPAIR: get_optional_env_and_parse("LINK_CONSISTENCY_REPAIR")?.unwrap_or(false),
+        })
+    }
+}
+
+#[allow(non_snake_case)]
+pub struct OutboxConfig {
+    pub INTERVAL_SECS: u64,
+    pub BATCH_SIZE: u64,
+
+    /// Relay attempts after which an event is marked as failed
+    pub MAX_ATTEMPTS: u32,
+
+    /// How long a staged or sending event is left to its writer before the relay takes it
+    pub LEASE_SECS: i64,
+}
+
+impl ConfigLoader for OutboxConfig {
+    fn load() -> InternalResult<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            INTERVAL_SECS: get_optional_env_and_parse("OUTBOX_INTERVAL_SECS")?.unwrap_or(5),
+            BATCH_SIZE: get_optional_env_and_parse("OUTBOX_BATCH_SIZE")?.unwrap_or(100),
+            MAX_ATTEMPTS: get_optional_env_and_parse("OUTBOX_MAX_ATTEMPTS")?.unwrap_or(20),
+            LEASE_SECS: get_optional_env_and_parse("OUTBOX_LEASE_SECS")?.unwrap_or(60),
+        })
+    }
+}
+
+/// How long user repo info records stay in the collection before they're archived
+#[allow(non_snake_case)]
+pub struct InfoRetentionConfig {
+    /// Zero keeps the records forever
+    pub DAYS: i64,
+    pub INTERVAL_SECS: u64,
+    pub BATCH_SIZE: u64,
+}
+
+impl ConfigLoader for InfoRetentionConfig {
+    fn load() -> InternalResult<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            DAYS: get_optional_env_and_parse("INFO_RETENTION_DAYS")?.unwrap_or(0),
+            INTERVAL_SECS: get_optional_env_and_parse("INFO_RETENTION_INTERVAL_SECS")?
+                .unwrap_or(3600),
+            BATCH_SIZE: get_optional_env_and_parse("INFO_RETENTION_BATCH_SIZE")?.unwrap_or(1000),
+        })
+    }
+}
+
+#[allow(non_snake_case)]
+pub struct WebhookConfig {
+    /// Attempts per event and subscription, including the first one
+    pub MAX_ATTEMPTS: u32,
+
+    /// Delay before the first retry, doubled for each next one
+    pub BASE_DELAY_MS: u64,
+    pub TIMEOUT_SECS: u64,
+
+    /// How often scheduled attempts are looked up, and how many at once
+    pub RELAY_INTERVAL_MS: u64,
+    pub BATCH_SIZE: u64,
+
+    /// Lets webhooks target loopback, private and link-local addresses
+    pub ALLOW_PRIVATE_TARGETS: bool,
+}
+
+impl ConfigLoader for WebhookConfig {
+    fn load() -> InternalResult<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            MAX_ATTEMPTS: get_optional_env_and_parse("WEBHOOK_MAX_ATTEMPTS")?.unwrap_or(5),
+            BASE_DELAY_MS: get_optional_env_and_parse("WEBHOOK_BASE_DELAY_MS")?.unwrap_or(1000),
+            TIMEOUT_SECS: get_optional_env_and_parse("WEBHOOK_TIMEOUT_SECS")?.unwrap_or(10),
+            RELAY_INTERVAL_MS: get_optional_env_and_parse("WEBHOOK_RELAY_INTERVAL_MS")?
+                .unwrap_or(1000),
+            BATCH_SIZE: get_optional_env_and_parse("WEBHOOK_BATCH_SIZE")?.unwrap_or(100),
+            ALLOW_PRIVATE_TARGETS: get_optional_env_and_parse("WEBHOOK_ALLOW_PRIVATE_TARGETS")?
+                .unwrap_or(false),
+        })
+    }
+}
+
 #[allow(non_snake_case)]
 pub struct SqlDbConfig {
     pub TEST_URL: String,
@@ -113,6 +294,10 @@ pub struct AwsConfig {
     pub SECRET_ACCESS_KEY: String,
     pub BUCKET_NAME: String,
     pub BUCKET_REGION: String,
+
+    /// Custom endpoint of the S3-compatible storage, e.g. MinIO or LocalStack
+    pub ENDPOINT: Option<String>,
+    pub PATH_STYLE: bool,
 }
 
 impl ConfigLoader for AwsConfig {
@@ -125,6 +310,126 @@ impl ConfigLoader for AwsConfig {
             SECRET_ACCESS_KEY: get_env("AWS_SECRET_ACCESS_KEY")?,
             BUCKET_NAME: get_env("AWS_BUCKET_NAME")?,
             BUCKET_REGION: get_env("AWS_BUCKET_REGION")?,
+            ENDPOINT: env::var("AWS_ENDPOINT").ok(),
+            PATH_STYLE: get_optional_env_and_parse("AWS_PATH_STYLE")?.unwrap_or(false),
+        })
+    }
+}
+
+fn load_aws(backend: StoreBackend) -> InternalResult<Option<AwsConfig>> {
+    match backend {
+        StoreBackend::S3 => Ok(Some(AwsConfig::load()?)),
+        StoreBackend::Local | StoreBackend::Memory => Ok(None),
+    }
+}
+
+/// Wire format of broker messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageCodec {
+    Json,
+    MessagePack,
+    Cbor,
+}
+
+impl FromStr for MessageCodec {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "msgpack" => Ok(Self::MessagePack),
+            "cbor" => Ok(Self::Cbor),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Where app queues live, the in-process one suits single-node deployments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrokerBackend {
+    RabbitMQ,
+    Memory,
+    File,
+}
+
+impl FromStr for BrokerBackend {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rabbitmq" => Ok(Self::RabbitMQ),
+            "memory" => Ok(Self::Memory),
+            "file" => Ok(Self::File),
+            _ => Err(()),
+        }
+    }
+}
+
+/// When the file broker flushes its logs to disk
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsyncMode {
+    Always,
+    Interval,
+    Never,
+}
+
+impl FromStr for FsyncMode {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "always" => Ok(Self::Always),
+            "interval" => Ok(Self::Interval),
+            "never" => Ok(Self::Never),
+            _ => Err(()),
+        }
+    }
+}
+
+#[allow(non_snake_case)]
+pub struct BrokerConfig {
+    pub BACKEND: BrokerBackend,
+    pub CODEC: MessageCodec,
+
+    /// Deliveries of a failing message before it is dead-lettered
+    pub RETRY_MAX_ATTEMPTS: u32,
+    pub RETRY_BASE_DELAY_MS: u64,
+    pub RETRY_MAX_DELAY_MS: u64,
+
+    /// Messages an in-process queue holds before publishers wait for room
+    pub MEMORY_CAPACITY: usize,
+
+    /// Directory with the logs of the file broker, one subdirectory per queue
+    pub FILE_DIR: String,
+    pub FILE_SEGMENT_BYTES: u64,
+    pub FILE_FSYNC: FsyncMode,
+    pub FILE_FSYNC_INTERVAL_MS: u64,
+}
+
+impl ConfigLoader for BrokerConfig {
+    fn load() -> InternalResult<Self>
+    where
+        Self: Sized,
+    {
+        Ok(Self {
+            BACKEND: get_optional_env_and_parse("BROKER_BACKEND")?
+                .unwrap_or(BrokerBackend::RabbitMQ),
+            CODEC: get_optional_env_and_parse("BROKER_CODEC")?.unwrap_or(MessageCodec::Json),
+            RETRY_MAX_ATTEMPTS: get_optional_env_and_parse("BROKER_RETRY_MAX_ATTEMPTS")?
+                .unwrap_or(5),
+            RETRY_BASE_DELAY_MS: get_optional_env_and_parse("BROKER_RETRY_BASE_DELAY_MS")?
+                .unwrap_or(1000),
+            RETRY_MAX_DELAY_MS: get_optional_env_and_parse("BROKER_RETRY_MAX_DELAY_MS")?
+                .unwrap_or(60000),
+            MEMORY_CAPACITY: get_optional_env_and_parse("BROKER_MEMORY_CAPACITY")?.unwrap_or(1024),
+            FILE_DIR: get_optional_env_and_parse("BROKER_FILE_DIR")?
+                .unwrap_or("store/broker".to_string()),
+            FILE_SEGMENT_BYTES: get_optional_env_and_parse("BROKER_FILE_SEGMENT_BYTES")?
+                .unwrap_or(64 * 1024 * 1024),
+            FILE_FSYNC: get_optional_env_and_parse("BROKER_FILE_FSYNC")?
+                .unwrap_or(FsyncMode::Always),
+            FILE_FSYNC_INTERVAL_MS: get_optional_env_and_parse("BROKER_FILE_FSYNC_INTERVAL_MS")?
+                .unwrap_or(1000),
         })
     }
 }
@@ -135,6 +440,17 @@ pub struct RabbitMQConfig {
     pub PORT: u16,
     pub USER: String,
     pub PASSWORD: String,
+
+    /// Delay before reconnecting to the broker, doubled after each failed attempt
+    pub RECONNECT_BASE_DELAY_MS: u64,
+    pub RECONNECT_MAX_DELAY_MS: u64,
+
+    /// Messages a publisher keeps while disconnected, 0 fails publishing right away
+    pub PUBLISH_BUFFER: usize,
+    pub CONFIRM_TIMEOUT_MS: u64,
+
+    /// Topic exchange every app message is published to
+    pub EXCHANGE: String,
 }
 impl ConfigLoader for RabbitMQConfig {
     fn load() -> InternalResult<Self>
@@ -146,6 +462,17 @@ impl ConfigLoader for RabbitMQConfig {
             PORT: get_end_and_parse("RABBITMQ_PORT")?,
             USER: get_env("RABBITMQ_USER")?,
             PASSWORD: get_env("RABBITMQ_PASSWORD")?,
+            RECONNECT_BASE_DELAY_MS: get_optional_env_and_parse(
+                "RABBITMQ_RECONNECT_BASE_DELAY_MS",
+            )?
+            .unwrap_or(500),
+            RECONNECT_MAX_DELAY_MS: get_optional_env_and_parse("RABBITMQ_RECONNECT_MAX_DELAY_MS")?
+                .unwrap_or(30000),
+            PUBLISH_BUFFER: get_optional_env_and_parse("RABBITMQ_PUBLISH_BUFFER")?.unwrap_or(0),
+            CONFIRM_TIMEOUT_MS: get_optional_env_and_parse("RABBITMQ_CONFIRM_TIMEOUT_MS")?
+                .unwrap_or(5000),
+            EXCHANGE: get_optional_env_and_parse("RABBITMQ_EXCHANGE")?
+                .unwrap_or("entity_events".to_string()),
         })
     }
 }
@@ -181,3 +508,51 @@ fn get_end_and_parse<T: FromStr>(name: &'static str) -> InternalResult<T> {
         .parse()
         .map_err(|_| InternalError::ConfigParseImpossible(name))
 }
+
+fn get_optional_env_and_parse<T: FromStr>(name: &'static str) -> InternalResult<Option<T>> {
+    env::var(name)
+        .ok()
+        .map(|val| {
+            val.parse()
+                .map_err(|_| InternalError::ConfigParseImpossible(name))
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn store_backend_parse_success() {
+        assert_eq!("local".parse(), Ok(StoreBackend::Local));
+        assert_eq!("Memory".parse(), Ok(StoreBackend::Memory));
+        assert_eq!("S3".parse(), Ok(StoreBackend::S3));
+        assert_eq!("ftp".parse::<StoreBackend>(), Err(()));
+    }
+
+    #[test]
+    fn store_backend_defaults_to_local_success() {
+        env::remove_var("STORE_BACKEND");
+
+        let store = StoreConfig::load().unwrap();
+
+        assert_eq!(store.BACKEND, StoreBackend::Local);
+    }
+
+    #[test]
+    fn aws_loaded_only_for_s3_success() {
+        env::remove_var("AWS_ACCESS_KEY");
+
+        let local = load_aws(StoreBackend::Local);
+        let memory = load_aws(StoreBackend::Memory);
+        let s3 = load_aws(StoreBackend::S3);
+
+        assert!(matches!(local, Ok(None)));
+        assert!(matches!(memory, Ok(None)));
+        assert!(matches!(
+            s3,
+            Err(InternalError::ConfigMissingEnv("AWS_ACCESS_KEY"))
+        ));
+    }
+}
@@ -1,7 +1,13 @@
+use std::sync::Arc;
+
 use migration::{Migrator, MigratorTrait};
 use mongodb::bson::doc;
+use object_store::aws::AmazonS3Builder;
+use object_store::local::LocalFileSystem;
+use object_store::memory::InMemory;
+use object_store::ObjectStore;
 
-use crate::config::config;
+use crate::config::{config, AwsConfig, StoreBackend};
 use crate::error::InternalResult;
 
 pub async fn init_sql_database() -> InternalResult<sea_orm::DbConn> {
@@ -23,6 +29,42 @@ pub async fn init_nosql_database() -> InternalResult<mongodb::Database> {
     Ok(conn)
 }
 
+pub async fn init_object_store() -> InternalResult<Arc<dyn ObjectStore>> {
+    let store: Arc<dyn ObjectStore> = match config().STORE.BACKEND {
+        StoreBackend::Local => Arc::new(LocalFileSystem::new_with_prefix(
+            &config().RESERVE.LOCAL_STORE,
+        )?),
+        StoreBackend::Memory => Arc::new(InMemory::new()),
+        StoreBackend::S3 => {
+            let aws = config()
+                .AWS
+                .as_ref()
+                .expect("AWS is loaded for the S3 backend");
+            Arc::new(s3_builder(aws).build()?)
+        }
+    };
+
+    // Fails early on wrong credentials or a missing bucket
+    store.list_with_delimiter(None).await?;
+    println!("Connected to {:?} object store", config().STORE.BACKEND);
+    Ok(store)
+}
+
+fn s3_builder(aws: &AwsConfig) -> AmazonS3Builder {
+    let builder = AmazonS3Builder::new()
+        .with_bucket_name(&aws.BUCKET_NAME)
+        .with_region(&aws.BUCKET_REGION)
+        .with_access_key_id(&aws.ACCESS_KEY)
+        .with_secret_access_key(&aws.SECRET_ACCESS_KEY)
+        .with_virtual_hosted_style_request(!aws.PATH_STYLE);
+    match &aws.ENDPOINT {
+        Some(endpoint) => builder
+            .with_allow_http(endpoint.starts_with("http://"))
+            .with_endpoint(endpoint),
+        None => builder,
+    }
+}
+
 pub async fn init_test_sql_database() -> sea_orm::DbConn {
     let conn = sea_orm::Database::connect(&config().SQL_DB.TEST_URL)
         .await
@@ -30,3 +72,59 @@ pub async fn init_test_sql_database() -> sea_orm::DbConn {
     Migrator::up(&conn, None).await.unwrap();
     conn
 }
+
+#[cfg(test)]
+mod tests {
+    use object_store::aws::AmazonS3ConfigKey;
+    use object_store::ClientConfigKey;
+
+    use super::*;
+
+    fn aws(endpoint: Option<&str>, path_style: bool) -> AwsConfig {
+        AwsConfig {
+            ACCESS_KEY: "key".to_string(),
+            SECRET_ACCESS_KEY: "secret".to_string(),
+            BUCKET_NAME: "bucket".to_string(),
+            BUCKET_REGION: "us-east-1".to_string(),
+            ENDPOINT: endpoint.map(str::to_string),
+            PATH_STYLE: path_style,
+        }
+    }
+
+    #[test]
+    fn s3_builder_with_endpoint_success() {
+        let builder = s3_builder(&aws(Some("http://localhost:9000"), true));
+
+        assert_eq!(
+            builder.get_config_value(&AmazonS3ConfigKey::Endpoint),
+            Some("http://localhost:9000".to_string())
+        );
+        assert_eq!(
+            builder.get_config_value(&AmazonS3ConfigKey::VirtualHostedStyleRequest),
+            Some("false".to_string())
+        );
+        assert_eq!(
+            builder.get_config_value(&AmazonS3ConfigKey::Client(ClientConfigKey::AllowHttp)),
+            Some("true".to_string())
+        );
+        assert_eq!(
+            builder.get_config_value(&AmazonS3ConfigKey::Bucket),
+            Some("bucket".to_string())
+        );
+    }
+
+    #[test]
+    fn s3_builder_without_endpoint_success() {
+        let builder = s3_builder(&aws(None, false));
+
+        assert_eq!(builder.get_config_value(&AmazonS3ConfigKey::Endpoint), None);
+        assert_eq!(
+            builder.get_config_value(&AmazonS3ConfigKey::VirtualHostedStyleRequest),
+            Some("true".to_string())
+        );
+        assert_ne!(
+            builder.get_config_value(&AmazonS3ConfigKey::Client(ClientConfigKey::AllowHttp)),
+            Some("true".to_string())
+        );
+    }
+}
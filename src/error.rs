@@ -21,6 +21,12 @@ pub enum InternalError {
     #[error(transparent)]
     StoreIssue(#[from] object_store::Error),
 
+    #[error(transparent)]
+    HttpClient(#[from] reqwest::Error),
+
+    #[error(transparent)]
+    Repository(#[from] repo::dao::error::RepoError),
+
     #[error("Missing environment variable: {0}")]
     ConfigMissingEnv(&'static str),
 
@@ -1,21 +1,62 @@
+use tracing_subscriber::EnvFilter;
+
 use crate::web::state::AppState;
 
 mod config;
 pub mod db;
 pub mod error;
-mod message_broker;
+pub mod message_broker;
 mod runtime;
 pub mod web;
 
+pub use config::LinkBackend;
+
 // TODO Think about the change of generics in general repository and service traits to the associated types
 // TODO Think about urls in tests and version based technique
 pub async fn main() -> error::InternalResult<()> {
+    // Background jobs log through tracing, `RUST_LOG` narrows it down
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    tracing_subscriber::fmt().with_env_filter(filter).init();
+
     let sql_conn = db::init_sql_database().await?;
     let nosql_conn = db::init_nosql_database().await?;
-    let rabbitmq_conn = message_broker::get_rabbitmq_connection().await?;
+    let broker = message_broker::connect_broker().await?;
+
+    let state = AppState::build(sql_conn, nosql_conn, broker.clone()).await?;
+    let receive_loops = runtime::run_detached_tasks(&state);
+    web::start_server(state, shutdown_signal()).await?;
 
-    let state = AppState::build(sql_conn, nosql_conn, rabbitmq_conn).await?;
-    runtime::run_detached_tasks(&state);
-    web::start_server(state).await?;
+    println!("Shutting down");
+    broker.close().await?;
+    runtime::drain(receive_loops).await;
     Ok(())
 }
+
+/// Resolves on Ctrl+C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            eprintln!("Can't listen for Ctrl+C: {e}");
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                eprintln!("Can't listen for SIGTERM: {e}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
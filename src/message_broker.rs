@@ -1,18 +1,60 @@
-use crate::config::config;
-use amqprs::connection::OpenConnectionArguments;
+use std::time::Duration;
+
+use crate::config::{config, BrokerBackend};
 use message_broker::error::MBrokerResult;
+use message_broker::file::FileBroker;
+use message_broker::memory::MemoryBroker;
+use message_broker::rabbitmq::{RabbitMQConnection, RabbitMQConnectionOptions};
+
+/// Backend the app queues live on
+#[derive(Clone)]
+pub enum Broker {
+    RabbitMQ(RabbitMQConnection),
+    Memory(MemoryBroker),
+    File(FileBroker),
+}
+
+impl Broker {
+    /// Publishers fail with `Closed` from now on, receivers drain what is due
+    /// and then fail with `Closed` too
+    pub async fn close(&self) -> MBrokerResult<()> {
+        match self {
+            Broker::RabbitMQ(conn) => conn.close().await,
+            Broker::Memory(broker) => {
+                broker.close();
+                Ok(())
+            }
+            Broker::File(broker) => broker.close().await,
+        }
+    }
+}
+
+pub async fn connect_broker() -> MBrokerResult<Broker> {
+    match config().BROKER.BACKEND {
+        BrokerBackend::RabbitMQ => Ok(Broker::RabbitMQ(get_rabbitmq_connection().await?)),
+        BrokerBackend::Memory => {
+            println!("Using in-process message broker");
+            Ok(Broker::Memory(MemoryBroker::default()))
+        }
+        BrokerBackend::File => {
+            let dir = &config().BROKER.FILE_DIR;
+            println!("Using file message broker in {dir}");
+            Ok(Broker::File(FileBroker::new(dir)))
+        }
+    }
+}
 
-pub async fn get_rabbitmq_connection() -> MBrokerResult<amqprs::connection::Connection> {
-    let conn = amqprs::connection::Connection::open(&OpenConnectionArguments::new(
-        &config().RABBITMQ.HOST,
-        config().RABBITMQ.PORT,
-        &config().RABBITMQ.USER,
-        &config().RABBITMQ.PASSWORD,
-    ))
+async fn get_rabbitmq_connection() -> MBrokerResult<RabbitMQConnection> {
+    let rabbitmq = &config().RABBITMQ;
+    let conn = RabbitMQConnection::connect(RabbitMQConnectionOptions {
+        host: rabbitmq.HOST.clone(),
+        port: rabbitmq.PORT,
+        user: rabbitmq.USER.clone(),
+        password: rabbitmq.PASSWORD.clone(),
+        reconnect_base_delay: Duration::from_millis(rabbitmq.RECONNECT_BASE_DELAY_MS),
+        reconnect_max_delay: Duration::from_millis(rabbitmq.RECONNECT_MAX_DELAY_MS),
+    })
     .await?;
-    conn.register_callback(amqprs::callbacks::DefaultConnectionCallback)
-        .await
-        .unwrap();
     println!("Connected to RabbitMQ");
     Ok(conn)
 }
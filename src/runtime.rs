@@ -1,15 +1,190 @@
+use crate::config::config;
+use crate::web::error::ApiError;
 use crate::web::state::AppState;
+use chrono::Utc;
+use message_broker::error::MBrokerError;
 use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio_stream::StreamExt;
 
-pub fn run_detached_tasks(state: &AppState) {
+const RECEIVE_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// How long the receive loops get to settle their messages on shutdown
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Returns the receive loops, they end once the broker is closed and drained
+pub fn run_detached_tasks(state: &AppState) -> Vec<JoinHandle<()>> {
     let user_repo_info_receiver = Arc::clone(&state.user_repo_info_state.receiver);
 
+    let user_repo_info_loop = tokio::spawn(async move {
+        loop {
+            match user_repo_info_receiver.receive().await {
+                Ok(_) => {}
+                // The in-process or file broker was closed and drained
+                Err(ApiError::MessageBroker(MBrokerError::Closed)) => break,
+                // Failed messages are retried by the broker and the receiver recovers its
+                // channel, broker errors only pause the loop until the connection is back
+                Err(ApiError::MessageBroker(e)) => {
+                    tracing::error!(error = %e, "Receive failed");
+                    tokio::time::sleep(RECEIVE_RETRY_DELAY).await;
+                }
+                Err(e) => tracing::error!(error = %e, "Receive failed"),
+            }
+        }
+    });
+
+    let audit_event_receiver = Arc::clone(&state.audit_event_state.receiver);
+
+    let audit_event_loop = tokio::spawn(async move {
+        loop {
+            match audit_event_receiver.receive().await {
+                Ok(_) => {}
+                // The in-process or file broker was closed and drained
+                Err(ApiError::MessageBroker(MBrokerError::Closed)) => break,
+                Err(ApiError::MessageBroker(e)) => {
+                    tracing::error!(error = %e, "Receive failed");
+                    tokio::time::sleep(RECEIVE_RETRY_DELAY).await;
+                }
+                Err(e) => tracing::error!(error = %e, "Receive failed"),
+            }
+        }
+    });
+
+    run_outbox_relay(state);
+    run_info_retention(state);
+    run_link_consistency_check(state);
+    run_webhook_dispatcher(state);
+    run_webhook_relay(state);
+
+    vec![user_repo_info_loop, audit_event_loop]
+}
+
+/// Waits for the receive loops to drain the closed broker
+pub async fn drain(receive_loops: Vec<JoinHandle<()>>) {
+    let all = async {
+        for receive_loop in receive_loops {
+            let _ = receive_loop.await;
+        }
+    };
+    if tokio::time::timeout(DRAIN_TIMEOUT, all).await.is_err() {
+        tracing::warn!("Receive loops didn't drain in {DRAIN_TIMEOUT:?}");
+    }
+}
+
+/// Every event is scheduled before any attempt, so pending attempts outlive restarts
+fn run_webhook_dispatcher(state: &AppState) {
+    let events = state.event_hub.subscribe_all();
+    let webhook_service = Arc::clone(&state.webhook_state.service);
+
+    tokio::spawn(async move {
+        tokio::pin!(events);
+        while let Some(event) = events.next().await {
+            if let Err(e) = webhook_service.enqueue(event).await {
+                tracing::error!(error = %e, "Webhook event not scheduled");
+            }
+        }
+    });
+}
+
+fn run_webhook_relay(state: &AppState) {
+    let webhook = &config().WEBHOOK;
+    let batch_size = webhook.BATCH_SIZE.max(1);
+    let interval_ms = webhook.RELAY_INTERVAL_MS.max(1);
+    let webhook_service = Arc::clone(&state.webhook_state.service);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_millis(interval_ms));
+        loop {
+            interval.tick().await;
+            if let Err(e) = webhook_service.deliver_pending(batch_size).await {
+                tracing::error!(error = %e, "Webhook relay failed");
+            }
+        }
+    });
+}
+
+fn run_info_retention(state: &AppState) {
+    let retention = &config().INFO_RETENTION;
+    if retention.DAYS <= 0 {
+        return;
+    }
+    let (days, batch_size) = (retention.DAYS, retention.BATCH_SIZE);
+    let interval_secs = retention.INTERVAL_SECS.max(1);
+    let info_service = Arc::clone(&state.user_repo_info_state.service);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+        loop {
+            interval.tick().await;
+            let before = Utc::now() - chrono::Duration::days(days);
+            let (mut batches, mut records) = (0, 0);
+            // Archives batch by batch until nothing is left to expire
+            loop {
+                match info_service.archive_expired(before, batch_size).await {
+                    Ok(Some(archive)) => {
+                        batches += 1;
+                        records += archive.records;
+                    }
+                    Ok(None) => break,
+                    Err(e) => {
+                        tracing::error!(error = %e, "Retention run stopped");
+                        break;
+                    }
+                }
+            }
+            if batches != 0 {
+                tracing::info!(records, batches, "Archived expired user repo info records");
+            }
+        }
+    });
+}
+
+fn run_outbox_relay(state: &AppState) {
+    let outbox = &config().OUTBOX;
+    let (batch_size, max_attempts) = (outbox.BATCH_SIZE, outbox.MAX_ATTEMPTS);
+    let interval_secs = outbox.INTERVAL_SECS.max(1);
+    let relays = [
+        Arc::clone(&state.user_repo_info_state.relay),
+        Arc::clone(&state.audit_event_state.relay),
+    ];
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+        loop {
+            interval.tick().await;
+            for relay in &relays {
+                match relay.relay_pending(batch_size, max_attempts).await {
+                    Ok(sent) if sent != 0 => tracing::info!(sent, "Relayed outbox events"),
+                    Ok(_) => {}
+                    Err(e) => tracing::error!(error = %e, "Outbox relay failed"),
+                }
+            }
+        }
+    });
+}
+
+fn run_link_consistency_check(state: &AppState) {
+    let interval_secs = config().LINK_CONSISTENCY.INTERVAL_SECS;
+    if interval_secs == 0 {
+        return;
+    }
+    let repair = config().LINK_CONSISTENCY.REPAIR;
+    let user_repo_service = Arc::clone(&state.user_repo_state.service);
+
     tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
         loop {
-            let res = user_repo_info_receiver.receive().await;
-            if let Err(e) = res {
-                eprintln!("{e}");
-                break;
+            interval.tick().await;
+            match user_repo_service.check_consistency(repair).await {
+                Ok(report) if !report.dangling.is_empty() => tracing::warn!(
+                    dangling = report.dangling.len(),
+                    checked = report.checked,
+                    repair,
+                    "Found dangling user repo links"
+                ),
+                Ok(_) => {}
+                Err(e) => tracing::error!(error = %e, "Link consistency check failed"),
             }
         }
     });
@@ -52,7 +52,11 @@ impl OpenApi {
 pub enum EntityApi {
     Users,
     Repos,
+    UserRepos,
     UserRepoInfos,
+    AuditEvents,
+    Webhooks,
+    DeadLetters,
 }
 
 impl EntityApi {
@@ -62,7 +66,11 @@ impl EntityApi {
         match self {
             Users => "/users",
             Repos => "/repos",
+            UserRepos => "/user-repos",
             UserRepoInfos => "/user-repo-infos",
+            AuditEvents => "/audit-events",
+            Webhooks => "/webhooks",
+            DeadLetters => "/dead-letters",
         }
     }
 
@@ -72,7 +80,11 @@ impl EntityApi {
         match self {
             Users => "Users",
             Repos => "Repositories",
+            UserRepos => "User repositories",
             UserRepoInfos => "User repo information",
+            AuditEvents => "Audit events",
+            Webhooks => "Webhooks",
+            DeadLetters => "Dead letters",
         }
     }
 }
@@ -0,0 +1,42 @@
+use crate::web::state::AppState;
+use async_graphql::{Context, Object, ResultExt};
+
+use dto::audit_event_dto::{AuditEventDto, AuditEventFilterDto};
+use dto::DtoList;
+use mongodb::bson::oid::ObjectId;
+
+#[derive(Default)]
+pub struct QueryAuditEvent;
+
+#[Object]
+impl QueryAuditEvent {
+    async fn get<'a>(
+        &self,
+        ctx: &Context<'a>,
+        id: ObjectId,
+    ) -> async_graphql::Result<AuditEventDto> {
+        let AppState {
+            audit_event_state: state,
+            ..
+        } = ctx.data_unchecked::<AppState>();
+        state.service.get(&id).await.extend()
+    }
+
+    async fn list<'a>(
+        &self,
+        ctx: &Context<'a>,
+        filter: Option<AuditEventFilterDto>,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> async_graphql::Result<DtoList<AuditEventDto>> {
+        let AppState {
+            audit_event_state: state,
+            ..
+        } = ctx.data_unchecked::<AppState>();
+        state
+            .service
+            .list_filtered(filter.unwrap_or_default(), take, offset)
+            .await
+            .extend()
+    }
+}
@@ -0,0 +1,2 @@
+pub mod graphql_audit_event_controller;
+pub mod rest_audit_event_controller;
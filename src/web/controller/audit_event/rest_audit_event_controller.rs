@@ -0,0 +1,68 @@
+use crate::web::controller::PaginationParams;
+use crate::web::error::ApiResult;
+use crate::web::openapi::{ApiResponses, ObjectIdPathParam};
+use crate::web::state::{AppState, AuditEventState};
+
+use super::super::EntityApi;
+use axum::extract::{Path, Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use collection::audit_event::{AuditEntity, AuditOperation};
+use dto::audit_event_dto::{AuditEventDto, AuditEventFilterDto, AuditFieldChangeDto};
+use dto::DtoList;
+use mongodb::bson::oid::ObjectId;
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        get_audit_event, list_audit_events,
+    ),
+    components(
+        schemas(
+            AuditEventDto, DtoList<AuditEventDto>, AuditFieldChangeDto, AuditEntity, AuditOperation,
+        )
+    ),
+    tags(
+        (name = EntityApi::AuditEvents.to_str_tag())
+    ),
+)]
+pub struct AuditEventOpenApi;
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/:event_id", get(get_audit_event))
+        .route("/", get(list_audit_events))
+        .with_state(state)
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    params(ObjectIdPathParam),
+    responses (ApiResponses<AuditEventDto>),
+    tag = EntityApi::AuditEvents.to_str_tag(),
+)]
+async fn get_audit_event(
+    State(state): State<AuditEventState>,
+    Path(event_id): Path<ObjectId>,
+) -> ApiResult<Json<AuditEventDto>> {
+    let res = state.service.get(&event_id).await?;
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    get,
+    path = "",
+    params(PaginationParams, AuditEventFilterDto),
+    responses (ApiResponses<DtoList<AuditEventDto>>),
+    tag = EntityApi::AuditEvents.to_str_tag(),
+)]
+async fn list_audit_events(
+    State(state): State<AuditEventState>,
+    Query(PaginationParams { take, offset }): Query<PaginationParams>,
+    Query(filter): Query<AuditEventFilterDto>,
+) -> ApiResult<Json<DtoList<AuditEventDto>>> {
+    let res = state.service.list_filtered(filter, take, offset).await?;
+    Ok(Json(res))
+}
@@ -0,0 +1 @@
+pub mod rest_dead_letter_controller;
@@ -0,0 +1,80 @@
+use crate::web::error::ApiResult;
+use crate::web::openapi::{ApiResponses, QueuePathParam};
+use crate::web::state::{AppState, DeadLetterState};
+
+use super::super::EntityApi;
+use axum::extract::{Path, Query, State};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use dto::dead_letter_dto::{DeadLetterBatchDto, DeadLetterDto, DeadLetterLimitDto};
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        list_dead_letters, replay_dead_letters, purge_dead_letters,
+    ),
+    components(
+        schemas(DeadLetterDto, DeadLetterBatchDto)
+    ),
+    tags(
+        (name = EntityApi::DeadLetters.to_str_tag())
+    ),
+)]
+pub struct DeadLetterOpenApi;
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/:queue", get(list_dead_letters).delete(purge_dead_letters))
+        .route("/:queue/replay", post(replay_dead_letters))
+        .with_state(state)
+}
+
+/// Oldest dead letters of the queue, they are left in place
+#[utoipa::path(
+    get,
+    path = "/{queue}",
+    params(QueuePathParam, DeadLetterLimitDto),
+    responses (ApiResponses<Vec<DeadLetterDto>>),
+    tag = EntityApi::DeadLetters.to_str_tag(),
+)]
+async fn list_dead_letters(
+    State(state): State<DeadLetterState>,
+    Path(queue): Path<String>,
+    Query(DeadLetterLimitDto { limit }): Query<DeadLetterLimitDto>,
+) -> ApiResult<Json<Vec<DeadLetterDto>>> {
+    let res = state.service.peek(&queue, limit).await?;
+    Ok(Json(res))
+}
+
+/// Moves dead letters back to the queue with a fresh retry budget
+#[utoipa::path(
+    post,
+    path = "/{queue}/replay",
+    params(QueuePathParam, DeadLetterLimitDto),
+    responses (ApiResponses<DeadLetterBatchDto>),
+    tag = EntityApi::DeadLetters.to_str_tag(),
+)]
+async fn replay_dead_letters(
+    State(state): State<DeadLetterState>,
+    Path(queue): Path<String>,
+    Query(DeadLetterLimitDto { limit }): Query<DeadLetterLimitDto>,
+) -> ApiResult<Json<DeadLetterBatchDto>> {
+    let res = state.service.replay(&queue, limit).await?;
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{queue}",
+    params(QueuePathParam),
+    responses (ApiResponses<DeadLetterBatchDto>),
+    tag = EntityApi::DeadLetters.to_str_tag(),
+)]
+async fn purge_dead_letters(
+    State(state): State<DeadLetterState>,
+    Path(queue): Path<String>,
+) -> ApiResult<Json<DeadLetterBatchDto>> {
+    let res = state.service.purge(&queue).await?;
+    Ok(Json(res))
+}
@@ -1,26 +1,43 @@
+use audit_event::graphql_audit_event_controller::QueryAuditEvent;
+use audit_event::rest_audit_event_controller::{self, AuditEventOpenApi};
 use axum::Router;
-use repo::graphql_repo_controller::{MutationRepo, QueryRepo};
+use dead_letter::rest_dead_letter_controller::{self, DeadLetterOpenApi};
+use repo::graphql_repo_controller::{MutationRepo, QueryRepo, RepoOwnerLoader, SubscriptionRepo};
 use repo::rest_repo_controller::{self, RepoOpenApi};
 use serde::Deserialize;
-use user::graphql_user_controller::{MutationUser, QueryUser};
+use user::graphql_user_controller::{MutationUser, QueryUser, SubscriptionUser};
 use user::rest_user_controller::{self, UserOpenApi};
+use user_export::rest_user_export_controller::{self, UserExportOpenApi};
+use user_repo::rest_link_consistency_controller::{self, LinkConsistencyOpenApi};
 use user_repo::rest_user_repo_controller::{self, UserRepoOpenApi};
-use user_repo_info::graphql_user_repo_info_controller::QueryUserRepoInfo;
+use user_repo_info::graphql_user_repo_info_controller::{
+    QueryUserRepoInfo, SubscriptionUserRepoInfo,
+};
 use user_repo_info::rest_user_repo_info_controller::{self, UserRepoInfoOpenApi};
+use webhook::rest_webhook_controller::{self, WebhookOpenApi};
 
 use crate::web::error::ApiErrorResponse;
 
+mod audit_event;
+mod dead_letter;
 mod repo;
 mod user;
+mod user_export;
 mod user_repo;
 mod user_repo_info;
+mod webhook;
 
 use crate::web::api::{EntityApi, OpenApi, API};
 use crate::web::state::AppState;
-use async_graphql::{http::GraphiQLSource, EmptySubscription, Schema, SimpleObject};
-use async_graphql_axum::GraphQL;
-use axum::response::{self, IntoResponse};
+use async_graphql::dataloader::DataLoader;
+use async_graphql::{http::GraphiQLSource, MergedSubscription, Schema, SimpleObject};
+use async_graphql_axum::{GraphQL, GraphQLSubscription};
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::http::header;
+use axum::response::{self, IntoResponse, Response};
 use axum::routing::get;
+use tower::ServiceExt;
 use utoipa::openapi::{Info, OpenApiBuilder};
 use utoipa::OpenApi as OpenApiMethod;
 use utoipa_swagger_ui::SwaggerUi;
@@ -33,6 +50,17 @@ pub async fn graphiql() -> impl IntoResponse {
     )
 }
 
+/// GraphiQL for browsers, graphql-ws subscriptions for connections asking for an upgrade
+async fn graphql_get(State(schema): State<AppSchema>, req: Request) -> Response {
+    match req.headers().contains_key(header::UPGRADE) {
+        true => {
+            let Ok(res) = GraphQLSubscription::new(schema).oneshot(req).await;
+            res.map(Body::new)
+        }
+        false => graphiql().await.into_response(),
+    }
+}
+
 #[derive(SimpleObject, Default)]
 pub struct MutationRoot {
     repos: MutationRepo,
@@ -44,14 +72,24 @@ pub struct QueryRoot {
     repos: QueryRepo,
     users: QueryUser,
     user_repo_infos: QueryUserRepoInfo,
+    audit_events: QueryAuditEvent,
 }
 
-fn schema(state: AppState) -> Schema<QueryRoot, MutationRoot, EmptySubscription> {
+#[derive(MergedSubscription, Default)]
+pub struct SubscriptionRoot(SubscriptionRepo, SubscriptionUser, SubscriptionUserRepoInfo);
+
+pub type AppSchema = Schema<QueryRoot, MutationRoot, SubscriptionRoot>;
+
+pub fn schema(state: AppState) -> AppSchema {
     Schema::build(
         QueryRoot::default(),
         MutationRoot::default(),
-        EmptySubscription,
+        SubscriptionRoot::default(),
     )
+    .data(DataLoader::new(
+        RepoOwnerLoader(state.user_repo_state.clone()),
+        tokio::spawn,
+    ))
     .data(state)
     .finish()
 }
@@ -62,7 +100,12 @@ fn schema(state: AppState) -> Schema<QueryRoot, MutationRoot, EmptySubscription>
         (path = EntityApi::Repos.to_endpoint(), api = RepoOpenApi),
         (path = EntityApi::Users.to_endpoint(), api = UserOpenApi),
         (path = EntityApi::Users.to_endpoint(), api = UserRepoOpenApi),
+        (path = EntityApi::Users.to_endpoint(), api = UserExportOpenApi),
+        (path = EntityApi::UserRepos.to_endpoint(), api = LinkConsistencyOpenApi),
         (path = EntityApi::UserRepoInfos.to_endpoint(), api = UserRepoInfoOpenApi),
+        (path = EntityApi::AuditEvents.to_endpoint(), api = AuditEventOpenApi),
+        (path = EntityApi::Webhooks.to_endpoint(), api = WebhookOpenApi),
+        (path = EntityApi::DeadLetters.to_endpoint(), api = DeadLetterOpenApi),
     ),
     components(
         schemas(ApiErrorResponse<String>)
@@ -75,7 +118,9 @@ pub fn api_routes(state: AppState) -> Router {
     let api_router = Router::new()
         .route(
             API::GraphQL.to_endpoint(),
-            get(graphiql).post_service(GraphQL::new(schema)),
+            get(graphql_get)
+                .post_service(GraphQL::new(schema.clone()))
+                .with_state(schema),
         )
         .nest(
             EntityApi::Repos.to_endpoint(),
@@ -89,9 +134,29 @@ pub fn api_routes(state: AppState) -> Router {
             EntityApi::Users.to_endpoint(),
             rest_user_repo_controller::routes(state.clone()),
         )
+        .nest(
+            EntityApi::Users.to_endpoint(),
+            rest_user_export_controller::routes(state.clone()),
+        )
+        .nest(
+            EntityApi::UserRepos.to_endpoint(),
+            rest_link_consistency_controller::routes(state.clone()),
+        )
         .nest(
             EntityApi::UserRepoInfos.to_endpoint(),
             rest_user_repo_info_controller::routes(state.clone()),
+        )
+        .nest(
+            EntityApi::AuditEvents.to_endpoint(),
+            rest_audit_event_controller::routes(state.clone()),
+        )
+        .nest(
+            EntityApi::Webhooks.to_endpoint(),
+            rest_webhook_controller::routes(state.clone()),
+        )
+        .nest(
+            EntityApi::DeadLetters.to_endpoint(),
+            rest_dead_letter_controller::routes(state.clone()),
         );
 
     let api_version_doc = OpenApiBuilder::new()
@@ -1,21 +1,85 @@
-use crate::web::state::AppState;
+use std::collections::HashMap;
+
+use crate::web::service::event_hub::EntityEvent;
+use crate::web::state::{AppState, UserRepoState};
 use crate::web::utils::validation::GraphQLValidator;
-use async_graphql::{Context, Object, ResultExt};
+use async_graphql::dataloader::{DataLoader, Loader};
+use async_graphql::{ComplexObject, Context, Object, ResultExt, SimpleObject, Subscription};
+use dto::entity_event_dto::{EntityEventKind, RepoEventDto};
 use dto::repo_dto::{CreateUpdateRepoDto, RepoDto};
+use dto::user_dto::UserDto;
 use dto::DtoList;
+use tokio_stream::{Stream, StreamExt};
 use uuid::Uuid;
 
+/// Repo together with its owner. The owners of all repos in a response are
+/// loaded at once by [`RepoOwnerLoader`]
+#[derive(SimpleObject)]
+#[graphql(complex)]
+pub struct RepoWithOwner {
+    #[graphql(flatten)]
+    repo: RepoDto,
+}
+
+#[ComplexObject]
+impl RepoWithOwner {
+    async fn owner<'a>(&self, ctx: &Context<'a>) -> async_graphql::Result<Option<UserDto>> {
+        let loader = ctx.data_unchecked::<DataLoader<RepoOwnerLoader>>();
+        loader.load_one(self.repo.id).await
+    }
+}
+
+impl From<RepoDto> for RepoWithOwner {
+    fn from(repo: RepoDto) -> Self {
+        Self { repo }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct RepoWithOwnerList {
+    pub dtos: Vec<RepoWithOwner>,
+    pub count: u64,
+    pub last_taken_entity_number: Option<u64>,
+}
+
+impl From<DtoList<RepoDto>> for RepoWithOwnerList {
+    fn from(list: DtoList<RepoDto>) -> Self {
+        let DtoList {
+            dtos,
+            count,
+            last_taken_entity_number,
+        } = list;
+        Self {
+            dtos: dtos.into_iter().map(RepoWithOwner::from).collect(),
+            count,
+            last_taken_entity_number,
+        }
+    }
+}
+
+/// Batches the owner lookups of the repos resolved in one query
+pub struct RepoOwnerLoader(pub UserRepoState);
+
+impl Loader<Uuid> for RepoOwnerLoader {
+    type Value = UserDto;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[Uuid]) -> Result<HashMap<Uuid, UserDto>, Self::Error> {
+        self.0.service.get_owners(keys).await.extend()
+    }
+}
+
 #[derive(Default)]
 pub struct QueryRepo;
 
 #[Object]
 impl QueryRepo {
-    async fn get<'a>(&self, ctx: &Context<'a>, id: Uuid) -> async_graphql::Result<RepoDto> {
+    async fn get<'a>(&self, ctx: &Context<'a>, id: Uuid) -> async_graphql::Result<RepoWithOwner> {
         let AppState {
             repo_state: state, ..
         } = ctx.data_unchecked::<AppState>();
         ctx.data_unchecked::<AppState>();
-        state.service.get(&id).await.extend()
+        state.service.get(&id).await.map(Into::into).extend()
     }
 
     async fn list<'a>(
@@ -23,12 +87,17 @@ impl QueryRepo {
         ctx: &Context<'a>,
         take: Option<u64>,
         offset: Option<u64>,
-    ) -> async_graphql::Result<DtoList<RepoDto>> {
+    ) -> async_graphql::Result<RepoWithOwnerList> {
         let AppState {
             repo_state: state, ..
         } = ctx.data_unchecked::<AppState>();
         ctx.data_unchecked::<AppState>();
-        state.service.list(take, offset).await.extend()
+        state
+            .service
+            .list(take, offset)
+            .await
+            .map(Into::into)
+            .extend()
     }
 }
 
@@ -37,24 +106,41 @@ pub struct MutationRepo;
 
 #[Object]
 impl MutationRepo {
-    async fn delete<'a>(&self, ctx: &Context<'a>, id: Uuid) -> async_graphql::Result<RepoDto> {
+    /// `actor` is recorded in the audit log as given
+    async fn delete<'a>(
+        &self,
+        ctx: &Context<'a>,
+        id: Uuid,
+        actor: Option<String>,
+    ) -> async_graphql::Result<RepoWithOwner> {
         let AppState {
             repo_state: state, ..
         } = ctx.data_unchecked::<AppState>();
         ctx.data_unchecked::<AppState>();
-        state.service.delete(&id).await.extend()
+        state
+            .service
+            .delete(&id, actor)
+            .await
+            .map(Into::into)
+            .extend()
     }
 
     async fn create<'a>(
         &self,
         ctx: &Context<'a>,
         #[graphql(validator(custom = "GraphQLValidator::default()"))] repo_dto: CreateUpdateRepoDto,
-    ) -> async_graphql::Result<RepoDto> {
+        actor: Option<String>,
+    ) -> async_graphql::Result<RepoWithOwner> {
         let AppState {
             repo_state: state, ..
         } = ctx.data_unchecked::<AppState>();
         ctx.data_unchecked::<AppState>();
-        state.service.create(repo_dto).await.extend()
+        state
+            .service
+            .create(repo_dto, actor)
+            .await
+            .map(Into::into)
+            .extend()
     }
 
     async fn update<'a>(
@@ -62,11 +148,42 @@ impl MutationRepo {
         ctx: &Context<'a>,
         id: Uuid,
         #[graphql(validator(custom = "GraphQLValidator::default()"))] repo_dto: CreateUpdateRepoDto,
-    ) -> async_graphql::Result<RepoDto> {
+        actor: Option<String>,
+    ) -> async_graphql::Result<RepoWithOwner> {
         let AppState {
             repo_state: state, ..
         } = ctx.data_unchecked::<AppState>();
         ctx.data_unchecked::<AppState>();
-        state.service.update(&id, repo_dto).await.extend()
+        state
+            .service
+            .update(&id, repo_dto, actor)
+            .await
+            .map(Into::into)
+            .extend()
+    }
+}
+
+#[derive(Default)]
+pub struct SubscriptionRepo;
+
+#[Subscription]
+impl SubscriptionRepo {
+    /// Changes of the repo with the id or of all repos
+    async fn repo_changes<'a>(
+        &self,
+        ctx: &Context<'a>,
+        id: Option<Uuid>,
+        kind: Option<EntityEventKind>,
+    ) -> impl Stream<Item = RepoEventDto> {
+        let AppState { event_hub, .. } = ctx.data_unchecked::<AppState>();
+        event_hub.subscribe().filter_map(move |event| match event {
+            EntityEvent::Repo(event)
+                if id.is_none_or(|id| id == event.repo.id)
+                    && kind.is_none_or(|kind| kind == event.kind) =>
+            {
+                Some(event)
+            }
+            _ => None,
+        })
     }
 }
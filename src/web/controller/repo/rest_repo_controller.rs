@@ -1,5 +1,6 @@
 use axum::extract::{Path, Query, State};
-use axum::routing::{post, put};
+use axum::http::HeaderMap;
+use axum::routing::{get, post, put};
 use axum::{Json, Router};
 use utoipa::OpenApi;
 use uuid::Uuid;
@@ -7,11 +8,14 @@ use uuid::Uuid;
 use super::super::EntityApi;
 use crate::web::controller::PaginationParams;
 use crate::web::error::ApiResult;
-use crate::web::openapi::{ApiResponses, UuidPathParam};
-use crate::web::state::{AppState, RepoState};
+use crate::web::openapi::{ActorHeaderParam, ApiResponses, UuidPathParam};
+use crate::web::state::{AppState, RepoState, UserRepoState};
+use crate::web::utils::actor;
 use crate::web::utils::validation::ValidationWrapper;
 use dto::repo_dto::CreateUpdateRepoDto;
-use dto::{repo_dto::RepoDto, DtoList};
+use dto::user_dto::UserDto;
+use dto::user_repo_dto::TransferRepoDto;
+use dto::{repo_dto::RepoDto, DtoList, OneToOneDto};
 use entity::RepositoryType;
 
 #[derive(OpenApi)]
@@ -19,12 +23,15 @@ use entity::RepositoryType;
     paths(
         get_repo, delete_repo,
         list_repos, create_repo,
-        update_repo,
+        update_repo, get_repo_owner,
+        transfer_repo,
     ),
     components(
         schemas(
             RepoDto, RepositoryType,
-            CreateUpdateRepoDto, DtoList<RepoDto>
+            CreateUpdateRepoDto, DtoList<RepoDto>,
+            UserDto, TransferRepoDto,
+            OneToOneDto<UserDto, RepoDto>,
         )
     ),
     tags(
@@ -37,28 +44,35 @@ pub fn routes(state: AppState) -> Router {
     Router::new()
         .route("/", post(create_repo).get(list_repos))
         .route("/:id", put(update_repo).get(get_repo).delete(delete_repo))
+        .route("/:id/owner", get(get_repo_owner))
+        .route("/:id/transfer", post(transfer_repo))
         .with_state(state)
 }
 
 #[utoipa::path(
     post,
     path = "",
+    params(ActorHeaderParam),
     request_body = CreateUpdateRepoDto,
     responses (ApiResponses<RepoDto>),
     tag = EntityApi::Repos.to_str_tag(),
 )]
 async fn create_repo(
     State(state): State<RepoState>,
+    headers: HeaderMap,
     repo_dto: ValidationWrapper<CreateUpdateRepoDto>,
 ) -> ApiResult<Json<RepoDto>> {
-    let repo = state.service.create(repo_dto.0).await?;
+    let repo = state
+        .service
+        .create(repo_dto.0, actor::actor(&headers))
+        .await?;
     Ok(Json(repo))
 }
 
 #[utoipa::path(
     put,
     path = "/{id}",
-    params(UuidPathParam),
+    params(UuidPathParam, ActorHeaderParam),
     request_body = CreateUpdateRepoDto,
     responses (ApiResponses<RepoDto>),
     tag = EntityApi::Repos.to_str_tag(),
@@ -66,9 +80,13 @@ async fn create_repo(
 async fn update_repo(
     State(state): State<RepoState>,
     Path(id): Path<Uuid>,
+    headers: HeaderMap,
     repo_dto: ValidationWrapper<CreateUpdateRepoDto>,
 ) -> ApiResult<Json<RepoDto>> {
-    let repo = state.service.update(&id, repo_dto.0).await?;
+    let repo = state
+        .service
+        .update(&id, repo_dto.0, actor::actor(&headers))
+        .await?;
     Ok(Json(repo))
 }
 
@@ -105,14 +123,50 @@ async fn get_repo(
 #[utoipa::path(
     delete,
     path = "/{id}",
-    params(UuidPathParam),
+    params(UuidPathParam, ActorHeaderParam),
     responses (ApiResponses<RepoDto>),
     tag = EntityApi::Repos.to_str_tag(),
 )]
 async fn delete_repo(
     State(state): State<RepoState>,
     Path(id): Path<Uuid>,
+    headers: HeaderMap,
 ) -> ApiResult<Json<RepoDto>> {
-    let repo = state.service.delete(&id).await?;
+    let repo = state.service.delete(&id, actor::actor(&headers)).await?;
     Ok(Json(repo))
 }
+
+#[utoipa::path(
+    get,
+    path = "/{id}/owner",
+    params(UuidPathParam),
+    responses (ApiResponses<UserDto>),
+    tag = EntityApi::Repos.to_str_tag(),
+)]
+async fn get_repo_owner(
+    State(state): State<UserRepoState>,
+    Path(id): Path<Uuid>,
+) -> ApiResult<Json<UserDto>> {
+    let user = state.service.get_owner(&id).await?;
+    Ok(Json(user))
+}
+
+#[utoipa::path(
+    post,
+    path = "/{id}/transfer",
+    params(UuidPathParam),
+    request_body = TransferRepoDto,
+    responses (ApiResponses<OneToOneDto<UserDto, RepoDto>>),
+    tag = EntityApi::Repos.to_str_tag(),
+)]
+async fn transfer_repo(
+    State(state): State<UserRepoState>,
+    Path(id): Path<Uuid>,
+    transfer_dto: ValidationWrapper<TransferRepoDto>,
+) -> ApiResult<Json<OneToOneDto<UserDto, RepoDto>>> {
+    let res = state
+        .service
+        .transfer(&id, &transfer_dto.0.to_user_id)
+        .await?;
+    Ok(Json(res))
+}
@@ -1,6 +1,8 @@
+use crate::web::service::event_hub::EntityEvent;
 use crate::web::state::AppState;
 use crate::web::utils::validation::GraphQLValidator;
-use async_graphql::{Context, MergedObject, Object, ResultExt};
+use async_graphql::{Context, MergedObject, Object, ResultExt, Subscription};
+use dto::entity_event_dto::{EntityEventKind, UserEventDto};
 use dto::user_dto::{CreateUserDto, UpdateUserDto, UserDto};
 
 use crate::web::controller::user_repo::graphql_user_repo_controller::{
@@ -9,6 +11,7 @@ use crate::web::controller::user_repo::graphql_user_repo_controller::{
 use dto::user_repo_info_dto::UserRepoInfoDto;
 use dto::DtoList;
 use mongodb::bson::oid::ObjectId;
+use tokio_stream::{Stream, StreamExt};
 
 #[derive(MergedObject, Default)]
 pub struct QueryUser(QueryUserToMerge, UserRepoQuery);
@@ -63,22 +66,29 @@ struct MutationUserToMerge;
 
 #[Object]
 impl MutationUserToMerge {
-    async fn delete<'a>(&self, ctx: &Context<'a>, id: ObjectId) -> async_graphql::Result<UserDto> {
+    /// `actor` is recorded in the audit log as given
+    async fn delete<'a>(
+        &self,
+        ctx: &Context<'a>,
+        id: ObjectId,
+        actor: Option<String>,
+    ) -> async_graphql::Result<UserDto> {
         let AppState {
             user_state: state, ..
         } = ctx.data_unchecked::<AppState>();
-        state.service.delete(&id).await.extend()
+        state.service.delete(&id, actor).await.extend()
     }
 
     async fn create<'a>(
         &self,
         ctx: &Context<'a>,
         #[graphql(validator(custom = "GraphQLValidator::default()"))] user_dto: CreateUserDto,
+        actor: Option<String>,
     ) -> async_graphql::Result<UserDto> {
         let AppState {
             user_state: state, ..
         } = ctx.data_unchecked::<AppState>();
-        state.service.create(user_dto).await.extend()
+        state.service.create(user_dto, actor).await.extend()
     }
 
     async fn update<'a>(
@@ -86,10 +96,36 @@ impl MutationUserToMerge {
         ctx: &Context<'a>,
         id: ObjectId,
         #[graphql(validator(custom = "GraphQLValidator::default()"))] user_dto: UpdateUserDto,
+        actor: Option<String>,
     ) -> async_graphql::Result<UserDto> {
         let AppState {
             user_state: state, ..
         } = ctx.data_unchecked::<AppState>();
-        state.service.update(&id, user_dto).await.extend()
+        state.service.update(&id, user_dto, actor).await.extend()
+    }
+}
+
+#[derive(Default)]
+pub struct SubscriptionUser;
+
+#[Subscription]
+impl SubscriptionUser {
+    /// Changes of the user with the id or of all users
+    async fn user_changes<'a>(
+        &self,
+        ctx: &Context<'a>,
+        id: Option<ObjectId>,
+        kind: Option<EntityEventKind>,
+    ) -> impl Stream<Item = UserEventDto> {
+        let AppState { event_hub, .. } = ctx.data_unchecked::<AppState>();
+        event_hub.subscribe().filter_map(move |event| match event {
+            EntityEvent::User(event)
+                if id.is_none_or(|id| Some(id) == event.user.id)
+                    && kind.is_none_or(|kind| kind == event.kind) =>
+            {
+                Some(event)
+            }
+            _ => None,
+        })
     }
 }
@@ -1,12 +1,18 @@
 use crate::web::controller::PaginationParams;
 use crate::web::error::ApiResult;
-use crate::web::openapi::{ApiResponses, ObjectIdPathParam};
-use crate::web::state::{AppState, UserState};
+use crate::web::openapi::{
+    ActorHeaderParam, ApiResponses, LastEventIdHeaderParam, ObjectIdPathParam,
+};
+use crate::web::state::{AppState, UserRepoInfoState, UserState};
 use crate::web::utils::validation::ValidationWrapper;
+use crate::web::utils::{actor, sse};
 use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
+use axum::response::sse::{Event, Sse};
 use axum::routing::{get, post, put};
 use axum::{Json, Router};
 use mongodb::bson::oid::ObjectId;
+use tokio_stream::Stream;
 use utoipa::OpenApi;
 
 use super::super::EntityApi;
@@ -20,6 +26,7 @@ use dto::DtoList;
         get_user, delete_user,
         list_users, create_user,
         update_user, list_user_repos_info,
+        stream_user_repos_info,
     ),
     components(
         schemas(
@@ -39,28 +46,34 @@ pub fn routes(state: AppState) -> Router {
         .route("/", post(create_user).get(list_users))
         .route("/:id", put(update_user).get(get_user).delete(delete_user))
         .route("/:id/repo-infos", get(list_user_repos_info))
+        .route("/:id/repo-infos/stream", get(stream_user_repos_info))
         .with_state(state)
 }
 
 #[utoipa::path(
     post,
     path = "",
+    params(ActorHeaderParam),
     request_body = CreateUserDto,
     responses (ApiResponses<UserDto>),
     tag = EntityApi::Users.to_str_tag(),
 )]
 async fn create_user(
     State(state): State<UserState>,
+    headers: HeaderMap,
     user_dto: ValidationWrapper<CreateUserDto>,
 ) -> ApiResult<Json<UserDto>> {
-    let user = state.service.create(user_dto.0).await?;
+    let user = state
+        .service
+        .create(user_dto.0, actor::actor(&headers))
+        .await?;
     Ok(Json(user))
 }
 
 #[utoipa::path(
     put,
     path = "/{id}",
-    params(ObjectIdPathParam),
+    params(ObjectIdPathParam, ActorHeaderParam),
     request_body = UpdateUserDto,
     responses (ApiResponses<UserDto>),
     tag = EntityApi::Users.to_str_tag(),
@@ -68,9 +81,13 @@ async fn create_user(
 async fn update_user(
     State(state): State<UserState>,
     Path(id): Path<ObjectId>,
+    headers: HeaderMap,
     user_dto: ValidationWrapper<UpdateUserDto>,
 ) -> ApiResult<Json<UserDto>> {
-    let user = state.service.update(&id, user_dto.0).await?;
+    let user = state
+        .service
+        .update(&id, user_dto.0, actor::actor(&headers))
+        .await?;
     Ok(Json(user))
 }
 
@@ -105,6 +122,25 @@ async fn list_user_repos_info(
     Ok(Json(users))
 }
 
+#[utoipa::path(
+    get,
+    path = "/{id}/repo-infos/stream",
+    params(ObjectIdPathParam, LastEventIdHeaderParam),
+    responses (ApiResponses<UserRepoInfoDto>),
+    tag = EntityApi::Users.to_str_tag(),
+)]
+async fn stream_user_repos_info(
+    State(state): State<UserState>,
+    State(info_state): State<UserRepoInfoState>,
+    Path(id): Path<ObjectId>,
+    headers: HeaderMap,
+) -> ApiResult<Sse<impl Stream<Item = Result<Event, axum::Error>>>> {
+    state.service.get(&id).await?;
+    let last_event_id = sse::last_event_id(&headers)?;
+    let stream = info_state.service.stream(Some(id), last_event_id).await?;
+    Ok(sse::user_repo_info_sse(stream))
+}
+
 #[utoipa::path(
     get,
     path = "/{id}",
@@ -123,14 +159,15 @@ async fn get_user(
 #[utoipa::path(
     delete,
     path = "/{id}",
-    params(ObjectIdPathParam),
+    params(ObjectIdPathParam, ActorHeaderParam),
     responses (ApiResponses<UserDto>),
     tag = EntityApi::Users.to_str_tag(),
 )]
 async fn delete_user(
     State(state): State<UserState>,
     Path(id): Path<ObjectId>,
+    headers: HeaderMap,
 ) -> ApiResult<Json<UserDto>> {
-    let user = state.service.delete(&id).await?;
+    let user = state.service.delete(&id, actor::actor(&headers)).await?;
     Ok(Json(user))
 }
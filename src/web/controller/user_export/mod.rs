@@ -0,0 +1 @@
+pub mod rest_user_export_controller;
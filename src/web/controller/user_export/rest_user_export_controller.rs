@@ -0,0 +1,103 @@
+use crate::web::error::ApiResult;
+use crate::web::openapi::{ApiResponses, ObjectIdPathParam};
+use crate::web::state::{AppState, UserExportState};
+
+use super::super::EntityApi;
+use axum::extract::{Path, State};
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::{Json, Router};
+use dto::user_export_dto::{UserExportDto, UserExportStatus};
+use mongodb::bson::oid::ObjectId;
+use utoipa::OpenApi;
+use uuid::Uuid;
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        start_export, get_export, download_export,
+    ),
+    components(
+        schemas(
+            UserExportDto, UserExportStatus,
+        )
+    ),
+    tags(
+        (name = EntityApi::Users.to_str_tag())
+    ),
+)]
+pub struct UserExportOpenApi;
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/:user_id/export", get(start_export))
+        .route("/:user_id/export/:export_id", get(get_export))
+        .route("/:user_id/export/:export_id/download", get(download_export))
+        .with_state(state)
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/export",
+    params(ObjectIdPathParam),
+    responses (ApiResponses<UserExportDto>),
+    tag = EntityApi::Users.to_str_tag(),
+)]
+async fn start_export(
+    State(state): State<UserExportState>,
+    Path(user_id): Path<ObjectId>,
+) -> ApiResult<Json<UserExportDto>> {
+    let res = state.service.start(&user_id).await?;
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{user_id}/export/{export_id}",
+    params(
+        ("user_id" = String, Path, pattern = "^[0-9a-fA-F]{24}$"),
+        ("export_id" = Uuid, Path),
+    ),
+    responses (ApiResponses<UserExportDto>),
+    tag = EntityApi::Users.to_str_tag(),
+)]
+async fn get_export(
+    State(state): State<UserExportState>,
+    Path((user_id, export_id)): Path<(ObjectId, Uuid)>,
+) -> ApiResult<Json<UserExportDto>> {
+    let res = state.service.get(&user_id, &export_id).await?;
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{user_id}/export/{export_id}/download",
+    params(
+        ("user_id" = String, Path, pattern = "^[0-9a-fA-F]{24}$"),
+        ("export_id" = Uuid, Path),
+    ),
+    responses(
+        (
+            status = OK,
+            description = "Zip with manifest.json, user.json, repos.json and user_repo_infos.json",
+            content_type = "application/zip",
+            body = Vec<u8>,
+        ),
+    ),
+    tag = EntityApi::Users.to_str_tag(),
+)]
+async fn download_export(
+    State(state): State<UserExportState>,
+    Path((user_id, export_id)): Path<(ObjectId, Uuid)>,
+) -> ApiResult<impl IntoResponse> {
+    let archive = state.service.download(&user_id, &export_id).await?;
+    let disposition = format!("attachment; filename=\"user-{user_id}-export-{export_id}.zip\"");
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        archive,
+    ))
+}
@@ -1,7 +1,11 @@
 use crate::web::state::AppState;
+use crate::web::utils::validation::GraphQLValidator;
 use async_graphql::{Context, Object, ResultExt};
 use dto::repo_dto::RepoDto;
 use dto::user_dto::UserDto;
+use dto::user_repo_dto::{
+    BulkUserRepoDto, BulkUserRepoResultDto, LinkMetadataDto, LinkedRepoDto, UpdateLinkMetadataDto,
+};
 use dto::{OneToManyDto, OneToOneDto};
 use mongodb::bson::oid::ObjectId;
 use uuid::Uuid;
@@ -17,7 +21,7 @@ impl UserRepoQuery {
         user_id: ObjectId,
         take: Option<u64>,
         offset: Option<u64>,
-    ) -> async_graphql::Result<OneToManyDto<UserDto, RepoDto>> {
+    ) -> async_graphql::Result<OneToManyDto<UserDto, LinkedRepoDto>> {
         let AppState {
             user_repo_state: state,
             ..
@@ -29,6 +33,23 @@ impl UserRepoQuery {
             .map(|user_repos| user_repos.into())
             .extend()
     }
+
+    async fn link_metadata<'a>(
+        &self,
+        ctx: &Context<'a>,
+        user_id: ObjectId,
+        repo_id: Uuid,
+    ) -> async_graphql::Result<LinkMetadataDto> {
+        let AppState {
+            user_repo_state: state,
+            ..
+        } = ctx.data_unchecked::<AppState>();
+        state
+            .service
+            .get_metadata(&user_id, &repo_id)
+            .await
+            .extend()
+    }
 }
 
 #[derive(Default)]
@@ -49,16 +70,79 @@ impl UserRepoMutation {
         state.service.delete_pair(&user_id, &repo_id).await.extend()
     }
 
+    /// `actor` is recorded as given, it isn't authenticated
     async fn add_repo<'a>(
         &self,
         ctx: &Context<'a>,
         user_id: ObjectId,
         repo_id: Uuid,
+        actor: Option<String>,
+    ) -> async_graphql::Result<OneToOneDto<UserDto, RepoDto>> {
+        let AppState {
+            user_repo_state: state,
+            ..
+        } = ctx.data_unchecked::<AppState>();
+        state
+            .service
+            .add_pair_by(&user_id, &repo_id, actor)
+            .await
+            .extend()
+    }
+
+    async fn update_link_metadata<'a>(
+        &self,
+        ctx: &Context<'a>,
+        user_id: ObjectId,
+        repo_id: Uuid,
+        dto: UpdateLinkMetadataDto,
+    ) -> async_graphql::Result<LinkMetadataDto> {
+        let AppState {
+            user_repo_state: state,
+            ..
+        } = ctx.data_unchecked::<AppState>();
+        state
+            .service
+            .update_metadata(&user_id, &repo_id, dto)
+            .await
+            .extend()
+    }
+
+    async fn add_repos<'a>(
+        &self,
+        ctx: &Context<'a>,
+        user_id: ObjectId,
+        #[graphql(validator(custom = "GraphQLValidator::default()"))] dto: BulkUserRepoDto,
+    ) -> async_graphql::Result<BulkUserRepoResultDto> {
+        let AppState {
+            user_repo_state: state,
+            ..
+        } = ctx.data_unchecked::<AppState>();
+        state.service.add_pairs(&user_id, dto).await.extend()
+    }
+
+    async fn delete_repos<'a>(
+        &self,
+        ctx: &Context<'a>,
+        user_id: ObjectId,
+        #[graphql(validator(custom = "GraphQLValidator::default()"))] dto: BulkUserRepoDto,
+    ) -> async_graphql::Result<BulkUserRepoResultDto> {
+        let AppState {
+            user_repo_state: state,
+            ..
+        } = ctx.data_unchecked::<AppState>();
+        state.service.delete_pairs(&user_id, dto).await.extend()
+    }
+
+    async fn transfer_repo<'a>(
+        &self,
+        ctx: &Context<'a>,
+        repo_id: Uuid,
+        to_user_id: ObjectId,
     ) -> async_graphql::Result<OneToOneDto<UserDto, RepoDto>> {
         let AppState {
             user_repo_state: state,
             ..
         } = ctx.data_unchecked::<AppState>();
-        state.service.add_pair(&user_id, &repo_id).await.extend()
+        state.service.transfer(&repo_id, &to_user_id).await.extend()
     }
 }
@@ -1,2 +1,3 @@
 pub mod graphql_user_repo_controller;
+pub mod rest_link_consistency_controller;
 pub mod rest_user_repo_controller;
@@ -0,0 +1,60 @@
+use crate::web::error::ApiResult;
+use crate::web::openapi::ApiResponses;
+use crate::web::state::{AppState, UserRepoState};
+
+use super::super::EntityApi;
+use axum::extract::{Query, State};
+use axum::routing::post;
+use axum::{Json, Router};
+use dto::user_repo_dto::{DanglingLinkDto, DanglingLinkReason, LinkConsistencyReportDto};
+use serde::Deserialize;
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        check_consistency,
+    ),
+    components(
+        schemas(
+            LinkConsistencyReportDto,
+            DanglingLinkDto,
+            DanglingLinkReason,
+        )
+    ),
+    tags(
+        (name = EntityApi::UserRepos.to_str_tag())
+    ),
+)]
+pub struct LinkConsistencyOpenApi;
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/consistency-check", post(check_consistency))
+        .with_state(state)
+}
+
+#[derive(Deserialize, utoipa::IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ConsistencyParams {
+    /// Delete the dangling links
+    repair: Option<bool>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/consistency-check",
+    params(ConsistencyParams),
+    responses (ApiResponses<LinkConsistencyReportDto>),
+    tag = EntityApi::UserRepos.to_str_tag(),
+)]
+async fn check_consistency(
+    State(state): State<UserRepoState>,
+    Query(ConsistencyParams { repair }): Query<ConsistencyParams>,
+) -> ApiResult<Json<LinkConsistencyReportDto>> {
+    let report = state
+        .service
+        .check_consistency(repair.unwrap_or(false))
+        .await?;
+    Ok(Json(report))
+}
@@ -1,14 +1,21 @@
 use crate::web::controller::PaginationParams;
 use crate::web::error::ApiResult;
-use crate::web::openapi::{ApiResponses, ObjectIdPathParam};
+use crate::web::openapi::{ActorHeaderParam, ApiResponses, ObjectIdPathParam};
 use crate::web::state::{AppState, UserRepoState};
+use crate::web::utils::actor;
+use crate::web::utils::validation::ValidationWrapper;
 
 use super::super::EntityApi;
 use axum::extract::{Path, Query, State};
+use axum::http::HeaderMap;
 use axum::routing::{get, post};
 use axum::{Json, Router};
 use dto::repo_dto::RepoDto;
 use dto::user_dto::UserDto;
+use dto::user_repo_dto::{
+    BulkOperationStatus, BulkUserRepoDto, BulkUserRepoOutcomeDto, BulkUserRepoResultDto,
+    LinkMetadataDto, LinkRole, LinkedRepoDto, UpdateLinkMetadataDto,
+};
 use dto::{OneToManyDto, OneToOneDto};
 use mongodb::bson::oid::ObjectId;
 use utoipa::OpenApi;
@@ -17,12 +24,21 @@ use uuid::Uuid;
 #[derive(OpenApi)]
 #[openapi(
     paths(
-        list_pairs, delete_pair, add_pair,
+        list_pairs, delete_pair, add_pair, add_pairs, delete_pairs,
+        get_metadata, update_metadata,
     ),
     components(
         schemas(
-            OneToManyDto<UserDto, RepoDto>,
+            OneToManyDto<UserDto, LinkedRepoDto>,
             OneToOneDto<UserDto, RepoDto>,
+            LinkedRepoDto,
+            LinkMetadataDto,
+            LinkRole,
+            UpdateLinkMetadataDto,
+            BulkUserRepoDto,
+            BulkUserRepoResultDto,
+            BulkUserRepoOutcomeDto,
+            BulkOperationStatus,
         )
     ),
     tags(
@@ -37,7 +53,14 @@ pub fn routes(state: AppState) -> Router {
             "/:user_id/repos/:repo_id",
             post(add_pair).delete(delete_pair),
         )
-        .route("/:user_id/repos", get(list_pairs))
+        .route(
+            "/:user_id/repos/:repo_id/metadata",
+            get(get_metadata).patch(update_metadata),
+        )
+        .route(
+            "/:user_id/repos",
+            get(list_pairs).post(add_pairs).delete(delete_pairs),
+        )
         .with_state(state)
 }
 
@@ -47,6 +70,7 @@ pub fn routes(state: AppState) -> Router {
     params(
         ("user_id" = String, Path, pattern = "^[0-9a-fA-F]{24}$"),
         ("repo_id" = Uuid, Path),
+        ActorHeaderParam,
     ),
     responses (ApiResponses<OneToOneDto<UserDto, RepoDto>>),
     tag = EntityApi::Users.to_str_tag(),
@@ -54,8 +78,10 @@ pub fn routes(state: AppState) -> Router {
 async fn add_pair(
     State(state): State<UserRepoState>,
     Path((user_id, repo_id)): Path<(ObjectId, Uuid)>,
+    headers: HeaderMap,
 ) -> ApiResult<Json<OneToOneDto<UserDto, RepoDto>>> {
-    let res = state.service.add_pair(&user_id, &repo_id).await?;
+    let actor = actor::actor(&headers);
+    let res = state.service.add_pair_by(&user_id, &repo_id, actor).await?;
     Ok(Json(res))
 }
 
@@ -84,14 +110,93 @@ async fn delete_pair(
         ObjectIdPathParam,
         PaginationParams,
     ),
-    responses (ApiResponses<OneToOneDto<UserDto, RepoDto>>),
+    responses (ApiResponses<OneToManyDto<UserDto, LinkedRepoDto>>),
     tag = EntityApi::Users.to_str_tag(),
 )]
 async fn list_pairs(
     State(state): State<UserRepoState>,
     Path(user_id): Path<ObjectId>,
     Query(PaginationParams { take, offset }): Query<PaginationParams>,
-) -> ApiResult<Json<OneToManyDto<UserDto, RepoDto>>> {
+) -> ApiResult<Json<OneToManyDto<UserDto, LinkedRepoDto>>> {
     let res = state.service.list_pairs(&user_id, take, offset).await?;
     Ok(Json(res))
 }
+
+#[utoipa::path(
+    post,
+    path = "/{user_id}/repos",
+    params(
+        ("user_id" = String, Path, pattern = "^[0-9a-fA-F]{24}$"),
+    ),
+    request_body = BulkUserRepoDto,
+    responses (ApiResponses<BulkUserRepoResultDto>),
+    tag = EntityApi::Users.to_str_tag(),
+)]
+async fn add_pairs(
+    State(state): State<UserRepoState>,
+    Path(user_id): Path<ObjectId>,
+    dto: ValidationWrapper<BulkUserRepoDto>,
+) -> ApiResult<Json<BulkUserRepoResultDto>> {
+    let res = state.service.add_pairs(&user_id, dto.0).await?;
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{user_id}/repos",
+    params(
+        ("user_id" = String, Path, pattern = "^[0-9a-fA-F]{24}$"),
+    ),
+    request_body = BulkUserRepoDto,
+    responses (ApiResponses<BulkUserRepoResultDto>),
+    tag = EntityApi::Users.to_str_tag(),
+)]
+async fn delete_pairs(
+    State(state): State<UserRepoState>,
+    Path(user_id): Path<ObjectId>,
+    dto: ValidationWrapper<BulkUserRepoDto>,
+) -> ApiResult<Json<BulkUserRepoResultDto>> {
+    let res = state.service.delete_pairs(&user_id, dto.0).await?;
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{user_id}/repos/{repo_id}/metadata",
+    params(
+        ("user_id" = String, Path, pattern = "^[0-9a-fA-F]{24}$"),
+        ("repo_id" = Uuid, Path),
+    ),
+    responses (ApiResponses<LinkMetadataDto>),
+    tag = EntityApi::Users.to_str_tag(),
+)]
+async fn get_metadata(
+    State(state): State<UserRepoState>,
+    Path((user_id, repo_id)): Path<(ObjectId, Uuid)>,
+) -> ApiResult<Json<LinkMetadataDto>> {
+    let res = state.service.get_metadata(&user_id, &repo_id).await?;
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/{user_id}/repos/{repo_id}/metadata",
+    params(
+        ("user_id" = String, Path, pattern = "^[0-9a-fA-F]{24}$"),
+        ("repo_id" = Uuid, Path),
+    ),
+    request_body = UpdateLinkMetadataDto,
+    responses (ApiResponses<LinkMetadataDto>),
+    tag = EntityApi::Users.to_str_tag(),
+)]
+async fn update_metadata(
+    State(state): State<UserRepoState>,
+    Path((user_id, repo_id)): Path<(ObjectId, Uuid)>,
+    dto: ValidationWrapper<UpdateLinkMetadataDto>,
+) -> ApiResult<Json<LinkMetadataDto>> {
+    let res = state
+        .service
+        .update_metadata(&user_id, &repo_id, dto.0)
+        .await?;
+    Ok(Json(res))
+}
@@ -1,9 +1,15 @@
+use crate::web::service::event_hub::EntityEvent;
 use crate::web::state::AppState;
-use async_graphql::{Context, Object, ResultExt};
+use crate::web::utils::validation::GraphQLValidator;
+use async_graphql::{Context, Object, ResultExt, Subscription};
 
-use dto::user_repo_info_dto::UserRepoInfoDto;
+use dto::user_repo_info_dto::{
+    UserRepoInfoArchiveDto, UserRepoInfoDto, UserRepoInfoStatsDto, UserRepoInfoStatsFilterDto,
+};
 use dto::DtoList;
 use mongodb::bson::oid::ObjectId;
+use tokio_stream::{Stream, StreamExt};
+use uuid::Uuid;
 
 #[derive(Default)]
 pub struct QueryUserRepoInfo;
@@ -34,4 +40,60 @@ impl QueryUserRepoInfo {
         } = ctx.data_unchecked::<AppState>();
         state.service.list(take, offset).await.extend()
     }
+
+    async fn archives<'a>(
+        &self,
+        ctx: &Context<'a>,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> async_graphql::Result<DtoList<UserRepoInfoArchiveDto>> {
+        let AppState {
+            user_repo_info_state: state,
+            ..
+        } = ctx.data_unchecked::<AppState>();
+        state.service.list_archives(take, offset).await.extend()
+    }
+
+    async fn stats<'a>(
+        &self,
+        ctx: &Context<'a>,
+        #[graphql(validator(custom = "GraphQLValidator::default()"))] filter: Option<
+            UserRepoInfoStatsFilterDto,
+        >,
+    ) -> async_graphql::Result<UserRepoInfoStatsDto> {
+        let AppState {
+            user_repo_info_state: state,
+            ..
+        } = ctx.data_unchecked::<AppState>();
+        state
+            .service
+            .stats(filter.unwrap_or_default())
+            .await
+            .extend()
+    }
+}
+
+#[derive(Default)]
+pub struct SubscriptionUserRepoInfo;
+
+#[Subscription]
+impl SubscriptionUserRepoInfo {
+    /// Link operations of the user or on the repo, a transfer is reported to both its users
+    async fn user_repo_links<'a>(
+        &self,
+        ctx: &Context<'a>,
+        user_id: Option<ObjectId>,
+        repo_id: Option<Uuid>,
+    ) -> impl Stream<Item = UserRepoInfoDto> {
+        let AppState { event_hub, .. } = ctx.data_unchecked::<AppState>();
+        event_hub.subscribe().filter_map(move |event| match event {
+            EntityEvent::UserRepoInfo(info)
+                if user_id.is_none_or(|id| id == info.user_id || Some(id) == info.from_user_id)
+                    && repo_id.is_none_or(|id| id == info.repo_id) =>
+            {
+                Some(info)
+            }
+            _ => None,
+        })
+    }
 }
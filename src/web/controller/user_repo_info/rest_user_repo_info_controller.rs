@@ -1,25 +1,38 @@
 use crate::web::controller::PaginationParams;
-use crate::web::error::ApiResult;
-use crate::web::openapi::{ApiResponses, ObjectIdPathParam};
+use crate::web::error::{ApiError, ApiResult};
+use crate::web::openapi::{ApiResponses, LastEventIdHeaderParam, ObjectIdPathParam, UuidPathParam};
 use crate::web::state::{AppState, UserRepoInfoState};
+use crate::web::utils::sse;
 
 use super::super::EntityApi;
 use axum::extract::{Path, Query, State};
-use axum::routing::get;
+use axum::http::HeaderMap;
+use axum::response::sse::{Event, Sse};
+use axum::routing::{get, post};
 use axum::{Json, Router};
-use dto::user_repo_info_dto::UserRepoInfoDto;
+use dto::user_repo_info_dto::{
+    LinkActivityDto, RepoChurnDto, StatsPeriod, UserActivityDto, UserNetLinksDto,
+    UserRepoInfoArchiveDto, UserRepoInfoDto, UserRepoInfoStatsDto, UserRepoInfoStatsFilterDto,
+};
 use dto::DtoList;
 use mongodb::bson::oid::ObjectId;
+use tokio_stream::Stream;
 use utoipa::OpenApi;
+use uuid::Uuid;
+use validator::Validate;
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
-        get_user_repo_info, list_user_repo_info,
+        get_user_repo_info, list_user_repo_info, list_archives, restore_archive,
+        user_repo_info_stats, stream_user_repo_info,
     ),
     components(
         schemas(
-            UserRepoInfoDto, DtoList<UserRepoInfoDto>,
+            UserRepoInfoDto, DtoList<UserRepoInfoDto>, UserRepoInfoArchiveDto,
+            DtoList<UserRepoInfoArchiveDto>,
+            UserRepoInfoStatsDto, LinkActivityDto, UserActivityDto, RepoChurnDto,
+            UserNetLinksDto, StatsPeriod,
         )
     ),
     tags(
@@ -32,6 +45,10 @@ pub fn routes(state: AppState) -> Router {
     Router::new()
         .route("/:info_id", get(get_user_repo_info))
         .route("/", get(list_user_repo_info))
+        .route("/stats", get(user_repo_info_stats))
+        .route("/stream", get(stream_user_repo_info))
+        .route("/archives", get(list_archives))
+        .route("/archives/:archive_id/restore", post(restore_archive))
         .with_state(state)
 }
 
@@ -64,3 +81,65 @@ async fn list_user_repo_info(
     let res = state.service.list(take, offset).await?;
     Ok(Json(res))
 }
+
+#[utoipa::path(
+    get,
+    path = "/archives",
+    params(PaginationParams),
+    responses (ApiResponses<DtoList<UserRepoInfoArchiveDto>>),
+    tag = EntityApi::UserRepoInfos.to_str_tag(),
+)]
+async fn list_archives(
+    State(state): State<UserRepoInfoState>,
+    Query(PaginationParams { take, offset }): Query<PaginationParams>,
+) -> ApiResult<Json<DtoList<UserRepoInfoArchiveDto>>> {
+    let res = state.service.list_archives(take, offset).await?;
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    post,
+    path = "/archives/{id}/restore",
+    params(UuidPathParam),
+    responses (ApiResponses<UserRepoInfoArchiveDto>),
+    tag = EntityApi::UserRepoInfos.to_str_tag(),
+)]
+async fn restore_archive(
+    State(state): State<UserRepoInfoState>,
+    Path(archive_id): Path<Uuid>,
+) -> ApiResult<Json<UserRepoInfoArchiveDto>> {
+    let res = state.service.restore_archive(&archive_id).await?;
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    get,
+    path = "/stats",
+    params(UserRepoInfoStatsFilterDto),
+    responses (ApiResponses<UserRepoInfoStatsDto>),
+    tag = EntityApi::UserRepoInfos.to_str_tag(),
+)]
+async fn user_repo_info_stats(
+    State(state): State<UserRepoInfoState>,
+    Query(filter): Query<UserRepoInfoStatsFilterDto>,
+) -> ApiResult<Json<UserRepoInfoStatsDto>> {
+    filter.validate().map_err(ApiError::Validation)?;
+    let res = state.service.stats(filter).await?;
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    get,
+    path = "/stream",
+    params(LastEventIdHeaderParam),
+    responses (ApiResponses<UserRepoInfoDto>),
+    tag = EntityApi::UserRepoInfos.to_str_tag(),
+)]
+async fn stream_user_repo_info(
+    State(state): State<UserRepoInfoState>,
+    headers: HeaderMap,
+) -> ApiResult<Sse<impl Stream<Item = Result<Event, axum::Error>>>> {
+    let last_event_id = sse::last_event_id(&headers)?;
+    let stream = state.service.stream(None, last_event_id).await?;
+    Ok(sse::user_repo_info_sse(stream))
+}
@@ -0,0 +1 @@
+pub mod rest_webhook_controller;
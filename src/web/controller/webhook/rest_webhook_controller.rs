@@ -0,0 +1,135 @@
+use crate::web::controller::PaginationParams;
+use crate::web::error::ApiResult;
+use crate::web::openapi::{ApiResponses, ObjectIdPathParam};
+use crate::web::state::{AppState, WebhookState};
+use crate::web::utils::validation::ValidationWrapper;
+
+use super::super::EntityApi;
+use axum::extract::{Path, Query, State};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use collection::webhook_subscription::WebhookEventType;
+use dto::webhook_dto::{CreateWebhookSubscriptionDto, WebhookDeliveryDto, WebhookSubscriptionDto};
+use dto::DtoList;
+use mongodb::bson::oid::ObjectId;
+use utoipa::OpenApi;
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        create_webhook, list_webhooks,
+        get_webhook, delete_webhook,
+        list_webhook_deliveries, redeliver_webhook,
+    ),
+    components(
+        schemas(
+            CreateWebhookSubscriptionDto, WebhookSubscriptionDto, DtoList<WebhookSubscriptionDto>,
+            WebhookDeliveryDto, DtoList<WebhookDeliveryDto>, WebhookEventType,
+        )
+    ),
+    tags(
+        (name = EntityApi::Webhooks.to_str_tag())
+    ),
+)]
+pub struct WebhookOpenApi;
+
+pub fn routes(state: AppState) -> Router {
+    Router::new()
+        .route("/", post(create_webhook).get(list_webhooks))
+        .route("/:id", get(get_webhook).delete(delete_webhook))
+        .route("/:id/deliveries", get(list_webhook_deliveries))
+        .route("/deliveries/:id/redeliver", post(redeliver_webhook))
+        .with_state(state)
+}
+
+#[utoipa::path(
+    post,
+    path = "",
+    request_body = CreateWebhookSubscriptionDto,
+    responses (ApiResponses<WebhookSubscriptionDto>),
+    tag = EntityApi::Webhooks.to_str_tag(),
+)]
+async fn create_webhook(
+    State(state): State<WebhookState>,
+    webhook_dto: ValidationWrapper<CreateWebhookSubscriptionDto>,
+) -> ApiResult<Json<WebhookSubscriptionDto>> {
+    let res = state.service.create(webhook_dto.0).await?;
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    get,
+    path = "",
+    params(PaginationParams),
+    responses (ApiResponses<DtoList<WebhookSubscriptionDto>>),
+    tag = EntityApi::Webhooks.to_str_tag(),
+)]
+async fn list_webhooks(
+    State(state): State<WebhookState>,
+    Query(PaginationParams { take, offset }): Query<PaginationParams>,
+) -> ApiResult<Json<DtoList<WebhookSubscriptionDto>>> {
+    let res = state.service.list(take, offset).await?;
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    params(ObjectIdPathParam),
+    responses (ApiResponses<WebhookSubscriptionDto>),
+    tag = EntityApi::Webhooks.to_str_tag(),
+)]
+async fn get_webhook(
+    State(state): State<WebhookState>,
+    Path(id): Path<ObjectId>,
+) -> ApiResult<Json<WebhookSubscriptionDto>> {
+    let res = state.service.get(&id).await?;
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    params(ObjectIdPathParam),
+    responses (ApiResponses<WebhookSubscriptionDto>),
+    tag = EntityApi::Webhooks.to_str_tag(),
+)]
+async fn delete_webhook(
+    State(state): State<WebhookState>,
+    Path(id): Path<ObjectId>,
+) -> ApiResult<Json<WebhookSubscriptionDto>> {
+    let res = state.service.delete(&id).await?;
+    Ok(Json(res))
+}
+
+#[utoipa::path(
+    get,
+    path = "/{id}/deliveries",
+    params(ObjectIdPathParam, PaginationParams),
+    responses (ApiResponses<DtoList<WebhookDeliveryDto>>),
+    tag = EntityApi::Webhooks.to_str_tag(),
+)]
+async fn list_webhook_deliveries(
+    State(state): State<WebhookState>,
+    Path(id): Path<ObjectId>,
+    Query(PaginationParams { take, offset }): Query<PaginationParams>,
+) -> ApiResult<Json<DtoList<WebhookDeliveryDto>>> {
+    let res = state.service.list_deliveries(&id, take, offset).await?;
+    Ok(Json(res))
+}
+
+/// Sends the logged payload once more, the attempt is added to the delivery log
+#[utoipa::path(
+    post,
+    path = "/deliveries/{id}/redeliver",
+    params(ObjectIdPathParam),
+    responses (ApiResponses<WebhookDeliveryDto>),
+    tag = EntityApi::Webhooks.to_str_tag(),
+)]
+async fn redeliver_webhook(
+    State(state): State<WebhookState>,
+    Path(id): Path<ObjectId>,
+) -> ApiResult<Json<WebhookDeliveryDto>> {
+    let res = state.service.redeliver(&id).await?;
+    Ok(Json(res))
+}
@@ -6,6 +6,7 @@ use repo::dao::error::RepoError;
 use serde::{Deserialize, Serialize};
 use strum::AsRefStr;
 use thiserror::Error;
+use uuid::Uuid;
 
 pub type ApiResult<T> = Result<T, ApiError>;
 
@@ -23,6 +24,18 @@ pub enum ApiError {
     #[error(transparent)]
     MessageBroker(#[from] message_broker::error::MBrokerError),
 
+    #[error("Export {0} is not completed yet")]
+    ExportNotCompleted(Uuid),
+
+    #[error("Queue {0} has no dead-letter queue")]
+    UnknownQueue(String),
+
+    #[error("Last-Event-ID {0} is not a valid event id")]
+    InvalidLastEventId(String),
+
+    #[error("Webhook target is not allowed. {0}")]
+    ForbiddenWebhookTarget(String),
+
     #[error("Endpoint {uri} not found")]
     EndpointNotFound { hostname: String, uri: String },
 }
@@ -36,6 +49,18 @@ impl IntoResponse for ApiError {
                 self.to_response(StatusCode::UNPROCESSABLE_ENTITY, self.to_string())
             }
 
+            Self::ExportNotCompleted(_) => self.to_response(StatusCode::CONFLICT, self.to_string()),
+
+            Self::UnknownQueue(_) => self.to_response(StatusCode::NOT_FOUND, self.to_string()),
+
+            Self::ForbiddenWebhookTarget(_) => {
+                self.to_response(StatusCode::UNPROCESSABLE_ENTITY, self.to_string())
+            }
+
+            Self::InvalidLastEventId(_) => {
+                self.to_response(StatusCode::BAD_REQUEST, self.to_string())
+            }
+
             Self::EndpointNotFound { .. } => {
                 self.to_response(StatusCode::NOT_FOUND, self.to_string())
             }
@@ -47,6 +72,9 @@ impl IntoResponse for ApiError {
                     eprintln!("{err}");
                     self.to_internal_error()
                 }
+                RepoError::NotConnectedWithAny(..) => {
+                    self.to_response(StatusCode::NOT_FOUND, self.to_string())
+                }
                 _ => self.to_response(StatusCode::CONFLICT, self.to_string()),
             },
             Self::MessageBroker(_) => self.to_internal_error(),
@@ -80,6 +108,19 @@ impl ApiError {
         (code, Json(response)).into_response()
     }
 
+    /// Message that is safe to show to the client, internal details are hidden
+    pub fn client_message(&self) -> String {
+        match self {
+            Self::Repository(
+                RepoError::SqlExecution(_)
+                | RepoError::MongoExecution(_)
+                | RepoError::ObjectStore(_),
+            )
+            | Self::MessageBroker(_) => "Something went wrong".to_string(),
+            _ => self.to_string(),
+        }
+    }
+
     fn to_internal_error(&self) -> Response {
         self.to_response(StatusCode::INTERNAL_SERVER_ERROR, "Something went wrong")
     }
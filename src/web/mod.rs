@@ -2,6 +2,7 @@ use crate::config::config;
 use crate::error::InternalResult;
 use crate::web::state::AppState;
 use axum::Router;
+use std::future::Future;
 
 pub mod api;
 mod controller;
@@ -11,11 +12,19 @@ pub mod service;
 pub mod state;
 pub mod utils;
 
-pub async fn start_server(state: AppState) -> InternalResult<()> {
+pub use controller::{schema, AppSchema};
+
+/// Serves until `shutdown` resolves and the open requests are finished
+pub async fn start_server(
+    state: AppState,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> InternalResult<()> {
     let listener = tokio::net::TcpListener::bind(&config().SERVER.SOCKET_ADDR).await?;
     println!("Server started on socket: {}", listener.local_addr()?);
     let app = app(state.clone());
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown)
+        .await?;
     Ok(())
 }
 
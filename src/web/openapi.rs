@@ -24,6 +24,32 @@ pub struct UuidPathParam {
     id: Uuid,
 }
 
+#[derive(IntoParams)]
+#[allow(dead_code)]
+pub struct QueuePathParam {
+    /// Name of the work queue, e.g. `user_repo_info` or `audit_events`.
+    queue: String,
+}
+
+#[derive(IntoParams)]
+#[into_params(parameter_in = Header)]
+#[allow(dead_code)]
+pub struct LastEventIdHeaderParam {
+    /// Id of the last received event, the feed resumes after it.
+    #[param(rename = "Last-Event-ID")]
+    last_event_id: Option<String>,
+}
+
+#[derive(IntoParams)]
+#[into_params(parameter_in = Header)]
+#[allow(dead_code)]
+pub struct ActorHeaderParam {
+    /// Who makes the change, recorded in the audit log. Asserted by the client,
+    /// the API doesn't authenticate it.
+    #[param(rename = "x-actor")]
+    actor: Option<String>,
+}
+
 #[derive(IntoParams)]
 #[allow(dead_code)]
 pub struct ObjectIdPathParam {
@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use dto::audit_event_dto::{AuditEventDto, CreateAuditEventDto};
+use message_broker::Delivery;
+
+use crate::web::error::ApiResult;
+use crate::web::service::AuditEventServiceTrait;
+
+pub struct AuditEventReceiver {
+    broker_receiver: Arc<dyn message_broker::Receiver<CreateAuditEventDto>>,
+    service: Arc<dyn AuditEventServiceTrait>,
+}
+
+impl AuditEventReceiver {
+    pub fn new(
+        broker_receiver: Arc<dyn message_broker::Receiver<CreateAuditEventDto>>,
+        service: Arc<dyn AuditEventServiceTrait>,
+    ) -> Self {
+        Self {
+            broker_receiver,
+            service,
+        }
+    }
+}
+
+#[async_trait]
+impl message_broker::Receiver<AuditEventDto, ApiResult<AuditEventDto>> for AuditEventReceiver {
+    async fn receive(&self) -> ApiResult<AuditEventDto> {
+        let Delivery { message, acker } = self.broker_receiver.receive().await?;
+        let dto = match self.service.create(message).await {
+            Ok(dto) => dto,
+            Err(err) => {
+                acker.nack(err.to_string()).await?;
+                return Err(err);
+            }
+        };
+        acker.ack().await?;
+        Ok(dto)
+    }
+}
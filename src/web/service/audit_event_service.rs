@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use mongodb::bson::oid::ObjectId;
+
+use dto::audit_event_dto::{AuditEventDto, AuditEventFilterDto, CreateAuditEventDto};
+use dto::DtoList;
+use repo::dao::AuditEventRepositoryTrait;
+
+use crate::web::error::ApiResult;
+use crate::web::service::{AuditEventServiceTrait, PersistentServiceTrait};
+
+#[derive(Clone)]
+pub struct AuditEventService {
+    repo: Arc<dyn AuditEventRepositoryTrait>,
+}
+
+impl AuditEventService {
+    pub fn new(repo: Arc<dyn AuditEventRepositoryTrait>) -> Self {
+        Self { repo }
+    }
+}
+
+#[async_trait]
+impl AuditEventServiceTrait for AuditEventService {
+    async fn list_filtered(
+        &self,
+        filter: AuditEventFilterDto,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> ApiResult<DtoList<AuditEventDto>> {
+        Ok(self.repo.list_filtered(filter, take, offset).await?)
+    }
+}
+
+#[async_trait]
+impl PersistentServiceTrait<CreateAuditEventDto, AuditEventDto, ObjectId> for AuditEventService {
+    async fn create(&self, dto: CreateAuditEventDto) -> ApiResult<AuditEventDto> {
+        Ok(self.repo.create(dto).await?)
+    }
+
+    async fn get(&self, id: &ObjectId) -> ApiResult<AuditEventDto> {
+        Ok(self.repo.get(id).await?)
+    }
+
+    async fn list(
+        &self,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> ApiResult<DtoList<AuditEventDto>> {
+        Ok(self.repo.list(take, offset).await?)
+    }
+}
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use dto::dead_letter_dto::{DeadLetterBatchDto, DeadLetterDto};
+use message_broker::{DeadLetter, DeadLetterQueue};
+
+use crate::web::error::{ApiError, ApiResult};
+use crate::web::service::DeadLetterServiceTrait;
+
+const DEFAULT_PEEK_LIMIT: u32 = 20;
+
+#[derive(Clone)]
+pub struct DeadLetterService {
+    queues: HashMap<&'static str, Arc<dyn DeadLetterQueue>>,
+}
+
+impl DeadLetterService {
+    pub fn new(queues: HashMap<&'static str, Arc<dyn DeadLetterQueue>>) -> Self {
+        Self { queues }
+    }
+
+    fn queue(&self, queue: &str) -> ApiResult<&Arc<dyn DeadLetterQueue>> {
+        self.queues
+            .get(queue)
+            .ok_or_else(|| ApiError::UnknownQueue(queue.to_string()))
+    }
+}
+
+#[async_trait]
+impl DeadLetterServiceTrait for DeadLetterService {
+    async fn peek(&self, queue: &str, limit: Option<u32>) -> ApiResult<Vec<DeadLetterDto>> {
+        let letters = self
+            .queue(queue)?
+            .peek(limit.unwrap_or(DEFAULT_PEEK_LIMIT))
+            .await?;
+        Ok(letters
+            .into_iter()
+            .map(
+                |DeadLetter {
+                     payload,
+                     attempts,
+                     error,
+                 }| DeadLetterDto {
+                    payload: String::from_utf8_lossy(&payload).into_owned(),
+                    attempts,
+                    error,
+                },
+            )
+            .collect())
+    }
+
+    async fn replay(&self, queue: &str, limit: Option<u32>) -> ApiResult<DeadLetterBatchDto> {
+        let count = self.queue(queue)?.replay(limit).await?;
+        Ok(DeadLetterBatchDto {
+            queue: queue.to_string(),
+            count,
+        })
+    }
+
+    async fn purge(&self, queue: &str) -> ApiResult<DeadLetterBatchDto> {
+        let count = self.queue(queue)?.purge().await?;
+        Ok(DeadLetterBatchDto {
+            queue: queue.to_string(),
+            count,
+        })
+    }
+}
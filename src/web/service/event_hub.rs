@@ -0,0 +1,61 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::{BroadcastStream, UnboundedReceiverStream};
+use tokio_stream::{Stream, StreamExt};
+
+use dto::entity_event_dto::{RepoEventDto, UserEventDto};
+use dto::user_repo_info_dto::UserRepoInfoDto;
+
+/// How many events a slow subscriber may fall behind before it starts missing them
+const EVENT_HUB_CAPACITY: usize = 1024;
+
+#[derive(Clone, Debug)]
+pub enum EntityEvent {
+    Repo(RepoEventDto),
+    User(UserEventDto),
+    UserRepoInfo(UserRepoInfoDto),
+}
+
+/// In-process fan-out of entity changes to live subscribers
+#[derive(Clone)]
+pub struct EventHub {
+    sender: broadcast::Sender<EntityEvent>,
+
+    /// Subscribers that must see every event however far they fall behind
+    listeners: Arc<Mutex<Vec<mpsc::UnboundedSender<EntityEvent>>>>,
+}
+
+impl Default for EventHub {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_HUB_CAPACITY);
+        Self {
+            sender,
+            listeners: Arc::default(),
+        }
+    }
+}
+
+impl EventHub {
+    /// Events published while nobody is subscribed are dropped
+    pub fn publish(&self, event: EntityEvent) {
+        self.listeners
+            .lock()
+            .unwrap()
+            .retain(|listener| listener.send(event.clone()).is_ok());
+        let _ = self.sender.send(event);
+    }
+
+    /// Stream of the events published after subscribing, lagged events are skipped
+    pub fn subscribe(&self) -> impl Stream<Item = EntityEvent> {
+        BroadcastStream::new(self.sender.subscribe()).filter_map(Result::ok)
+    }
+
+    /// Stream of every event published after subscribing, nothing is skipped.
+    /// Meant for the background consumers, the stream buffers without a bound
+    pub fn subscribe_all(&self) -> impl Stream<Item = EntityEvent> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.listeners.lock().unwrap().push(sender);
+        UnboundedReceiverStream::new(receiver)
+    }
+}
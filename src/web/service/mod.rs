@@ -1,30 +1,57 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+
 use async_trait::async_trait;
+use axum::body::Bytes;
+use chrono::{DateTime, Utc};
 use mongodb::bson::oid::ObjectId;
+use tokio_stream::Stream;
 use uuid::Uuid;
 
+use dto::audit_event_dto::{AuditEventDto, AuditEventFilterDto, CreateAuditEventDto};
+use dto::dead_letter_dto::{DeadLetterBatchDto, DeadLetterDto};
 use dto::user_dto::{CreateUserDto, UpdateUserDto, UserDto};
-use dto::user_repo_info_dto::{CreateUserRepoInfoDto, UserRepoInfoDto};
+use dto::user_export_dto::UserExportDto;
+use dto::user_repo_dto::{
+    BulkUserRepoDto, BulkUserRepoResultDto, LinkConsistencyReportDto, LinkMetadataDto,
+    LinkedRepoDto, UpdateLinkMetadataDto,
+};
+use dto::user_repo_info_dto::{
+    CreateUserRepoInfoDto, UserRepoInfoArchiveDto, UserRepoInfoDto, UserRepoInfoStatsDto,
+    UserRepoInfoStatsFilterDto,
+};
+use dto::webhook_dto::{CreateWebhookSubscriptionDto, WebhookDeliveryDto, WebhookSubscriptionDto};
 use dto::{
     repo_dto::{CreateUpdateRepoDto, RepoDto},
     DtoList, OneToManyDto, OneToOneDto,
 };
 
 use super::error::ApiResult;
+use event_hub::EntityEvent;
+use outbox_publisher::StagedEvent;
 
+pub mod audit_event_receiver;
+pub mod audit_event_service;
+pub mod dead_letter_service;
+pub mod event_hub;
+pub mod outbox_publisher;
 pub mod repo_service;
+pub mod user_export_service;
 pub mod user_repo_info_receiver;
 pub mod user_repo_info_service;
 pub mod user_repo_service;
 pub mod user_service;
+pub mod webhook_service;
 
 #[async_trait]
 pub trait ServiceTrait<C, U, R, I>: Send + Sync
 where
     R: async_graphql::OutputType + utoipa::ToSchema,
 {
-    async fn create(&self, dto: C) -> ApiResult<R>;
-    async fn update(&self, id: &I, dto: U) -> ApiResult<R>;
-    async fn delete(&self, id: &I) -> ApiResult<R>;
+    /// Mutations record `actor` in their audit event
+    async fn create(&self, dto: C, actor: Option<String>) -> ApiResult<R>;
+    async fn update(&self, id: &I, dto: U, actor: Option<String>) -> ApiResult<R>;
+    async fn delete(&self, id: &I, actor: Option<String>) -> ApiResult<R>;
     async fn get(&self, id: &I) -> ApiResult<R>;
     async fn list(&self, take: Option<u64>, offset: Option<u64>) -> ApiResult<DtoList<R>>;
 }
@@ -37,11 +64,24 @@ pub trait UserServiceTrait: ServiceTrait<CreateUserDto, UpdateUserDto, UserDto,
         page: Option<u64>,
         offset: Option<u64>,
     ) -> ApiResult<DtoList<UserRepoInfoDto>>;
+
+    /// Results follow the order of `ids`, missing and deleted users are reported per id
+    async fn get_many(&self, ids: &[ObjectId]) -> ApiResult<Vec<ApiResult<UserDto>>>;
 }
 
+#[async_trait]
 pub trait RepoServiceTrait:
     ServiceTrait<CreateUpdateRepoDto, CreateUpdateRepoDto, RepoDto, Uuid>
 {
+    /// Results follow the order of `ids`, missing and deleted repos are reported per id
+    async fn get_many(&self, ids: &[Uuid]) -> ApiResult<Vec<ApiResult<RepoDto>>>;
+}
+
+#[async_trait]
+pub trait UserExportServiceTrait: Send + Sync {
+    async fn start(&self, user_id: &ObjectId) -> ApiResult<UserExportDto>;
+    async fn get(&self, user_id: &ObjectId, export_id: &Uuid) -> ApiResult<UserExportDto>;
+    async fn download(&self, user_id: &ObjectId, export_id: &Uuid) -> ApiResult<Bytes>;
 }
 
 #[async_trait]
@@ -58,6 +98,93 @@ where
 pub trait UserRepoInfoServiceTrait:
     PersistentServiceTrait<CreateUserRepoInfoDto, UserRepoInfoDto, ObjectId>
 {
+    /// Moves up to `batch_size` records executed before the date to the archive,
+    /// returns `None` when nothing has expired
+    async fn archive_expired(
+        &self,
+        before: DateTime<Utc>,
+        batch_size: u64,
+    ) -> ApiResult<Option<UserRepoInfoArchiveDto>>;
+    async fn list_archives(
+        &self,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> ApiResult<DtoList<UserRepoInfoArchiveDto>>;
+    async fn restore_archive(&self, archive_id: &Uuid) -> ApiResult<UserRepoInfoArchiveDto>;
+    async fn stats(&self, filter: UserRepoInfoStatsFilterDto) -> ApiResult<UserRepoInfoStatsDto>;
+
+    /// Records persisted after `last_event_id` followed by the live ones, all users when
+    /// `user_id` is `None`
+    async fn stream(
+        &self,
+        user_id: Option<ObjectId>,
+        last_event_id: Option<ObjectId>,
+    ) -> ApiResult<UserRepoInfoStream>;
+}
+
+pub type UserRepoInfoStream = Pin<Box<dyn Stream<Item = UserRepoInfoDto> + Send>>;
+
+#[async_trait]
+pub trait AuditEventServiceTrait:
+    PersistentServiceTrait<CreateAuditEventDto, AuditEventDto, ObjectId>
+{
+    async fn list_filtered(
+        &self,
+        filter: AuditEventFilterDto,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> ApiResult<DtoList<AuditEventDto>>;
+}
+
+#[async_trait]
+pub trait WebhookServiceTrait:
+    PersistentServiceTrait<CreateWebhookSubscriptionDto, WebhookSubscriptionDto, ObjectId>
+{
+    async fn delete(&self, id: &ObjectId) -> ApiResult<WebhookSubscriptionDto>;
+    async fn list_deliveries(
+        &self,
+        subscription_id: &ObjectId,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> ApiResult<DtoList<WebhookDeliveryDto>>;
+
+    /// Sends the payload of a logged delivery once more, numbered after the last
+    /// stored attempt of its event
+    async fn redeliver(&self, delivery_id: &ObjectId) -> ApiResult<WebhookDeliveryDto>;
+
+    /// Schedules the first attempt of the event for every matching subscription
+    async fn enqueue(&self, event: EntityEvent) -> ApiResult<()>;
+
+    /// Makes the due attempts, failed ones are scheduled again until attempts
+    /// run out. Returns how many attempts were made
+    async fn deliver_pending(&self, batch_size: u64) -> ApiResult<usize>;
+}
+
+#[async_trait]
+pub trait DeadLetterServiceTrait: Send + Sync {
+    async fn peek(&self, queue: &str, limit: Option<u32>) -> ApiResult<Vec<DeadLetterDto>>;
+    async fn replay(&self, queue: &str, limit: Option<u32>) -> ApiResult<DeadLetterBatchDto>;
+    async fn purge(&self, queue: &str) -> ApiResult<DeadLetterBatchDto>;
+}
+
+/// Messages describing a write, kept in the outbox together with it
+#[async_trait]
+pub trait OutboxTrait<M: Send + Sync + 'static>: Send + Sync {
+    /// Called before the write. `message` is known upfront or `None` when it's built
+    /// from the result of the write, such an event is dropped unless committed
+    async fn stage(&self, message: Option<&M>) -> ApiResult<StagedEvent>;
+
+    /// The write succeeded, sends the message. Unsent messages are left to the relay
+    async fn commit(&self, staged: StagedEvent, message: M) -> ApiResult<()>;
+
+    /// The write failed, the event is removed
+    async fn cancel(&self, staged: StagedEvent) -> ApiResult<()>;
+}
+
+#[async_trait]
+pub trait OutboxRelayTrait: Send + Sync {
+    /// Publishes the oldest pending and abandoned events, returns how many reached the broker
+    async fn relay_pending(&self, batch_size: u64, max_attempts: u32) -> ApiResult<u64>;
 }
 
 #[async_trait]
@@ -67,7 +194,49 @@ pub trait BlobConnServiceTrait<K, V, S, M>: Send + Sync {
     async fn delete_pair(&self, key_id: &K, val_id: &V) -> ApiResult<S>;
 }
 
+#[async_trait]
 pub trait UserRepoServiceTrait:
-    BlobConnServiceTrait<ObjectId, Uuid, OneToOneDto<UserDto, RepoDto>, OneToManyDto<UserDto, RepoDto>>
+    BlobConnServiceTrait<
+    ObjectId,
+    Uuid,
+    OneToOneDto<UserDto, RepoDto>,
+    OneToManyDto<UserDto, LinkedRepoDto>,
+>
 {
+    /// Same as `add_pair`, but records who created the link
+    async fn add_pair_by(
+        &self,
+        user_id: &ObjectId,
+        repo_id: &Uuid,
+        actor: Option<String>,
+    ) -> ApiResult<OneToOneDto<UserDto, RepoDto>>;
+    async fn get_metadata(&self, user_id: &ObjectId, repo_id: &Uuid) -> ApiResult<LinkMetadataDto>;
+    async fn update_metadata(
+        &self,
+        user_id: &ObjectId,
+        repo_id: &Uuid,
+        dto: UpdateLinkMetadataDto,
+    ) -> ApiResult<LinkMetadataDto>;
+    async fn get_owner(&self, repo_id: &Uuid) -> ApiResult<UserDto>;
+    /// Owners by repo id, unlinked repos and links to deleted entities are left out
+    async fn get_owners(&self, repo_ids: &[Uuid]) -> ApiResult<HashMap<Uuid, UserDto>>;
+    async fn transfer(
+        &self,
+        repo_id: &Uuid,
+        to_user_id: &ObjectId,
+    ) -> ApiResult<OneToOneDto<UserDto, RepoDto>>;
+    async fn add_pairs(
+        &self,
+        user_id: &ObjectId,
+        dto: BulkUserRepoDto,
+    ) -> ApiResult<BulkUserRepoResultDto>;
+    async fn delete_pairs(
+        &self,
+        user_id: &ObjectId,
+        dto: BulkUserRepoDto,
+    ) -> ApiResult<BulkUserRepoResultDto>;
+
+    /// Cross-checks every link against users and repos. Dangling links
+    /// are removed when `repair` is set
+    async fn check_consistency(&self, repair: bool) -> ApiResult<LinkConsistencyReportDto>;
 }
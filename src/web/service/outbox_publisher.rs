@@ -0,0 +1,203 @@
+use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use mongodb::bson::oid::ObjectId;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use dto::outbox_event_dto::{CreateOutboxEventDto, OutboxEventDto};
+use message_broker::codec::{Codec, JsonCodec};
+use message_broker::error::MBrokerError;
+use repo::dao::OutboxEventRepositoryTrait;
+
+use crate::web::error::ApiResult;
+use crate::web::service::{OutboxRelayTrait, OutboxTrait};
+
+/// Outbox event written before the write it describes. It is either committed
+/// once the write succeeds or cancelled when it fails
+#[must_use]
+pub struct StagedEvent {
+    id: ObjectId,
+}
+
+/// Persists every message to the outbox before the write it describes, so a
+/// message is never lost when the broker is unavailable. Delivery is at least once.
+/// Messages are kept as JSON, the broker publisher encodes them with its own codec.
+///
+/// An event is sent only by whoever holds its lease: the committing request right
+/// after the write, the relay for pending events and for events whose lease expired
+pub struct OutboxPublisher<M> {
+    queue: &'static str,
+    repo: Arc<dyn OutboxEventRepositoryTrait>,
+    broker_publisher: Arc<dyn message_broker::Publisher<M>>,
+    lease: Duration,
+    _message: PhantomData<fn() -> M>,
+}
+
+impl<M> OutboxPublisher<M>
+where
+    M: Serialize + DeserializeOwned + Send + Sync,
+{
+    pub fn new(
+        queue: &'static str,
+        repo: Arc<dyn OutboxEventRepositoryTrait>,
+        broker_publisher: Arc<dyn message_broker::Publisher<M>>,
+        lease: Duration,
+    ) -> Self {
+        Self {
+            queue,
+            repo,
+            broker_publisher,
+            lease,
+            _message: PhantomData,
+        }
+    }
+
+    /// Sends a claimed event, failed sends are left to the relay
+    async fn send(
+        &self,
+        id: &ObjectId,
+        attempts: u32,
+        message: M,
+        max_attempts: Option<u32>,
+    ) -> ApiResult<Sent> {
+        match self.broker_publisher.publish(message).await {
+            Ok(_) => {
+                self.repo.mark_sent(id).await?;
+                Ok(Sent::Confirmed)
+            }
+            Err(err) if err.is_permanent() => {
+                self.repo.mark_failed(id, err.to_string(), true).await?;
+                Ok(Sent::Dropped(Some(err)))
+            }
+            // Nacked, unconfirmed and undelivered messages are sent again by the relay.
+            // A buffered message isn't confirmed either and is lost on restart
+            Err(err) => {
+                let exhausted = max_attempts.is_some_and(|max| attempts + 1 >= max);
+                self.repo
+                    .mark_failed(id, err.to_string(), exhausted)
+                    .await?;
+                Ok(Sent::Pending)
+            }
+        }
+    }
+
+    async fn relay(&self, event: OutboxEventDto, max_attempts: u32) -> ApiResult<Sent> {
+        // Staged without a payload, the write it waited for never committed
+        if event.payload.is_empty() {
+            let error = "Abandoned before its write was committed".to_string();
+            self.repo.mark_failed(&event.id, error, true).await?;
+            return Ok(Sent::Dropped(None));
+        }
+        let message = match JsonCodec.decode::<M>(&event.payload) {
+            Ok(message) => message,
+            Err(error) => {
+                // Later attempts can't decode it either
+                self.repo
+                    .mark_failed(&event.id, error.to_string(), true)
+                    .await?;
+                return Ok(Sent::Dropped(None));
+            }
+        };
+        self.repo
+            .claim(&event.id, None, Utc::now() + self.lease)
+            .await?;
+        self.send(&event.id, event.attempts, message, Some(max_attempts))
+            .await
+    }
+}
+
+enum Sent {
+    Confirmed,
+
+    /// Sending again won't help, the event is marked as exhausted
+    Dropped(Option<MBrokerError>),
+    Pending,
+}
+
+#[async_trait]
+impl<M> OutboxTrait<M> for OutboxPublisher<M>
+where
+    M: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    async fn stage(&self, message: Option<&M>) -> ApiResult<StagedEvent> {
+        let payload = match message {
+            Some(message) => JsonCodec.encode(message)?,
+            None => vec![],
+        };
+        let dto = CreateOutboxEventDto::new(self.queue, payload, Utc::now() + self.lease);
+        let event = self.repo.create(dto).await?;
+        Ok(StagedEvent { id: event.id })
+    }
+
+    async fn commit(&self, staged: StagedEvent, message: M) -> ApiResult<()> {
+        let payload = JsonCodec.encode(&message)?;
+        self.repo
+            .claim(&staged.id, Some(payload), Utc::now() + self.lease)
+            .await?;
+        // The write already succeeded, so a failed send doesn't fail the request.
+        // Failed sends stay pending and are retried by the relay
+        if let Sent::Dropped(Some(err)) = self.send(&staged.id, 0, message, None).await? {
+            tracing::error!(error = %err, id = %staged.id, "Outbox event dropped");
+        }
+        Ok(())
+    }
+
+    async fn cancel(&self, staged: StagedEvent) -> ApiResult<()> {
+        Ok(self.repo.delete(&staged.id).await?)
+    }
+}
+
+#[async_trait]
+impl<M> OutboxRelayTrait for OutboxPublisher<M>
+where
+    M: Serialize + DeserializeOwned + Send + Sync,
+{
+    async fn relay_pending(&self, batch_size: u64, max_attempts: u32) -> ApiResult<u64> {
+        let mut sent = 0;
+        let events = self
+            .repo
+            .list_relayable(self.queue, Utc::now(), batch_size)
+            .await?;
+        for event in events {
+            match self.relay(event, max_attempts).await? {
+                Sent::Confirmed => sent += 1,
+                Sent::Dropped(_) => {}
+                // The broker is most likely down, the rest waits for the next run
+                Sent::Pending => break,
+            }
+        }
+        Ok(sent)
+    }
+}
+
+/// Stages the event, runs the write and commits the message built from its
+/// result. A failed write cancels the event, so nothing is sent for it
+pub async fn write_with_outbox<M, T>(
+    outbox: &dyn OutboxTrait<M>,
+    message: Option<&M>,
+    write: impl Future<Output = ApiResult<T>> + Send,
+    describe: impl FnOnce(&T) -> M + Send,
+) -> ApiResult<T>
+where
+    M: Send + Sync + 'static,
+{
+    let staged = outbox.stage(message).await?;
+    match write.await {
+        Ok(value) => {
+            outbox.commit(staged, describe(&value)).await?;
+            Ok(value)
+        }
+        Err(err) => {
+            // The relay drops an empty event once its lease expires, a staged
+            // message would be sent without its write
+            if let Err(cancel_err) = outbox.cancel(staged).await {
+                tracing::error!(error = %cancel_err, "Can't cancel outbox event");
+            }
+            Err(err)
+        }
+    }
+}
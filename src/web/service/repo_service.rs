@@ -1,10 +1,19 @@
+use std::future::Future;
 use std::sync::Arc;
 
 use async_trait::async_trait;
 use uuid::Uuid;
 
+use collection::audit_event::{AuditEntity, AuditOperation};
+use dto::audit_event_dto::CreateAuditEventDto;
+use dto::entity_event_dto::{EntityEventKind, RepoEventDto};
+use repo::dao::error::RepoResult;
 use repo::dao::RepoRepositoryTrait;
 
+use crate::web::service::event_hub::{EntityEvent, EventHub};
+use crate::web::service::outbox_publisher::write_with_outbox;
+use crate::web::service::OutboxTrait;
+
 use super::DtoList;
 use super::ServiceTrait;
 use super::{ApiResult, RepoServiceTrait};
@@ -13,20 +22,54 @@ use super::{CreateUpdateRepoDto, RepoDto};
 #[derive(Clone)]
 pub struct RepositoryService {
     repo: Arc<dyn RepoRepositoryTrait>,
+    audit_outbox: Arc<dyn OutboxTrait<CreateAuditEventDto>>,
+    event_hub: EventHub,
 }
 
 #[async_trait]
 impl ServiceTrait<CreateUpdateRepoDto, CreateUpdateRepoDto, RepoDto, Uuid> for RepositoryService {
-    async fn create(&self, repo_dto: CreateUpdateRepoDto) -> ApiResult<RepoDto> {
-        Ok(self.repo.create(repo_dto).await?)
+    async fn create(
+        &self,
+        repo_dto: CreateUpdateRepoDto,
+        actor: Option<String>,
+    ) -> ApiResult<RepoDto> {
+        let id = Uuid::new_v4();
+        let expected = (None, RepoDto::expected(id, &repo_dto));
+        let write = async { Ok((None, self.repo.create_with_id(id, repo_dto).await?)) };
+        let repo = self
+            .audited(AuditOperation::Create, actor, expected, write)
+            .await?;
+        self.publish_event(EntityEventKind::Created, &repo);
+        Ok(repo)
     }
 
-    async fn update(&self, id: &Uuid, repo_dto: CreateUpdateRepoDto) -> ApiResult<RepoDto> {
-        Ok(self.repo.update(id, repo_dto).await?)
+    async fn update(
+        &self,
+        id: &Uuid,
+        repo_dto: CreateUpdateRepoDto,
+        actor: Option<String>,
+    ) -> ApiResult<RepoDto> {
+        let before = self.repo.get(id).await?;
+        let expected = (Some(before.clone()), before.updated_with(&repo_dto));
+        let write = async {
+            let (before, repo) = self.repo.update(id, repo_dto).await?;
+            Ok((Some(before), repo))
+        };
+        let repo = self
+            .audited(AuditOperation::Update, actor, expected, write)
+            .await?;
+        self.publish_event(EntityEventKind::Updated, &repo);
+        Ok(repo)
     }
 
-    async fn delete(&self, id: &Uuid) -> ApiResult<RepoDto> {
-        Ok(self.repo.delete(id).await?)
+    async fn delete(&self, id: &Uuid, actor: Option<String>) -> ApiResult<RepoDto> {
+        let expected = (None, self.repo.get(id).await?);
+        let write = async { Ok((None, self.repo.delete(id).await?)) };
+        let repo = self
+            .audited(AuditOperation::Delete, actor, expected, write)
+            .await?;
+        self.publish_event(EntityEventKind::Deleted, &repo);
+        Ok(repo)
     }
 
     async fn get(&self, id: &Uuid) -> ApiResult<RepoDto> {
@@ -38,10 +81,67 @@ impl ServiceTrait<CreateUpdateRepoDto, CreateUpdateRepoDto, RepoDto, Uuid> for R
     }
 }
 
-impl RepoServiceTrait for RepositoryService {}
+#[async_trait]
+impl RepoServiceTrait for RepositoryService {
+    async fn get_many(&self, ids: &[Uuid]) -> ApiResult<Vec<ApiResult<RepoDto>>> {
+        let repos = self.repo.get_many(ids).await?;
+        Ok(repos
+            .into_iter()
+            .map(|res| res.map_err(|err| err.into()))
+            .collect())
+    }
+}
 
 impl RepositoryService {
-    pub fn new(repo: Arc<dyn RepoRepositoryTrait>) -> Self {
-        Self { repo }
+    pub fn new(
+        repo: Arc<dyn RepoRepositoryTrait>,
+        audit_outbox: Arc<dyn OutboxTrait<CreateAuditEventDto>>,
+        event_hub: EventHub,
+    ) -> Self {
+        Self {
+            repo,
+            audit_outbox,
+            event_hub,
+        }
+    }
+
+    fn publish_event(&self, kind: EntityEventKind, repo: &RepoDto) {
+        self.event_hub.publish(EntityEvent::Repo(RepoEventDto {
+            kind,
+            repo: repo.clone(),
+        }));
+    }
+
+    /// Runs the write with its audit event kept in the outbox. The event is staged
+    /// from the `expected` outcome, so it is still sent when the process stops before
+    /// the commit, which replaces it with the outcome of the write. Both hold the
+    /// repo and, for updates, the repo it replaced
+    async fn audited(
+        &self,
+        operation: AuditOperation,
+        actor: Option<String>,
+        expected: (Option<RepoDto>, RepoDto),
+        write: impl Future<Output = RepoResult<(Option<RepoDto>, RepoDto)>> + Send,
+    ) -> ApiResult<RepoDto> {
+        let write = async { Ok(write.await?) };
+        let describe = |(before, repo): &(Option<RepoDto>, RepoDto)| {
+            let (before, after) = match operation {
+                AuditOperation::Create => (None, Some(repo)),
+                AuditOperation::Update => (before.as_ref(), Some(repo)),
+                AuditOperation::Delete => (Some(repo), None),
+            };
+            CreateAuditEventDto::new(
+                AuditEntity::Repository,
+                repo.id,
+                operation,
+                actor.clone(),
+                before,
+                after,
+            )
+        };
+        let staged = describe(&expected);
+        let (_, repo) =
+            write_with_outbox(self.audit_outbox.as_ref(), Some(&staged), write, describe).await?;
+        Ok(repo)
     }
 }
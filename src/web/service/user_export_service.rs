@@ -0,0 +1,106 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use axum::body::Bytes;
+use mongodb::bson::oid::ObjectId;
+use uuid::Uuid;
+
+use dto::user_export_dto::{UserExportArchiveDto, UserExportDto, UserExportStatus};
+use dto::OneToManyDto;
+use repo::dao::UserExportRepositoryTrait;
+
+use crate::web::error::{ApiError, ApiResult};
+use crate::web::service::{UserExportServiceTrait, UserRepoServiceTrait, UserServiceTrait};
+
+#[derive(Clone)]
+pub struct UserExportService {
+    repo: Arc<dyn UserExportRepositoryTrait>,
+    user_service: Arc<dyn UserServiceTrait>,
+    user_repo_service: Arc<dyn UserRepoServiceTrait>,
+}
+
+impl UserExportService {
+    pub fn new(
+        repo: Arc<dyn UserExportRepositoryTrait>,
+        user_service: Arc<dyn UserServiceTrait>,
+        user_repo_service: Arc<dyn UserRepoServiceTrait>,
+    ) -> Self {
+        Self {
+            repo,
+            user_service,
+            user_repo_service,
+        }
+    }
+
+    /// A failed export is recorded in its manifest. Failing to record the
+    /// outcome leaves the export pending, so that error is returned
+    async fn generate(&self, manifest: UserExportDto) -> ApiResult<UserExportDto> {
+        let manifest = match self.export(manifest.clone()).await {
+            Ok(manifest) => manifest,
+            Err(err) => {
+                let mut manifest = manifest;
+                manifest.fail(err.to_string());
+                manifest
+            }
+        };
+        self.repo.save_manifest(&manifest).await?;
+        Ok(manifest)
+    }
+
+    async fn export(&self, mut manifest: UserExportDto) -> ApiResult<UserExportDto> {
+        let user_id = manifest.user_id;
+        let OneToManyDto {
+            one: user,
+            many: repos,
+            ..
+        } = self
+            .user_repo_service
+            .list_pairs(&user_id, None, None)
+            .await?;
+        let infos = self
+            .user_service
+            .list_user_repos_info(user_id, None, None)
+            .await?;
+
+        manifest.complete(repos.dtos.len() as u64, infos.dtos.len() as u64);
+        let archive = UserExportArchiveDto {
+            manifest,
+            user,
+            repos: repos.dtos.into_iter().map(|linked| linked.repo).collect(),
+            user_repo_infos: infos.dtos,
+        };
+        self.repo.save_archive(&archive).await?;
+        Ok(archive.manifest)
+    }
+}
+
+#[async_trait]
+impl UserExportServiceTrait for UserExportService {
+    async fn start(&self, user_id: &ObjectId) -> ApiResult<UserExportDto> {
+        self.user_service.get(user_id).await?;
+        let manifest = UserExportDto::new(*user_id);
+        self.repo.save_manifest(&manifest).await?;
+
+        let service = self.clone();
+        let pending = manifest.clone();
+        tokio::spawn(async move {
+            let export_id = pending.id;
+            if let Err(err) = service.generate(pending).await {
+                tracing::error!(error = %err, %export_id, "Export is left pending");
+            }
+        });
+        Ok(manifest)
+    }
+
+    async fn get(&self, user_id: &ObjectId, export_id: &Uuid) -> ApiResult<UserExportDto> {
+        Ok(self.repo.get_manifest(user_id, export_id).await?)
+    }
+
+    async fn download(&self, user_id: &ObjectId, export_id: &Uuid) -> ApiResult<Bytes> {
+        let manifest = self.repo.get_manifest(user_id, export_id).await?;
+        if manifest.status != UserExportStatus::Completed {
+            Err(ApiError::ExportNotCompleted(*export_id))?
+        }
+        Ok(self.repo.get_archive(user_id, export_id).await?)
+    }
+}
@@ -3,23 +3,28 @@ use std::sync::Arc;
 use async_trait::async_trait;
 
 use dto::user_repo_info_dto::{CreateUserRepoInfoDto, UserRepoInfoDto};
+use message_broker::Delivery;
 
 use crate::web::error::ApiResult;
+use crate::web::service::event_hub::{EntityEvent, EventHub};
 use crate::web::service::UserRepoInfoServiceTrait;
 
 pub struct UserRepoInfoReceiver {
     broker_receiver: Arc<dyn message_broker::Receiver<CreateUserRepoInfoDto>>,
     service: Arc<dyn UserRepoInfoServiceTrait>,
+    event_hub: EventHub,
 }
 
 impl UserRepoInfoReceiver {
     pub fn new(
         broker_receiver: Arc<dyn message_broker::Receiver<CreateUserRepoInfoDto>>,
         service: Arc<dyn UserRepoInfoServiceTrait>,
+        event_hub: EventHub,
     ) -> Self {
         Self {
             broker_receiver,
             service,
+            event_hub,
         }
     }
 }
@@ -28,9 +33,19 @@ impl UserRepoInfoReceiver {
 impl message_broker::Receiver<UserRepoInfoDto, ApiResult<UserRepoInfoDto>>
     for UserRepoInfoReceiver
 {
+    /// The message is acked only once the record is persisted
     async fn receive(&self) -> ApiResult<UserRepoInfoDto> {
-        let info = self.broker_receiver.receive().await?;
-        let dto = self.service.create(info).await?;
+        let Delivery { message, acker } = self.broker_receiver.receive().await?;
+        let dto = match self.service.create(message).await {
+            Ok(dto) => dto,
+            Err(err) => {
+                acker.nack(err.to_string()).await?;
+                return Err(err);
+            }
+        };
+        acker.ack().await?;
+        self.event_hub
+            .publish(EntityEvent::UserRepoInfo(dto.clone()));
         Ok(dto)
     }
 }
@@ -1,27 +1,157 @@
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures_util::{future, stream, Stream, StreamExt};
 use mongodb::bson::oid::ObjectId;
+use uuid::Uuid;
 
-use dto::user_repo_info_dto::{CreateUserRepoInfoDto, UserRepoInfoDto};
+use dto::user_repo_info_dto::{
+    CreateUserRepoInfoDto, UserRepoInfoArchiveDto, UserRepoInfoDto, UserRepoInfoStatsDto,
+    UserRepoInfoStatsFilterDto,
+};
 use dto::DtoList;
-use repo::dao::UserRepoInfoRepositoryTrait;
+use repo::dao::error::RepoError;
+use repo::dao::{UserRepoInfoArchiveRepositoryTrait, UserRepoInfoRepositoryTrait};
 
 use crate::web::error::ApiResult;
-use crate::web::service::{PersistentServiceTrait, UserRepoInfoServiceTrait};
+use crate::web::service::event_hub::{EntityEvent, EventHub};
+use crate::web::service::{PersistentServiceTrait, UserRepoInfoServiceTrait, UserRepoInfoStream};
+
+/// Missed records are replayed in pages of this size
+const REPLAY_PAGE_SIZE: u64 = 100;
 
 #[derive(Clone)]
 pub struct UserRepoInfoService {
     repo: Arc<dyn UserRepoInfoRepositoryTrait>,
+    archive_repo: Arc<dyn UserRepoInfoArchiveRepositoryTrait>,
+    event_hub: EventHub,
 }
 
 impl UserRepoInfoService {
-    pub fn new(repo: Arc<dyn UserRepoInfoRepositoryTrait>) -> Self {
-        Self { repo }
+    pub fn new(
+        repo: Arc<dyn UserRepoInfoRepositoryTrait>,
+        archive_repo: Arc<dyn UserRepoInfoArchiveRepositoryTrait>,
+        event_hub: EventHub,
+    ) -> Self {
+        Self {
+            repo,
+            archive_repo,
+            event_hub,
+        }
+    }
+
+    /// Records missed after the id, a page is read once the previous one is sent.
+    /// A failed read ends the stream, the client resumes from its last event
+    fn replay(
+        &self,
+        after: ObjectId,
+        user_id: Option<ObjectId>,
+    ) -> impl Stream<Item = UserRepoInfoDto> + Send {
+        let repo = self.repo.clone();
+        stream::unfold(Some(after), move |after| {
+            let repo = repo.clone();
+            async move {
+                let page = match repo.list_after(after?, user_id, REPLAY_PAGE_SIZE).await {
+                    Ok(page) => page,
+                    Err(err) => {
+                        tracing::warn!(%err, "Replay of missed user repo infos failed");
+                        return None;
+                    }
+                };
+                let next = match page.len() as u64 == REPLAY_PAGE_SIZE {
+                    true => page.last().and_then(|info| info.id),
+                    false => None,
+                };
+                Some((stream::iter(page), next))
+            }
+        })
+        .flatten()
     }
 }
 
-impl UserRepoInfoServiceTrait for UserRepoInfoService {}
+#[async_trait]
+impl UserRepoInfoServiceTrait for UserRepoInfoService {
+    async fn archive_expired(
+        &self,
+        before: DateTime<Utc>,
+        batch_size: u64,
+    ) -> ApiResult<Option<UserRepoInfoArchiveDto>> {
+        let records = self.repo.list_expired(before, batch_size).await?;
+        if records.is_empty() {
+            return Ok(None);
+        }
+
+        // Records are deleted only once the batch is safely stored
+        let archive = self.archive_repo.save_batch(&records).await?;
+        let ids: Vec<ObjectId> = records.iter().filter_map(|r| r.id).collect();
+        self.repo.delete_many(&ids).await?;
+        Ok(Some(archive))
+    }
+
+    async fn list_archives(
+        &self,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> ApiResult<DtoList<UserRepoInfoArchiveDto>> {
+        Ok(self.archive_repo.list_batches(take, offset).await?)
+    }
+
+    async fn restore_archive(&self, archive_id: &Uuid) -> ApiResult<UserRepoInfoArchiveDto> {
+        let archive = self.archive_repo.get_manifest(archive_id).await?;
+        for record in self.archive_repo.get_batch(archive_id).await? {
+            // Skips records left by a previous, interrupted restore
+            if let Some(id) = record.id {
+                match self.repo.get(&id).await {
+                    Ok(_) => continue,
+                    Err(RepoError::NotFoundWithObjectId(..)) => {}
+                    Err(err) => Err(err)?,
+                }
+            }
+            self.repo.restore(record).await?;
+        }
+        self.archive_repo.delete_batch(archive_id).await?;
+        Ok(archive)
+    }
+
+    async fn stats(&self, filter: UserRepoInfoStatsFilterDto) -> ApiResult<UserRepoInfoStatsDto> {
+        Ok(self.repo.stats(filter).await?)
+    }
+
+    async fn stream(
+        &self,
+        user_id: Option<ObjectId>,
+        last_event_id: Option<ObjectId>,
+    ) -> ApiResult<UserRepoInfoStream> {
+        // Subscribing before reading the missed records so nothing slips in between
+        let live = self.event_hub.subscribe().filter_map(move |event| {
+            future::ready(match event {
+                EntityEvent::UserRepoInfo(info)
+                    if user_id
+                        .is_none_or(|id| id == info.user_id || Some(id) == info.from_user_id) =>
+                {
+                    Some(info)
+                }
+                _ => None,
+            })
+        });
+        let missed = match last_event_id {
+            Some(after) => self.replay(after, user_id).boxed(),
+            None => stream::empty().boxed(),
+        };
+
+        // Live records already replayed are skipped
+        let mut last_seen = last_event_id;
+        let stream = missed.chain(live).filter(move |info| {
+            let fresh = info.id > last_seen;
+            if fresh {
+                last_seen = info.id;
+            }
+            future::ready(fresh)
+        });
+        Ok(Box::pin(stream))
+    }
+}
 #[async_trait]
 impl PersistentServiceTrait<CreateUserRepoInfoDto, UserRepoInfoDto, ObjectId>
     for UserRepoInfoService
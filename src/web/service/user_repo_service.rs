@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+use std::future::Future;
 use std::sync::Arc;
 
 use async_trait::async_trait;
@@ -7,13 +9,20 @@ use uuid::Uuid;
 use collection::user_repo_info::UserRepoInfoOperation;
 use dto::repo_dto::RepoDto;
 use dto::user_dto::UserDto;
+use dto::user_repo_dto::{
+    BulkOperationStatus, BulkUserRepoDto, BulkUserRepoOutcomeDto, BulkUserRepoResultDto,
+    DanglingLinkDto, DanglingLinkReason, LinkConsistencyReportDto, LinkMetadataDto, LinkedRepoDto,
+    UpdateLinkMetadataDto,
+};
 use dto::user_repo_info_dto::CreateUserRepoInfoDto;
 use dto::{DtoList, OneToManyDto, OneToOneDto};
+use repo::dao::error::{Entity, RepoError, RepoResult};
 use repo::dao::UserRepoRepositoryTrait;
 
-use crate::web::error::ApiResult;
+use crate::web::error::{ApiError, ApiResult};
+use crate::web::service::outbox_publisher::{write_with_outbox, StagedEvent};
 use crate::web::service::{
-    BlobConnServiceTrait, RepoServiceTrait, UserRepoServiceTrait, UserServiceTrait,
+    BlobConnServiceTrait, OutboxTrait, RepoServiceTrait, UserRepoServiceTrait, UserServiceTrait,
 };
 
 #[derive(Clone)]
@@ -21,7 +30,7 @@ pub struct UserRepoService {
     repo: Arc<dyn UserRepoRepositoryTrait>,
     user_service: Arc<dyn UserServiceTrait>,
     repo_service: Arc<dyn RepoServiceTrait>,
-    info_publisher: Arc<dyn message_broker::Publisher<CreateUserRepoInfoDto>>,
+    info_outbox: Arc<dyn OutboxTrait<CreateUserRepoInfoDto>>,
 }
 
 impl UserRepoService {
@@ -29,18 +38,412 @@ impl UserRepoService {
         repo: Arc<dyn UserRepoRepositoryTrait>,
         user_service: Arc<dyn UserServiceTrait>,
         repo_service: Arc<dyn RepoServiceTrait>,
-        info_publisher: Arc<dyn message_broker::Publisher<CreateUserRepoInfoDto>>,
+        info_outbox: Arc<dyn OutboxTrait<CreateUserRepoInfoDto>>,
     ) -> Self {
         Self {
             repo,
             user_service,
             repo_service,
-            info_publisher,
+            info_outbox,
+        }
+    }
+
+    /// Runs the link write with its info event kept in the outbox
+    async fn with_info<T: Send>(
+        &self,
+        message: CreateUserRepoInfoDto,
+        write: impl Future<Output = RepoResult<T>> + Send,
+    ) -> ApiResult<T> {
+        let write = async { Ok(write.await?) };
+        write_with_outbox(self.info_outbox.as_ref(), Some(&message), write, |_| {
+            message.clone()
+        })
+        .await
+    }
+
+    async fn cancel_info(&self, staged: StagedEvent) {
+        if let Err(err) = self.info_outbox.cancel(staged).await {
+            tracing::error!(error = %err, "Can't cancel outbox event");
+        }
+    }
+
+    async fn apply_pairs(
+        &self,
+        user_id: &ObjectId,
+        repo_ids: &[Uuid],
+        operation: UserRepoInfoOperation,
+    ) -> Vec<Result<(), String>> {
+        let ids = repo_ids.iter().collect();
+        let results = match operation {
+            UserRepoInfoOperation::CreateLink => self.repo.add_pairs(user_id, ids).await,
+            UserRepoInfoOperation::DeleteLink => self.repo.delete_pairs(user_id, ids).await,
+            // Needs the user to transfer to, it's never applied in bulk
+            UserRepoInfoOperation::Transfer => {
+                let err = "Repos can't be transferred in bulk".to_string();
+                return repo_ids.iter().map(|_| Err(err.clone())).collect();
+            }
+        };
+        results
+            .into_iter()
+            .map(|res| res.map_err(|err| ApiError::from(err).client_message()))
+            .collect()
+    }
+
+    /// Metadata of the links that exist, taken before they are unlinked
+    async fn snapshot_metadata(
+        &self,
+        user_id: &ObjectId,
+        repo_ids: &[Uuid],
+    ) -> HashMap<Uuid, LinkMetadataDto> {
+        let mut snapshot = HashMap::new();
+        for repo_id in repo_ids {
+            if let Ok(metadata) = self.repo.get_metadata(user_id, repo_id).await {
+                snapshot.insert(*repo_id, metadata);
+            }
+        }
+        snapshot
+    }
+
+    /// Links the repos back with the metadata they had before they were unlinked
+    async fn restore_pairs(
+        &self,
+        user_id: &ObjectId,
+        repo_ids: &[Uuid],
+        snapshot: &HashMap<Uuid, LinkMetadataDto>,
+    ) -> Vec<Result<(), String>> {
+        let mut results = vec![];
+        for repo_id in repo_ids {
+            let res = match snapshot.get(repo_id) {
+                Some(metadata) => {
+                    self.repo
+                        .add_pair_with_metadata(user_id, repo_id, metadata)
+                        .await
+                }
+                None => self.repo.add_pair(user_id, repo_id).await,
+            };
+            results.push(res.map_err(|err| ApiError::from(err).client_message()));
+        }
+        results
+    }
+
+    /// Applies the operation to every repo separately. When `all_or_nothing` is set
+    /// and any of them fails, the applied ones are reverted and no events are published
+    async fn apply_bulk(
+        &self,
+        user_id: &ObjectId,
+        dto: BulkUserRepoDto,
+        operation: UserRepoInfoOperation,
+    ) -> ApiResult<BulkUserRepoResultDto> {
+        let user = self.user_service.get(user_id).await?;
+
+        let repos = self.repo_service.get_many(&dto.repo_ids).await?;
+        let mut outcomes: Vec<BulkUserRepoOutcomeDto> = dto
+            .repo_ids
+            .iter()
+            .zip(repos)
+            .map(|(repo_id, res)| match res {
+                Ok(repo) => BulkUserRepoOutcomeDto::succeeded(repo),
+                Err(err) => BulkUserRepoOutcomeDto::failed(*repo_id, err.client_message()),
+            })
+            .collect();
+
+        // Every found repo gets its event staged, the ones left failed are cancelled
+        let mut staged = vec![];
+        for (i, outcome) in outcomes
+            .iter()
+            .enumerate()
+            .filter(|(_, o)| o.is_succeeded())
+        {
+            let message = CreateUserRepoInfoDto::new(*user_id, outcome.repo_id, operation);
+            match self.info_outbox.stage(Some(&message)).await {
+                Ok(event) => staged.push((i, event)),
+                Err(err) => {
+                    for (_, event) in staged {
+                        self.cancel_info(event).await;
+                    }
+                    return Err(err);
+                }
+            }
+        }
+
+        let found_ids = Self::succeeded_ids(&outcomes);
+        let snapshot = match operation {
+            UserRepoInfoOperation::DeleteLink if dto.all_or_nothing => {
+                self.snapshot_metadata(user_id, &found_ids).await
+            }
+            _ => HashMap::new(),
+        };
+        let results = self.apply_pairs(user_id, &found_ids, operation).await;
+        Self::merge_results(&mut outcomes, results);
+
+        let has_failed = outcomes.iter().any(|o| !o.is_succeeded());
+        if dto.all_or_nothing && has_failed {
+            let applied_ids = Self::succeeded_ids(&outcomes);
+            let results = match operation {
+                UserRepoInfoOperation::CreateLink => {
+                    let reverse = UserRepoInfoOperation::DeleteLink;
+                    self.apply_pairs(user_id, &applied_ids, reverse).await
+                }
+                UserRepoInfoOperation::DeleteLink => {
+                    self.restore_pairs(user_id, &applied_ids, &snapshot).await
+                }
+                UserRepoInfoOperation::Transfer => {
+                    let reverse = UserRepoInfoOperation::Transfer;
+                    self.apply_pairs(user_id, &applied_ids, reverse).await
+                }
+            };
+            for (outcome, res) in outcomes
+                .iter_mut()
+                .filter(|o| o.is_succeeded())
+                .zip(results)
+            {
+                match res {
+                    Ok(_) => outcome.status = BulkOperationStatus::RolledBack,
+                    Err(err) => {
+                        outcome.status = BulkOperationStatus::RollbackFailed;
+                        outcome.error = Some(err);
+                    }
+                }
+            }
+        }
+
+        let (committed, cancelled): (Vec<_>, Vec<_>) = staged
+            .into_iter()
+            .partition(|(i, _)| outcomes[*i].is_applied());
+        for (_, event) in cancelled {
+            self.cancel_info(event).await;
+        }
+        for (i, event) in committed {
+            let message = CreateUserRepoInfoDto::new(*user_id, outcomes[i].repo_id, operation);
+            self.info_outbox.commit(event, message).await?;
+        }
+        Ok(BulkUserRepoResultDto { user, outcomes })
+    }
+
+    fn is_missing<T>(res: ApiResult<T>) -> ApiResult<bool> {
+        match res {
+            Ok(_) => Ok(false),
+            Err(ApiError::Repository(
+                RepoError::NotFoundWithObjectId(..)
+                | RepoError::NotFoundWithUuid(..)
+                | RepoError::DeletedWithObjectId(..)
+                | RepoError::DeletedWithUuid(..),
+            )) => Ok(true),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// A failed repair is reported with the link instead of stopping the check
+    fn dangling(
+        user_id: ObjectId,
+        repo_id: Uuid,
+        reason: DanglingLinkReason,
+        repaired: Option<ApiResult<()>>,
+    ) -> DanglingLinkDto {
+        let (repaired, error) = match repaired {
+            Some(Ok(())) => (true, None),
+            Some(Err(err)) => (false, Some(err.to_string())),
+            None => (false, None),
+        };
+        DanglingLinkDto {
+            user_id,
+            repo_id,
+            reason,
+            repaired,
+            error,
+        }
+    }
+
+    fn succeeded_ids(outcomes: &[BulkUserRepoOutcomeDto]) -> Vec<Uuid> {
+        outcomes
+            .iter()
+            .filter(|o| o.is_succeeded())
+            .map(|o| o.repo_id)
+            .collect()
+    }
+
+    fn merge_results(outcomes: &mut [BulkUserRepoOutcomeDto], results: Vec<Result<(), String>>) {
+        for (outcome, res) in outcomes
+            .iter_mut()
+            .filter(|o| o.is_succeeded())
+            .zip(results)
+        {
+            if let Err(err) = res {
+                *outcome = BulkUserRepoOutcomeDto::failed(outcome.repo_id, err);
+            }
         }
     }
 }
 
-impl UserRepoServiceTrait for UserRepoService {}
+#[async_trait]
+impl UserRepoServiceTrait for UserRepoService {
+    async fn add_pair_by(
+        &self,
+        user_id: &ObjectId,
+        repo_id: &Uuid,
+        actor: Option<String>,
+    ) -> ApiResult<OneToOneDto<UserDto, RepoDto>> {
+        let user = self.user_service.get(user_id).await?;
+        let repo = self.repo_service.get(repo_id).await?;
+        let message = CreateUserRepoInfoDto::new(
+            user.id.unwrap(),
+            repo.id,
+            UserRepoInfoOperation::CreateLink,
+        );
+        let metadata = LinkMetadataDto::new(actor);
+        self.with_info(
+            message,
+            self.repo
+                .add_pair_with_metadata(user_id, repo_id, &metadata),
+        )
+        .await?;
+        Ok(OneToOneDto::new(user, repo))
+    }
+
+    async fn get_metadata(&self, user_id: &ObjectId, repo_id: &Uuid) -> ApiResult<LinkMetadataDto> {
+        self.user_service.get(user_id).await?;
+        self.repo_service.get(repo_id).await?;
+        Ok(self.repo.get_metadata(user_id, repo_id).await?)
+    }
+
+    async fn update_metadata(
+        &self,
+        user_id: &ObjectId,
+        repo_id: &Uuid,
+        dto: UpdateLinkMetadataDto,
+    ) -> ApiResult<LinkMetadataDto> {
+        let mut metadata = self.get_metadata(user_id, repo_id).await?;
+        metadata.update(dto);
+        self.repo
+            .update_metadata(user_id, repo_id, &metadata)
+            .await?;
+        Ok(metadata)
+    }
+
+    async fn get_owner(&self, repo_id: &Uuid) -> ApiResult<UserDto> {
+        let user_id = self.repo.get_key(repo_id).await?;
+        // A link left to a deleted repo doesn't make its user an owner
+        if Self::is_missing(self.repo_service.get(repo_id).await)? {
+            Err(RepoError::NotConnectedWithAny(
+                Entity::Repository,
+                repo_id.to_string(),
+                Entity::User,
+            ))?;
+        }
+        self.user_service.get(&user_id).await
+    }
+
+    async fn get_owners(&self, repo_ids: &[Uuid]) -> ApiResult<HashMap<Uuid, UserDto>> {
+        let mut linked = vec![];
+        for repo_id in repo_ids {
+            match self.repo.get_key(repo_id).await {
+                Ok(user_id) => linked.push((*repo_id, user_id)),
+                Err(RepoError::NotConnectedWithAny(..)) => continue,
+                Err(err) => Err(err)?,
+            }
+        }
+
+        let ids: Vec<Uuid> = linked.iter().map(|(repo_id, _)| *repo_id).collect();
+        let user_ids: Vec<ObjectId> = linked.iter().map(|(_, user_id)| *user_id).collect();
+        let repos = self.repo_service.get_many(&ids).await?;
+        let users = self.user_service.get_many(&user_ids).await?;
+
+        let mut owners = HashMap::new();
+        for ((repo_id, repo), user) in ids.into_iter().zip(repos).zip(users) {
+            if let (Ok(_), Ok(user)) = (repo, user) {
+                owners.insert(repo_id, user);
+            }
+        }
+        Ok(owners)
+    }
+
+    async fn transfer(
+        &self,
+        repo_id: &Uuid,
+        to_user_id: &ObjectId,
+    ) -> ApiResult<OneToOneDto<UserDto, RepoDto>> {
+        let repo = self.repo_service.get(repo_id).await?;
+        let from_user_id = self.repo.get_key(repo_id).await?;
+        self.user_service.get(&from_user_id).await?;
+        let to_user = self.user_service.get(to_user_id).await?;
+
+        let message = CreateUserRepoInfoDto::transfer(from_user_id, *to_user_id, repo.id);
+        self.with_info(
+            message,
+            self.repo.transfer_pair(&from_user_id, to_user_id, repo_id),
+        )
+        .await?;
+        Ok(OneToOneDto::new(to_user, repo))
+    }
+
+    async fn check_consistency(&self, repair: bool) -> ApiResult<LinkConsistencyReportDto> {
+        let pairs = self.repo.list_all_pairs().await?;
+        let orphaned = self.repo.list_orphaned_pairs().await?;
+        let checked = (pairs.len() + orphaned.len()) as u64;
+        let user_ids: Vec<ObjectId> = pairs.iter().map(|(user_id, _)| *user_id).collect();
+        let repo_ids: Vec<Uuid> = pairs.iter().map(|(_, repo_id)| *repo_id).collect();
+        let users = self.user_service.get_many(&user_ids).await?;
+        let repos = self.repo_service.get_many(&repo_ids).await?;
+
+        let mut dangling = vec![];
+        for (((user_id, repo_id), user), repo) in pairs.into_iter().zip(users).zip(repos) {
+            let reason = match (Self::is_missing(user)?, Self::is_missing(repo)?) {
+                (false, false) => continue,
+                (true, false) => DanglingLinkReason::UserMissing,
+                (false, true) => DanglingLinkReason::RepoMissing,
+                (true, true) => DanglingLinkReason::BothMissing,
+            };
+
+            let repaired = match repair {
+                true => {
+                    let message = CreateUserRepoInfoDto::new(
+                        user_id,
+                        repo_id,
+                        UserRepoInfoOperation::DeleteLink,
+                    );
+                    let write = self.repo.delete_pair(&user_id, &repo_id);
+                    Some(self.with_info(message, write).await)
+                }
+                false => None,
+            };
+            dangling.push(Self::dangling(user_id, repo_id, reason, repaired));
+        }
+
+        // Nothing was linked, so removing the entry isn't recorded as a link event
+        for (user_id, repo_id) in orphaned {
+            let repaired = match repair {
+                true => Some(
+                    self.repo
+                        .delete_orphaned_pair(&user_id, &repo_id)
+                        .await
+                        .map_err(ApiError::from),
+                ),
+                false => None,
+            };
+            let reason = DanglingLinkReason::LinkMissing;
+            dangling.push(Self::dangling(user_id, repo_id, reason, repaired));
+        }
+        Ok(LinkConsistencyReportDto { checked, dangling })
+    }
+
+    async fn add_pairs(
+        &self,
+        user_id: &ObjectId,
+        dto: BulkUserRepoDto,
+    ) -> ApiResult<BulkUserRepoResultDto> {
+        self.apply_bulk(user_id, dto, UserRepoInfoOperation::CreateLink)
+            .await
+    }
+
+    async fn delete_pairs(
+        &self,
+        user_id: &ObjectId,
+        dto: BulkUserRepoDto,
+    ) -> ApiResult<BulkUserRepoResultDto> {
+        self.apply_bulk(user_id, dto, UserRepoInfoOperation::DeleteLink)
+            .await
+    }
+}
 
 #[async_trait]
 impl
@@ -48,7 +451,7 @@ impl
         ObjectId,
         Uuid,
         OneToOneDto<UserDto, RepoDto>,
-        OneToManyDto<UserDto, RepoDto>,
+        OneToManyDto<UserDto, LinkedRepoDto>,
     > for UserRepoService
 {
     async fn add_pair(
@@ -56,17 +459,7 @@ impl
         key_id: &ObjectId,
         val_id: &Uuid,
     ) -> ApiResult<OneToOneDto<UserDto, RepoDto>> {
-        let user = self.user_service.get(key_id).await?;
-        let repo = self.repo_service.get(val_id).await?;
-        self.repo.add_pair(key_id, val_id).await?;
-        self.info_publisher
-            .publish(CreateUserRepoInfoDto {
-                user_id: user.id.unwrap(),
-                repo_id: repo.id,
-                operation: UserRepoInfoOperation::CreateLink,
-            })
-            .await?;
-        Ok(OneToOneDto::new(user, repo))
+        self.add_pair_by(key_id, val_id, None).await
     }
 
     async fn list_pairs(
@@ -74,31 +467,26 @@ impl
         key_id: &ObjectId,
         take: Option<u64>,
         offset: Option<u64>,
-    ) -> ApiResult<OneToManyDto<UserDto, RepoDto>> {
+    ) -> ApiResult<OneToManyDto<UserDto, LinkedRepoDto>> {
         let user = self.user_service.get(key_id).await?;
-        let repo_ids = self.repo.list_pairs(key_id).await?;
-        let len = repo_ids.len();
-        let offset_num = offset.unwrap_or(0) as usize;
-        let take_num = take
-            .filter(|&n| n != 0)
-            .map(|n| n as usize)
-            .unwrap_or(repo_ids.len());
-
-        let repo_ids: Vec<_> = repo_ids
-            .into_iter()
-            .skip(offset_num)
-            .take(take_num)
-            .collect();
+        let (pairs, count) = self
+            .repo
+            .list_pairs_with_metadata(key_id, take, offset)
+            .await?;
 
+        let ids: Vec<Uuid> = pairs.iter().map(|(id, _)| *id).collect();
+        let found = self.repo_service.get_many(&ids).await?;
+
+        // Links to missing or deleted repos are reported apart from the page
         let mut repos = vec![];
-        for id in &repo_ids {
-            let repo = self.repo_service.get(id).await?;
-            repos.push(repo);
+        let mut missing = vec![];
+        for ((id, metadata), res) in pairs.into_iter().zip(found) {
+            match res {
+                Ok(repo) => repos.push(LinkedRepoDto { repo, metadata }),
+                Err(_) => missing.push(id.to_string()),
+            }
         }
-        Ok(OneToManyDto::new(
-            user,
-            DtoList::new(repos, len as u64, take, offset),
-        ))
+        Ok(OneToManyDto::new(user, DtoList::new(repos, count, take, offset)).with_missing(missing))
     }
 
     async fn delete_pair(
@@ -108,13 +496,12 @@ impl
     ) -> ApiResult<OneToOneDto<UserDto, RepoDto>> {
         let user = self.user_service.get(key_id).await?;
         let repo = self.repo_service.get(val_id).await?;
-        self.repo.delete_pair(key_id, val_id).await?;
-        self.info_publisher
-            .publish(CreateUserRepoInfoDto {
-                user_id: user.id.unwrap(),
-                repo_id: repo.id,
-                operation: UserRepoInfoOperation::DeleteLink,
-            })
+        let message = CreateUserRepoInfoDto::new(
+            user.id.unwrap(),
+            repo.id,
+            UserRepoInfoOperation::DeleteLink,
+        );
+        self.with_info(message, self.repo.delete_pair(key_id, val_id))
             .await?;
         Ok(OneToOneDto::new(user, repo))
     }
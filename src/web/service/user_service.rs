@@ -1,31 +1,84 @@
 use async_trait::async_trait;
+use std::future::Future;
 use std::sync::Arc;
 
 use mongodb::bson::oid::ObjectId;
 
+use collection::audit_event::{AuditEntity, AuditOperation};
+use dto::audit_event_dto::CreateAuditEventDto;
+use dto::entity_event_dto::{EntityEventKind, UserEventDto};
 use dto::user_dto::{CreateUserDto, UpdateUserDto, UserDto};
 use dto::user_repo_info_dto::UserRepoInfoDto;
 use dto::DtoList;
+use repo::dao::error::RepoResult;
 use repo::dao::{UserRepoInfoRepositoryTrait, UserRepositoryTrait};
 
 use crate::web::error::ApiResult;
-use crate::web::service::{ServiceTrait, UserServiceTrait};
+use crate::web::service::event_hub::{EntityEvent, EventHub};
+use crate::web::service::outbox_publisher::write_with_outbox;
+use crate::web::service::{OutboxTrait, ServiceTrait, UserServiceTrait};
 
 pub struct UserService {
     repo: Arc<dyn UserRepositoryTrait>,
     user_repo_info_repo: Arc<dyn UserRepoInfoRepositoryTrait>,
+    audit_outbox: Arc<dyn OutboxTrait<CreateAuditEventDto>>,
+    event_hub: EventHub,
 }
 
 impl UserService {
     pub fn new(
         repo: Arc<dyn UserRepositoryTrait>,
         user_repo_info_repo: Arc<dyn UserRepoInfoRepositoryTrait>,
+        audit_outbox: Arc<dyn OutboxTrait<CreateAuditEventDto>>,
+        event_hub: EventHub,
     ) -> Self {
         Self {
             repo,
             user_repo_info_repo,
+            audit_outbox,
+            event_hub,
         }
     }
+
+    fn publish_event(&self, kind: EntityEventKind, user: &UserDto) {
+        self.event_hub.publish(EntityEvent::User(UserEventDto {
+            kind,
+            user: user.clone(),
+        }));
+    }
+
+    /// Runs the write with its audit event kept in the outbox. The event is staged
+    /// from the `expected` outcome, so it is still sent when the process stops before
+    /// the commit, which replaces it with the outcome of the write. Both hold the
+    /// user and, for updates, the user it replaced
+    async fn audited(
+        &self,
+        operation: AuditOperation,
+        actor: Option<String>,
+        expected: (Option<UserDto>, UserDto),
+        write: impl Future<Output = RepoResult<(Option<UserDto>, UserDto)>> + Send,
+    ) -> ApiResult<UserDto> {
+        let write = async { Ok(write.await?) };
+        let describe = |(before, user): &(Option<UserDto>, UserDto)| {
+            let (before, after) = match operation {
+                AuditOperation::Create => (None, Some(user)),
+                AuditOperation::Update => (before.as_ref(), Some(user)),
+                AuditOperation::Delete => (Some(user), None),
+            };
+            CreateAuditEventDto::new(
+                AuditEntity::User,
+                user.id.unwrap(),
+                operation,
+                actor.clone(),
+                before,
+                after,
+            )
+        };
+        let staged = describe(&expected);
+        let (_, user) =
+            write_with_outbox(self.audit_outbox.as_ref(), Some(&staged), write, describe).await?;
+        Ok(user)
+    }
 }
 
 #[async_trait]
@@ -41,20 +94,56 @@ impl UserServiceTrait for UserService {
             .list_by_user_id(id, page, offset)
             .await?)
     }
+
+    async fn get_many(&self, ids: &[ObjectId]) -> ApiResult<Vec<ApiResult<UserDto>>> {
+        let users = self.repo.get_many(ids).await?;
+        Ok(users
+            .into_iter()
+            .map(|res| res.map_err(|err| err.into()))
+            .collect())
+    }
 }
 
 #[async_trait]
 impl ServiceTrait<CreateUserDto, UpdateUserDto, UserDto, ObjectId> for UserService {
-    async fn create(&self, dto: CreateUserDto) -> ApiResult<UserDto> {
-        Ok(self.repo.create(dto).await?)
+    async fn create(&self, dto: CreateUserDto, actor: Option<String>) -> ApiResult<UserDto> {
+        let id = ObjectId::new();
+        let expected = (None, UserDto::expected(id, &dto));
+        let write = async { Ok((None, self.repo.create_with_id(id, dto).await?)) };
+        let user = self
+            .audited(AuditOperation::Create, actor, expected, write)
+            .await?;
+        self.publish_event(EntityEventKind::Created, &user);
+        Ok(user)
     }
 
-    async fn update(&self, id: &ObjectId, dto: UpdateUserDto) -> ApiResult<UserDto> {
-        Ok(self.repo.update(id, dto).await?)
+    async fn update(
+        &self,
+        id: &ObjectId,
+        dto: UpdateUserDto,
+        actor: Option<String>,
+    ) -> ApiResult<UserDto> {
+        let before = self.repo.get(id).await?;
+        let expected = (Some(before.clone()), before.updated_with(&dto));
+        let write = async {
+            let (before, user) = self.repo.update(id, dto).await?;
+            Ok((Some(before), user))
+        };
+        let user = self
+            .audited(AuditOperation::Update, actor, expected, write)
+            .await?;
+        self.publish_event(EntityEventKind::Updated, &user);
+        Ok(user)
     }
 
-    async fn delete(&self, id: &ObjectId) -> ApiResult<UserDto> {
-        Ok(self.repo.delete(id).await?)
+    async fn delete(&self, id: &ObjectId, actor: Option<String>) -> ApiResult<UserDto> {
+        let expected = (None, self.repo.get(id).await?);
+        let write = async { Ok((None, self.repo.delete(id).await?)) };
+        let user = self
+            .audited(AuditOperation::Delete, actor, expected, write)
+            .await?;
+        self.publish_event(EntityEventKind::Deleted, &user);
+        Ok(user)
     }
 
     async fn get(&self, id: &ObjectId) -> ApiResult<UserDto> {
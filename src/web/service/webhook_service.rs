@@ -0,0 +1,380 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use mongodb::bson::oid::ObjectId;
+use reqwest::header::CONTENT_TYPE;
+use serde::Serialize;
+use serde_json::json;
+use sha2::Sha256;
+use tokio::task::JoinSet;
+
+use collection::user_repo_info::UserRepoInfoOperation;
+use collection::webhook_subscription::WebhookEventType;
+use dto::entity_event_dto::{EntityEventKind, RepoEventDto, UserEventDto};
+use dto::webhook_dto::{
+    CreateWebhookDeliveryDto, CreateWebhookSubscriptionDto, WebhookDeliveryDto,
+    WebhookSubscriptionDto,
+};
+use dto::DtoList;
+use repo::dao::error::RepoError;
+use repo::dao::{WebhookDeliveryRepositoryTrait, WebhookSubscriptionRepositoryTrait};
+
+use crate::web::error::{ApiError, ApiResult};
+use crate::web::service::event_hub::EntityEvent;
+use crate::web::service::{PersistentServiceTrait, WebhookServiceTrait};
+use crate::web::utils::webhook_target;
+
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+pub const DELIVERY_HEADER: &str = "X-Webhook-Delivery";
+
+/// `sha256=` followed by the hex encoded HMAC-SHA256 of the body
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Retries of an unreachable receiver are made at least once a day
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Clone, Copy, Debug)]
+pub struct WebhookRetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+}
+
+impl WebhookRetryPolicy {
+    /// Delay after the failed attempt, doubled for each next one
+    fn delay(&self, attempt: u32) -> chrono::Duration {
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+        chrono::Duration::from_std(delay.min(MAX_RETRY_DELAY)).unwrap_or_default()
+    }
+}
+
+struct WebhookEvent {
+    id: ObjectId,
+    event_type: WebhookEventType,
+    payload: String,
+}
+
+impl WebhookEvent {
+    fn new(event_type: WebhookEventType, data: impl Serialize) -> Self {
+        let id = ObjectId::new();
+        let payload = json!({
+            "id": id.to_hex(),
+            "type": event_type,
+            "occurred_at": Utc::now(),
+            "data": data,
+        });
+        Self {
+            id,
+            event_type,
+            payload: payload.to_string(),
+        }
+    }
+}
+
+impl From<EntityEvent> for WebhookEvent {
+    fn from(event: EntityEvent) -> Self {
+        use EntityEventKind::*;
+        use WebhookEventType::*;
+
+        match event {
+            EntityEvent::Repo(RepoEventDto { kind, repo }) => {
+                let event_type = match kind {
+                    Created => RepoCreated,
+                    Updated => RepoUpdated,
+                    Deleted => RepoDeleted,
+                };
+                Self::new(event_type, repo)
+            }
+            EntityEvent::User(UserEventDto { kind, user }) => {
+                let event_type = match kind {
+                    Created => UserCreated,
+                    Updated => UserUpdated,
+                    Deleted => UserDeleted,
+                };
+                Self::new(event_type, user)
+            }
+            EntityEvent::UserRepoInfo(info) => {
+                let event_type = match info.operation {
+                    UserRepoInfoOperation::CreateLink => LinkCreated,
+                    UserRepoInfoOperation::DeleteLink => LinkDeleted,
+                    UserRepoInfoOperation::Transfer => LinkTransferred,
+                };
+                Self::new(event_type, info)
+            }
+        }
+    }
+}
+
+/// Attempt of the same event as the delivery, numbered `attempt`
+fn next_attempt(
+    delivery: &WebhookDeliveryDto,
+    attempt: u32,
+    scheduled_at: DateTime<Utc>,
+    locked_until: Option<DateTime<Utc>>,
+) -> CreateWebhookDeliveryDto {
+    CreateWebhookDeliveryDto {
+        subscription_id: delivery.subscription_id,
+        event_id: delivery.event_id,
+        event_type: delivery.event_type,
+        payload: delivery.payload.clone(),
+        attempt,
+        scheduled_at,
+        locked_until,
+    }
+}
+
+#[derive(Clone)]
+struct WebhookSender {
+    client: reqwest::Client,
+    delivery_repo: Arc<dyn WebhookDeliveryRepositoryTrait>,
+    retry: WebhookRetryPolicy,
+
+    /// How long a claimed attempt is left to its sender
+    lease: chrono::Duration,
+    allow_private_targets: bool,
+}
+
+impl WebhookSender {
+    /// Makes a claimed attempt, its outcome lands in the delivery log
+    async fn attempt(
+        &self,
+        subscription: &WebhookSubscriptionDto,
+        delivery: &WebhookDeliveryDto,
+    ) -> ApiResult<WebhookDeliveryDto> {
+        let (status_code, error) = match self.post(subscription, delivery).await {
+            Ok(status_code) => (Some(status_code), None),
+            Err(error) => (None, Some(error)),
+        };
+        Ok(self
+            .delivery_repo
+            .complete(&delivery.id, status_code, error)
+            .await?)
+    }
+
+    async fn post(
+        &self,
+        subscription: &WebhookSubscriptionDto,
+        delivery: &WebhookDeliveryDto,
+    ) -> Result<u16, String> {
+        // Host names are checked by the resolver of the client, IP literals only here
+        if !self.allow_private_targets {
+            webhook_target::check_target(&subscription.url).await?;
+        }
+        let type_name = json!(delivery.event_type)
+            .as_str()
+            .unwrap_or_default()
+            .to_owned();
+        let res = self
+            .client
+            .post(&subscription.url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(
+                SIGNATURE_HEADER,
+                sign(&subscription.secret, &delivery.payload),
+            )
+            .header(EVENT_HEADER, type_name)
+            .header(DELIVERY_HEADER, delivery.event_id.to_hex())
+            .body(delivery.payload.clone())
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        Ok(res.status().as_u16())
+    }
+
+    /// Makes a due attempt, a failed one is scheduled again until attempts run out
+    async fn deliver(
+        &self,
+        subscription: &WebhookSubscriptionDto,
+        delivery: &WebhookDeliveryDto,
+    ) -> ApiResult<()> {
+        let delivery = self.attempt(subscription, delivery).await?;
+        if delivery.success || delivery.attempt >= self.retry.max_attempts {
+            return Ok(());
+        }
+        let scheduled_at = Utc::now() + self.retry.delay(delivery.attempt);
+        let next = next_attempt(&delivery, delivery.attempt + 1, scheduled_at, None);
+        self.delivery_repo.create(next).await?;
+        Ok(())
+    }
+}
+
+#[derive(Clone)]
+pub struct WebhookService {
+    subscription_repo: Arc<dyn WebhookSubscriptionRepositoryTrait>,
+    delivery_repo: Arc<dyn WebhookDeliveryRepositoryTrait>,
+    sender: WebhookSender,
+}
+
+impl WebhookService {
+    /// Unless private targets are allowed, the client must resolve hosts with
+    /// [`webhook_target::PublicResolver`] and must not follow redirects
+    pub fn new(
+        subscription_repo: Arc<dyn WebhookSubscriptionRepositoryTrait>,
+        delivery_repo: Arc<dyn WebhookDeliveryRepositoryTrait>,
+        client: reqwest::Client,
+        retry: WebhookRetryPolicy,
+        lease: chrono::Duration,
+        allow_private_targets: bool,
+    ) -> Self {
+        let sender = WebhookSender {
+            client,
+            delivery_repo: Arc::clone(&delivery_repo),
+            retry,
+            lease,
+            allow_private_targets,
+        };
+        Self {
+            subscription_repo,
+            delivery_repo,
+            sender,
+        }
+    }
+
+    /// Subscription of the delivery, `None` once it is deleted
+    async fn find_subscription(
+        &self,
+        subscription_id: &ObjectId,
+    ) -> ApiResult<Option<WebhookSubscriptionDto>> {
+        match self.subscription_repo.get(subscription_id).await {
+            Ok(subscription) => Ok(Some(subscription)),
+            Err(RepoError::NotFoundWithObjectId(..)) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[async_trait]
+impl WebhookServiceTrait for WebhookService {
+    async fn delete(&self, id: &ObjectId) -> ApiResult<WebhookSubscriptionDto> {
+        Ok(self.subscription_repo.delete(id).await?)
+    }
+
+    async fn list_deliveries(
+        &self,
+        subscription_id: &ObjectId,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> ApiResult<DtoList<WebhookDeliveryDto>> {
+        self.subscription_repo.get(subscription_id).await?;
+        Ok(self
+            .delivery_repo
+            .list_by_subscription(subscription_id, take, offset)
+            .await?)
+    }
+
+    async fn redeliver(&self, delivery_id: &ObjectId) -> ApiResult<WebhookDeliveryDto> {
+        let delivery = self.delivery_repo.get(delivery_id).await?;
+        let subscription = self
+            .subscription_repo
+            .get(&delivery.subscription_id)
+            .await?;
+        // Later attempts may have been made since the redelivered one
+        let attempt = self
+            .delivery_repo
+            .last_attempt(&delivery.subscription_id, &delivery.event_id)
+            .await?
+            + 1;
+        let now = Utc::now();
+        let claimed = next_attempt(&delivery, attempt, now, Some(now + self.sender.lease));
+        let delivery = self.delivery_repo.create(claimed).await?;
+        self.sender.attempt(&subscription, &delivery).await
+    }
+
+    async fn enqueue(&self, event: EntityEvent) -> ApiResult<()> {
+        let event = WebhookEvent::from(event);
+        let subscriptions = self
+            .subscription_repo
+            .list_by_event(event.event_type)
+            .await?;
+        for subscription in subscriptions {
+            self.delivery_repo
+                .create(CreateWebhookDeliveryDto {
+                    subscription_id: subscription.id,
+                    event_id: event.id,
+                    event_type: event.event_type,
+                    payload: event.payload.clone(),
+                    attempt: 1,
+                    scheduled_at: Utc::now(),
+                    locked_until: None,
+                })
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn deliver_pending(&self, batch_size: u64) -> ApiResult<usize> {
+        let due = self.delivery_repo.list_due(Utc::now(), batch_size).await?;
+        let mut subscriptions = HashMap::new();
+        let mut deliveries = JoinSet::new();
+        for delivery in due {
+            self.delivery_repo
+                .claim(&delivery.id, Utc::now() + self.sender.lease)
+                .await?;
+            let subscription = match subscriptions.get(&delivery.subscription_id) {
+                Some(subscription) => Option::clone(subscription),
+                None => {
+                    let subscription = self.find_subscription(&delivery.subscription_id).await?;
+                    subscriptions.insert(delivery.subscription_id, subscription.clone());
+                    subscription
+                }
+            };
+            let Some(subscription) = subscription else {
+                let error = "Subscription is deleted".to_string();
+                self.delivery_repo
+                    .complete(&delivery.id, None, Some(error))
+                    .await?;
+                continue;
+            };
+            let sender = self.sender.clone();
+            deliveries.spawn(async move { sender.deliver(&subscription, &delivery).await });
+        }
+
+        // A slow receiver must not hold back the others, so errors are reported at the end
+        let (mut made, mut res) = (0, Ok(()));
+        while let Some(delivery) = deliveries.join_next().await {
+            match delivery {
+                Ok(Ok(())) => made += 1,
+                Ok(Err(err)) => res = Err(err),
+                Err(_) => {}
+            }
+        }
+        res.map(|_| made)
+    }
+}
+
+#[async_trait]
+impl PersistentServiceTrait<CreateWebhookSubscriptionDto, WebhookSubscriptionDto, ObjectId>
+    for WebhookService
+{
+    async fn create(&self, dto: CreateWebhookSubscriptionDto) -> ApiResult<WebhookSubscriptionDto> {
+        if !self.sender.allow_private_targets {
+            webhook_target::check_target(&dto.url)
+                .await
+                .map_err(ApiError::ForbiddenWebhookTarget)?;
+        }
+        Ok(self.subscription_repo.create(dto).await?)
+    }
+
+    async fn get(&self, id: &ObjectId) -> ApiResult<WebhookSubscriptionDto> {
+        Ok(self.subscription_repo.get(id).await?)
+    }
+
+    async fn list(
+        &self,
+        take: Option<u64>,
+        offset: Option<u64>,
+    ) -> ApiResult<DtoList<WebhookSubscriptionDto>> {
+        Ok(self.subscription_repo.list(take, offset).await?)
+    }
+}
@@ -1,37 +1,83 @@
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use axum::extract::FromRef;
 use mongodb::Collection;
-use object_store::aws::AmazonS3Builder;
+use object_store::prefix::PrefixStore;
 use object_store::ObjectStore;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use tokio::sync::Mutex;
 
+use collection::audit_event::{
+    AuditEntity, AuditEvent, AuditEventCollection, AuditOperation, TestAuditEventCollection,
+};
+use collection::outbox_event::{OutboxEvent, OutboxEventCollection, TestOutboxEventCollection};
 use collection::user::{TestUserCollection, User, UserCollection};
 use collection::user_repo_info::{
-    TestUserRepoInfoCollection, UserRepoInfo, UserRepoInfoCollection,
+    TestUserRepoInfoCollection, UserRepoInfo, UserRepoInfoCollection, UserRepoInfoOperation,
+};
+use collection::webhook_delivery::{
+    TestWebhookDeliveryCollection, WebhookDelivery, WebhookDeliveryCollection,
+};
+use collection::webhook_subscription::{
+    TestWebhookSubscriptionCollection, WebhookSubscription, WebhookSubscriptionCollection,
 };
+use dto::audit_event_dto::{AuditEventDto, CreateAuditEventDto};
 use dto::user_repo_info_dto::{CreateUserRepoInfoDto, UserRepoInfoDto};
+use message_broker::codec::{CborCodec, Codec, JsonCodec, MessagePackCodec};
 use message_broker::error::MBrokerResult;
-use message_broker::rabbitmq::{RabbitMQOptions, RabbitMQPublisher, RabbitMQReceiver};
-use message_broker::Subscriber;
+use message_broker::file::{
+    FileDeadLetterQueue, FileOptions, FilePublisher, FileReceiver, FsyncPolicy,
+};
+use message_broker::memory::{
+    MemoryDeadLetterQueue, MemoryOptions, MemoryPublisher, MemoryReceiver,
+};
+use message_broker::rabbitmq::{
+    RabbitMQDeadLetterQueue, RabbitMQExchange, RabbitMQExchangeKind, RabbitMQOptions,
+    RabbitMQPublisher, RabbitMQReceiver,
+};
+use message_broker::{DeadLetterQueue, Delivery, RetryOptions, RoutedPublisher, Subscriber};
+use repo::dao::audit_event_repository::AuditEventRepository;
+use repo::dao::outbox_event_repository::OutboxEventRepository;
 use repo::dao::repo_repository::RepoRepository;
+use repo::dao::user_export_repository::UserExportRepository;
 use repo::dao::user_repo::UserRepository;
+use repo::dao::user_repo_info_archive_repository::UserRepoInfoArchiveRepository;
 use repo::dao::user_repo_info_repository::UserRepoInfoRepository;
 use repo::dao::user_repo_repository::UserRepoRepository;
+use repo::dao::user_repo_sql_repository::UserRepoSqlRepository;
+use repo::dao::webhook_delivery_repository::WebhookDeliveryRepository;
+use repo::dao::webhook_subscription_repository::WebhookSubscriptionRepository;
 use repo::dao::{
-    RepoRepositoryTrait, UserRepoInfoRepositoryTrait, UserRepoRepositoryTrait, UserRepositoryTrait,
+    AuditEventRepositoryTrait, OutboxEventRepositoryTrait, RepoRepositoryTrait,
+    UserExportRepositoryTrait, UserRepoInfoArchiveRepositoryTrait, UserRepoInfoRepositoryTrait,
+    UserRepoRepositoryTrait, UserRepositoryTrait, WebhookDeliveryRepositoryTrait,
+    WebhookSubscriptionRepositoryTrait,
 };
 
-use crate::config::config;
+use crate::config::{config, FsyncMode, LinkBackend, MessageCodec};
 use crate::error::InternalResult;
+use crate::message_broker::Broker;
 use crate::web::error::ApiResult;
+use crate::web::service::audit_event_receiver::AuditEventReceiver;
+use crate::web::service::audit_event_service::AuditEventService;
+use crate::web::service::dead_letter_service::DeadLetterService;
+use crate::web::service::event_hub::EventHub;
+use crate::web::service::outbox_publisher::OutboxPublisher;
+use crate::web::service::user_export_service::UserExportService;
 use crate::web::service::user_repo_info_receiver::UserRepoInfoReceiver;
 use crate::web::service::user_repo_info_service::UserRepoInfoService;
 use crate::web::service::user_repo_service::UserRepoService;
 use crate::web::service::user_service::UserService;
+use crate::web::service::webhook_service::{WebhookRetryPolicy, WebhookService};
 use crate::web::service::{
-    RepoServiceTrait, UserRepoInfoServiceTrait, UserRepoServiceTrait, UserServiceTrait,
+    AuditEventServiceTrait, DeadLetterServiceTrait, OutboxRelayTrait, OutboxTrait,
+    RepoServiceTrait, UserExportServiceTrait, UserRepoInfoServiceTrait, UserRepoServiceTrait,
+    UserServiceTrait, WebhookServiceTrait,
 };
+use crate::web::utils::webhook_target::PublicResolver;
 
 use super::service::repo_service::RepositoryService;
 
@@ -39,56 +85,117 @@ use super::service::repo_service::RepositoryService;
 pub struct AppState {
     pub _sql_conn: Option<sea_orm::DbConn>,
     pub _nosql_conn: Option<mongodb::Database>,
-    pub _rabbitmq_conn: Option<amqprs::connection::Connection>,
+    pub _broker: Option<Broker>,
     pub repo_state: RepoState,
     pub user_state: UserState,
     pub user_repo_state: UserRepoState,
     pub user_repo_info_state: UserRepoInfoState,
+    pub user_export_state: UserExportState,
+    pub audit_event_state: AuditEventState,
+    pub webhook_state: WebhookState,
+    pub dead_letter_state: DeadLetterState,
+    pub event_hub: EventHub,
 }
 
 impl AppState {
     pub async fn build(
         sql_conn: sea_orm::DbConn,
         nosql_conn: mongodb::Database,
-        rabbitmq_conn: amqprs::connection::Connection,
+        broker: Broker,
     ) -> InternalResult<AppState> {
-        let repo_state = RepoState::build(sql_conn.clone()).await?;
-
-        let user_repo_info_state =
-            UserRepoInfoState::build(nosql_conn.clone(), rabbitmq_conn.clone()).await?;
-
-        let user_state = UserState::build(nosql_conn.clone(), &user_repo_info_state).await?;
-
-        let user_repo_state =
-            UserRepoState::build(&user_state, &repo_state, &user_repo_info_state).await?;
+        let event_hub = EventHub::default();
+
+        let audit_event_state = AuditEventState::build(nosql_conn.clone(), broker.clone()).await?;
+
+        let repo_state = RepoState::build(sql_conn.clone(), &audit_event_state, &event_hub).await?;
+
+        let store = crate::db::init_object_store().await?;
+
+        let user_repo_info_state = UserRepoInfoState::build(
+            nosql_conn.clone(),
+            broker.clone(),
+            Arc::clone(&store),
+            &event_hub,
+        )
+        .await?;
+
+        let user_state = UserState::build(
+            nosql_conn.clone(),
+            &user_repo_info_state,
+            &audit_event_state,
+            &event_hub,
+        )
+        .await?;
+
+        let user_repo_state = UserRepoState::build(
+            store,
+            sql_conn.clone(),
+            &user_state,
+            &repo_state,
+            &user_repo_info_state,
+        )
+        .await?;
+
+        let user_export_state =
+            UserExportState::build(&config().STORE.EXPORT_ROOT, &user_state, &user_repo_state)
+                .await?;
+        let webhook_state = WebhookState::build(nosql_conn.clone()).await?;
+        let dead_letter_state = DeadLetterState::build(broker.clone()).await?;
 
         Ok(AppState {
             _sql_conn: Some(sql_conn),
             _nosql_conn: Some(nosql_conn),
-            _rabbitmq_conn: Some(rabbitmq_conn),
+            _broker: Some(broker),
             repo_state,
             user_state,
             user_repo_state,
             user_repo_info_state,
+            user_export_state,
+            audit_event_state,
+            webhook_state,
+            dead_letter_state,
+            event_hub,
         })
     }
 
     pub async fn build_test() -> InternalResult<AppState> {
+        Self::build_test_with_links(LinkBackend::ObjectStore).await
+    }
+
+    pub async fn build_test_with_links(link_backend: LinkBackend) -> InternalResult<AppState> {
         let sql_conn = crate::db::init_test_sql_database().await;
-        let user_repo_info_state = UserRepoInfoState::build_test().await?;
-        let repo_state = RepoState::build(sql_conn.clone()).await?;
-        let user_state = UserState::build_test(&user_repo_info_state).await?;
-        let user_repo_state =
-            UserRepoState::build_test(&user_state, &repo_state, &user_repo_info_state).await?;
+        let event_hub = EventHub::default();
+        let audit_event_state = AuditEventState::build_test().await?;
+        let user_repo_info_state = UserRepoInfoState::build_test(&event_hub).await?;
+        let repo_state = RepoState::build(sql_conn.clone(), &audit_event_state, &event_hub).await?;
+        let user_state =
+            UserState::build_test(&user_repo_info_state, &audit_event_state, &event_hub).await?;
+        let user_repo_state = UserRepoState::build_test(
+            sql_conn.clone(),
+            link_backend,
+            &user_state,
+            &repo_state,
+            &user_repo_info_state,
+        )
+        .await?;
+        let user_export_state =
+            UserExportState::build("exports", &user_state, &user_repo_state).await?;
+        let webhook_state = WebhookState::build_test(true).await?;
+        let dead_letter_state = DeadLetterState::build_test().await?;
 
         Ok(AppState {
             _sql_conn: Some(sql_conn),
             _nosql_conn: None,
-            _rabbitmq_conn: None,
+            _broker: None,
             repo_state,
             user_state,
             user_repo_state,
             user_repo_info_state,
+            user_export_state,
+            audit_event_state,
+            webhook_state,
+            dead_letter_state,
+            event_hub,
         })
     }
 }
@@ -100,9 +207,17 @@ pub struct RepoState {
 }
 
 impl RepoState {
-    async fn build(conn: sea_orm::DbConn) -> InternalResult<Self> {
+    async fn build(
+        conn: sea_orm::DbConn,
+        audit_event_state: &AuditEventState,
+        event_hub: &EventHub,
+    ) -> InternalResult<Self> {
         let repo: Arc<dyn RepoRepositoryTrait> = Arc::new(RepoRepository::new(conn));
-        let service = Arc::new(RepositoryService::new(Arc::clone(&repo)));
+        let service = Arc::new(RepositoryService::new(
+            Arc::clone(&repo),
+            Arc::clone(&audit_event_state.outbox),
+            event_hub.clone(),
+        ));
         Ok(RepoState { repo, service })
     }
 }
@@ -123,6 +238,8 @@ impl UserState {
     async fn build(
         conn: mongodb::Database,
         user_repo_info_state: &UserRepoInfoState,
+        audit_event_state: &AuditEventState,
+        event_hub: &EventHub,
     ) -> InternalResult<Self> {
         let mongo_collection: Collection<User> = schema::get_collection(&conn).await?;
         let collection = Arc::new(UserCollection::new(mongo_collection));
@@ -130,16 +247,24 @@ impl UserState {
         let service = Arc::new(UserService::new(
             Arc::clone(&repo),
             Arc::clone(&user_repo_info_state.repo),
+            Arc::clone(&audit_event_state.outbox),
+            event_hub.clone(),
         ));
         Ok(UserState { service, repo })
     }
 
-    async fn build_test(user_repo_info_state: &UserRepoInfoState) -> InternalResult<Self> {
+    async fn build_test(
+        user_repo_info_state: &UserRepoInfoState,
+        audit_event_state: &AuditEventState,
+        event_hub: &EventHub,
+    ) -> InternalResult<Self> {
         let collection = Arc::new(TestUserCollection::default());
         let repo: Arc<dyn UserRepositoryTrait> = Arc::new(UserRepository::new(collection));
         let service = Arc::new(UserService::new(
             Arc::clone(&repo),
             Arc::clone(&user_repo_info_state.repo),
+            Arc::clone(&audit_event_state.outbox),
+            event_hub.clone(),
         ));
         Ok(UserState { repo, service })
     }
@@ -153,58 +278,77 @@ impl FromRef<AppState> for UserState {
 
 #[derive(Clone)]
 pub struct UserRepoState {
+    pub store: Arc<dyn ObjectStore>,
     pub repo: Arc<dyn UserRepoRepositoryTrait>,
     pub service: Arc<dyn UserRepoServiceTrait>,
 }
 
 impl UserRepoState {
     pub async fn build(
+        store: Arc<dyn ObjectStore>,
+        sql_conn: sea_orm::DbConn,
         user_state: &UserState,
         repo_state: &RepoState,
         user_repo_info_state: &UserRepoInfoState,
     ) -> InternalResult<Self> {
-        let _store = AmazonS3Builder::new()
-            .with_bucket_name(&config().AWS.BUCKET_NAME)
-            .with_region(&config().AWS.BUCKET_REGION)
-            .with_access_key_id(&config().AWS.ACCESS_KEY)
-            .with_secret_access_key(&config().AWS.SECRET_ACCESS_KEY)
-            .build()?;
-
-        println!("AWS S3 bucket is temporarily disabled. Instead the local one is used");
-        let store =
-            object_store::local::LocalFileSystem::new_with_prefix(&config().RESERVE.LOCAL_STORE)?;
-        Self::new(store, user_state, repo_state, user_repo_info_state)
+        Self::new(
+            store,
+            sql_conn,
+            config().STORE.LINK_BACKEND,
+            user_state,
+            repo_state,
+            user_repo_info_state,
+        )
+        .await
     }
 
     pub async fn build_test(
+        sql_conn: sea_orm::DbConn,
+        link_backend: LinkBackend,
         user_state: &UserState,
         repo_state: &RepoState,
         user_repo_info_state: &UserRepoInfoState,
     ) -> InternalResult<Self> {
-        let store = object_store::memory::InMemory::new();
-        Self::new(store, user_state, repo_state, user_repo_info_state)
+        let store = Arc::new(object_store::memory::InMemory::new());
+        Self::new(
+            store,
+            sql_conn,
+            link_backend,
+            user_state,
+            repo_state,
+            user_repo_info_state,
+        )
+        .await
     }
 
-    fn new(
-        store: impl ObjectStore,
+    async fn new(
+        store: Arc<dyn ObjectStore>,
+        sql_conn: sea_orm::DbConn,
+        link_backend: LinkBackend,
         user_state: &UserState,
         repo_state: &RepoState,
         user_repo_info_state: &UserRepoInfoState,
     ) -> InternalResult<Self> {
-        let store = Arc::new(store);
-        let repo: Arc<dyn UserRepoRepositoryTrait> = Arc::new(UserRepoRepository::new(store));
+        let repo: Arc<dyn UserRepoRepositoryTrait> = match link_backend {
+            LinkBackend::ObjectStore => Arc::new(UserRepoRepository::new(Arc::clone(&store))),
+            LinkBackend::Postgres => Arc::new(UserRepoSqlRepository::new(sql_conn)),
+        };
 
         let user_service = Arc::clone(&user_state.service);
         let repo_service = Arc::clone(&repo_state.service);
-        let info_publisher = Arc::clone(&user_repo_info_state.publisher);
+        let info_outbox = Arc::clone(&user_repo_info_state.outbox);
         let service = Arc::new(UserRepoService::new(
             Arc::clone(&repo),
             user_service,
             repo_service,
-            info_publisher,
+            info_outbox,
         ));
 
-        Ok(UserRepoState { repo, service })
+        Ok(UserRepoState {
+            store,
+            repo,
+            service,
+        })
     }
 }
 
@@ -214,18 +358,186 @@ impl FromRef<AppState> for UserRepoState {
     }
 }
 
+#[derive(Clone)]
+pub struct UserExportState {
+    pub repo: Arc<dyn UserExportRepositoryTrait>,
+    pub service: Arc<dyn UserExportServiceTrait>,
+}
+
+impl UserExportState {
+    /// Exports share the store of the links under a root of their own
+    async fn build(
+        root: &str,
+        user_state: &UserState,
+        user_repo_state: &UserRepoState,
+    ) -> InternalResult<Self> {
+        let store = PrefixStore::new(Arc::clone(&user_repo_state.store), root);
+        let repo: Arc<dyn UserExportRepositoryTrait> =
+            Arc::new(UserExportRepository::new(Arc::new(store)));
+        let service = Arc::new(UserExportService::new(
+            Arc::clone(&repo),
+            Arc::clone(&user_state.service),
+            Arc::clone(&user_repo_state.service),
+        ));
+        Ok(UserExportState { repo, service })
+    }
+}
+
+impl FromRef<AppState> for UserExportState {
+    fn from_ref(app_state: &AppState) -> UserExportState {
+        app_state.user_export_state.clone()
+    }
+}
+
+const USER_REPO_INFO_QUEUE: &str = "user_repo_info";
+const AUDIT_EVENT_QUEUE: &str = "audit_events";
+
+/// Queues of the app with the routing keys they get from the events exchange,
+/// other consumers bind their own queues to the same exchange
+const BROKER_QUEUES: [(&str, &str); 2] = [
+    (USER_REPO_INFO_QUEUE, "user_repo_info.#"),
+    (AUDIT_EVENT_QUEUE, "audit.#"),
+];
+
+fn user_repo_info_routing_key(dto: &CreateUserRepoInfoDto) -> String {
+    let operation = match dto.operation {
+        UserRepoInfoOperation::CreateLink => "create_link",
+        UserRepoInfoOperation::DeleteLink => "delete_link",
+        UserRepoInfoOperation::Transfer => "transfer",
+    };
+    format!("user_repo_info.{operation}")
+}
+
+fn audit_event_routing_key(dto: &CreateAuditEventDto) -> String {
+    let entity = match dto.entity {
+        AuditEntity::Repository => "repository",
+        AuditEntity::User => "user",
+    };
+    let operation = match dto.operation {
+        AuditOperation::Create => "create",
+        AuditOperation::Update => "update",
+        AuditOperation::Delete => "delete",
+    };
+    format!("audit.{entity}.{operation}")
+}
+
+/// Failed messages are retried the same way on every backend
+fn retry_options() -> RetryOptions {
+    let broker = &config().BROKER;
+    RetryOptions {
+        max_attempts: broker.RETRY_MAX_ATTEMPTS.max(1),
+        base_delay: Duration::from_millis(broker.RETRY_BASE_DELAY_MS),
+        max_delay: Duration::from_millis(broker.RETRY_MAX_DELAY_MS),
+    }
+}
+
+fn memory_options(queue_name: &'static str) -> MemoryOptions {
+    MemoryOptions {
+        queue_name,
+        capacity: config().BROKER.MEMORY_CAPACITY,
+        retry: retry_options(),
+    }
+}
+
+fn file_options(queue_name: &'static str) -> FileOptions {
+    let broker = &config().BROKER;
+    FileOptions {
+        queue_name,
+        segment_bytes: broker.FILE_SEGMENT_BYTES,
+        fsync: match broker.FILE_FSYNC {
+            FsyncMode::Always => FsyncPolicy::Always,
+            FsyncMode::Interval => {
+                FsyncPolicy::Interval(Duration::from_millis(broker.FILE_FSYNC_INTERVAL_MS))
+            }
+            FsyncMode::Never => FsyncPolicy::Never,
+        },
+        retry: retry_options(),
+    }
+}
+
+fn rabbitmq_options(queue_name: &'static str) -> RabbitMQOptions {
+    let rabbitmq = &config().RABBITMQ;
+    let binding_keys = BROKER_QUEUES
+        .iter()
+        .filter(|(name, _)| *name == queue_name)
+        .map(|(_, binding_key)| *binding_key)
+        .collect();
+    RabbitMQOptions {
+        queue_name,
+        durable: true,
+        exchange: Some(RabbitMQExchange {
+            name: &rabbitmq.EXCHANGE,
+            kind: RabbitMQExchangeKind::Topic,
+            durable: true,
+        }),
+        binding_keys,
+        retry: retry_options(),
+        publish_buffer: rabbitmq.PUBLISH_BUFFER,
+        confirm_timeout: Duration::from_millis(rabbitmq.CONFIRM_TIMEOUT_MS),
+    }
+}
+
+type BrokerQueue<M> = (
+    Arc<dyn message_broker::Receiver<M>>,
+    Arc<dyn message_broker::Publisher<M>>,
+);
+
+/// Receiver and publisher of a queue on the configured backend, both speaking
+/// the configured codec
+async fn broker_queue<M>(broker: Broker, queue_name: &'static str) -> InternalResult<BrokerQueue<M>>
+where
+    M: Serialize + DeserializeOwned + Send + Sync + 'static,
+{
+    async fn open<M, C>(broker: Broker, queue_name: &'static str) -> MBrokerResult<BrokerQueue<M>>
+    where
+        M: Serialize + DeserializeOwned + Send + Sync + 'static,
+        C: Codec,
+    {
+        Ok(match broker {
+            Broker::RabbitMQ(conn) => {
+                let options = rabbitmq_options(queue_name);
+                let receiver = RabbitMQReceiver::<C>::init(conn.clone(), &options).await?;
+                let publisher = RabbitMQPublisher::<C>::init(conn, &options).await?;
+                (Arc::new(receiver), Arc::new(publisher))
+            }
+            Broker::Memory(broker) => {
+                let options = memory_options(queue_name);
+                let receiver = MemoryReceiver::<C>::init(broker.clone(), &options).await?;
+                let publisher = MemoryPublisher::<C>::init(broker, &options).await?;
+                (Arc::new(receiver), Arc::new(publisher))
+            }
+            Broker::File(broker) => {
+                let options = file_options(queue_name);
+                let receiver = FileReceiver::<C>::init(broker.clone(), &options).await?;
+                let publisher = FilePublisher::<C>::init(broker, &options).await?;
+                (Arc::new(receiver), Arc::new(publisher))
+            }
+        })
+    }
+
+    Ok(match config().BROKER.CODEC {
+        MessageCodec::Json => open::<M, JsonCodec>(broker, queue_name).await?,
+        MessageCodec::MessagePack => open::<M, MessagePackCodec>(broker, queue_name).await?,
+        MessageCodec::Cbor => open::<M, CborCodec>(broker, queue_name).await?,
+    })
+}
+
 #[derive(Clone)]
 pub struct UserRepoInfoState {
     pub repo: Arc<dyn UserRepoInfoRepositoryTrait>,
+    pub archive_repo: Arc<dyn UserRepoInfoArchiveRepositoryTrait>,
     pub service: Arc<dyn UserRepoInfoServiceTrait>,
     pub receiver: Arc<dyn message_broker::Receiver<UserRepoInfoDto, ApiResult<UserRepoInfoDto>>>,
-    pub publisher: Arc<dyn message_broker::Publisher<CreateUserRepoInfoDto>>,
+    pub outbox: Arc<dyn OutboxTrait<CreateUserRepoInfoDto>>,
+    pub relay: Arc<dyn OutboxRelayTrait>,
 }
 
 impl UserRepoInfoState {
     pub async fn build(
         nosql_conn: mongodb::Database,
-        rabbitmq_conn: amqprs::connection::Connection,
+        broker: Broker,
+        store: Arc<dyn ObjectStore>,
+        event_hub: &EventHub,
     ) -> InternalResult<Self> {
         let collection: Collection<UserRepoInfo> = schema::get_collection(&nosql_conn).await?;
 
@@ -234,48 +546,74 @@ impl UserRepoInfoState {
         let repo: Arc<dyn UserRepoInfoRepositoryTrait> =
             Arc::new(UserRepoInfoRepository::new(collection));
 
-        let service: Arc<dyn UserRepoInfoServiceTrait> =
-            Arc::new(UserRepoInfoService::new(Arc::clone(&repo)));
+        let archive_repo: Arc<dyn UserRepoInfoArchiveRepositoryTrait> =
+            Arc::new(UserRepoInfoArchiveRepository::new(store));
 
-        let options = RabbitMQOptions {
-            queue_name: "user_repo_info",
-            durable: true,
-        };
-        let rabbitmq_receiver =
-            Arc::new(RabbitMQReceiver::init(rabbitmq_conn.clone(), &options).await?);
+        let service: Arc<dyn UserRepoInfoServiceTrait> = Arc::new(UserRepoInfoService::new(
+            Arc::clone(&repo),
+            Arc::clone(&archive_repo),
+            event_hub.clone(),
+        ));
 
-        let rabbitmq_publisher: Arc<dyn message_broker::Publisher<CreateUserRepoInfoDto>> =
-            Arc::new(RabbitMQPublisher::init(rabbitmq_conn, &options).await?);
+        let (broker_receiver, broker_publisher) =
+            broker_queue(broker, USER_REPO_INFO_QUEUE).await?;
+        let broker_publisher = Arc::new(RoutedPublisher::new(
+            broker_publisher,
+            user_repo_info_routing_key,
+        ));
 
         let receiver = Arc::new(UserRepoInfoReceiver::new(
-            rabbitmq_receiver,
+            broker_receiver,
             Arc::clone(&service),
+            event_hub.clone(),
+        ));
+
+        let outbox_collection: Collection<OutboxEvent> =
+            schema::get_collection(&nosql_conn).await?;
+        let outbox_repo: Arc<dyn OutboxEventRepositoryTrait> = Arc::new(
+            OutboxEventRepository::new(Arc::new(OutboxEventCollection {
+                collection: outbox_collection,
+            })),
+        );
+        let outbox = Arc::new(OutboxPublisher::new(
+            USER_REPO_INFO_QUEUE,
+            outbox_repo,
+            broker_publisher,
+            chrono::Duration::seconds(config().OUTBOX.LEASE_SECS),
         ));
 
         Ok(UserRepoInfoState {
             repo,
+            archive_repo,
             service,
             receiver,
-            publisher: rabbitmq_publisher,
+            outbox: outbox.clone(),
+            relay: outbox,
         })
     }
 
-    pub async fn build_test() -> InternalResult<Self> {
+    pub async fn build_test(event_hub: &EventHub) -> InternalResult<Self> {
         let collection = Arc::new(TestUserRepoInfoCollection::default());
 
         let user_repo_info_repository: Arc<dyn UserRepoInfoRepositoryTrait> =
             Arc::new(UserRepoInfoRepository::new(collection));
 
-        let user_repo_info_service = Arc::new(UserRepoInfoService::new(Arc::clone(
-            &user_repo_info_repository,
-        )));
+        let archive_repo: Arc<dyn UserRepoInfoArchiveRepositoryTrait> = Arc::new(
+            UserRepoInfoArchiveRepository::new(Arc::new(object_store::memory::InMemory::new())),
+        );
+
+        let user_repo_info_service = Arc::new(UserRepoInfoService::new(
+            Arc::clone(&user_repo_info_repository),
+            Arc::clone(&archive_repo),
+            event_hub.clone(),
+        ));
 
         let queue: Arc<Mutex<Vec<CreateUserRepoInfoDto>>> = Arc::new(Mutex::new(vec![]));
 
         let receiver: Arc<
             dyn message_broker::Receiver<
                 CreateUserRepoInfoDto,
-                MBrokerResult<CreateUserRepoInfoDto>,
+                MBrokerResult<Delivery<CreateUserRepoInfoDto>>,
             >,
         > = Arc::new(message_broker::tests::ReceiverMock::new(Arc::clone(&queue)));
 
@@ -284,19 +622,32 @@ impl UserRepoInfoState {
         > = Arc::new(UserRepoInfoReceiver::new(
             receiver,
             user_repo_info_service.clone(),
+            event_hub.clone(),
         ));
 
-        let publisher: Arc<dyn message_broker::Publisher<CreateUserRepoInfoDto>> =
+        let broker_publisher: Arc<dyn message_broker::Publisher<CreateUserRepoInfoDto>> =
             Arc::new(message_broker::tests::PublisherMock::new(
                 Arc::clone(&queue),
                 Arc::clone(&user_repo_info_receiver),
             ));
 
+        let outbox_repo: Arc<dyn OutboxEventRepositoryTrait> = Arc::new(
+            OutboxEventRepository::new(Arc::new(TestOutboxEventCollection::default())),
+        );
+        let outbox = Arc::new(OutboxPublisher::new(
+            USER_REPO_INFO_QUEUE,
+            outbox_repo,
+            broker_publisher,
+            chrono::Duration::seconds(60),
+        ));
+
         Ok(UserRepoInfoState {
             repo: user_repo_info_repository,
+            archive_repo,
             service: user_repo_info_service,
             receiver: user_repo_info_receiver,
-            publisher,
+            outbox: outbox.clone(),
+            relay: outbox,
         })
     }
 }
@@ -306,3 +657,258 @@ impl FromRef<AppState> for UserRepoInfoState {
         app_state.user_repo_info_state.clone()
     }
 }
+
+#[derive(Clone)]
+pub struct AuditEventState {
+    pub repo: Arc<dyn AuditEventRepositoryTrait>,
+    pub service: Arc<dyn AuditEventServiceTrait>,
+    pub receiver: Arc<dyn message_broker::Receiver<AuditEventDto, ApiResult<AuditEventDto>>>,
+    pub outbox: Arc<dyn OutboxTrait<CreateAuditEventDto>>,
+    pub relay: Arc<dyn OutboxRelayTrait>,
+}
+
+impl AuditEventState {
+    pub async fn build(nosql_conn: mongodb::Database, broker: Broker) -> InternalResult<Self> {
+        let collection: Collection<AuditEvent> = schema::get_collection(&nosql_conn).await?;
+        let collection = Arc::new(AuditEventCollection { collection });
+
+        let repo: Arc<dyn AuditEventRepositoryTrait> =
+            Arc::new(AuditEventRepository::new(collection));
+
+        let service: Arc<dyn AuditEventServiceTrait> =
+            Arc::new(AuditEventService::new(Arc::clone(&repo)));
+
+        let (broker_receiver, broker_publisher) = broker_queue(broker, AUDIT_EVENT_QUEUE).await?;
+        let broker_publisher = Arc::new(RoutedPublisher::new(
+            broker_publisher,
+            audit_event_routing_key,
+        ));
+
+        let receiver = Arc::new(AuditEventReceiver::new(
+            broker_receiver,
+            Arc::clone(&service),
+        ));
+
+        let outbox_collection: Collection<OutboxEvent> =
+            schema::get_collection(&nosql_conn).await?;
+        let outbox_repo: Arc<dyn OutboxEventRepositoryTrait> = Arc::new(
+            OutboxEventRepository::new(Arc::new(OutboxEventCollection {
+                collection: outbox_collection,
+            })),
+        );
+        let outbox = Arc::new(OutboxPublisher::new(
+            AUDIT_EVENT_QUEUE,
+            outbox_repo,
+            broker_publisher,
+            chrono::Duration::seconds(config().OUTBOX.LEASE_SECS),
+        ));
+
+        Ok(AuditEventState {
+            repo,
+            service,
+            receiver,
+            outbox: outbox.clone(),
+            relay: outbox,
+        })
+    }
+
+    pub async fn build_test() -> InternalResult<Self> {
+        let collection = Arc::new(TestAuditEventCollection::default());
+
+        let repo: Arc<dyn AuditEventRepositoryTrait> =
+            Arc::new(AuditEventRepository::new(collection));
+
+        let service: Arc<dyn AuditEventServiceTrait> =
+            Arc::new(AuditEventService::new(Arc::clone(&repo)));
+
+        let queue: Arc<Mutex<Vec<CreateAuditEventDto>>> = Arc::new(Mutex::new(vec![]));
+
+        let receiver: Arc<
+            dyn message_broker::Receiver<
+                CreateAuditEventDto,
+                MBrokerResult<Delivery<CreateAuditEventDto>>,
+            >,
+        > = Arc::new(message_broker::tests::ReceiverMock::new(Arc::clone(&queue)));
+
+        let audit_event_receiver: Arc<
+            dyn message_broker::Receiver<AuditEventDto, ApiResult<AuditEventDto>>,
+        > = Arc::new(AuditEventReceiver::new(receiver, Arc::clone(&service)));
+
+        let broker_publisher: Arc<dyn message_broker::Publisher<CreateAuditEventDto>> =
+            Arc::new(message_broker::tests::PublisherMock::new(
+                Arc::clone(&queue),
+                Arc::clone(&audit_event_receiver),
+            ));
+
+        let outbox_repo: Arc<dyn OutboxEventRepositoryTrait> = Arc::new(
+            OutboxEventRepository::new(Arc::new(TestOutboxEventCollection::default())),
+        );
+        let outbox = Arc::new(OutboxPublisher::new(
+            AUDIT_EVENT_QUEUE,
+            outbox_repo,
+            broker_publisher,
+            chrono::Duration::seconds(60),
+        ));
+
+        Ok(AuditEventState {
+            repo,
+            service,
+            receiver: audit_event_receiver,
+            outbox: outbox.clone(),
+            relay: outbox,
+        })
+    }
+}
+
+impl FromRef<AppState> for AuditEventState {
+    fn from_ref(app_state: &AppState) -> Self {
+        app_state.audit_event_state.clone()
+    }
+}
+
+#[derive(Clone)]
+pub struct DeadLetterState {
+    pub service: Arc<dyn DeadLetterServiceTrait>,
+}
+
+impl DeadLetterState {
+    pub async fn build(broker: Broker) -> InternalResult<Self> {
+        let mut queues: HashMap<&'static str, Arc<dyn DeadLetterQueue>> = HashMap::new();
+        for (queue_name, _) in BROKER_QUEUES {
+            let queue: Arc<dyn DeadLetterQueue> = match &broker {
+                Broker::RabbitMQ(conn) => Arc::new(
+                    RabbitMQDeadLetterQueue::init(conn.clone(), &rabbitmq_options(queue_name))
+                        .await?,
+                ),
+                Broker::Memory(broker) => Arc::new(
+                    MemoryDeadLetterQueue::init(broker.clone(), &memory_options(queue_name))
+                        .await?,
+                ),
+                Broker::File(broker) => Arc::new(
+                    FileDeadLetterQueue::init(broker.clone(), &file_options(queue_name)).await?,
+                ),
+            };
+            queues.insert(queue_name, queue);
+        }
+        let service = Arc::new(DeadLetterService::new(queues));
+        Ok(DeadLetterState { service })
+    }
+
+    pub async fn build_test() -> InternalResult<Self> {
+        let mut queues: HashMap<&'static str, Arc<dyn DeadLetterQueue>> = HashMap::new();
+        for (queue_name, _) in BROKER_QUEUES {
+            queues.insert(
+                queue_name,
+                Arc::new(message_broker::tests::DeadLetterQueueMock::default()),
+            );
+        }
+        let service = Arc::new(DeadLetterService::new(queues));
+        Ok(DeadLetterState { service })
+    }
+}
+
+impl FromRef<AppState> for DeadLetterState {
+    fn from_ref(app_state: &AppState) -> Self {
+        app_state.dead_letter_state.clone()
+    }
+}
+
+#[derive(Clone)]
+pub struct WebhookState {
+    pub subscription_repo: Arc<dyn WebhookSubscriptionRepositoryTrait>,
+    pub delivery_repo: Arc<dyn WebhookDeliveryRepositoryTrait>,
+    pub service: Arc<dyn WebhookServiceTrait>,
+}
+
+impl WebhookState {
+    pub async fn build(nosql_conn: mongodb::Database) -> InternalResult<Self> {
+        let subscriptions: Collection<WebhookSubscription> =
+            schema::get_collection(&nosql_conn).await?;
+        let subscription_repo: Arc<dyn WebhookSubscriptionRepositoryTrait> = Arc::new(
+            WebhookSubscriptionRepository::new(Arc::new(WebhookSubscriptionCollection {
+                collection: subscriptions,
+            })),
+        );
+
+        let deliveries: Collection<WebhookDelivery> = schema::get_collection(&nosql_conn).await?;
+        let delivery_repo: Arc<dyn WebhookDeliveryRepositoryTrait> = Arc::new(
+            WebhookDeliveryRepository::new(Arc::new(WebhookDeliveryCollection {
+                collection: deliveries,
+            })),
+        );
+
+        let webhook = &config().WEBHOOK;
+        let retry = WebhookRetryPolicy {
+            max_attempts: webhook.MAX_ATTEMPTS.max(1),
+            base_delay: Duration::from_millis(webhook.BASE_DELAY_MS),
+        };
+        Self::new(
+            subscription_repo,
+            delivery_repo,
+            retry,
+            Duration::from_secs(webhook.TIMEOUT_SECS),
+            webhook.ALLOW_PRIVATE_TARGETS,
+        )
+    }
+
+    /// Test receivers listen on the loopback, so only tests of the target
+    /// checks keep private targets forbidden
+    pub async fn build_test(allow_private_targets: bool) -> InternalResult<Self> {
+        let subscription_repo: Arc<dyn WebhookSubscriptionRepositoryTrait> =
+            Arc::new(WebhookSubscriptionRepository::new(Arc::new(
+                TestWebhookSubscriptionCollection::default(),
+            )));
+        let delivery_repo: Arc<dyn WebhookDeliveryRepositoryTrait> = Arc::new(
+            WebhookDeliveryRepository::new(Arc::new(TestWebhookDeliveryCollection::default())),
+        );
+
+        let retry = WebhookRetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(10),
+        };
+        Self::new(
+            subscription_repo,
+            delivery_repo,
+            retry,
+            Duration::from_secs(5),
+            allow_private_targets,
+        )
+    }
+
+    fn new(
+        subscription_repo: Arc<dyn WebhookSubscriptionRepositoryTrait>,
+        delivery_repo: Arc<dyn WebhookDeliveryRepositoryTrait>,
+        retry: WebhookRetryPolicy,
+        timeout: Duration,
+        allow_private_targets: bool,
+    ) -> InternalResult<Self> {
+        // A redirect could lead to an address the target checks never saw
+        let mut client = reqwest::Client::builder()
+            .timeout(timeout)
+            .redirect(reqwest::redirect::Policy::none());
+        if !allow_private_targets {
+            client = client.dns_resolver(Arc::new(PublicResolver));
+        }
+        // Outlives the request, so a claimed attempt isn't made twice at once
+        let lease = chrono::Duration::from_std(timeout * 2).unwrap_or_default();
+        let service = Arc::new(WebhookService::new(
+            Arc::clone(&subscription_repo),
+            Arc::clone(&delivery_repo),
+            client.build()?,
+            retry,
+            lease,
+            allow_private_targets,
+        ));
+        Ok(WebhookState {
+            subscription_repo,
+            delivery_repo,
+            service,
+        })
+    }
+}
+
+impl FromRef<AppState> for WebhookState {
+    fn from_ref(app_state: &AppState) -> Self {
+        app_state.webhook_state.clone()
+    }
+}
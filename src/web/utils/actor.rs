@@ -0,0 +1,13 @@
+use axum::http::HeaderMap;
+
+/// The API has no authentication, so whoever makes a change is named by the
+/// client and recorded as given
+pub const ACTOR_HEADER: &str = "x-actor";
+
+/// Client-asserted name of whoever makes the change, if any
+pub fn actor(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(ACTOR_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+}
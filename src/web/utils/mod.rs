@@ -1,4 +1,7 @@
+pub mod actor;
+pub mod sse;
 pub mod validation;
+pub mod webhook_target;
 
 use axum::body::Body;
 use axum::extract::{Host, Request};
@@ -0,0 +1,36 @@
+use axum::http::HeaderMap;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use mongodb::bson::oid::ObjectId;
+use tokio_stream::{Stream, StreamExt};
+
+use crate::web::error::{ApiError, ApiResult};
+use crate::web::service::UserRepoInfoStream;
+
+const LAST_EVENT_ID: &str = "last-event-id";
+
+/// Id sent back by a reconnecting `EventSource`, if any
+pub fn last_event_id(headers: &HeaderMap) -> ApiResult<Option<ObjectId>> {
+    headers
+        .get(LAST_EVENT_ID)
+        .map(|id| {
+            let id = String::from_utf8_lossy(id.as_bytes());
+            ObjectId::parse_str(id.as_ref())
+                .map_err(|_| ApiError::InvalidLastEventId(id.to_string()))
+        })
+        .transpose()
+}
+
+/// Each record is sent as a `user_repo_info` event with its id as the event id
+pub fn user_repo_info_sse(
+    stream: UserRepoInfoStream,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let events = stream.map(|info| {
+        let event = Event::default().event("user_repo_info");
+        match info.id {
+            Some(id) => event.id(id.to_hex()),
+            None => event,
+        }
+        .json_data(info)
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
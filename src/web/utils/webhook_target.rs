@@ -0,0 +1,92 @@
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::Url;
+
+/// Whether the address is reachable from the internet. Webhooks must not reach
+/// the services running next to this one
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_v4(ip),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || a == 0
+        // Shared address space of carrier-grade NAT
+        || (a == 100 && (64..128).contains(&b))
+        // Benchmarking and reserved ranges
+        || (a == 198 && (18..20).contains(&b))
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let [first, second, ..] = ip.segments();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // Unique local
+        || (first & 0xfe00) == 0xfc00
+        // Link-local
+        || (first & 0xffc0) == 0xfe80
+        // Documentation
+        || (first == 0x2001 && second == 0x0db8))
+}
+
+/// Addresses of the host, fails if any of them isn't public
+async fn resolve_public(host: &str, port: u16) -> Result<Vec<SocketAddr>, String> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host, port))
+        .await
+        .map_err(|err| format!("Can't resolve {host}. {err}"))?
+        .collect();
+    match addrs.iter().find(|addr| !is_public(addr.ip())) {
+        Some(addr) => Err(format!("{host} resolves to non-public {}", addr.ip())),
+        None => Ok(addrs),
+    }
+}
+
+/// Checks that the URL is http(s) and its host resolves to public addresses only
+pub async fn check_target(url: &str) -> Result<(), String> {
+    let url = Url::parse(url).map_err(|err| err.to_string())?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!("Scheme {} is not supported", url.scheme()));
+    }
+    let host = url.host_str().ok_or("URL has no host")?;
+    // IPv6 hosts are kept in brackets
+    let literal = host.trim_start_matches('[').trim_end_matches(']');
+    match literal.parse::<IpAddr>() {
+        Ok(ip) if !is_public(ip) => Err(format!("{ip} is not a public address")),
+        Ok(_) => Ok(()),
+        Err(_) => {
+            let port = url.port_or_known_default().unwrap_or_default();
+            resolve_public(host, port).await.map(|_| ())
+        }
+    }
+}
+
+/// Resolves hosts to public addresses only. The check happens on every
+/// connection, so a host can't be pointed at an internal address after its
+/// subscription was accepted
+pub struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs = resolve_public(name.as_str(), 0).await?;
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
@@ -0,0 +1,99 @@
+use axum::http::StatusCode;
+use serial_test::serial;
+
+use collection::audit_event::{AuditEntity, AuditOperation};
+use dto::audit_event_dto::AuditEventDto;
+use dto::DtoList;
+use repo::utils::user::user_test_helper;
+
+use crate::common::Setup;
+use crate::helpers::{repository_api_helper, user_api_helper};
+
+#[tokio::test]
+#[serial]
+async fn create_repo_is_audited_success() {
+    let setup = Setup::new().await;
+    let repo = repository_api_helper::create_repo(&setup.client).await;
+    let expected_code = StatusCode::OK;
+
+    let res = setup
+        .client
+        .get("/api/v1/audit-events")
+        .add_query_param("entity", "Repository")
+        .add_query_param("entity_id", repo.id)
+        .await;
+    let events = res.json::<DtoList<AuditEventDto>>();
+
+    assert_eq!(res.status_code(), expected_code);
+    assert_eq!(events.count, 1);
+    assert_eq!(events.dtos[0].operation, AuditOperation::Create);
+    assert!(events.dtos[0]
+        .changes
+        .iter()
+        .all(|change| change.before.is_none() && change.after.is_some()));
+}
+
+#[tokio::test]
+#[serial]
+async fn update_user_records_diff_success() {
+    let setup = Setup::new().await;
+    let user = user_api_helper::create_user1(&setup.client).await;
+    let user_id = user.id.unwrap();
+    let update_dto = user_test_helper::get_update_dto();
+    let expected_code = StatusCode::OK;
+
+    setup
+        .client
+        .put(&format!("/api/v1/users/{user_id}"))
+        .add_header("x-actor".parse().unwrap(), "admin".parse().unwrap())
+        .json(&update_dto)
+        .await;
+    let res = setup
+        .client
+        .get("/api/v1/audit-events")
+        .add_query_param("entity", "User")
+        .add_query_param("entity_id", user_id.to_hex())
+        .add_query_param("operation", "Update")
+        .await;
+    let events = res.json::<DtoList<AuditEventDto>>();
+    let fields: Vec<&str> = events.dtos[0]
+        .changes
+        .iter()
+        .map(|change| change.field.as_str())
+        .collect();
+
+    assert_eq!(res.status_code(), expected_code);
+    assert_eq!(events.count, 1);
+    assert_eq!(events.dtos[0].entity, AuditEntity::User);
+    assert_eq!(events.dtos[0].actor, Some("admin".to_string()));
+    assert!(fields.contains(&"username"));
+    assert!(fields.contains(&"age"));
+    assert!(!fields.contains(&"email"));
+}
+
+#[tokio::test]
+#[serial]
+async fn get_audit_event_success() {
+    let setup = Setup::new().await;
+    let repo = repository_api_helper::create_repo(&setup.client).await;
+    let expected_code = StatusCode::OK;
+
+    setup
+        .client
+        .delete(&format!("/api/v1/repos/{}", repo.id))
+        .await;
+    let res = setup
+        .client
+        .get("/api/v1/audit-events")
+        .add_query_param("operation", "Delete")
+        .await;
+    let event = &res.json::<DtoList<AuditEventDto>>().dtos[0];
+    let res = setup
+        .client
+        .get(&format!("/api/v1/audit-events/{}", event.id.unwrap()))
+        .await;
+
+    assert_eq!(res.status_code(), expected_code);
+    assert_eq!(&res.json::<AuditEventDto>(), event);
+    assert_eq!(event.entity_id, repo.id.to_string());
+}
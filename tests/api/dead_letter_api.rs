@@ -0,0 +1,51 @@
+use axum::http::StatusCode;
+use serial_test::serial;
+
+use dto::dead_letter_dto::{DeadLetterBatchDto, DeadLetterDto};
+
+use super::common::Setup;
+
+#[tokio::test]
+#[serial]
+async fn list_dead_letters_success() {
+    let setup = Setup::new().await;
+
+    let res = setup.client.get("/api/v1/dead-letters/user_repo_info").await;
+
+    assert_eq!(res.status_code(), StatusCode::OK);
+    assert_eq!(res.json::<Vec<DeadLetterDto>>(), vec![]);
+}
+
+#[tokio::test]
+#[serial]
+async fn replay_and_purge_dead_letters_success() {
+    let setup = Setup::new().await;
+    let expected_body = DeadLetterBatchDto {
+        queue: "audit_events".to_string(),
+        count: 0,
+    };
+
+    let replayed = setup
+        .client
+        .post("/api/v1/dead-letters/audit_events/replay")
+        .add_query_param("limit", 10)
+        .await;
+    let purged = setup.client.delete("/api/v1/dead-letters/audit_events").await;
+
+    assert_eq!(replayed.json::<DeadLetterBatchDto>(), expected_body);
+    assert_eq!(purged.json::<DeadLetterBatchDto>(), expected_body);
+}
+
+#[tokio::test]
+#[serial]
+async fn list_dead_letters_of_unknown_queue_failure() {
+    let setup = Setup::new().await;
+
+    let res = setup.client.get("/api/v1/dead-letters/unknown").await;
+
+    assert_eq!(
+        res.status_code(),
+        StatusCode::NOT_FOUND,
+        "Unknown queue must not be found"
+    );
+}
@@ -1,6 +1,12 @@
+mod audit_event_api;
+mod dead_letter_api;
 mod repo_api;
+mod subscription_api;
 mod user_api;
+mod user_export_api;
 mod user_repo_api;
 mod user_repo_info_api;
+mod user_repo_postgres_api;
 
 use super::common;
+mod webhook_api;
@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use futures_util::SinkExt;
+use serde_json::{json, Value};
+use serial_test::serial;
+use tokio::net::TcpListener;
+use tokio_stream::StreamExt;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::header::SEC_WEBSOCKET_PROTOCOL;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::Message;
+
+use dto::OneToOneDto;
+
+use crate::common::Setup;
+use crate::helpers::{repository_api_helper, user_repo_api_helper};
+
+const EVENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[tokio::test]
+#[serial]
+async fn repo_changes_filtered_by_kind_success() {
+    let setup = Setup::new().await;
+    let schema = ia_11_vorobei_ant::web::schema(setup.state.clone());
+    let mut stream =
+        schema.execute_stream("subscription { repoChanges(kind: DELETED) { kind repo { id } } }");
+
+    let (res, repo) = tokio::join!(
+        tokio::time::timeout(EVENT_TIMEOUT, stream.next()),
+        repository_api_helper::delete_repo(&setup.client),
+    );
+    let res = res.unwrap().unwrap();
+
+    assert!(res.errors.is_empty());
+    assert_eq!(
+        res.data.into_json().unwrap(),
+        json!({"repoChanges": {"kind": "DELETED", "repo": {"id": repo.id}}})
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn user_repo_links_filtered_by_user_success() {
+    let setup = Setup::new().await;
+    let OneToOneDto {
+        left: user,
+        right: repo,
+    } = user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let user_id = user.id.unwrap();
+    let schema = ia_11_vorobei_ant::web::schema(setup.state.clone());
+    let mut stream = schema.execute_stream(format!(
+        "subscription {{ userRepoLinks(userId: \"{user_id}\") {{ operation repoId }} }}"
+    ));
+
+    let endpoint = format!("/api/v1/users/{}/repos/{}", user_id, repo.id);
+    let (res, _) = tokio::join!(
+        tokio::time::timeout(EVENT_TIMEOUT, stream.next()),
+        setup.client.post(&endpoint),
+    );
+    let res = res.unwrap().unwrap();
+
+    assert!(res.errors.is_empty());
+    assert_eq!(
+        res.data.into_json().unwrap(),
+        json!({"userRepoLinks": {"operation": "CREATE_LINK", "repoId": repo.id}})
+    );
+}
+
+#[tokio::test]
+#[serial]
+async fn graphql_ws_subscription_success() {
+    let setup = Setup::new().await;
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = ia_11_vorobei_ant::web::app(setup.state.clone());
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let mut req = format!("ws://{addr}/api/v1/graphql")
+        .into_client_request()
+        .unwrap();
+    req.headers_mut().insert(
+        SEC_WEBSOCKET_PROTOCOL,
+        HeaderValue::from_static("graphql-transport-ws"),
+    );
+    let (mut socket, res) = tokio_tungstenite::connect_async(req).await.unwrap();
+    let init = json!({"type": "connection_init"});
+    socket.send(Message::text(init.to_string())).await.unwrap();
+    let ack = tokio::time::timeout(EVENT_TIMEOUT, socket.next()).await.unwrap();
+    let subscribe = json!({
+        "id": "1",
+        "type": "subscribe",
+        "payload": {"query": "subscription { repoChanges(kind: DELETED) { kind } }"},
+    });
+    socket.send(Message::text(subscribe.to_string())).await.unwrap();
+
+    // The subscription starts listening once the server polls it, so repos are
+    // deleted until one of the events gets through
+    let mut next = None;
+    for _ in 0..10 {
+        repository_api_helper::delete_repo(&setup.client).await;
+        if let Ok(msg) = tokio::time::timeout(Duration::from_millis(500), socket.next()).await {
+            next = msg;
+            break;
+        }
+    }
+
+    assert_eq!(
+        res.headers().get(SEC_WEBSOCKET_PROTOCOL).unwrap(),
+        "graphql-transport-ws"
+    );
+    let ack: Value = serde_json::from_str(ack.unwrap().unwrap().to_text().unwrap()).unwrap();
+    assert_eq!(ack, json!({"type": "connection_ack"}));
+    let next: Value = serde_json::from_str(next.unwrap().unwrap().to_text().unwrap()).unwrap();
+    assert_eq!(
+        next,
+        json!({"id": "1", "type": "next", "payload": {"data": {"repoChanges": {"kind": "DELETED"}}}})
+    );
+}
@@ -184,6 +184,7 @@ async fn get_all_user_repo_info_for_user_success() {
     let OneToManyDto {
         one: user,
         many: repos,
+        ..
     } = user_repo_api_helper::create_connected_user_and_repos(&setup.client).await;
     let expected_code = StatusCode::OK;
 
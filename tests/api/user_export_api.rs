@@ -0,0 +1,117 @@
+use std::io::Cursor;
+use std::time::Duration;
+
+use axum::http::{header, StatusCode};
+use axum_test::TestServer;
+use mongodb::bson::oid::ObjectId;
+use serial_test::serial;
+use uuid::Uuid;
+use zip::ZipArchive;
+
+use dto::repo_dto::RepoDto;
+use dto::user_dto::UserDto;
+use dto::user_export_dto::{UserExportDto, UserExportStatus};
+use dto::user_repo_info_dto::UserRepoInfoDto;
+use dto::OneToManyDto;
+
+use crate::common::Setup;
+use crate::helpers::user_repo_api_helper;
+
+async fn wait_for_export(client: &TestServer, export: &UserExportDto) -> UserExportDto {
+    let endpoint = format!("/api/v1/users/{}/export/{}", export.user_id, export.id);
+    for _ in 0..50 {
+        let res = client.get(&endpoint).await;
+        let export: UserExportDto = res.json();
+        if export.status != UserExportStatus::Pending {
+            return export;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("Export {} was not finished in time", export.id)
+}
+
+#[tokio::test]
+#[serial]
+async fn start_export_success() {
+    let setup = Setup::new().await;
+    let OneToManyDto { one: user, .. } =
+        user_repo_api_helper::create_connected_user_and_repos(&setup.client).await;
+    let expected_code = StatusCode::OK;
+
+    let endpoint = format!("/api/v1/users/{}/export", user.id.unwrap());
+    let res = setup.client.get(&endpoint).await;
+
+    assert_eq!(res.status_code(), expected_code);
+    let export: UserExportDto = res.json();
+    assert_eq!(export.user_id, user.id.unwrap());
+    assert_eq!(export.status, UserExportStatus::Pending);
+}
+
+#[tokio::test]
+#[serial]
+async fn download_completed_export_success() {
+    let setup = Setup::new().await;
+    let OneToManyDto {
+        one: user,
+        many: repos,
+        ..
+    } = user_repo_api_helper::create_connected_user_and_repos(&setup.client).await;
+    let expected_code = StatusCode::OK;
+
+    let endpoint = format!("/api/v1/users/{}/export", user.id.unwrap());
+    let export: UserExportDto = setup.client.get(&endpoint).await.json();
+    let export = wait_for_export(&setup.client, &export).await;
+    let res = setup
+        .client
+        .get(&format!("{endpoint}/{}/download", export.id))
+        .await;
+
+    assert_eq!(export.status, UserExportStatus::Completed);
+    assert_eq!(export.repos_count, Some(repos.count));
+    assert_eq!(export.user_repo_infos_count, Some(repos.count));
+    assert_eq!(res.status_code(), expected_code);
+    assert_eq!(res.header(header::CONTENT_TYPE), "application/zip");
+    let mut zip = ZipArchive::new(Cursor::new(res.as_bytes().to_vec())).unwrap();
+    let manifest: UserExportDto =
+        serde_json::from_reader(zip.by_name("manifest.json").unwrap()).unwrap();
+    let archived_user: UserDto =
+        serde_json::from_reader(zip.by_name("user.json").unwrap()).unwrap();
+    let archived_repos: Vec<RepoDto> =
+        serde_json::from_reader(zip.by_name("repos.json").unwrap()).unwrap();
+    let infos: Vec<UserRepoInfoDto> =
+        serde_json::from_reader(zip.by_name("user_repo_infos.json").unwrap()).unwrap();
+    assert_eq!(manifest, export);
+    assert_eq!(archived_user, user);
+    assert_eq!(archived_repos, repos.dtos);
+    assert_eq!(infos.len() as u64, repos.count);
+}
+
+#[tokio::test]
+#[serial]
+async fn start_export_of_nonexistent_user_failure() {
+    let setup = Setup::new().await;
+    let expected_code = StatusCode::CONFLICT;
+
+    let endpoint = format!("/api/v1/users/{}/export", ObjectId::new());
+    let res = setup.client.get(&endpoint).await;
+
+    assert_eq!(res.status_code(), expected_code);
+}
+
+#[tokio::test]
+#[serial]
+async fn download_nonexistent_export_failure() {
+    let setup = Setup::new().await;
+    let OneToManyDto { one: user, .. } =
+        user_repo_api_helper::create_connected_user_and_repos(&setup.client).await;
+    let expected_code = StatusCode::CONFLICT;
+
+    let endpoint = format!(
+        "/api/v1/users/{}/export/{}/download",
+        user.id.unwrap(),
+        Uuid::new_v4()
+    );
+    let res = setup.client.get(&endpoint).await;
+
+    assert_eq!(res.status_code(), expected_code);
+}
@@ -1,12 +1,21 @@
+use std::collections::HashMap;
+
 use axum::http::StatusCode;
+use serde_json::{json, Value};
 use serial_test::serial;
 
 use dto::DtoList;
 use dto::{OneToManyDto, OneToOneDto};
 use dto::repo_dto::RepoDto;
 use dto::user_dto::UserDto;
+use dto::user_repo_dto::{
+    BulkOperationStatus, BulkUserRepoDto, BulkUserRepoResultDto, DanglingLinkReason,
+    LinkConsistencyReportDto, LinkMetadataDto, LinkRole, LinkedRepoDto, TransferRepoDto,
+    UpdateLinkMetadataDto,
+};
+use uuid::Uuid;
 use crate::common::Setup;
-use crate::helpers::user_repo_api_helper;
+use crate::helpers::{repository_api_helper, user_api_helper, user_repo_api_helper};
 
 #[tokio::test]
 #[serial]
@@ -105,6 +114,7 @@ async fn list_pairs_with_pagination_success() {
     let OneToManyDto {
         one: user,
         many: repos,
+        ..
     } = user_repo_api_helper::create_connected_user_and_repos(&setup.client).await;
     let take = 3;
     let offset = 2;
@@ -131,3 +141,464 @@ async fn list_pairs_with_pagination_success() {
     assert_eq!(res.status_code(), expected_code);
     assert_eq!(res.json::<OneToManyDto<UserDto, RepoDto>>(), expected_body);
 }
+
+#[tokio::test]
+#[serial]
+async fn get_repo_owner_success() {
+    let setup = Setup::new().await;
+    let OneToOneDto {
+        left: user,
+        right: repo,
+    } = user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let expected_code = StatusCode::OK;
+
+    let endpoint = format!("/api/v1/users/{}/repos/{}", user.id.unwrap(), repo.id);
+    setup.client.post(&endpoint).await;
+    let res = setup
+        .client
+        .get(&format!("/api/v1/repos/{}/owner", repo.id))
+        .await;
+
+    assert_eq!(res.status_code(), expected_code);
+    assert_eq!(res.json::<UserDto>(), user);
+}
+
+#[tokio::test]
+#[serial]
+async fn list_repos_with_owners_graphql_success() {
+    let setup = Setup::new().await;
+    let OneToOneDto {
+        left: user,
+        right: repo,
+    } = user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let unlinked = repository_api_helper::create_repo(&setup.client).await;
+    let schema = ia_11_vorobei_ant::web::schema(setup.state.clone());
+
+    let endpoint = format!("/api/v1/users/{}/repos/{}", user.id.unwrap(), repo.id);
+    setup.client.post(&endpoint).await;
+    let res = schema
+        .execute("{ repos { list { dtos { id owner { username } } } } }")
+        .await;
+
+    assert!(res.errors.is_empty());
+    let data = res.data.into_json().unwrap();
+    let owners: HashMap<String, Value> = data["repos"]["list"]["dtos"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|dto| (dto["id"].as_str().unwrap().to_string(), dto["owner"].clone()))
+        .collect();
+    assert_eq!(
+        owners[&repo.id.to_string()],
+        json!({"username": user.username})
+    );
+    assert_eq!(owners[&unlinked.id.to_string()], Value::Null);
+}
+
+#[tokio::test]
+#[serial]
+async fn get_owner_of_unlinked_repo_failure() {
+    let setup = Setup::new().await;
+    let OneToOneDto { right: repo, .. } =
+        user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let expected_code = StatusCode::NOT_FOUND;
+
+    let res = setup
+        .client
+        .get(&format!("/api/v1/repos/{}/owner", repo.id))
+        .await;
+
+    assert_eq!(res.status_code(), expected_code);
+}
+
+#[tokio::test]
+#[serial]
+async fn add_pairs_with_partial_failure_success() {
+    let setup = Setup::new().await;
+    let OneToOneDto {
+        left: user,
+        right: repo,
+    } = user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let missing_id = Uuid::new_v4();
+    let expected_code = StatusCode::OK;
+    let dto = BulkUserRepoDto {
+        repo_ids: vec![repo.id, missing_id],
+        all_or_nothing: false,
+    };
+
+    let endpoint = format!("/api/v1/users/{}/repos", user.id.unwrap());
+    let res = setup.client.post(&endpoint).json(&dto).await;
+    let body = res.json::<BulkUserRepoResultDto>();
+
+    assert_eq!(res.status_code(), expected_code);
+    assert_eq!(body.user, user);
+    assert_eq!(body.outcomes[0].status, BulkOperationStatus::Succeeded);
+    assert_eq!(body.outcomes[0].repo, Some(repo));
+    assert_eq!(body.outcomes[1].status, BulkOperationStatus::Failed);
+    assert_eq!(body.outcomes[1].repo_id, missing_id);
+}
+
+#[tokio::test]
+#[serial]
+async fn add_pairs_all_or_nothing_rolled_back() {
+    let setup = Setup::new().await;
+    let OneToOneDto {
+        left: user,
+        right: repo,
+    } = user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let dto = BulkUserRepoDto {
+        repo_ids: vec![repo.id, Uuid::new_v4()],
+        all_or_nothing: true,
+    };
+
+    let endpoint = format!("/api/v1/users/{}/repos", user.id.unwrap());
+    let res = setup.client.post(&endpoint).json(&dto).await;
+    let body = res.json::<BulkUserRepoResultDto>();
+    let list_res = setup.client.get(&endpoint).await;
+
+    assert_eq!(res.status_code(), StatusCode::OK);
+    assert_eq!(body.outcomes[0].status, BulkOperationStatus::RolledBack);
+    assert_eq!(body.outcomes[1].status, BulkOperationStatus::Failed);
+    assert_eq!(list_res.json::<OneToManyDto<UserDto, RepoDto>>().many.count, 0);
+}
+
+#[tokio::test]
+#[serial]
+async fn delete_pairs_success() {
+    let setup = Setup::new().await;
+    let OneToManyDto {
+        one: user,
+        many: repos,
+        ..
+    } = user_repo_api_helper::create_connected_user_and_repos(&setup.client).await;
+    let dto = BulkUserRepoDto {
+        repo_ids: repos.dtos.iter().map(|repo| repo.id).collect(),
+        all_or_nothing: true,
+    };
+
+    let endpoint = format!("/api/v1/users/{}/repos", user.id.unwrap());
+    let res = setup.client.delete(&endpoint).json(&dto).await;
+    let body = res.json::<BulkUserRepoResultDto>();
+    let list_res = setup.client.get(&endpoint).await;
+
+    assert_eq!(res.status_code(), StatusCode::OK);
+    assert!(body.outcomes.iter().all(|o| o.status == BulkOperationStatus::Succeeded));
+    assert_eq!(list_res.json::<OneToManyDto<UserDto, RepoDto>>().many.count, 0);
+}
+
+#[tokio::test]
+#[serial]
+async fn delete_pairs_all_or_nothing_keeps_metadata() {
+    let setup = Setup::new().await;
+    let OneToManyDto {
+        one: user,
+        many: repos,
+        ..
+    } = user_repo_api_helper::create_connected_user_and_repos(&setup.client).await;
+    let repo = &repos.dtos[0];
+    let update_dto = UpdateLinkMetadataDto {
+        role: Some(LinkRole::Maintainer),
+        pinned: Some(true),
+    };
+    let dto = BulkUserRepoDto {
+        repo_ids: vec![repo.id, Uuid::new_v4()],
+        all_or_nothing: true,
+    };
+
+    let endpoint = format!("/api/v1/users/{}/repos", user.id.unwrap());
+    let metadata_endpoint = format!("{endpoint}/{}/metadata", repo.id);
+    let metadata = setup
+        .client
+        .patch(&metadata_endpoint)
+        .json(&update_dto)
+        .await
+        .json::<LinkMetadataDto>();
+    let res = setup.client.delete(&endpoint).json(&dto).await;
+    let body = res.json::<BulkUserRepoResultDto>();
+    let metadata_res = setup.client.get(&metadata_endpoint).await;
+
+    assert_eq!(res.status_code(), StatusCode::OK);
+    assert_eq!(body.outcomes[0].status, BulkOperationStatus::RolledBack);
+    assert_eq!(metadata_res.json::<LinkMetadataDto>(), metadata);
+}
+
+#[tokio::test]
+#[serial]
+async fn add_pairs_with_empty_ids_failure() {
+    let setup = Setup::new().await;
+    let OneToOneDto { left: user, .. } =
+        user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let dto = BulkUserRepoDto {
+        repo_ids: vec![],
+        all_or_nothing: false,
+    };
+
+    let endpoint = format!("/api/v1/users/{}/repos", user.id.unwrap());
+    let res = setup.client.post(&endpoint).json(&dto).await;
+
+    assert_eq!(res.status_code(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+#[serial]
+async fn transfer_repo_success() {
+    let setup = Setup::new().await;
+    let OneToOneDto {
+        left: user,
+        right: repo,
+    } = user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let new_owner = user_api_helper::create_user2(&setup.client).await;
+    let expected_code = StatusCode::OK;
+    let expected_body = OneToOneDto::new(new_owner.clone(), repo.clone());
+    let dto = TransferRepoDto {
+        to_user_id: new_owner.id.unwrap(),
+    };
+
+    let endpoint = format!("/api/v1/users/{}/repos/{}", user.id.unwrap(), repo.id);
+    setup.client.post(&endpoint).await;
+    let res = setup
+        .client
+        .post(&format!("/api/v1/repos/{}/transfer", repo.id))
+        .json(&dto)
+        .await;
+    let owner_res = setup
+        .client
+        .get(&format!("/api/v1/repos/{}/owner", repo.id))
+        .await;
+    let old_list_res = setup
+        .client
+        .get(&format!("/api/v1/users/{}/repos", user.id.unwrap()))
+        .await;
+
+    assert_eq!(res.status_code(), expected_code);
+    assert_eq!(res.json::<OneToOneDto<UserDto, RepoDto>>(), expected_body);
+    assert_eq!(owner_res.json::<UserDto>(), new_owner);
+    assert_eq!(old_list_res.json::<OneToManyDto<UserDto, RepoDto>>().many.count, 0);
+}
+
+#[tokio::test]
+#[serial]
+async fn transfer_unlinked_repo_failure() {
+    let setup = Setup::new().await;
+    let OneToOneDto { right: repo, .. } =
+        user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let new_owner = user_api_helper::create_user2(&setup.client).await;
+    let expected_code = StatusCode::NOT_FOUND;
+    let dto = TransferRepoDto {
+        to_user_id: new_owner.id.unwrap(),
+    };
+
+    let res = setup
+        .client
+        .post(&format!("/api/v1/repos/{}/transfer", repo.id))
+        .json(&dto)
+        .await;
+
+    assert_eq!(res.status_code(), expected_code);
+}
+
+#[tokio::test]
+#[serial]
+async fn transfer_repo_to_nonexistent_user_failure() {
+    let setup = Setup::new().await;
+    let OneToOneDto {
+        left: user,
+        right: repo,
+    } = user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let expected_code = StatusCode::CONFLICT;
+    let dto = TransferRepoDto {
+        to_user_id: mongodb::bson::oid::ObjectId::new(),
+    };
+
+    let endpoint = format!("/api/v1/users/{}/repos/{}", user.id.unwrap(), repo.id);
+    setup.client.post(&endpoint).await;
+    let res = setup
+        .client
+        .post(&format!("/api/v1/repos/{}/transfer", repo.id))
+        .json(&dto)
+        .await;
+    let owner_res = setup
+        .client
+        .get(&format!("/api/v1/repos/{}/owner", repo.id))
+        .await;
+
+    assert_eq!(res.status_code(), expected_code);
+    assert_eq!(owner_res.json::<UserDto>(), user);
+}
+
+#[tokio::test]
+#[serial]
+async fn check_consistency_reports_deleted_repo_success() {
+    let setup = Setup::new().await;
+    let OneToOneDto {
+        left: user,
+        right: repo,
+    } = user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let expected_code = StatusCode::OK;
+
+    let endpoint = format!("/api/v1/users/{}/repos/{}", user.id.unwrap(), repo.id);
+    setup.client.post(&endpoint).await;
+    setup
+        .client
+        .delete(&format!("/api/v1/repos/{}", repo.id))
+        .await;
+    let res = setup
+        .client
+        .post("/api/v1/user-repos/consistency-check")
+        .await;
+    let report = res.json::<LinkConsistencyReportDto>();
+    let owner_res = setup
+        .client
+        .get(&format!("/api/v1/repos/{}/owner", repo.id))
+        .await;
+
+    assert_eq!(res.status_code(), expected_code);
+    assert_eq!(report.checked, 1);
+    assert_eq!(report.dangling.len(), 1);
+    assert_eq!(report.dangling[0].repo_id, repo.id);
+    assert_eq!(report.dangling[0].reason, DanglingLinkReason::RepoMissing);
+    assert!(!report.dangling[0].repaired);
+    assert_eq!(owner_res.status_code(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[serial]
+async fn check_consistency_with_repair_success() {
+    let setup = Setup::new().await;
+    let OneToOneDto {
+        left: user,
+        right: repo,
+    } = user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let expected_code = StatusCode::OK;
+
+    let endpoint = format!("/api/v1/users/{}/repos/{}", user.id.unwrap(), repo.id);
+    setup.client.post(&endpoint).await;
+    setup
+        .client
+        .delete(&format!("/api/v1/users/{}", user.id.unwrap()))
+        .await;
+    let res = setup
+        .client
+        .post("/api/v1/user-repos/consistency-check")
+        .add_query_param("repair", true)
+        .await;
+    let report = res.json::<LinkConsistencyReportDto>();
+    let second_res = setup
+        .client
+        .post("/api/v1/user-repos/consistency-check")
+        .await;
+
+    assert_eq!(res.status_code(), expected_code);
+    assert_eq!(report.dangling[0].reason, DanglingLinkReason::UserMissing);
+    assert!(report.dangling[0].repaired);
+    assert_eq!(report.dangling[0].error, None);
+    assert_eq!(second_res.json::<LinkConsistencyReportDto>().checked, 0);
+}
+
+#[tokio::test]
+#[serial]
+async fn get_link_metadata_success() {
+    let setup = Setup::new().await;
+    let OneToOneDto {
+        left: user,
+        right: repo,
+    } = user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let expected_code = StatusCode::OK;
+
+    let endpoint = format!("/api/v1/users/{}/repos/{}", user.id.unwrap(), repo.id);
+    setup
+        .client
+        .post(&endpoint)
+        .add_header("x-actor".parse().unwrap(), "admin".parse().unwrap())
+        .await;
+    let res = setup.client.get(&format!("{endpoint}/metadata")).await;
+    let metadata = res.json::<LinkMetadataDto>();
+
+    assert_eq!(res.status_code(), expected_code);
+    assert_eq!(metadata.linked_by, Some("admin".to_string()));
+    assert_eq!(metadata.role, LinkRole::Owner);
+    assert!(!metadata.pinned);
+}
+
+#[tokio::test]
+#[serial]
+async fn update_link_metadata_success() {
+    let setup = Setup::new().await;
+    let OneToManyDto {
+        one: user,
+        many: repos,
+        ..
+    } = user_repo_api_helper::create_connected_user_and_repos(&setup.client).await;
+    let pinned_repo = repos.dtos.last().unwrap();
+    let expected_code = StatusCode::OK;
+    let dto = UpdateLinkMetadataDto {
+        role: Some(LinkRole::Maintainer),
+        pinned: Some(true),
+    };
+
+    let endpoint = format!(
+        "/api/v1/users/{}/repos/{}/metadata",
+        user.id.unwrap(),
+        pinned_repo.id
+    );
+    let res = setup.client.patch(&endpoint).json(&dto).await;
+    let metadata = res.json::<LinkMetadataDto>();
+    let list_res = setup
+        .client
+        .get(&format!("/api/v1/users/{}/repos", user.id.unwrap()))
+        .await;
+    let linked = list_res.json::<OneToManyDto<UserDto, LinkedRepoDto>>();
+
+    assert_eq!(res.status_code(), expected_code);
+    assert_eq!(metadata.role, LinkRole::Maintainer);
+    assert!(metadata.pinned);
+    assert_eq!(linked.many.dtos[0].repo, *pinned_repo);
+    assert_eq!(linked.many.dtos[0].metadata, metadata);
+}
+
+#[tokio::test]
+#[serial]
+async fn get_metadata_of_unlinked_repo_failure() {
+    let setup = Setup::new().await;
+    let OneToOneDto {
+        left: user,
+        right: repo,
+    } = user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let expected_code = StatusCode::CONFLICT;
+
+    let endpoint = format!(
+        "/api/v1/users/{}/repos/{}/metadata",
+        user.id.unwrap(),
+        repo.id
+    );
+    let res = setup.client.get(&endpoint).await;
+
+    assert_eq!(res.status_code(), expected_code);
+}
+
+#[tokio::test]
+#[serial]
+async fn list_pairs_reports_deleted_repo_success() {
+    let setup = Setup::new().await;
+    let OneToManyDto {
+        one: user,
+        many: repos,
+        ..
+    } = user_repo_api_helper::create_connected_user_and_repos(&setup.client).await;
+    let deleted_repo = &repos.dtos[0];
+    let expected_code = StatusCode::OK;
+
+    setup
+        .client
+        .delete(&format!("/api/v1/repos/{}", deleted_repo.id))
+        .await;
+    let endpoint = format!("/api/v1/users/{}/repos", user.id.unwrap());
+    let res = setup.client.get(&endpoint).await;
+    let body = res.json::<OneToManyDto<UserDto, RepoDto>>();
+
+    assert_eq!(res.status_code(), expected_code);
+    assert_eq!(body.many.dtos.len(), repos.dtos.len() - 1);
+    assert_eq!(body.many.dtos, repos.dtos[1..]);
+    assert_eq!(body.many.count, repos.count);
+    assert_eq!(body.missing, vec![deleted_repo.id.to_string()]);
+}
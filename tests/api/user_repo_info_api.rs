@@ -1,16 +1,18 @@
-use axum::http::StatusCode;
+use axum::http::{HeaderName, HeaderValue, StatusCode};
 use mongodb::bson::oid::ObjectId;
 use serde_json::{json, Value};
 use serial_test::serial;
+use tokio_stream::StreamExt;
 use uuid::Uuid;
 
 use collection::user_repo_info::UserRepoInfoOperation;
-use dto::user_repo_info_dto::UserRepoInfoDto;
+use dto::user_repo_dto::TransferRepoDto;
+use dto::user_repo_info_dto::{UserRepoInfoArchiveDto, UserRepoInfoDto, UserRepoInfoStatsDto};
 use dto::DtoList;
 use dto::{OneToManyDto, OneToOneDto};
 
 use crate::common::Setup;
-use crate::helpers::user_repo_api_helper;
+use crate::helpers::{user_api_helper, user_repo_api_helper};
 
 #[tokio::test]
 #[serial]
@@ -87,6 +89,7 @@ async fn list_two_user_repo_info_success() {
     let OneToManyDto {
         one: user,
         many: repos,
+        ..
     } = user_repo_api_helper::create_connected_user_and_repos(&setup.client).await;
     let expected_code = StatusCode::OK;
     let take: usize = 2;
@@ -127,6 +130,8 @@ async fn list_two_user_repo_info_success() {
             repo_id,
             operation: UserRepoInfoOperation::CreateLink,
             executed_at: Default::default(),
+            from_user_id: None,
+            restored_at: None,
         };
 
         assert_eq!(
@@ -148,5 +153,180 @@ fn create_info_dto(
         repo_id,
         operation,
         executed_at: Default::default(),
+        from_user_id: None,
+        restored_at: None,
     }
 }
+
+#[tokio::test]
+#[serial]
+async fn list_archives_empty_success() {
+    let setup = Setup::new().await;
+    let expected_code = StatusCode::OK;
+
+    let res = setup.client.get("/api/v1/user-repo-infos/archives").await;
+
+    assert_eq!(res.status_code(), expected_code);
+    assert!(res.json::<DtoList<UserRepoInfoArchiveDto>>().dtos.is_empty());
+}
+
+#[tokio::test]
+#[serial]
+async fn restore_nonexistent_archive_failure() {
+    let setup = Setup::new().await;
+    let expected_code = StatusCode::CONFLICT;
+
+    let res = setup
+        .client
+        .post(&format!(
+            "/api/v1/user-repo-infos/archives/{}/restore",
+            Uuid::new_v4()
+        ))
+        .await;
+
+    assert_eq!(res.status_code(), expected_code);
+}
+
+#[tokio::test]
+#[serial]
+async fn user_repo_info_stats_success() {
+    let setup = Setup::new().await;
+    let OneToOneDto {
+        left: user,
+        right: repo,
+    } = user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let user_id = user.id.unwrap();
+    let expected_code = StatusCode::OK;
+
+    let endpoint = format!("/api/v1/users/{}/repos/{}", user_id, repo.id);
+    setup.client.post(&endpoint).await;
+    setup.client.delete(&endpoint).await;
+    let res = setup
+        .client
+        .get("/api/v1/user-repo-infos/stats")
+        .add_query_param("period", "Week")
+        .await;
+    let stats: UserRepoInfoStatsDto = res.json();
+
+    assert_eq!(res.status_code(), expected_code);
+    assert_eq!(stats.activity.len(), 1);
+    assert_eq!((stats.activity[0].created, stats.activity[0].deleted), (1, 1));
+    assert_eq!(stats.most_active_users[0].user_id, user_id);
+    assert_eq!(stats.churned_repos[0].repo_id, repo.id);
+    assert_eq!(stats.net_links[0].net, 0);
+}
+
+#[tokio::test]
+#[serial]
+async fn user_repo_info_stats_with_invalid_top_failure() {
+    let setup = Setup::new().await;
+    let expected_code = StatusCode::UNPROCESSABLE_ENTITY;
+
+    let zero_res = setup
+        .client
+        .get("/api/v1/user-repo-infos/stats")
+        .add_query_param("top", 0)
+        .await;
+    let huge_res = setup
+        .client
+        .get("/api/v1/user-repo-infos/stats")
+        .add_query_param("top", 1000000)
+        .await;
+
+    assert_eq!(zero_res.status_code(), expected_code);
+    assert_eq!(huge_res.status_code(), expected_code);
+}
+
+#[tokio::test]
+#[serial]
+async fn stream_resumes_after_last_event_id_success() {
+    let setup = Setup::new().await;
+    let OneToOneDto {
+        left: user,
+        right: repo,
+    } = user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let user_id = user.id.unwrap();
+
+    let endpoint = format!("/api/v1/users/{}/repos/{}", user_id, repo.id);
+    setup.client.post(&endpoint).await;
+    setup.client.delete(&endpoint).await;
+    let infos: DtoList<UserRepoInfoDto> = setup.client.get("/api/v1/user-repo-infos").await.json();
+    let mut stream = setup
+        .state
+        .user_repo_info_state
+        .service
+        .stream(Some(user_id), infos.dtos[0].id)
+        .await
+        .unwrap();
+    let missed = stream.next().await.unwrap();
+
+    assert_eq!(missed.id, infos.dtos[1].id);
+    assert_eq!(missed.operation, UserRepoInfoOperation::DeleteLink);
+}
+
+#[tokio::test]
+#[serial]
+async fn stream_of_user_includes_transfers_away_success() {
+    let setup = Setup::new().await;
+    let OneToOneDto {
+        left: user,
+        right: repo,
+    } = user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let new_owner = user_api_helper::create_user2(&setup.client).await;
+    let user_id = user.id.unwrap();
+    let dto = TransferRepoDto {
+        to_user_id: new_owner.id.unwrap(),
+    };
+
+    let endpoint = format!("/api/v1/users/{}/repos/{}", user_id, repo.id);
+    setup.client.post(&endpoint).await;
+    setup
+        .client
+        .post(&format!("/api/v1/repos/{}/transfer", repo.id))
+        .json(&dto)
+        .await;
+    let infos: DtoList<UserRepoInfoDto> = setup.client.get("/api/v1/user-repo-infos").await.json();
+    let mut stream = setup
+        .state
+        .user_repo_info_state
+        .service
+        .stream(Some(user_id), infos.dtos[0].id)
+        .await
+        .unwrap();
+    let missed = stream.next().await.unwrap();
+
+    assert_eq!(missed.operation, UserRepoInfoOperation::Transfer);
+    assert_eq!(missed.from_user_id, Some(user_id));
+}
+
+#[tokio::test]
+#[serial]
+async fn stream_invalid_last_event_id_failure() {
+    let setup = Setup::new().await;
+    let expected_code = StatusCode::BAD_REQUEST;
+
+    let res = setup
+        .client
+        .get("/api/v1/user-repo-infos/stream")
+        .add_header(
+            HeaderName::from_static("last-event-id"),
+            HeaderValue::from_static("not-an-id"),
+        )
+        .await;
+
+    assert_eq!(res.status_code(), expected_code);
+}
+
+#[tokio::test]
+#[serial]
+async fn stream_nonexistent_user_failure() {
+    let setup = Setup::new().await;
+    let expected_code = StatusCode::CONFLICT;
+
+    let res = setup
+        .client
+        .get(&format!("/api/v1/users/{}/repo-infos/stream", ObjectId::new()))
+        .await;
+
+    assert_eq!(res.status_code(), expected_code);
+}
@@ -0,0 +1,94 @@
+use axum::http::StatusCode;
+use serial_test::serial;
+
+use dto::repo_dto::RepoDto;
+use dto::user_dto::UserDto;
+use dto::user_repo_dto::{LinkMetadataDto, LinkRole, LinkedRepoDto, TransferRepoDto};
+use dto::{OneToManyDto, OneToOneDto};
+use ia_11_vorobei_ant::LinkBackend;
+
+use crate::common::Setup;
+use crate::helpers::{user_api_helper, user_repo_api_helper};
+
+#[tokio::test]
+#[serial]
+async fn add_and_list_pairs_postgres_success() {
+    let setup = Setup::with_link_backend(LinkBackend::Postgres).await;
+    let OneToOneDto {
+        left: user,
+        right: repo,
+    } = user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let expected_code = StatusCode::OK;
+    let expected_body = OneToOneDto::new(user.clone(), repo.clone());
+
+    let endpoint = format!("/api/v1/users/{}/repos/{}", user.id.unwrap(), repo.id);
+    let res = setup.client.post(&endpoint).await;
+    let existing_res = setup.client.post(&endpoint).await;
+    let list_res = setup
+        .client
+        .get(&format!("/api/v1/users/{}/repos", user.id.unwrap()))
+        .await;
+    let metadata_res = setup.client.get(&format!("{endpoint}/metadata")).await;
+
+    assert_eq!(res.status_code(), expected_code);
+    assert_eq!(res.json::<OneToOneDto<UserDto, RepoDto>>(), expected_body);
+    assert_eq!(existing_res.status_code(), StatusCode::CONFLICT);
+    let linked = list_res.json::<OneToManyDto<UserDto, LinkedRepoDto>>().many;
+    assert_eq!(linked.count, 1);
+    assert_eq!(linked.dtos[0].repo, repo);
+    assert_eq!(metadata_res.json::<LinkMetadataDto>().role, LinkRole::Owner);
+}
+
+#[tokio::test]
+#[serial]
+async fn delete_pair_postgres_success() {
+    let setup = Setup::with_link_backend(LinkBackend::Postgres).await;
+    let OneToOneDto {
+        left: user,
+        right: repo,
+    } = user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let expected_code = StatusCode::OK;
+
+    let endpoint = format!("/api/v1/users/{}/repos/{}", user.id.unwrap(), repo.id);
+    setup.client.post(&endpoint).await;
+    let res = setup.client.delete(&endpoint).await;
+    let again_res = setup.client.delete(&endpoint).await;
+    let owner_res = setup
+        .client
+        .get(&format!("/api/v1/repos/{}/owner", repo.id))
+        .await;
+
+    assert_eq!(res.status_code(), expected_code);
+    assert_eq!(again_res.status_code(), StatusCode::CONFLICT);
+    assert_eq!(owner_res.status_code(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+#[serial]
+async fn transfer_repo_postgres_success() {
+    let setup = Setup::with_link_backend(LinkBackend::Postgres).await;
+    let OneToOneDto {
+        left: user,
+        right: repo,
+    } = user_repo_api_helper::create_user_and_repo(&setup.client).await;
+    let new_owner = user_api_helper::create_user2(&setup.client).await;
+    let expected_code = StatusCode::OK;
+    let dto = TransferRepoDto {
+        to_user_id: new_owner.id.unwrap(),
+    };
+
+    let endpoint = format!("/api/v1/users/{}/repos/{}", user.id.unwrap(), repo.id);
+    setup.client.post(&endpoint).await;
+    let res = setup
+        .client
+        .post(&format!("/api/v1/repos/{}/transfer", repo.id))
+        .json(&dto)
+        .await;
+    let owner_res = setup
+        .client
+        .get(&format!("/api/v1/repos/{}/owner", repo.id))
+        .await;
+
+    assert_eq!(res.status_code(), expected_code);
+    assert_eq!(owner_res.json::<UserDto>(), new_owner);
+}
@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use axum::http::StatusCode;
+use serde_json::Value;
+use serial_test::serial;
+use tokio_stream::StreamExt;
+
+use collection::webhook_subscription::WebhookEventType;
+use dto::webhook_dto::WebhookDeliveryDto;
+use dto::DtoList;
+use ia_11_vorobei_ant::web::error::ApiError;
+use ia_11_vorobei_ant::web::service::webhook_service::{self, SIGNATURE_HEADER};
+use ia_11_vorobei_ant::web::state::WebhookState;
+use repo::utils::webhook::webhook_test_helper;
+
+use super::common::Setup;
+use crate::helpers::repository_api_helper;
+use crate::helpers::webhook_api_helper::{self, WebhookReceiver};
+
+/// Runs the relay a few times, long enough for the retries of the test state
+async fn deliver_pending(setup: &Setup) {
+    for _ in 0..3 {
+        let service = &setup.state.webhook_state.service;
+        service.deliver_pending(10).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn deliver_signed_event_success() {
+    let setup = Setup::new().await;
+    let receiver = WebhookReceiver::spawn(StatusCode::OK).await;
+    let webhook = webhook_api_helper::create_webhook(
+        &setup.client,
+        &receiver.url,
+        vec![WebhookEventType::RepoCreated],
+    )
+    .await;
+    let secret = webhook_test_helper::get_create_dto(vec![]).secret;
+    let mut events = Box::pin(setup.state.event_hub.subscribe());
+
+    let repo = repository_api_helper::create_repo(&setup.client).await;
+    let event = events.next().await.unwrap();
+    setup.state.webhook_state.service.enqueue(event).await.unwrap();
+    deliver_pending(&setup).await;
+
+    let requests = receiver.requests();
+    assert_eq!(requests.len(), 1, "Event must be delivered exactly once");
+    let (headers, body) = &requests[0];
+    assert_eq!(
+        headers[SIGNATURE_HEADER].to_str().unwrap(),
+        webhook_service::sign(&secret, body),
+        "Signature doesn't correspond to the payload"
+    );
+    let payload: Value = serde_json::from_str(body).unwrap();
+    assert_eq!(payload["type"], "RepoCreated");
+    assert_eq!(payload["data"]["id"], repo.id.to_string());
+
+    let res = setup
+        .client
+        .get(&format!("/api/v1/webhooks/{}/deliveries", webhook.id))
+        .await;
+    let deliveries: DtoList<WebhookDeliveryDto> = res.json();
+    assert_eq!(deliveries.dtos.len(), 1);
+    assert!(deliveries.dtos[0].success, "Delivery must be logged as successful");
+}
+
+#[tokio::test]
+#[serial]
+async fn deliver_retries_then_redeliver_success() {
+    let setup = Setup::new().await;
+    let receiver = WebhookReceiver::spawn(StatusCode::INTERNAL_SERVER_ERROR).await;
+    let webhook = webhook_api_helper::create_webhook(
+        &setup.client,
+        &receiver.url,
+        vec![WebhookEventType::RepoCreated],
+    )
+    .await;
+    let mut events = Box::pin(setup.state.event_hub.subscribe());
+
+    repository_api_helper::create_repo(&setup.client).await;
+    let event = events.next().await.unwrap();
+    setup.state.webhook_state.service.enqueue(event).await.unwrap();
+    deliver_pending(&setup).await;
+
+    let endpoint = format!("/api/v1/webhooks/{}/deliveries", webhook.id);
+    let deliveries: DtoList<WebhookDeliveryDto> = setup.client.get(&endpoint).await.json();
+    let attempts: Vec<(u32, bool)> =
+        deliveries.dtos.iter().map(|d| (d.attempt, d.success)).collect();
+    assert_eq!(attempts, vec![(3, false), (2, false), (1, false)]);
+    assert_eq!(receiver.requests().len(), 3);
+
+    receiver.respond_with(StatusCode::OK);
+    let first = &deliveries.dtos[2];
+    let res = setup
+        .client
+        .post(&format!("/api/v1/webhooks/deliveries/{}/redeliver", first.id))
+        .await;
+    let redelivered: WebhookDeliveryDto = res.json();
+
+    assert_eq!(res.status_code(), StatusCode::OK);
+    assert_eq!(redelivered.attempt, 4, "Attempt must follow the last stored one");
+    assert!(redelivered.success, "Redelivery must succeed once the receiver recovers");
+    assert_eq!(redelivered.event_id, deliveries.dtos[0].event_id);
+    assert_eq!(receiver.requests()[3].1, deliveries.dtos[0].payload);
+}
+
+#[tokio::test]
+#[serial]
+async fn create_webhook_with_invalid_url_failure() {
+    let setup = Setup::new().await;
+    for url in ["not a url", "ftp://example.com/webhooks"] {
+        let mut create_dto =
+            webhook_test_helper::get_create_dto(vec![WebhookEventType::LinkCreated]);
+        create_dto.url = url.to_string();
+
+        let res = setup.client.post("/api/v1/webhooks").json(&create_dto).await;
+
+        assert_eq!(
+            res.status_code(),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Webhook with URL {url} must be rejected"
+        );
+    }
+}
+
+#[tokio::test]
+async fn create_webhook_with_private_target_failure() {
+    let state = WebhookState::build_test(false).await.unwrap();
+    let urls = [
+        "http://localhost:8080/webhooks",
+        "http://127.0.0.1/webhooks",
+        "http://10.0.0.1/webhooks",
+        "http://169.254.169.254/latest/meta-data",
+        "http://[::1]/webhooks",
+        "http://[::ffff:192.168.0.1]/webhooks",
+    ];
+    for url in urls {
+        let mut create_dto =
+            webhook_test_helper::get_create_dto(vec![WebhookEventType::LinkCreated]);
+        create_dto.url = url.to_string();
+
+        let res = state.service.create(create_dto).await;
+
+        assert!(
+            matches!(res, Err(ApiError::ForbiddenWebhookTarget(_))),
+            "Webhook to {url} must be rejected"
+        );
+    }
+}
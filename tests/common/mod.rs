@@ -2,9 +2,12 @@ use axum_test::TestServer;
 use sea_orm::EntityTrait;
 
 use entity::prelude::Repository;
+use ia_11_vorobei_ant::web::state::AppState;
+use ia_11_vorobei_ant::LinkBackend;
 
 pub struct Setup {
     pub client: TestServer,
+    pub state: AppState,
 }
 
 impl Setup {
@@ -16,13 +19,16 @@ impl Setup {
 
 impl Setup {
     pub async fn new() -> Self {
-        let state = ia_11_vorobei_ant::web::state::AppState::build_test()
-            .await
-            .unwrap();
-        let app = ia_11_vorobei_ant::web::app(state);
+        Self::with_link_backend(LinkBackend::ObjectStore).await
+    }
+
+    pub async fn with_link_backend(link_backend: LinkBackend) -> Self {
+        let state = AppState::build_test_with_links(link_backend).await.unwrap();
+        let app = ia_11_vorobei_ant::web::app(state.clone());
 
         let instance = Self {
             client: TestServer::new(app).unwrap(),
+            state,
         };
 
         instance.clean_up().await;
@@ -1,3 +1,4 @@
 pub mod repository_api_helper;
 pub mod user_api_helper;
 pub mod user_repo_api_helper;
+pub mod webhook_api_helper;
@@ -0,0 +1,69 @@
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::sync::{Arc, Mutex};
+
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use axum_test::TestServer;
+
+use collection::webhook_subscription::WebhookEventType;
+use dto::webhook_dto::WebhookSubscriptionDto;
+use repo::utils::webhook::webhook_test_helper;
+
+type ReceivedRequests = Arc<Mutex<Vec<(HeaderMap, String)>>>;
+
+/// Local endpoint recording every webhook it receives
+pub struct WebhookReceiver {
+    pub url: String,
+    status: Arc<AtomicU16>,
+    requests: ReceivedRequests,
+}
+
+impl WebhookReceiver {
+    pub async fn spawn(status: StatusCode) -> Self {
+        let status = Arc::new(AtomicU16::new(status.as_u16()));
+        let requests = ReceivedRequests::default();
+        let app = Router::new()
+            .route("/", post(receive))
+            .with_state((Arc::clone(&status), Arc::clone(&requests)));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        Self {
+            url,
+            status,
+            requests,
+        }
+    }
+
+    pub fn respond_with(&self, status: StatusCode) {
+        self.status.store(status.as_u16(), Ordering::SeqCst);
+    }
+
+    pub fn requests(&self) -> Vec<(HeaderMap, String)> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn receive(
+    State((status, requests)): State<(Arc<AtomicU16>, ReceivedRequests)>,
+    headers: HeaderMap,
+    body: String,
+) -> StatusCode {
+    requests.lock().unwrap().push((headers, body));
+    StatusCode::from_u16(status.load(Ordering::SeqCst)).unwrap()
+}
+
+pub async fn create_webhook(
+    client: &TestServer,
+    url: &str,
+    events: Vec<WebhookEventType>,
+) -> WebhookSubscriptionDto {
+    let mut create_dto = webhook_test_helper::get_create_dto(events);
+    create_dto.url = url.to_string();
+    let res = client.post("/api/v1/webhooks").json(&create_dto).await;
+    res.json()
+}